@@ -0,0 +1,452 @@
+//! AtCoder support, through the official user history endpoints and the AtCoder Problems API.
+
+use std::{future::Future, sync::Arc, time::Instant};
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serenity::{
+    builder::CreateEmbed,
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::{channel::Message, colour::Colour},
+    utils::MessageBuilder,
+};
+use youmubot_prelude::{ratelimit::Ratelimit, *};
+
+use crate::{
+    db::AcUser,
+    judge::{self, changes_since, ContestInfo, Judge, Profile, RatingChange, SavedUser},
+};
+
+const BASE: &str = "https://atcoder.jp";
+const PROBLEMS_API: &str = "https://kenkoooo.com/atcoder/resources";
+
+/// How long the contest and problem lists are kept before being fetched again.
+const LIST_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+/// AtCoder has no documented limits, so we stay polite with one request per second.
+const REQUEST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+lazy_static! {
+    static ref HANDLE: Regex = Regex::new(r"^[A-Za-z0-9_]{3,16}$").unwrap();
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct HistoryEntry {
+    is_rated: bool,
+    place: u64,
+    old_rating: i64,
+    new_rating: i64,
+    contest_screen_name: String,
+    contest_name: String,
+    end_time: DateTime<FixedOffset>,
+}
+
+impl HistoryEntry {
+    /// The contest id, from the contest screen name (`abc300.contest.atcoder.jp`).
+    fn contest_id(&self) -> &str {
+        self.contest_screen_name
+            .split('.')
+            .next()
+            .unwrap_or(&self.contest_screen_name)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct ResultEntry {
+    place: u64,
+    user_screen_name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ApiContest {
+    id: String,
+    start_epoch_second: i64,
+    duration_second: u64,
+    title: String,
+}
+
+impl From<&ApiContest> for ContestInfo {
+    fn from(c: &ApiContest) -> Self {
+        ContestInfo {
+            id: c.id.clone(),
+            name: c.title.clone(),
+            url: contest_url(&c.id),
+            start: Utc.timestamp_opt(c.start_epoch_second, 0).earliest(),
+            duration: std::time::Duration::from_secs(c.duration_second),
+        }
+    }
+}
+
+/// A problem on AtCoder.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Problem {
+    pub id: String,
+    pub contest_id: String,
+    pub name: String,
+}
+
+fn contest_url(contest_id: &str) -> String {
+    format!("{}/contests/{}", BASE, contest_id)
+}
+
+/// A cached list, refreshed at most once every [LIST_TTL].
+struct CachedList<T>(RwLock<Option<(Arc<Vec<T>>, Instant)>>);
+
+impl<T> CachedList<T> {
+    fn new() -> Self {
+        Self(RwLock::new(None))
+    }
+
+    async fn get<F: Future<Output = Result<Vec<T>>>>(
+        &self,
+        fetch: impl FnOnce() -> F,
+    ) -> Result<Arc<Vec<T>>> {
+        if let Some((list, updated)) = &*self.0.read().await {
+            if updated.elapsed() < LIST_TTL {
+                return Ok(list.clone());
+            }
+        }
+        let mut v = self.0.write().await;
+        // Someone else might have fetched the list while we waited for the lock.
+        if let Some((list, updated)) = &*v {
+            if updated.elapsed() < LIST_TTL {
+                return Ok(list.clone());
+            }
+        }
+        let list = Arc::new(fetch().await?);
+        *v = Some((list.clone(), Instant::now()));
+        Ok(list)
+    }
+}
+
+/// The AtCoder judge.
+pub struct AtCoder {
+    client: Ratelimit<reqwest::Client>,
+    contests: CachedList<ApiContest>,
+    problems: CachedList<Problem>,
+}
+
+impl TypeMapKey for AtCoder {
+    type Value = AtCoder;
+}
+
+impl AtCoder {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client: Ratelimit::new(client, 1, REQUEST_INTERVAL),
+            contests: CachedList::new(),
+            problems: CachedList::new(),
+        }
+    }
+
+    /// Fetches a JSON document, returning `None` on 404.
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Option<T>> {
+        let client = self.client.borrow().await?;
+        metrics::api_request("atcoder");
+        let resp: Result<Option<T>> = async {
+            let resp = client.get(url).send().await?;
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            Ok(Some(resp.error_for_status()?.json().await?))
+        }
+        .await;
+        if resp.is_err() {
+            metrics::api_failure("atcoder");
+        }
+        resp
+    }
+
+    /// Gets the contest history of an user, or `None` if there is no such user.
+    async fn history(&self, handle: &str) -> Result<Option<Vec<HistoryEntry>>> {
+        if !HANDLE.is_match(handle) {
+            return Ok(None);
+        }
+        self.get_json(&format!("{}/users/{}/history/json", BASE, handle))
+            .await
+    }
+
+    async fn all_contests(&self) -> Result<Arc<Vec<ApiContest>>> {
+        self.contests
+            .get(|| async {
+                self.get_json(&format!("{}/contests.json", PROBLEMS_API))
+                    .await?
+                    .ok_or_else(|| Error::msg("cannot fetch the AtCoder contest list"))
+            })
+            .await
+    }
+
+    /// Gets a problem of a contest by its id.
+    pub async fn problem(&self, contest_id: &str, problem_id: &str) -> Result<Option<Problem>> {
+        let problems = self
+            .problems
+            .get(|| async {
+                self.get_json(&format!("{}/problems.json", PROBLEMS_API))
+                    .await?
+                    .ok_or_else(|| Error::msg("cannot fetch the AtCoder problem list"))
+            })
+            .await?;
+        Ok(problems
+            .iter()
+            .find(|p| p.contest_id == contest_id && p.id == problem_id)
+            .cloned())
+    }
+}
+
+/// The AtCoder rank and colour of a rating.
+fn rank_of(rating: i64) -> (&'static str, u32) {
+    match rating {
+        ..400 => ("Gray", 0x808080),
+        400..800 => ("Brown", 0x804000),
+        800..1200 => ("Green", 0x008000),
+        1200..1600 => ("Cyan", 0x00c0c0),
+        1600..2000 => ("Blue", 0x0000ff),
+        2000..2400 => ("Yellow", 0xc0c000),
+        2400..2800 => ("Orange", 0xff8000),
+        _ => ("Red", 0xff0000),
+    }
+}
+
+fn profile_of(handle: &str, history: &[HistoryEntry]) -> Profile {
+    let rated = history.iter().filter(|h| h.is_rated);
+    let rating = rated.clone().next_back().map(|h| h.new_rating);
+    let max_rating = rated.map(|h| h.new_rating).max();
+    Profile {
+        handle: handle.to_owned(),
+        rating,
+        max_rating,
+        rank: rating.map(|r| rank_of(r).0.to_owned()),
+        max_rank: max_rating.map(|r| rank_of(r).0.to_owned()),
+        color: Colour::new(rating.map(|r| rank_of(r).1).unwrap_or(0)),
+        avatar: None,
+        url: format!("{}/users/{}", BASE, handle),
+    }
+}
+
+fn rating_changes_of(history: &[HistoryEntry]) -> Vec<RatingChange> {
+    history
+        .iter()
+        .filter(|h| h.is_rated)
+        .map(|h| RatingChange {
+            contest_id: h.contest_id().to_owned(),
+            contest_name: h.contest_name.clone(),
+            contest_url: contest_url(h.contest_id()),
+            rank: h.place,
+            old_rating: h.old_rating,
+            new_rating: h.new_rating,
+            time: h.end_time.with_timezone(&Utc),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Judge for AtCoder {
+    const NAME: &'static str = "AtCoder";
+    type User = AcUser;
+
+    async fn profile(&self, handle: &str) -> Result<Option<Profile>> {
+        Ok(self.history(handle).await?.map(|h| profile_of(handle, &h)))
+    }
+
+    async fn rating_changes(&self, handle: &str) -> Result<Vec<RatingChange>> {
+        let history = self
+            .history(handle)
+            .await?
+            .ok_or_else(|| Error::msg("user not found"))?;
+        Ok(rating_changes_of(&history))
+    }
+
+    async fn profile_with_changes(
+        &self,
+        handle: &str,
+    ) -> Result<Option<(Profile, Vec<RatingChange>)>> {
+        Ok(self
+            .history(handle)
+            .await?
+            .map(|h| (profile_of(handle, &h), rating_changes_of(&h))))
+    }
+
+    async fn contests(&self) -> Result<Vec<ContestInfo>> {
+        Ok(self.all_contests().await?.iter().map(Into::into).collect())
+    }
+
+    async fn standings(
+        &self,
+        contest_id: &str,
+        handles: &[String],
+    ) -> Result<(ContestInfo, Vec<judge::StandingsRow>)> {
+        let contest = self
+            .contest(contest_id)
+            .await?
+            .ok_or_else(|| Error::msg("contest not found"))?;
+        // Full standings require logging in, so we use the final results.
+        let results: Vec<ResultEntry> = self
+            .get_json(&format!("{}/results/json", contest_url(contest_id)))
+            .await?
+            .ok_or_else(|| Error::msg("contest not found"))?;
+        let rows = results
+            .into_iter()
+            .filter(|r| {
+                handles
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(&r.user_screen_name))
+            })
+            .map(|r| judge::StandingsRow {
+                handle: r.user_screen_name,
+                rank: r.place,
+                points: None,
+            })
+            .collect();
+        Ok((contest, rows))
+    }
+}
+
+impl SavedUser for AcUser {
+    fn new(profile: &Profile, changes: &[RatingChange]) -> Self {
+        Self {
+            handle: profile.handle.clone(),
+            last_update: Utc::now(),
+            last_contest_id: changes.last().map(|rc| rc.contest_id.clone()),
+            rating: profile.rating,
+            failures: 0,
+        }
+    }
+
+    fn handle(&self) -> &str {
+        &self.handle
+    }
+
+    fn rating(&self) -> Option<i64> {
+        self.rating
+    }
+
+    fn last_update(&self) -> DateTime<Utc> {
+        self.last_update
+    }
+
+    fn failures_mut(&mut self) -> &mut u8 {
+        &mut self.failures
+    }
+
+    fn update(&mut self, profile: &Profile, changes: Vec<RatingChange>) -> Vec<RatingChange> {
+        let changes = changes_since(changes, self.last_contest_id.as_deref());
+        self.last_update = Utc::now();
+        self.rating = profile.rating;
+        if let Some(rc) = changes.last() {
+            self.last_contest_id = Some(rc.contest_id.clone());
+        }
+        changes
+    }
+}
+
+/// A contest or problem mentioned by an AtCoder link.
+pub(crate) enum LinkInfo {
+    Contest(ContestInfo),
+    Problem(Problem),
+}
+
+/// Resolves an AtCoder contest or problem link.
+pub(crate) async fn resolve_link(
+    atcoder: &AtCoder,
+    contest_id: &str,
+    problem_id: Option<&str>,
+) -> Result<LinkInfo> {
+    match problem_id {
+        Some(p) => atcoder
+            .problem(contest_id, p)
+            .await?
+            .map(LinkInfo::Problem)
+            .ok_or_else(|| Error::msg("No such problem in contest")),
+        None => atcoder
+            .contest(contest_id)
+            .await?
+            .map(LinkInfo::Contest)
+            .ok_or_else(|| Error::msg("Contest not found")),
+    }
+}
+
+/// Prints the info of the given AtCoder links.
+pub(crate) fn print_info_message(info: &[(LinkInfo, &str)]) -> CreateEmbed {
+    let mut m = MessageBuilder::new();
+    for (info, link) in info {
+        match info {
+            LinkInfo::Problem(p) => {
+                m.push(" - [")
+                    .push_bold_safe(format!("[{}] {}", p.id, p.name))
+                    .push(format!("]({})", link));
+            }
+            LinkInfo::Contest(c) => {
+                m.push(" - [")
+                    .push_bold_safe(&c.name)
+                    .push(format!("]({})", link));
+                if let Some(ts) = c.start {
+                    m.push(format!(
+                        " | from {} ({})",
+                        ts.format("<t:%s:F>"),
+                        ts.format("<t:%s:R>")
+                    ));
+                }
+                m.push(format!(" | duration **{}**", Duration(c.duration)));
+            }
+        }
+        m.push_line("");
+    }
+    CreateEmbed::new()
+        .title("AtCoder")
+        .color(0x222222)
+        .description(m.build())
+}
+
+#[group]
+#[prefixes("atcoder", "ac")]
+#[description = "AtCoder-related commands"]
+#[commands(profile, save, ranks, contestranks)]
+#[default_command(profile)]
+pub struct AtCoderCommands;
+
+#[command]
+#[aliases("p", "show", "u", "user", "get")]
+#[description = "Get an user's AtCoder profile"]
+#[usage = "[handle or tag = yourself]"]
+#[example = "tourist"]
+#[max_args(1)]
+pub async fn profile(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let handle = args
+        .single::<UsernameArg>()
+        .unwrap_or_else(|_| UsernameArg::mention(m.author.id));
+    judge::profile::<AtCoder>(ctx, m, handle).await
+}
+
+#[command]
+#[description = "Link your AtCoder account to the Discord account, to enjoy Youmu's tracking capabilities."]
+#[usage = "[handle]"]
+#[num_args(1)]
+pub async fn save(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let handle = args.single::<String>()?;
+    judge::save::<AtCoder>(ctx, m, handle).await
+}
+
+#[command]
+#[description = "See the AtCoder leaderboard of all people in the server."]
+#[only_in(guilds)]
+#[num_args(0)]
+pub async fn ranks(ctx: &Context, m: &Message) -> CommandResult {
+    judge::ranks::<AtCoder>(ctx, m).await
+}
+
+#[command]
+#[description = "See the results of people in the server in a finished AtCoder contest."]
+#[usage = "[the contest id]"]
+#[example = "abc300"]
+#[num_args(1)]
+#[only_in(guilds)]
+pub async fn contestranks(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let contest_id = args.single::<String>()?;
+    judge::contest_ranks::<AtCoder>(ctx, m, contest_id).await
+}
@@ -1,5 +1,5 @@
-use chrono::{DateTime, Utc};
-use codeforces::{RatingChange, User};
+use chrono::{DateTime, TimeZone, Utc};
+use codeforces::RatingChange;
 use serenity::model::id::UserId;
 use std::collections::HashMap;
 use youmubot_db::DB;
@@ -19,16 +19,86 @@ pub struct CfUser {
     pub failures: u8,
 }
 
-impl CfUser {
-    /// Save a new user as an internal CFUser.
-    /// Requires a vector of rating changes because we must rely on the Codeforces rating_changes API's return order to properly announce.
-    pub(crate) fn save(u: User, rc: Vec<RatingChange>) -> Self {
+/// A database map that stores an user with the respective AtCoder handle.
+pub type AtCoderSavedUsers = DB<HashMap<UserId, AcUser>>;
+
+/// A saved AtCoder user.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct AcUser {
+    pub handle: String,
+    pub last_update: DateTime<Utc>,
+    #[serde(default)]
+    pub last_contest_id: Option<String>,
+    pub rating: Option<i64>,
+    #[serde(default)]
+    pub failures: u8,
+}
+
+/// A database map that stores the rating history of Codeforces handles, keyed by the lowercased handle.
+pub type CfRatingHistory = DB<HashMap<String, RatingHistory>>;
+
+/// The stored rating history of a Codeforces handle.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct RatingHistory {
+    pub handle: String,
+    pub last_update: DateTime<Utc>,
+    pub changes: Vec<RatingPoint>,
+}
+
+/// The rating of an user after a rated contest.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct RatingPoint {
+    pub contest_id: u64,
+    pub contest_name: String,
+    pub rank: u64,
+    pub time: DateTime<Utc>,
+    pub rating: i64,
+}
+
+impl RatingHistory {
+    /// Create a rating history from the rating changes returned by the Codeforces API.
+    pub(crate) fn new(handle: impl Into<String>, rc: &[RatingChange]) -> Self {
         Self {
-            handle: u.handle,
+            handle: handle.into(),
             last_update: Utc::now(),
-            last_contest_id: rc.into_iter().last().map(|v| v.contest_id),
-            rating: u.rating,
-            failures: 0,
+            changes: rc
+                .iter()
+                .map(|rc| RatingPoint {
+                    contest_id: rc.contest_id,
+                    contest_name: rc.contest_name.clone(),
+                    rank: rc.rank,
+                    time: Utc
+                        .timestamp_opt(rc.rating_update_time_seconds as i64, 0)
+                        .earliest()
+                        .unwrap_or_else(Utc::now),
+                    rating: rc.new_rating,
+                })
+                .collect(),
         }
     }
+
+    /// Create a rating history from the rating changes of the Codeforces judge.
+    pub(crate) fn from_judge(handle: impl Into<String>, rc: &[crate::judge::RatingChange]) -> Self {
+        Self {
+            handle: handle.into(),
+            last_update: Utc::now(),
+            changes: rc
+                .iter()
+                .filter_map(|rc| {
+                    Some(RatingPoint {
+                        contest_id: rc.contest_id.parse().ok()?,
+                        contest_name: rc.contest_name.clone(),
+                        rank: rc.rank,
+                        time: rc.time,
+                        rating: rc.new_rating,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// The key of the handle in the [CfRatingHistory] database.
+    pub(crate) fn key(handle: &str) -> String {
+        handle.to_lowercase()
+    }
 }
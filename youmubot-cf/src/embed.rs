@@ -1,11 +1,7 @@
-use codeforces::{Contest, RatingChange, User};
+use codeforces::User;
 use inflector::Inflector;
-use serenity::{
-    builder::{CreateEmbed, CreateEmbedAuthor},
-    utils::MessageBuilder,
-};
+use serenity::builder::{CreateEmbed, CreateEmbedAuthor};
 use std::borrow::Borrow;
-use youmubot_prelude::*;
 
 fn unwrap_or_ref<'a, T: ?Sized, B: Borrow<T>>(opt: &'a Option<B>, default: &'a T) -> &'a T {
     opt.as_ref().map(|v| v.borrow()).unwrap_or(default)
@@ -58,55 +54,3 @@ pub fn user_embed(user: &User) -> CreateEmbed {
             false,
         )
 }
-
-/// Gets an embed of the Rating Change.
-pub fn rating_change_embed(
-    rating_change: &RatingChange,
-    user: &User,
-    contest: &Contest,
-    user_id: serenity::model::id::UserId,
-) -> CreateEmbed {
-    let delta = rating_change.new_rating - rating_change.old_rating;
-    let color = if delta < 0 { 0xff0000 } else { 0x00ff00 };
-    let message = if delta > 0 {
-        MessageBuilder::new()
-            .push(user_id.mention().to_string())
-            .push(" competed in ")
-            .push_bold_safe(&contest.name)
-            .push(", gaining ")
-            .push_bold_safe(delta.to_string())
-            .push(" rating placing at ")
-            .push_bold(format!("#{}", rating_change.rank))
-            .push("! 🎂🎂🎂")
-            .build()
-    } else {
-        MessageBuilder::new()
-            .push(user_id.mention().to_string())
-            .push(" competed in ")
-            .push_bold_safe(&contest.name)
-            .push(", but lost ")
-            .push_bold_safe((-delta).to_string())
-            .push(" rating placing at ")
-            .push_bold(format!("#{}", rating_change.rank))
-            .push("... 😭😭😭")
-            .build()
-    };
-
-    CreateEmbed::new()
-        .author({
-            CreateEmbedAuthor::new(&user.handle)
-                .icon_url(user.avatar.to_string())
-                .url(user.profile_url())
-        })
-        .color(color)
-        .description(message)
-        .field("Contest Link", contest.url(), true)
-        .field(
-            "Rating Change",
-            format!(
-                "from **{}** to **{}**",
-                rating_change.old_rating, rating_change.new_rating
-            ),
-            false,
-        )
-}
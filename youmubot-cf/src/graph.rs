@@ -0,0 +1,185 @@
+use crate::{
+    cf_api,
+    db::{CfRatingHistory, CfSavedUsers, RatingHistory},
+    CFClient,
+};
+use chrono::{DateTime, Duration, Utc};
+use codeforces::User;
+use plotters::prelude::*;
+use std::collections::HashSet;
+use youmubot_prelude::*;
+
+type Client = <CFClient as TypeMapKey>::Value;
+
+/// How long a stored rating history is used before being fetched again.
+const HISTORY_TTL: Duration = Duration::hours(6);
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 450;
+const FONT: &str = "sans-serif";
+
+/// The lower bound and colour of each Codeforces rank band.
+const RANK_BANDS: [(i64, RGBColor); 10] = [
+    (i64::MIN, RGBColor(0xcc, 0xcc, 0xcc)),
+    (1200, RGBColor(0x77, 0xff, 0x77)),
+    (1400, RGBColor(0x77, 0xdd, 0xbb)),
+    (1600, RGBColor(0xaa, 0xaa, 0xff)),
+    (1900, RGBColor(0xff, 0x88, 0xff)),
+    (2100, RGBColor(0xff, 0xcc, 0x88)),
+    (2300, RGBColor(0xff, 0xbb, 0x55)),
+    (2400, RGBColor(0xff, 0x77, 0x77)),
+    (2600, RGBColor(0xff, 0x33, 0x33)),
+    (3000, RGBColor(0xaa, 0x00, 0x00)),
+];
+
+/// Colours of the rating lines, one per compared user.
+const LINE_COLORS: [RGBColor; 5] = [
+    RGBColor(0x00, 0x00, 0x00),
+    RGBColor(0x1f, 0x4e, 0xd8),
+    RGBColor(0x13, 0x8a, 0x36),
+    RGBColor(0xb8, 0x32, 0xc7),
+    RGBColor(0xe0, 0x6c, 0x00),
+];
+
+/// The maximum number of users that can be compared in one graph.
+pub const MAX_USERS: usize = LINE_COLORS.len();
+
+/// The keys of the saved users in the [CfRatingHistory] database.
+/// Only their histories are stored, so that the database does not grow with every handle looked up.
+pub(crate) fn saved_handles(data: &TypeMap) -> Result<HashSet<String>> {
+    Ok(CfSavedUsers::open(data)
+        .borrow()?
+        .values()
+        .map(|u| RatingHistory::key(&u.handle))
+        .collect())
+}
+
+/// Gets the rating history of the user, from the local database if it is recent enough.
+/// Only the histories of saved users are stored.
+pub(crate) async fn rating_history(
+    data: &TypeMap,
+    client: &Client,
+    user: &User,
+) -> Result<RatingHistory> {
+    let key = RatingHistory::key(&user.handle);
+    let stored = CfRatingHistory::open(data).borrow()?.get(&key).cloned();
+    if let Some(history) = stored {
+        if Utc::now() - history.last_update < HISTORY_TTL {
+            return Ok(history);
+        }
+    }
+    let history = RatingHistory::new(&user.handle, &cf_api(user.rating_changes(client)).await?);
+    if saved_handles(data)?.contains(&key) {
+        CfRatingHistory::open(data)
+            .borrow_mut()?
+            .insert(key, history.clone());
+    }
+    Ok(history)
+}
+
+fn register_font() {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        plotters::style::register_font(
+            FONT,
+            FontStyle::Normal,
+            include_bytes!("../assets/DejaVuSansMono.ttf"),
+        )
+        .map_err(|_| ())
+        .expect("the bundled font must be valid");
+    });
+}
+
+/// Renders the rating histories into a PNG image, with Codeforces rank bands in the background.
+///
+/// Drawing takes a while, so it is done off the async runtime.
+pub(crate) async fn rating_graph(histories: Vec<RatingHistory>) -> Result<Vec<u8>> {
+    tokio::task::spawn_blocking(move || render(&histories)).await?
+}
+
+fn render(histories: &[RatingHistory]) -> Result<Vec<u8>> {
+    let points = || histories.iter().flat_map(|h| h.changes.iter());
+    let (Some(first), Some(last)) = (
+        points().map(|p| p.time).min(),
+        points().map(|p| p.time).max(),
+    ) else {
+        return Err(Error::msg("no rated contests to draw"));
+    };
+    let min_rating = points().map(|p| p.rating).min().unwrap() - 100;
+    let max_rating = points().map(|p| p.rating).max().unwrap() + 100;
+    let padding = ((last - first) / 20).max(Duration::days(7));
+    let (first, last) = (first - padding, last + padding);
+
+    register_font();
+    let mut buf = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+    draw(&mut buf, histories, first..last, min_rating..max_rating)
+        .map_err(|e| Error::msg(format!("cannot draw the rating graph: {}", e)))?;
+
+    let mut png = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png, WIDTH, HEIGHT);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&buf)?;
+    }
+    Ok(png)
+}
+
+fn draw(
+    buf: &mut [u8],
+    histories: &[RatingHistory],
+    time: std::ops::Range<DateTime<Utc>>,
+    rating: std::ops::Range<i64>,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let root = BitMapBackend::with_buffer(buf, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(15)
+        .x_label_area_size(30)
+        .y_label_area_size(50)
+        .build_cartesian_2d(time.clone(), rating.clone())?;
+
+    // Rank bands
+    for (i, (lower, color)) in RANK_BANDS.iter().enumerate() {
+        let upper = RANK_BANDS.get(i + 1).map(|v| v.0).unwrap_or(i64::MAX);
+        let (lower, upper) = ((*lower).max(rating.start), upper.min(rating.end));
+        if lower >= upper {
+            continue;
+        }
+        chart.draw_series(std::iter::once(Rectangle::new(
+            [(time.start, lower), (time.end, upper)],
+            color.mix(0.5).filled(),
+        )))?;
+    }
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(BLACK.mix(0.15))
+        .label_style((FONT, 14))
+        .x_label_formatter(&|t| t.format("%b %Y").to_string())
+        .draw()?;
+
+    for (history, color) in histories.iter().zip(LINE_COLORS) {
+        let points = history.changes.iter().map(|p| (p.time, p.rating));
+        chart
+            .draw_series(LineSeries::new(points.clone(), color.stroke_width(2)))?
+            .label(&history.handle)
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2)));
+        chart.draw_series(points.map(|p| Circle::new(p, 3, color.filled())))?;
+    }
+    if histories.len() > 1 {
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .label_font((FONT, 14))
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+
+    root.present()?;
+    Ok(())
+}
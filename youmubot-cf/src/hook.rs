@@ -12,6 +12,10 @@ use std::collections::HashMap as StdHashMap;
 use std::time::Instant;
 use youmubot_prelude::*;
 
+use crate::atcoder::{self, AtCoder};
+use crate::cf_api;
+use crate::submissions::{self, Submission};
+
 type Client = <crate::CFClient as TypeMapKey>::Value;
 
 lazy_static! {
@@ -20,7 +24,19 @@ lazy_static! {
     )
     .unwrap();
     static ref PROBLEMSET_LINK: Regex = Regex::new(
-        r"https?://codeforces\.com/problemset/problem/(?P<contest>\d+)/(?P<problem>\w+)"
+        r"https?://codeforces\.com/problemset/(?:gymP|p)roblem/(?P<contest>\d+)/(?P<problem>\w+)"
+    )
+    .unwrap();
+    static ref GROUP_LINK: Regex = Regex::new(
+        r"https?://codeforces\.com/(?:group/\w+|edu/course/\d+/lesson/\d+/\d+/practice)/contest/(?P<contest>\d+)(?:/problem/(?P<problem>\w+))?"
+    )
+    .unwrap();
+    static ref SUBMISSION_LINK: Regex = Regex::new(
+        r"https?://codeforces\.com/(?:(?:group/\w+/)?(?:contest|gym)/(?P<contest>\d+)/submission/|problemset/submission/(?P<problemset>\d+)/)(?P<submission>\d+)"
+    )
+    .unwrap();
+    static ref ATCODER_LINK: Regex = Regex::new(
+        r"https?://atcoder\.jp/contests/(?P<contest>[\w-]+)(?:/tasks/(?P<problem>\w+))?"
     )
     .unwrap();
 }
@@ -28,12 +44,14 @@ lazy_static! {
 enum ContestOrProblem {
     Contest(Contest, Option<Vec<Problem>>),
     Problem(Problem),
+    Submission(Submission),
 }
 
 /// Caches the contest list.
 pub struct ContestCache {
     contests: HashMap<u64, (Contest, Option<Vec<Problem>>)>,
-    all_list: RwLock<(StdHashMap<u64, Contest>, Instant)>,
+    /// The list of all contests and when it was fetched, fetched on first use.
+    all_list: RwLock<(StdHashMap<u64, Contest>, Option<Instant>)>,
     http: Client,
 }
 
@@ -43,19 +61,18 @@ impl TypeMapKey for ContestCache {
 
 impl ContestCache {
     /// Creates a new, empty cache.
-    pub(crate) async fn new(http: Client) -> Result<Self> {
-        let contests_list = Self::fetch_contest_list(http.clone()).await?;
-        Ok(Self {
+    pub(crate) fn new(http: Client) -> Self {
+        Self {
             contests: HashMap::new(),
-            all_list: RwLock::new((contests_list, Instant::now())),
+            all_list: RwLock::new((StdHashMap::new(), None)),
             http,
-        })
+        }
     }
 
     async fn fetch_contest_list(http: Client) -> Result<StdHashMap<u64, Contest>> {
         log::info!("Fetching contest list, this might take a few seconds to complete...");
-        let gyms = Contest::list(&http, true).await?;
-        let contests = Contest::list(&http, false).await?;
+        let gyms = cf_api(Contest::list(&http, true)).await?;
+        let contests = cf_api(Contest::list(&http, false)).await?;
         let r: StdHashMap<u64, Contest> = gyms
             .into_iter()
             .chain(contests.into_iter())
@@ -79,7 +96,11 @@ impl ContestCache {
         &self,
         contest_id: u64,
     ) -> Result<(Contest, Option<Vec<Problem>>)> {
-        let (c, p) = match Contest::standings(&self.http, contest_id, |f| f.limit(1, 1)).await {
+        let (c, p) = match cf_api(Contest::standings(&self.http, contest_id, |f| {
+            f.limit(1, 1)
+        }))
+        .await
+        {
             Ok((c, p, _)) => (c, Some(p)),
             Err(codeforces::Error::Codeforces(s)) if s.ends_with("has not started") => {
                 let c = self.get_from_list(contest_id).await?;
@@ -93,12 +114,12 @@ impl ContestCache {
 
     async fn get_from_list(&self, contest_id: u64) -> Result<Contest> {
         let last_updated = self.all_list.read().await.1;
-        if Instant::now() - last_updated > std::time::Duration::from_secs(60 * 60) {
+        if last_updated.is_none_or(|t| t.elapsed() > std::time::Duration::from_secs(60 * 60)) {
             // We update at most once an hour.
             let mut v = self.all_list.write().await;
             *v = (
                 Self::fetch_contest_list(self.http.clone()).await?,
-                Instant::now(),
+                Some(Instant::now()),
             );
         }
         self.all_list
@@ -122,16 +143,29 @@ impl Hook for InfoHook {
         }
         let data = ctx.data.read().await;
         let contest_cache = data.get::<ContestCache>().unwrap();
-        let matches = parse(&m.content[..], contest_cache)
+        let atcoder = data.get::<AtCoder>().unwrap();
+        let http = data.get::<submissions::Client>().unwrap();
+        let probes = submissions::Probes::new();
+        let matches = parse(&m.content[..], contest_cache, http, &probes)
+            .collect::<Vec<_>>()
+            .await;
+        let ac_matches = parse_atcoder(&m.content[..], atcoder)
             .collect::<Vec<_>>()
             .await;
+        let mut embeds = vec![];
         if !matches.is_empty() {
+            embeds.push(print_info_message(&matches[..]));
+        }
+        if !ac_matches.is_empty() {
+            embeds.push(atcoder::print_info_message(&ac_matches[..]));
+        }
+        if !embeds.is_empty() {
             m.channel_id
                 .send_message(
                     &ctx,
                     CreateMessage::new()
-                        .content("Here are the info of the given Codeforces links!")
-                        .embed(print_info_message(&matches[..])),
+                        .content("Here are the info of the given links!")
+                        .embeds(embeds),
                 )
                 .await?;
         }
@@ -142,35 +176,61 @@ impl Hook for InfoHook {
 fn parse<'a>(
     content: &'a str,
     contest_cache: &'a ContestCache,
+    http: &'a submissions::Client,
+    probes: &'a submissions::Probes,
 ) -> impl stream::Stream<Item = (ContestOrProblem, &'a str)> + 'a {
-    let matches = CONTEST_LINK
+    let submissions = SUBMISSION_LINK.captures_iter(content).collect::<Vec<_>>();
+    // Submission links also contain contest links, which should not be reported separately.
+    let in_submission = submissions
+        .iter()
+        .map(|c| c.get(0).unwrap().range())
+        .collect::<Vec<_>>();
+    let others = CONTEST_LINK
         .captures_iter(content)
         .chain(PROBLEMSET_LINK.captures_iter(content))
-        .map(|v| parse_capture(contest_cache, v))
+        .chain(GROUP_LINK.captures_iter(content))
+        .filter(move |c| {
+            let start = c.get(0).unwrap().start();
+            !in_submission.iter().any(|r| r.contains(&start))
+        });
+    let matches = submissions
+        .into_iter()
+        .chain(others)
+        .map(|v| parse_capture(contest_cache, http, probes, v))
         .collect::<stream::FuturesUnordered<_>>()
         .filter_map(|v| future::ready(v.ok()));
     matches
 }
 
-fn print_info_message(info: &[(ContestOrProblem, &str)]) -> CreateEmbed {
-    let (problems, contests): (Vec<_>, Vec<_>) = info.iter().partition(|(v, _)| match v {
-        ContestOrProblem::Problem(_) => true,
-        ContestOrProblem::Contest(_, _) => false,
-    });
-    let mut problems = problems
-        .into_iter()
-        .map(|(v, l)| match v {
-            ContestOrProblem::Problem(p) => (p, l),
-            _ => unreachable!(),
-        })
-        .collect::<Vec<_>>();
-    let contests = contests
-        .into_iter()
-        .map(|(v, l)| match v {
-            ContestOrProblem::Contest(c, p) => (c, p, l),
-            _ => unreachable!(),
+fn parse_atcoder<'a>(
+    content: &'a str,
+    atcoder: &'a AtCoder,
+) -> impl stream::Stream<Item = (atcoder::LinkInfo, &'a str)> + 'a {
+    ATCODER_LINK
+        .captures_iter(content)
+        .map(move |cap| async move {
+            let contest = cap.name("contest")?.as_str();
+            let problem = cap.name("problem").map(|v| v.as_str());
+            let info = atcoder::resolve_link(atcoder, contest, problem)
+                .await
+                .ok()?;
+            Some((info, cap.get(0).unwrap().as_str()))
         })
-        .collect::<Vec<_>>();
+        .collect::<stream::FuturesUnordered<_>>()
+        .filter_map(future::ready)
+}
+
+fn print_info_message(info: &[(ContestOrProblem, &str)]) -> CreateEmbed {
+    let mut problems = vec![];
+    let mut contests = vec![];
+    let mut submissions = vec![];
+    for (v, l) in info {
+        match v {
+            ContestOrProblem::Problem(p) => problems.push((p, l)),
+            ContestOrProblem::Contest(c, p) => contests.push((c, p, l)),
+            ContestOrProblem::Submission(s) => submissions.push((s, l)),
+        }
+    }
     problems.sort_by(|(a, _), (b, _)| a.rating.unwrap_or(1500).cmp(&b.rating.unwrap_or(1500)));
     let mut m = MessageBuilder::new();
     if !problems.is_empty() {
@@ -236,19 +296,77 @@ fn print_info_message(info: &[(ContestOrProblem, &str)]) -> CreateEmbed {
             m.push_line("");
         }
     }
+
+    if !submissions.is_empty() {
+        m.push_line("").push_bold_line("Submissions").push_line("");
+        for (submission, link) in submissions {
+            print_submission(&mut m, submission, link);
+        }
+    }
     CreateEmbed::new().description(m.build())
 }
 
+fn print_submission(m: &mut MessageBuilder, s: &Submission, link: &str) {
+    let author = s.author.team_name.clone().unwrap_or_else(|| {
+        s.author
+            .members
+            .iter()
+            .map(|m| m.handle.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let verdict = s.verdict();
+    m.push(" - [")
+        .push_bold(format!("#{}", s.id))
+        .push(format!("]({}) by ", link))
+        .push_mono_safe(author)
+        .push(" on ")
+        .push_bold_safe(format!(
+            "[{}{}] {}",
+            s.problem.contest_id.or(s.contest_id).unwrap_or(0),
+            s.problem.index,
+            s.problem.name
+        ))
+        .push(" | ")
+        .push_bold(verdict.to_string());
+    if verdict != submissions::Verdict::Ok && !verdict.is_pending() && s.passed_test_count > 0 {
+        m.push(format!(" on test {}", s.passed_test_count + 1));
+    }
+    m.push(format!(
+        " | **{}** ms | **{}** KB | ",
+        s.time_consumed_millis,
+        s.memory_consumed_bytes / 1024
+    ))
+    .push_safe(&s.programming_language);
+    if let Some(ts) = Utc.timestamp_opt(s.creation_time_seconds, 0).earliest() {
+        m.push(format!(" | {}", ts.format("<t:%s:R>")));
+    }
+    m.push_line("");
+}
+
 #[allow(clippy::needless_lifetimes)] // Doesn't really work
 async fn parse_capture<'a>(
     contest_cache: &ContestCache,
+    http: &submissions::Client,
+    probes: &submissions::Probes,
     cap: Captures<'a>,
 ) -> Result<(ContestOrProblem, &'a str), CommandError> {
     let contest_id: u64 = cap
         .name("contest")
+        .or_else(|| cap.name("problemset"))
         .ok_or_else(|| CommandError::from("Contest not captured"))?
         .as_str()
         .parse()?;
+    if let Some(submission) = cap.name("submission") {
+        let submission =
+            submissions::find_submission(http, probes, contest_id, submission.as_str().parse()?)
+                .await?
+                .ok_or_else(|| CommandError::from("No such submission in contest"))?;
+        return Ok((
+            ContestOrProblem::Submission(submission),
+            cap.get(0).unwrap().as_str(),
+        ));
+    }
     let (contest, problems) = contest_cache.get(contest_id).await?;
     match cap.name("problem") {
         Some(p) => {
@@ -0,0 +1,783 @@
+//! A common interface over the competitive programming judges that we support.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use announcer::MemberToChannels;
+use chrono::{DateTime, TimeZone, Utc};
+use pagination::paginate_from_fn;
+use serenity::{
+    builder::{CreateEmbed, CreateEmbedAuthor, CreateMessage},
+    framework::standard::CommandResult,
+    http::CacheHttp,
+    model::{channel::Message, colour::Colour, id::UserId},
+    utils::MessageBuilder,
+};
+use youmubot_db::DB;
+use youmubot_prelude::{
+    announcer::CacheAndHttp,
+    table_format::{table_formatting, Align},
+    *,
+};
+
+use crate::{
+    cf_api,
+    db::{CfRatingHistory, CfUser, RatingHistory},
+};
+
+/// The public profile of an user on a judge.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub handle: String,
+    pub rating: Option<i64>,
+    pub max_rating: Option<i64>,
+    pub rank: Option<String>,
+    pub max_rank: Option<String>,
+    pub color: Colour,
+    pub avatar: Option<String>,
+    pub url: String,
+}
+
+/// A rating change of an user after a rated contest.
+#[derive(Debug, Clone)]
+pub struct RatingChange {
+    pub contest_id: String,
+    pub contest_name: String,
+    pub contest_url: String,
+    pub rank: u64,
+    pub old_rating: i64,
+    pub new_rating: i64,
+    pub time: DateTime<Utc>,
+}
+
+/// A contest on a judge.
+#[derive(Debug, Clone)]
+pub struct ContestInfo {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub start: Option<DateTime<Utc>>,
+    pub duration: std::time::Duration,
+}
+
+/// A row in the standings of a contest.
+#[derive(Debug, Clone)]
+pub struct StandingsRow {
+    pub handle: String,
+    pub rank: u64,
+    /// The score, if the judge reports one.
+    pub points: Option<f64>,
+}
+
+/// A competitive programming judge.
+///
+/// Every judge is stored in the [TypeMap] under its own type.
+#[async_trait]
+pub trait Judge: TypeMapKey<Value = Self> + Send + Sync + Sized {
+    /// The name of the judge, as shown to users.
+    const NAME: &'static str;
+    /// The saved user type of this judge.
+    type User: SavedUser;
+
+    /// Gets the profile of an user, or `None` if the user does not exist.
+    async fn profile(&self, handle: &str) -> Result<Option<Profile>>;
+
+    /// Gets all rating changes of an user, oldest first.
+    async fn rating_changes(&self, handle: &str) -> Result<Vec<RatingChange>>;
+
+    /// Gets the profile of an user along with all their rating changes, oldest first,
+    /// or `None` if the user does not exist.
+    ///
+    /// Judges that can get both at once should override this.
+    async fn profile_with_changes(
+        &self,
+        handle: &str,
+    ) -> Result<Option<(Profile, Vec<RatingChange>)>> {
+        let Some(profile) = self.profile(handle).await? else {
+            return Ok(None);
+        };
+        let changes = self.rating_changes(&profile.handle).await?;
+        Ok(Some((profile, changes)))
+    }
+
+    /// Gets the list of all contests.
+    async fn contests(&self) -> Result<Vec<ContestInfo>>;
+
+    /// Gets the standings of a contest, restricted to the given handles.
+    async fn standings(
+        &self,
+        contest_id: &str,
+        handles: &[String],
+    ) -> Result<(ContestInfo, Vec<StandingsRow>)>;
+
+    /// Gets a single contest from the contest list.
+    async fn contest(&self, contest_id: &str) -> Result<Option<ContestInfo>> {
+        Ok(self
+            .contests()
+            .await?
+            .into_iter()
+            .find(|c| c.id == contest_id))
+    }
+
+    /// Called with the rating changes fetched by the announcer, for judges that keep
+    /// their own copy of the rating histories.
+    fn store_rating_changes(
+        _data: &TypeMap,
+        _changes: Vec<(Profile, Vec<RatingChange>)>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// An user saved in the database of a judge.
+pub trait SavedUser:
+    Clone + std::fmt::Debug + serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static
+{
+    /// Creates a new saved user from their profile and rating changes.
+    fn new(profile: &Profile, changes: &[RatingChange]) -> Self;
+    fn handle(&self) -> &str;
+    fn rating(&self) -> Option<i64>;
+    fn last_update(&self) -> DateTime<Utc>;
+    fn failures_mut(&mut self) -> &mut u8;
+    /// Updates the user with the new profile and all rating changes,
+    /// returning the rating changes that have not been seen before, oldest first.
+    fn update(&mut self, profile: &Profile, changes: Vec<RatingChange>) -> Vec<RatingChange>;
+}
+
+/// The database of saved users of a judge.
+pub type SavedUsers<J> = DB<HashMap<UserId, <J as Judge>::User>>;
+
+/// Returns the rating changes after the one with the given contest id.
+///
+/// We skip instead of take because sometimes judges perform rollbacks.
+pub(crate) fn changes_since(
+    changes: Vec<RatingChange>,
+    last_contest: Option<&str>,
+) -> Vec<RatingChange> {
+    match last_contest {
+        None => changes,
+        Some(last) => changes
+            .into_iter()
+            .skip_while(|rc| rc.contest_id != last)
+            .skip(1)
+            .collect(),
+    }
+}
+
+/// The Codeforces judge, wrapping the Codeforces client.
+pub struct Codeforces(pub(crate) Arc<codeforces::Client>);
+
+impl TypeMapKey for Codeforces {
+    type Value = Codeforces;
+}
+
+fn from_timestamp(secs: u64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(secs as i64, 0).earliest()
+}
+
+fn cf_profile(user: codeforces::User) -> Profile {
+    use inflector::Inflector;
+    Profile {
+        rating: user.rating,
+        max_rating: user.max_rating,
+        rank: user.rank.as_ref().map(|r| r.to_title_case()),
+        max_rank: user.max_rank.as_ref().map(|r| r.to_title_case()),
+        color: user.color().into(),
+        avatar: Some(user.title_photo.to_string()),
+        url: user.profile_url(),
+        handle: user.handle,
+    }
+}
+
+fn cf_rating_change(rc: codeforces::RatingChange) -> RatingChange {
+    RatingChange {
+        contest_id: rc.contest_id.to_string(),
+        contest_url: format!("https://codeforces.com/contest/{}", rc.contest_id),
+        contest_name: rc.contest_name,
+        rank: rc.rank,
+        old_rating: rc.old_rating,
+        new_rating: rc.new_rating,
+        time: from_timestamp(rc.rating_update_time_seconds).unwrap_or_else(Utc::now),
+    }
+}
+
+impl Codeforces {
+    async fn user(&self, handle: &str) -> Result<Option<codeforces::User>> {
+        match cf_api(codeforces::User::info(&self.0, &[handle])).await {
+            Ok(users) => Ok(users.into_iter().next()),
+            Err(codeforces::Error::Codeforces(s)) if s.contains("not found") => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn cf_contest(c: &codeforces::Contest) -> ContestInfo {
+    ContestInfo {
+        id: c.id.to_string(),
+        name: c.name.clone(),
+        url: c.url(),
+        start: c.start_time_seconds.and_then(from_timestamp),
+        duration: std::time::Duration::from_secs(c.duration_seconds),
+    }
+}
+
+#[async_trait]
+impl Judge for Codeforces {
+    const NAME: &'static str = "Codeforces";
+    type User = CfUser;
+
+    async fn profile(&self, handle: &str) -> Result<Option<Profile>> {
+        Ok(self.user(handle).await?.map(cf_profile))
+    }
+
+    async fn rating_changes(&self, handle: &str) -> Result<Vec<RatingChange>> {
+        Ok(self
+            .profile_with_changes(handle)
+            .await?
+            .ok_or_else(|| Error::msg("user not found"))?
+            .1)
+    }
+
+    async fn profile_with_changes(
+        &self,
+        handle: &str,
+    ) -> Result<Option<(Profile, Vec<RatingChange>)>> {
+        let Some(user) = self.user(handle).await? else {
+            return Ok(None);
+        };
+        let changes = cf_api(user.rating_changes(&self.0))
+            .await?
+            .into_iter()
+            .map(cf_rating_change)
+            .collect();
+        Ok(Some((cf_profile(user), changes)))
+    }
+
+    async fn contests(&self) -> Result<Vec<ContestInfo>> {
+        Ok(cf_api(codeforces::Contest::list(&self.0, false))
+            .await?
+            .iter()
+            .map(cf_contest)
+            .collect())
+    }
+
+    async fn standings(
+        &self,
+        contest_id: &str,
+        handles: &[String],
+    ) -> Result<(ContestInfo, Vec<StandingsRow>)> {
+        let id: u64 = contest_id.parse()?;
+        let (contest, _, rows) = cf_api(codeforces::Contest::standings(&self.0, id, |f| {
+            f.handles(handles.to_vec())
+        }))
+        .await?;
+        let rows = rows
+            .into_iter()
+            .flat_map(|row| {
+                let (rank, points) = (row.rank, row.points);
+                row.party.members.into_iter().map(move |m| StandingsRow {
+                    handle: m.handle,
+                    rank,
+                    points: Some(points),
+                })
+            })
+            .collect();
+        Ok((cf_contest(&contest), rows))
+    }
+
+    /// Keeps the rating histories used by the graphs fresh, since we fetched them anyway,
+    /// and forgets the histories of users that are no longer saved.
+    fn store_rating_changes(
+        data: &TypeMap,
+        changes: Vec<(Profile, Vec<RatingChange>)>,
+    ) -> Result<()> {
+        let saved = crate::graph::saved_handles(data)?;
+        let mut db = CfRatingHistory::open(data);
+        let mut db = db.borrow_mut()?;
+        db.retain(|key, _| saved.contains(key));
+        for (profile, changes) in changes {
+            db.insert(
+                RatingHistory::key(&profile.handle),
+                RatingHistory::from_judge(&profile.handle, &changes),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl SavedUser for CfUser {
+    fn new(profile: &Profile, changes: &[RatingChange]) -> Self {
+        Self {
+            handle: profile.handle.clone(),
+            last_update: Utc::now(),
+            last_contest_id: changes.last().and_then(|rc| rc.contest_id.parse().ok()),
+            rating: profile.rating,
+            failures: 0,
+        }
+    }
+
+    fn handle(&self) -> &str {
+        &self.handle
+    }
+
+    fn rating(&self) -> Option<i64> {
+        self.rating
+    }
+
+    fn last_update(&self) -> DateTime<Utc> {
+        self.last_update
+    }
+
+    fn failures_mut(&mut self) -> &mut u8 {
+        &mut self.failures
+    }
+
+    fn update(&mut self, profile: &Profile, changes: Vec<RatingChange>) -> Vec<RatingChange> {
+        let last = self.last_contest_id.map(|v| v.to_string());
+        let changes = changes_since(changes, last.as_deref());
+        self.last_update = Utc::now();
+        self.rating = profile.rating;
+        if let Some(id) = changes.last().and_then(|rc| rc.contest_id.parse().ok()) {
+            self.last_contest_id = Some(id);
+        }
+        changes
+    }
+}
+
+/// Create an embed representing the user.
+pub fn profile_embed<J: Judge>(profile: &Profile) -> CreateEmbed {
+    let rank = profile.rank.as_deref().unwrap_or("Unranked");
+    let max_rank = profile.max_rank.as_deref().unwrap_or("Unranked");
+    let rating = |v: Option<i64>| {
+        v.map(|v| v.to_string())
+            .unwrap_or_else(|| "unrated".to_owned())
+    };
+    let mut embed = CreateEmbed::new()
+        .color(profile.color)
+        .author(CreateEmbedAuthor::new(format!("{} • {}", J::NAME, rank)))
+        .title(&profile.handle)
+        .url(&profile.url)
+        .field(
+            "Rating",
+            format!(
+                "**{}** (max **{}**)",
+                rating(profile.rating),
+                rating(profile.max_rating)
+            ),
+            true,
+        )
+        .field("Rank", format!("**{}** (max **{}**)", rank, max_rank), true);
+    if let Some(avatar) = &profile.avatar {
+        embed = embed.thumbnail(avatar);
+    }
+    embed
+}
+
+/// Gets an embed of the Rating Change.
+pub fn rating_change_embed<J: Judge>(
+    rating_change: &RatingChange,
+    profile: &Profile,
+    user_id: UserId,
+) -> CreateEmbed {
+    let delta = rating_change.new_rating - rating_change.old_rating;
+    let color = if delta < 0 { 0xff0000 } else { 0x00ff00 };
+    let mut message = MessageBuilder::new();
+    message
+        .push(user_id.mention().to_string())
+        .push(" competed in ")
+        .push_bold_safe(&rating_change.contest_name);
+    if delta > 0 {
+        message
+            .push(", gaining ")
+            .push_bold_safe(delta.to_string())
+            .push(" rating placing at ")
+            .push_bold(format!("#{}", rating_change.rank))
+            .push("! 🎂🎂🎂");
+    } else {
+        message
+            .push(", but lost ")
+            .push_bold_safe((-delta).to_string())
+            .push(" rating placing at ")
+            .push_bold(format!("#{}", rating_change.rank))
+            .push("... 😭😭😭");
+    }
+
+    let mut author =
+        CreateEmbedAuthor::new(format!("{} • {}", profile.handle, J::NAME)).url(&profile.url);
+    if let Some(avatar) = &profile.avatar {
+        author = author.icon_url(avatar);
+    }
+    CreateEmbed::new()
+        .author(author)
+        .color(color)
+        .description(message.build())
+        .timestamp(rating_change.time)
+        .field("Contest Link", &rating_change.contest_url, true)
+        .field(
+            "Rating Change",
+            format!(
+                "from **{}** to **{}**",
+                rating_change.old_rating, rating_change.new_rating
+            ),
+            false,
+        )
+}
+
+/// The number of updates in a row a saved user can fail before they are removed.
+const MAX_FAILURES: u8 = 5;
+
+/// Updates the rating and rating changes of the saved users of a judge.
+pub struct JudgeAnnouncer<J>(PhantomData<J>);
+
+impl<J> JudgeAnnouncer<J> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[async_trait]
+impl<J: Judge> Announcer for JudgeAnnouncer<J> {
+    /// Rating changes are rare, so we don't need to be quick.
+    fn schedule(&self) -> Schedule {
+        Schedule::every(std::time::Duration::from_secs(10 * 60))
+            .with_jitter(std::time::Duration::from_secs(60))
+            .with_backoff(
+                std::time::Duration::from_secs(5 * 60),
+                std::time::Duration::from_secs(60 * 60),
+            )
+    }
+
+    async fn updates(
+        &mut self,
+        http: CacheAndHttp,
+        data: AppData,
+        channels: MemberToChannels,
+    ) -> Result<()> {
+        let data = data.read().await;
+        let judge = data.get::<J>().unwrap();
+        let mut users = SavedUsers::<J>::open(&data).borrow()?.clone();
+        let changes = users
+            .iter_mut()
+            .map(|(user_id, user)| {
+                let http = http.clone();
+                let channels = &channels;
+                async move {
+                    match update_user(http, channels, judge, *user_id, user).await {
+                        Ok(changes) => {
+                            *user.failures_mut() = 0;
+                            Some(changes)
+                        }
+                        Err(e) => {
+                            let failures = user.failures_mut();
+                            *failures = failures.saturating_add(1);
+                            let failures = *failures;
+                            eprintln!(
+                                "{}: cannot update user {}: {} [{} failures]",
+                                J::NAME,
+                                user.handle(),
+                                e,
+                                failures
+                            );
+                            None
+                        }
+                    }
+                }
+            })
+            .collect::<stream::FuturesUnordered<_>>()
+            .filter_map(future::ready)
+            .collect::<Vec<_>>()
+            .await;
+        J::store_rating_changes(&data, changes)?;
+        let mut db = SavedUsers::<J>::open(&data);
+        let mut db = db.borrow_mut()?;
+        for (key, mut user) in users {
+            match db.get(&key).map(|v| v.last_update()) {
+                Some(u) if u > user.last_update() => (),
+                _ if *user.failures_mut() >= MAX_FAILURES => {
+                    eprintln!(
+                        "{}: Removing user {} - {}: failures count too high",
+                        J::NAME,
+                        key,
+                        user.handle(),
+                    );
+                    db.remove(&key);
+                }
+                _ => {
+                    db.insert(key, user);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Updates the user and announces their new rating changes,
+/// returning their profile and all of their rating changes.
+async fn update_user<J: Judge>(
+    http: CacheAndHttp,
+    channels: &MemberToChannels,
+    judge: &J,
+    user_id: UserId,
+    user: &mut J::User,
+) -> Result<(Profile, Vec<RatingChange>)> {
+    let (profile, rating_changes) = judge
+        .profile_with_changes(user.handle())
+        .await?
+        .ok_or_else(|| Error::msg("Not found"))?;
+    let channels_list = channels
+        .channels_of(&http, user_id)
+        .await
+        .into_iter()
+        .map(|c| c.target())
+        .collect::<Vec<_>>();
+    let new_changes = user.update(&profile, rating_changes.clone());
+    if channels_list.is_empty() {
+        return Ok((profile, rating_changes));
+    }
+
+    for rc in new_changes {
+        channels_list
+            .iter()
+            .map(|channel| {
+                channel.send_message(
+                    http.http(),
+                    CreateMessage::new()
+                        .content(format!("Rating change for {}!", user_id.mention()))
+                        .embed(rating_change_embed::<J>(&rc, &profile, user_id)),
+                )
+            })
+            .collect::<stream::FuturesUnordered<_>>()
+            .map(|v| v.map(|_| ()))
+            .try_collect::<()>()
+            .await?;
+    }
+    Ok((profile, rating_changes))
+}
+
+/// Resolves an username argument into a handle of the judge.
+async fn resolve_handle<J: Judge>(
+    ctx: &Context,
+    data: &TypeMap,
+    m: &Message,
+    arg: UsernameArg,
+) -> Result<Option<String>> {
+    match arg {
+        UsernameArg::Raw(s) => Ok(Some(s)),
+        UsernameArg::Tagged(u) => {
+            let handle = SavedUsers::<J>::open(data)
+                .borrow()?
+                .get(&u)
+                .map(|u| u.handle().to_owned());
+            if handle.is_none() {
+                m.reply(&ctx, "no saved account found.").await?;
+            }
+            Ok(handle)
+        }
+    }
+}
+
+/// Shows the profile of an user on the judge.
+pub async fn profile<J: Judge>(ctx: &Context, m: &Message, arg: UsernameArg) -> CommandResult {
+    let data = ctx.data.read().await;
+    let judge = data.get::<J>().unwrap();
+    let Some(handle) = resolve_handle::<J>(ctx, &data, m, arg).await? else {
+        return Ok(());
+    };
+
+    match judge.profile(&handle).await? {
+        Some(profile) => {
+            m.channel_id
+                .send_message(
+                    &ctx,
+                    CreateMessage::new()
+                        .content(format!(
+                            "{}: Here is the user that you requested",
+                            m.author.mention()
+                        ))
+                        .embed(profile_embed::<J>(&profile)),
+                )
+                .await
+        }
+        None => m.reply(&ctx, "User not found").await,
+    }?;
+    Ok(())
+}
+
+/// Links an account on the judge to the author.
+pub async fn save<J: Judge>(ctx: &Context, m: &Message, handle: String) -> CommandResult {
+    let data = ctx.data.read().await;
+    let judge = data.get::<J>().unwrap();
+
+    match judge.profile_with_changes(&handle).await? {
+        None => {
+            m.reply(&ctx, "cannot find an account with such handle")
+                .await?;
+        }
+        Some((profile, rating_changes)) => {
+            let mut db = SavedUsers::<J>::open(&data);
+            m.reply(
+                &ctx,
+                format!(
+                    "{} account `{}` has been linked to your account.",
+                    J::NAME,
+                    &profile.handle
+                ),
+            )
+            .await?;
+            db.borrow_mut()?
+                .insert(m.author.id, J::User::new(&profile, &rating_changes));
+        }
+    }
+    Ok(())
+}
+
+/// Shows the leaderboard of the saved users of the judge in the server.
+pub async fn ranks<J: Judge>(ctx: &Context, m: &Message) -> CommandResult {
+    let data = ctx.data.read().await;
+    let everyone = {
+        let db = SavedUsers::<J>::open(&data);
+        let db = db.borrow()?;
+        db.iter().map(|(k, v)| (*k, v.clone())).collect::<Vec<_>>()
+    };
+    let guild = m.guild_id.expect("Guild-only command");
+    let mut ranks = everyone
+        .into_iter()
+        .map(|(id, user)| guild.member(&ctx, id).map(|mem| mem.map(|mem| (mem, user))))
+        .collect::<stream::FuturesUnordered<_>>()
+        .filter_map(|v| future::ready(v.ok()))
+        .collect::<Vec<_>>()
+        .await;
+    ranks.sort_by(|(_, a), (_, b)| b.rating().unwrap_or(-1).cmp(&a.rating().unwrap_or(-1)));
+
+    if ranks.is_empty() {
+        m.reply(&ctx, "No saved users in this server.").await?;
+        return Ok(());
+    }
+
+    let ranks = Arc::new(ranks);
+
+    const ITEMS_PER_PAGE: usize = 10;
+    let total_pages = ranks.len().div_ceil(ITEMS_PER_PAGE);
+    let last_updated = ranks.iter().map(|(_, u)| u.last_update()).min().unwrap();
+
+    paginate_reply(
+        paginate_from_fn(move |page, btns| {
+            use Align::*;
+            let ranks = ranks.clone();
+            Box::pin(async move {
+                let page = page as usize;
+                let start = ITEMS_PER_PAGE * page;
+                let end = ranks.len().min(start + ITEMS_PER_PAGE);
+                if start >= end {
+                    return Ok(None);
+                }
+                let ranks = &ranks[start..end];
+
+                const HEADERS: [&str; 4] = ["Rank", "Rating", "Handle", "Username"];
+                const ALIGNS: [Align; 4] = [Right, Right, Left, Left];
+
+                let ranks_arr = ranks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (mem, user))| {
+                        [
+                            format!("#{}", 1 + i + start),
+                            user.rating()
+                                .map(|v| v.to_string())
+                                .unwrap_or_else(|| "----".to_owned()),
+                            user.handle().to_owned(),
+                            mem.distinct(),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+
+                let table = table_formatting(&HEADERS, &ALIGNS, ranks_arr);
+
+                let content = MessageBuilder::new()
+                    .push_line(table)
+                    .push_line(format!(
+                        "Page **{}/{}**. Last updated **{}**",
+                        page + 1,
+                        total_pages,
+                        last_updated.to_rfc2822()
+                    ))
+                    .build();
+
+                Ok(Some(
+                    CreateReply::default().content(content).components(btns),
+                ))
+            })
+        })
+        .with_page_count(total_pages),
+        ctx,
+        m,
+        std::time::Duration::from_secs(60),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Shows the standings of the saved users of the judge in the server for a contest.
+///
+/// Only the top [MAX_ROWS] are shown, to fit in a message.
+pub async fn contest_ranks<J: Judge>(
+    ctx: &Context,
+    m: &Message,
+    contest_id: String,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    let judge = data.get::<J>().unwrap();
+    let guild = m.guild_id.unwrap(); // Guild-only command
+    let members = SavedUsers::<J>::open(&data)
+        .borrow()?
+        .iter()
+        .map(|(k, v)| (*k, v.handle().to_owned()))
+        .collect::<Vec<_>>();
+    let members = members
+        .into_iter()
+        .map(|(id, handle)| async move {
+            let member = guild.member(&ctx, id).await.ok()?;
+            Some((handle.to_lowercase(), member))
+        })
+        .collect::<stream::FuturesUnordered<_>>()
+        .filter_map(future::ready)
+        .collect::<HashMap<_, _>>()
+        .await;
+    if members.is_empty() {
+        m.reply(&ctx, "No saved users in this server.").await?;
+        return Ok(());
+    }
+    let handles = members.keys().cloned().collect::<Vec<_>>();
+    let (contest, rows) = judge.standings(&contest_id, &handles).await?;
+    let rows = rows
+        .into_iter()
+        .take(MAX_ROWS)
+        .filter_map(|row| {
+            let member = members.get(&row.handle.to_lowercase())?;
+            Some([
+                format!("#{}", row.rank),
+                row.handle,
+                member.distinct(),
+                row.points.map(|p| p.to_string()).unwrap_or_default(),
+            ])
+        })
+        .collect::<Vec<_>>();
+    if rows.is_empty() {
+        m.reply(&ctx, "No one in this server participated in the contest...")
+            .await?;
+        return Ok(());
+    }
+
+    use Align::*;
+    const MAX_ROWS: usize = 20;
+    const HEADERS: [&str; 4] = ["Rank", "Handle", "User", "Score"];
+    const ALIGNS: [Align; 4] = [Right, Left, Left, Right];
+    let content = MessageBuilder::new()
+        .push_bold_safe(&contest.name)
+        .push(" ")
+        .push_line(&contest.url)
+        .push_line(table_formatting(&HEADERS, &ALIGNS, rows))
+        .build();
+    m.reply(&ctx, content).await?;
+    Ok(())
+}
@@ -1,9 +1,13 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use codeforces::Contest;
 use pagination::paginate_from_fn;
 use serenity::{
-    builder::CreateMessage,
+    builder::{CreateAttachment, CreateMessage},
     framework::standard::{
         macros::{command, group},
         Args, CommandResult,
@@ -12,23 +16,27 @@ use serenity::{
     utils::MessageBuilder,
 };
 
-use db::{CfSavedUsers, CfUser};
+pub use atcoder::ATCODERCOMMANDS_GROUP as ATCODER_GROUP;
+use db::{AtCoderSavedUsers, CfRatingHistory, CfSavedUsers};
 pub use hook::InfoHook;
 use youmubot_prelude::announcer::AnnouncerHandler;
 use youmubot_prelude::table_format::table_formatting_unsafe;
 use youmubot_prelude::table_format::Align::{Left, Right};
-use youmubot_prelude::{
-    table_format::{table_formatting, Align},
-    *,
-};
+use youmubot_prelude::{table_format::Align, *};
 
-mod announcer;
+mod atcoder;
 mod db;
 mod embed;
+mod graph;
 mod hook;
+mod judge;
+mod submissions;
+mod virtual_contest;
 
 /// Live-commentating a Codeforces round.
 mod live;
+/// Predicting rating changes from the current standings.
+mod predict;
 
 /// The TypeMapKey holding the Client.
 struct CFClient;
@@ -37,21 +45,50 @@ impl TypeMapKey for CFClient {
     type Value = Arc<codeforces::Client>;
 }
 
+/// Runs a request to the Codeforces API, counting it in the metrics.
+pub(crate) async fn cf_api<T>(
+    req: impl std::future::Future<Output = codeforces::Result<T>>,
+) -> codeforces::Result<T> {
+    metrics::api_request("codeforces");
+    let r = req.await;
+    if r.is_err() {
+        metrics::api_failure("codeforces");
+    }
+    r
+}
+
 /// Sets up the CF databases.
-pub async fn setup(path: &std::path::Path, data: &mut TypeMap, announcers: &mut AnnouncerHandler) {
+pub async fn setup(config: &Config, data: &mut TypeMap, announcers: &mut AnnouncerHandler) {
+    let path = &config.paths.db;
     CfSavedUsers::insert_into(data, path.join("cf_saved_users.yaml"))
         .expect("Must be able to set up DB");
+    CfRatingHistory::insert_into(data, path.join("cf_rating_history.yaml"))
+        .expect("Must be able to set up DB");
+    AtCoderSavedUsers::insert_into(data, path.join("atcoder_saved_users.yaml"))
+        .expect("Must be able to set up DB");
     let client = Arc::new(codeforces::Client::new());
-    data.insert::<hook::ContestCache>(hook::ContestCache::new(client.clone()).await.unwrap());
+    data.insert::<hook::ContestCache>(hook::ContestCache::new(client.clone()));
+    data.insert::<judge::Codeforces>(judge::Codeforces(client.clone()));
     data.insert::<CFClient>(client);
+    let http = data.get::<HTTPClient>().unwrap().clone();
+    data.insert::<submissions::Client>(submissions::Client::new(
+        http.clone(),
+        &config.codeforces.api_url,
+    ));
+    data.insert::<atcoder::AtCoder>(atcoder::AtCoder::new(http));
     data.insert::<live::WatchData>(live::WatchData::new());
-    announcers.add("codeforces", announcer::Announcer);
+    data.insert::<virtual_contest::VirtualContests>(Default::default());
+    announcers.add(
+        "codeforces",
+        judge::JudgeAnnouncer::<judge::Codeforces>::new(),
+    );
+    announcers.add("atcoder", judge::JudgeAnnouncer::<atcoder::AtCoder>::new());
 }
 
 #[group]
 #[prefix = "cf"]
 #[description = "Codeforces-related commands"]
-#[commands(profile, save, ranks, watch, contestranks)]
+#[commands(profile, rating, save, ranks, watch, contestranks, virtual_contest)]
 #[default_command(profile)]
 pub struct Codeforces;
 
@@ -83,23 +120,34 @@ pub async fn profile(ctx: &Context, m: &Message, mut args: Args) -> CommandResul
         }
     };
 
-    let account = codeforces::User::info(http, &[&handle[..]])
+    let account = cf_api(codeforces::User::info(http, &[&handle[..]]))
         .await?
         .into_iter()
         .next();
 
     match account {
         Some(v) => {
-            m.channel_id
-                .send_message(&ctx, {
-                    CreateMessage::new()
-                        .content(format!(
-                            "{}: Here is the user that you requested",
-                            m.author.mention()
-                        ))
-                        .embed(embed::user_embed(&v))
-                })
-                .await
+            let mut embed = embed::user_embed(&v);
+            let mut message = CreateMessage::new().content(format!(
+                "{}: Here is the user that you requested",
+                m.author.mention()
+            ));
+            // The rating graph is only a nice-to-have here.
+            let graph = match graph::rating_history(&data, http, &v).await {
+                Ok(history) if !history.changes.is_empty() => {
+                    graph::rating_graph(vec![history]).await.pls_ok()
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    eprintln!("Cannot get rating history of {}: {}", v.handle, e);
+                    None
+                }
+            };
+            if let Some(graph) = graph {
+                embed = embed.image("attachment://rating.png");
+                message = message.add_file(CreateAttachment::bytes(graph, "rating.png"));
+            }
+            m.channel_id.send_message(&ctx, message.embed(embed)).await
         }
         None => m.reply(&ctx, "User not found").await,
     }?;
@@ -108,133 +156,127 @@ pub async fn profile(ctx: &Context, m: &Message, mut args: Args) -> CommandResul
 }
 
 #[command]
-#[description = "Link your Codeforces account to the Discord account, to enjoy Youmu's tracking capabilities."]
-#[usage = "[handle]"]
-#[num_args(1)]
-pub async fn save(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+#[aliases("graph", "history")]
+#[description = "Show the rating history of users. Give multiple handles or tags to compare them."]
+#[usage = "[handles or tags = yourself]..."]
+#[example = "natsukagami / tourist"]
+#[max_args(5)]
+pub async fn rating(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let data = ctx.data.read().await;
-    let handle = args.single::<String>()?;
     let http = data.get::<CFClient>().unwrap();
+    let mut targets = args
+        .iter::<UsernameArg>()
+        .map(|v| v.map_err(|e| Error::msg(e.to_string())))
+        .collect::<Result<Vec<_>>>()?;
+    if targets.is_empty() {
+        targets.push(UsernameArg::mention(m.author.id));
+    }
 
-    let account = codeforces::User::info(http, &[&handle[..]])
-        .await?
-        .into_iter()
-        .next();
+    let handles = {
+        let db = CfSavedUsers::open(&data);
+        let db = db.borrow()?;
+        targets
+            .into_iter()
+            .map(|target| match target {
+                UsernameArg::Raw(s) => Ok(s),
+                UsernameArg::Tagged(u) => db.get(&u).map(|cfu| cfu.handle.clone()).ok_or(u),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()
+    };
+    let mut handles = match handles {
+        Ok(v) => v,
+        Err(u) => {
+            m.reply(&ctx, format!("no saved account found for {}.", u.mention()))
+                .await?;
+            return Ok(());
+        }
+    };
+    // Handles are case-insensitive, keep the first spelling given.
+    let mut seen = HashSet::new();
+    handles.retain(|h| seen.insert(h.to_lowercase()));
+    if handles.len() > graph::MAX_USERS {
+        m.reply(
+            &ctx,
+            format!(
+                "at most {} users can be compared at once.",
+                graph::MAX_USERS
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
 
-    match account {
-        None => {
-            m.reply(&ctx, "cannot find an account with such handle")
+    let handles_ref = handles.iter().map(|h| h.as_str()).collect::<Vec<_>>();
+    let users = match cf_api(codeforces::User::info(http, &handles_ref)).await {
+        Ok(users) => users,
+        Err(e) => {
+            m.reply(&ctx, format!("cannot find the requested users: {}", e))
                 .await?;
+            return Ok(());
         }
-        Some(acc) => {
-            // Collect rating changes data.
-            let rating_changes = acc.rating_changes(http).await?;
-            let mut db = CfSavedUsers::open(&data);
-            m.reply(
-                &ctx,
-                format!("account `{}` has been linked to your account.", &acc.handle),
-            )
+    };
+    let mut histories = Vec::with_capacity(users.len());
+    for user in &users {
+        histories.push(graph::rating_history(&data, http, user).await?);
+    }
+    if histories.iter().all(|h| h.changes.is_empty()) {
+        m.reply(&ctx, "no rated contests found for the requested users.")
             .await?;
-            db.borrow_mut()?
-                .insert(m.author.id, CfUser::save(acc, rating_changes));
-        }
+        return Ok(());
     }
 
+    let graph = graph::rating_graph(histories.clone()).await?;
+    let summary = histories
+        .iter()
+        .map(|h| {
+            let rating = h
+                .changes
+                .last()
+                .map(|p| p.rating.to_string())
+                .unwrap_or_else(|| "unrated".to_owned());
+            MessageBuilder::new()
+                .push_mono_safe(&h.handle)
+                .push(format!(
+                    ": **{}** after **{}** contests",
+                    rating,
+                    h.changes.len()
+                ))
+                .build()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    m.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .content(format!(
+                    "{}: Here is the rating history that you requested\n{}",
+                    m.author.mention(),
+                    summary
+                ))
+                .add_file(CreateAttachment::bytes(graph, "rating.png"))
+                .reference_message(m),
+        )
+        .await?;
+
     Ok(())
 }
 
+#[command]
+#[description = "Link your Codeforces account to the Discord account, to enjoy Youmu's tracking capabilities."]
+#[usage = "[handle]"]
+#[num_args(1)]
+pub async fn save(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let handle = args.single::<String>()?;
+    judge::save::<judge::Codeforces>(ctx, m, handle).await
+}
+
 #[command]
 #[description = "See the leaderboard of all people in the server."]
 #[only_in(guilds)]
 #[num_args(0)]
 pub async fn ranks(ctx: &Context, m: &Message) -> CommandResult {
-    let data = ctx.data.read().await;
-    let everyone = {
-        let db = CfSavedUsers::open(&data);
-        let db = db.borrow()?;
-        db.iter().map(|(k, v)| (*k, v.clone())).collect::<Vec<_>>()
-    };
-    let guild = m.guild_id.expect("Guild-only command");
-    let mut ranks = everyone
-        .into_iter()
-        .map(|(id, cf_user)| {
-            guild
-                .member(&ctx, id)
-                .map(|mem| mem.map(|mem| (mem, cf_user)))
-        })
-        .collect::<stream::FuturesUnordered<_>>()
-        .filter_map(|v| future::ready(v.ok()))
-        .collect::<Vec<_>>()
-        .await;
-    ranks.sort_by(|(_, a), (_, b)| b.rating.unwrap_or(-1).cmp(&a.rating.unwrap_or(-1)));
-
-    if ranks.is_empty() {
-        m.reply(&ctx, "No saved users in this server.").await?;
-        return Ok(());
-    }
-
-    let ranks = Arc::new(ranks);
-
-    const ITEMS_PER_PAGE: usize = 10;
-    let total_pages = ranks.len().div_ceil(ITEMS_PER_PAGE);
-    let last_updated = ranks.iter().map(|(_, cfu)| cfu.last_update).min().unwrap();
-
-    paginate_reply(
-        paginate_from_fn(move |page, btns| {
-            use Align::*;
-            let ranks = ranks.clone();
-            Box::pin(async move {
-                let page = page as usize;
-                let start = ITEMS_PER_PAGE * page;
-                let end = ranks.len().min(start + ITEMS_PER_PAGE);
-                if start >= end {
-                    return Ok(None);
-                }
-                let ranks = &ranks[start..end];
-
-                const HEADERS: [&str; 4] = ["Rank", "Rating", "Handle", "Username"];
-                const ALIGNS: [Align; 4] = [Right, Right, Left, Left];
-
-                let ranks_arr = ranks
-                    .iter()
-                    .enumerate()
-                    .map(|(i, (mem, cfu))| {
-                        [
-                            format!("#{}", 1 + i + start),
-                            cfu.rating
-                                .map(|v| v.to_string())
-                                .unwrap_or_else(|| "----".to_owned()),
-                            cfu.handle.clone(),
-                            mem.distinct(),
-                        ]
-                    })
-                    .collect::<Vec<_>>();
-
-                let table = table_formatting(&HEADERS, &ALIGNS, ranks_arr);
-
-                let content = MessageBuilder::new()
-                    .push_line(table)
-                    .push_line(format!(
-                        "Page **{}/{}**. Last updated **{}**",
-                        page + 1,
-                        total_pages,
-                        last_updated.to_rfc2822()
-                    ))
-                    .build();
-
-                Ok(Some(
-                    CreateReply::default().content(content).components(btns),
-                ))
-            })
-        })
-        .with_page_count(total_pages),
-        ctx,
-        m,
-        std::time::Duration::from_secs(60),
-    )
-    .await?;
-
-    Ok(())
+    judge::ranks::<judge::Codeforces>(ctx, m).await
 }
 
 #[command]
@@ -260,9 +302,9 @@ pub async fn contestranks(ctx: &Context, m: &Message, mut args: Args) -> Command
         .collect::<HashMap<_, _>>()
         .await;
     let http = data.get::<CFClient>().unwrap();
-    let (contest, problems, ranks) = Contest::standings(http, contest_id, |f| {
+    let (contest, problems, ranks) = cf_api(Contest::standings(http, contest_id, |f| {
         f.handles(members.keys().cloned().collect())
-    })
+    }))
     .await?;
 
     // Table me
@@ -281,7 +323,7 @@ pub async fn contestranks(ctx: &Context, m: &Message, mut args: Args) -> Command
                 .collect::<Vec<_>>()
         })
         .collect::<Vec<_>>();
-    contest_rank_table(ctx, m, contest, problems, ranks).await?;
+    contest_rank_table(ctx, m, contest, problems, ranks, None).await?;
 
     Ok(())
 }
@@ -300,12 +342,80 @@ pub async fn watch(ctx: &Context, m: &Message, mut args: Args) -> CommandResult
     Ok(())
 }
 
+#[command("virtual")]
+#[aliases("vc", "mashup")]
+#[description = "Start a virtual contest from a contest or a list of problems, with ICPC-style scoring. Submissions of the participants are tracked and a live standings message is kept updated."]
+#[usage = "[contest id or space-separated problem list] / [duration = contest duration] / [space-separated participants = everyone in the server]"]
+#[example = "1900 / 2h"]
+#[example = "1900A 1901B / 1h / @alice @bob"]
+#[min_args(1)]
+#[required_permissions(MANAGE_CHANNELS)]
+#[only_in(guilds)]
+pub async fn virtual_contest(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let problems = args.single::<String>()?;
+    let duration = args.single::<youmubot_prelude::Duration>().ok();
+    // Mentions are usually separated by spaces, which do not split arguments.
+    let participants = args
+        .rest()
+        .split(|c: char| c == '/' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<UserId>().map(|v| v.0))
+        .collect::<Result<Vec<_>, _>>()?;
+    let participants = if participants.is_empty() {
+        None
+    } else {
+        Some(participants)
+    };
+
+    let problem_set = {
+        let data = ctx.data.read().await;
+        let contest_cache = data.get::<hook::ContestCache>().unwrap();
+        match virtual_contest::ProblemSet::parse(contest_cache, &problems).await {
+            Ok(v) => v,
+            Err(e) => {
+                m.reply(&ctx, format!("Cannot set up the problem set: {}", e))
+                    .await?;
+                return Ok(());
+            }
+        }
+    };
+    let duration = match duration.map(|v| v.0).or(problem_set.duration) {
+        Some(v) => v,
+        None => {
+            m.reply(&ctx, "Please specify the duration of the virtual contest.")
+                .await?;
+            return Ok(());
+        }
+    };
+    if duration < Duration::from_secs(60 * 5) || duration > Duration::from_secs(60 * 60 * 24) {
+        m.reply(
+            &ctx,
+            "The virtual contest should last between **5 minutes** and **1 day**.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    virtual_contest::run_virtual_contest(
+        ctx,
+        m.guild_id.unwrap(),
+        m.channel_id,
+        problem_set,
+        duration,
+        participants,
+    )
+    .await?;
+
+    Ok(())
+}
+
 pub(crate) async fn contest_rank_table(
     ctx: &Context,
     reply_to: &Message,
     contest: Contest,
     problems: Vec<codeforces::Problem>,
     ranks: Vec<(Member, String, codeforces::RanklistRow)>,
+    predictions: Option<HashMap<String, i64>>,
 ) -> Result<()> {
     const ITEMS_PER_PAGE: usize = 10;
     let total_pages = ranks.len().div_ceil(ITEMS_PER_PAGE);
@@ -317,12 +427,14 @@ pub(crate) async fn contest_rank_table(
         return Ok(());
     }
     let ranks = Arc::new(ranks);
+    let predictions = predictions.map(Arc::new);
 
     paginate_reply(
         paginate_from_fn(move |page, btns| {
             let contest = contest.clone();
             let problems = problems.clone();
             let ranks = ranks.clone();
+            let predictions = predictions.clone();
             Box::pin(async move {
                 let page = page as usize;
                 let start = page * ITEMS_PER_PAGE;
@@ -332,8 +444,11 @@ pub(crate) async fn contest_rank_table(
                 }
                 let ranks = &ranks[start..end];
 
+                // The predicted rating changes column, if available.
+                let delta_header = predictions.as_ref().map(|_| "Δ");
                 let score_headers: Vec<&str> = [
                     vec!["Rank", "Handle", "User", "Total", "Hacks"],
+                    delta_header.into_iter().collect(),
                     problems
                         .iter()
                         .map(|p| p.index.as_str())
@@ -343,6 +458,7 @@ pub(crate) async fn contest_rank_table(
 
                 let score_aligns: Vec<Align> = [
                     vec![Right, Left, Left, Right, Right],
+                    delta_header.map(|_| Right).into_iter().collect(),
                     problems.iter().map(|_| Right).collect::<Vec<Align>>(),
                 ]
                 .concat();
@@ -363,6 +479,12 @@ pub(crate) async fn contest_rank_table(
                             }
                         }
 
+                        let delta = predictions.as_ref().map(|p| {
+                            p.get(handle)
+                                .map(|d| format!("{:+}", d))
+                                .unwrap_or_else(|| "?".to_owned())
+                        });
+
                         [
                             vec![
                                 format!("{}", row.rank),
@@ -374,6 +496,7 @@ pub(crate) async fn contest_rank_table(
                                     row.successful_hack_count, row.unsuccessful_hack_count
                                 ),
                             ],
+                            delta.into_iter().collect(),
                             p_results,
                         ]
                         .concat()
@@ -1,4 +1,4 @@
-use crate::{db::CfSavedUsers, hook::ContestCache, CFClient};
+use crate::{cf_api, db::CfSavedUsers, hook::ContestCache, predict::RatingPredictor, CFClient};
 use chrono::TimeZone;
 use codeforces::{Contest, ContestPhase, Problem, ProblemResult, ProblemResultType, RanklistRow};
 use serenity::{
@@ -11,12 +11,16 @@ use serenity::{
 };
 use std::collections::HashSet as Set;
 use std::{collections::HashMap, sync::Arc, sync::Mutex as SyncMutex};
-use youmubot_prelude::*;
+use youmubot_prelude::{shutdown::Shutdown, *};
+
+/// Rating changes are predicted once every this many scans.
+const PREDICTION_INTERVAL: usize = 5;
 
 struct MemberResult {
     member: Member,
     handle: String,
     row: Option<RanklistRow>,
+    predicted_delta: Option<i64>,
 }
 
 /// The structure storing watch-specific stored data.
@@ -58,7 +62,7 @@ impl WatchData {
 
 /// Watch and commentate a contest.
 ///
-/// Does the thing on a channel, block until the contest ends.
+/// Does the thing on a channel, block until the contest ends or the bot shuts down.
 pub async fn watch_contest(
     ctx: &Context,
     guild: GuildId,
@@ -68,6 +72,7 @@ pub async fn watch_contest(
     let data = ctx.data.read().await;
     let db = CfSavedUsers::open(&data).borrow()?.clone();
     let member_cache = data.get::<member_cache::MemberCache>().unwrap().clone();
+    let shutdown = data.get::<Shutdown>().cloned().unwrap_or_default();
 
     let watch_data = data.get::<WatchData>().unwrap().clone();
     let _lock = match watch_data.watch(contest_id) {
@@ -122,12 +127,16 @@ pub async fn watch_contest(
                     CreateMessage::new().content(format!("Contest **{}** found, but has not started yet. Youmu will start watching as soon as it begins! (which is in about {})", contest.name, start_time.format("<t:%s:R>")))
                 )
                 .await?;
-        tokio::time::sleep(
-            (start_time - chrono::Utc::now() + chrono::Duration::seconds(30))
-                .to_std()
-                .unwrap(),
-        )
-        .await
+        let wait = (start_time - chrono::Utc::now() + chrono::Duration::seconds(30))
+            .to_std()
+            .unwrap();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => (),
+            _ = shutdown.started() => {
+                stop_watching(ctx, channel, &contest).await;
+                return Ok(());
+            }
+        }
     }
 
     let mut msg = channel
@@ -139,7 +148,7 @@ pub async fn watch_contest(
 
     let http = data.get::<CFClient>().unwrap();
     let (mut contest, problems, _) =
-        Contest::standings(http, contest_id, |f| f.limit(1, 1)).await?;
+        cf_api(Contest::standings(http, contest_id, |f| f.limit(1, 1))).await?;
     // Collect an initial member list.
     // This never changes during the scan.
     let mut member_results: HashMap<UserId, MemberResult> = db
@@ -154,6 +163,7 @@ pub async fn watch_contest(
                             member: m,
                             handle: cfu.handle,
                             row: None,
+                            predicted_delta: None,
                         },
                     )
                 })
@@ -166,26 +176,28 @@ pub async fn watch_contest(
 
     msg.edit(
         &ctx,
-        EditMessage::new().content(format!(
-            "Youmu is watching contest **{}**, with the following members: {}",
-            contest.name,
-            member_results
-                .values()
-                .map(|m| serenity::utils::MessageBuilder::new()
-                    .push_safe(m.member.distinct())
-                    .push(" (")
-                    .push_mono_safe(&m.handle)
-                    .push(")")
-                    .build())
-                .collect::<Vec<_>>()
-                .join(", "),
-        )),
+        EditMessage::new().content(watching_message(&contest, &member_results)),
     )
     .await?;
     msg.pin(ctx).await.ok();
 
-    loop {
-        if let Ok(messages) = scan_changes(http, &mut member_results, &mut contest).await {
+    let mut predictor = RatingPredictor::new(contest_id);
+    let mut predictions = None;
+    for scan in 0.. {
+        if scan % PREDICTION_INTERVAL == 0 && contest.phase != ContestPhase::Before {
+            match predictor.predict(http).await {
+                Ok(p) => predictions = Some(p),
+                Err(e) => eprintln!("Cannot predict rating changes of {}: {}", contest_id, e),
+            }
+        }
+        if let Ok(messages) = scan_changes(
+            http,
+            &mut member_results,
+            &mut contest,
+            predictions.as_ref(),
+        )
+        .await
+        {
             for message in messages {
                 channel
                     .send_message(
@@ -196,14 +208,28 @@ pub async fn watch_contest(
                     .ok();
             }
         }
+        msg.edit(
+            &ctx,
+            EditMessage::new().content(watching_message(&contest, &member_results)),
+        )
+        .await
+        .pls_ok();
         if contest.phase == ContestPhase::Finished {
             break;
         }
         // Sleep for a minute
-        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_secs(60)) => (),
+            _ = shutdown.started() => {
+                msg.unpin(ctx).await.ok();
+                stop_watching(ctx, channel, &contest).await;
+                return Ok(());
+            }
+        }
     }
 
-    // Announce the final results
+    // Announce the final results, with the most up-to-date predictions.
+    let predictions = predictor.predict(http).await.pls_ok().or(predictions);
     let mut ranks = member_results
         .into_iter()
         .filter_map(|(_, m)| {
@@ -215,10 +241,51 @@ pub async fn watch_contest(
     ranks.sort_by(|(_, _, a), (_, _, b)| a.rank.cmp(&b.rank));
 
     msg.unpin(ctx).await.ok();
-    crate::contest_rank_table(ctx, &msg, contest, problems, ranks).await?;
+    crate::contest_rank_table(ctx, &msg, contest, problems, ranks, predictions).await?;
     Ok(())
 }
 
+async fn stop_watching(ctx: &Context, channel: ChannelId, contest: &Contest) {
+    channel
+        .send_message(
+            ctx,
+            CreateMessage::new().content(format!(
+                "**{}**: Youmu is restarting, and stopped watching the contest.",
+                contest.name
+            )),
+        )
+        .await
+        .pls_ok();
+}
+
+fn format_delta(delta: i64) -> String {
+    format!("{:+}", delta)
+}
+
+fn watching_message(contest: &Contest, members: &HashMap<UserId, MemberResult>) -> String {
+    format!(
+        "Youmu is watching contest **{}**, with the following members: {}",
+        contest.name,
+        members
+            .values()
+            .map(|m| {
+                let mut b = MessageBuilder::new();
+                b.push_safe(m.member.distinct())
+                    .push(" (")
+                    .push_mono_safe(&m.handle);
+                if let Some(row) = &m.row {
+                    b.push(format!(", #{}", row.rank));
+                }
+                if let Some(delta) = m.predicted_delta {
+                    b.push(format!(", predicted **{}**", format_delta(delta)));
+                }
+                b.push(")").build()
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
 fn mention(phase: ContestPhase, m: &Member) -> String {
     match phase {
         ContestPhase::Before | ContestPhase::Coding =>
@@ -234,6 +301,7 @@ async fn scan_changes(
     http: &codeforces::Client,
     members: &mut HashMap<UserId, MemberResult>,
     contest: &mut Contest,
+    predictions: Option<&HashMap<String, i64>>,
 ) -> Result<Vec<String>> {
     let mut messages: Vec<String> = vec![];
     let (updated_contest, problems, ranks) = {
@@ -241,7 +309,7 @@ async fn scan_changes(
             .iter()
             .map(|(_, h)| h.handle.clone())
             .collect::<Vec<_>>();
-        Contest::standings(http, contest.id, |f| f.handles(handles)).await?
+        cf_api(Contest::standings(http, contest.id, |f| f.handles(handles))).await?
     };
     // Change of phase.
     if contest.phase != updated_contest.phase {
@@ -286,6 +354,8 @@ async fn scan_changes(
                 &old_row,
                 &row,
                 &member_result.member,
+                member_result.predicted_delta,
+                predictions.and_then(|p| p.get(&member_result.handle).copied()),
             ));
             for (problem, (old, new)) in problems.iter().zip(
                 old_row
@@ -312,6 +382,9 @@ async fn scan_changes(
         for handle in row.party.members.iter().map(|v| v.handle.as_str()) {
             if let Some(u) = handle_to_user_id.get_mut(handle) {
                 u.row = Some(row.clone());
+                if let Some(delta) = predictions.and_then(|p| p.get(handle)) {
+                    u.predicted_delta = Some(*delta);
+                }
             }
         }
     }
@@ -328,6 +401,8 @@ fn translate_overall_result(
     old_row: &RanklistRow,
     new_row: &RanklistRow,
     member: &Member,
+    old_delta: Option<i64>,
+    new_delta: Option<i64>,
 ) -> Vec<String> {
     let mention = || -> MessageBuilder {
         let mut m = MessageBuilder::new();
@@ -361,6 +436,29 @@ fn translate_overall_result(
         );
     }
 
+    // Predicted rating changes going from gain to loss or vice versa
+    if let (Some(old), Some(new)) = (old_delta, new_delta) {
+        if old <= 0 && new > 0 {
+            res.push(
+                mention()
+                    .push(format!(
+                        " is now predicted to **gain** rating: **{}** 📈",
+                        format_delta(new)
+                    ))
+                    .build(),
+            );
+        } else if old >= 0 && new < 0 {
+            res.push(
+                mention()
+                    .push(format!(
+                        " is now predicted to **lose** rating: **{}** 📉",
+                        format_delta(new)
+                    ))
+                    .build(),
+            );
+        }
+    }
+
     res
 }
 
@@ -0,0 +1,208 @@
+use crate::cf_api;
+use codeforces::{Contest, RanklistRow, User};
+use std::collections::HashMap;
+use youmubot_prelude::*;
+
+type Client = <crate::CFClient as TypeMapKey>::Value;
+
+/// The rating that unrated contestants are assumed to have.
+const DEFAULT_RATING: i64 = 1400;
+/// Ratings are clamped within `[0, MAX_RATING)` for the calculation.
+const MAX_RATING: i64 = 8000;
+/// How many handles to query at once from `user.info`.
+const HANDLES_PER_REQUEST: usize = 300;
+
+/// A contestant in the rating calculation.
+#[derive(Debug, Clone)]
+pub(crate) struct Contestant {
+    pub handle: String,
+    /// The rank of the contestant, with ties resolved to the lowest position.
+    pub rank: u64,
+    pub rating: i64,
+}
+
+/// Lookup table of `P(a beats b)` by `a - b`.
+struct EloTable(Vec<f64>);
+
+impl EloTable {
+    fn new() -> Self {
+        Self(
+            (-MAX_RATING..=MAX_RATING)
+                .map(|diff| 1.0 / (1.0 + 10f64.powf(-diff as f64 / 400.0)))
+                .collect(),
+        )
+    }
+
+    /// The probability that a contestant with rating `a` beats one with rating `b`.
+    fn win_probability(&self, a: i64, b: i64) -> f64 {
+        self.0[(a - b + MAX_RATING) as usize]
+    }
+}
+
+/// Predict the rating changes of all contestants, using the Codeforces rating formula.
+///
+/// Returns a map from handle to the predicted rating delta.
+pub(crate) fn predict(contestants: &[Contestant]) -> HashMap<String, i64> {
+    let n = contestants.len();
+    if n == 0 {
+        return HashMap::new();
+    }
+    let elo = EloTable::new();
+    let clamp = |r: i64| r.clamp(0, MAX_RATING - 1);
+    // Count the contestants with each rating, so seeds can be computed per rating rather than per contestant.
+    let mut counts = vec![0usize; MAX_RATING as usize];
+    for c in contestants {
+        counts[clamp(c.rating) as usize] += 1;
+    }
+    let ratings = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c > 0)
+        .map(|(r, &c)| (r as i64, c as f64))
+        .collect::<Vec<_>>();
+    // seeds[r] = 1 + sum over all contestants of P(contestant beats someone rated r)
+    let seeds = (0..MAX_RATING)
+        .map(|r| {
+            1.0 + ratings
+                .iter()
+                .map(|&(other, count)| count * elo.win_probability(other, r))
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+    let rating_to_rank = |rank: f64| -> i64 {
+        let (mut left, mut right) = (1, MAX_RATING);
+        while right - left > 1 {
+            let mid = (left + right) / 2;
+            if seeds[mid as usize] < rank {
+                right = mid;
+            } else {
+                left = mid;
+            }
+        }
+        left
+    };
+
+    let mut deltas = contestants
+        .iter()
+        .map(|c| {
+            let rating = clamp(c.rating);
+            // Exclude the contestant themselves, who "beats" themselves with probability 0.5.
+            let seed = seeds[rating as usize] - 0.5;
+            let mid_rank = (c.rank as f64 * seed).sqrt();
+            let need_rating = rating_to_rank(mid_rank);
+            (c, (need_rating - rating) / 2)
+        })
+        .collect::<Vec<_>>();
+    deltas.sort_by_key(|(c, _)| std::cmp::Reverse(c.rating));
+
+    // The total sum of the deltas should not be more than zero.
+    let sum = deltas.iter().map(|(_, d)| d).sum::<i64>();
+    let inc = -sum / n as i64 - 1;
+    deltas.iter_mut().for_each(|(_, d)| *d += inc);
+
+    // The sum of the deltas of the top contestants should be adjusted to zero.
+    let zero_sum_count = n.min(4 * (n as f64).sqrt().round() as usize);
+    let sum = deltas
+        .iter()
+        .take(zero_sum_count)
+        .map(|(_, d)| d)
+        .sum::<i64>();
+    let inc = (-sum / zero_sum_count as i64).clamp(-10, 0);
+    deltas
+        .into_iter()
+        .map(|(c, d)| (c.handle.clone(), d + inc))
+        .collect()
+}
+
+/// Predicts rating changes of a running contest from its current standings.
+///
+/// Ratings of the contestants are fetched once and remembered, since they do not change during the contest.
+pub(crate) struct RatingPredictor {
+    contest_id: u64,
+    ratings: HashMap<String, i64>,
+}
+
+impl RatingPredictor {
+    pub fn new(contest_id: u64) -> Self {
+        Self {
+            contest_id,
+            ratings: HashMap::new(),
+        }
+    }
+
+    /// Fetch the full standings and predict the rating changes of every contestant.
+    pub async fn predict(&mut self, http: &Client) -> Result<HashMap<String, i64>> {
+        let (_, _, rows) = cf_api(Contest::standings(http, self.contest_id, |f| f)).await?;
+        let rows = rows
+            .into_iter()
+            .filter(|row| row.rank > 0 && row.party.members.len() == 1)
+            .collect::<Vec<_>>();
+
+        let missing = rows
+            .iter()
+            .map(|row| row.party.members[0].handle.as_str())
+            .filter(|h| !self.ratings.contains_key(*h))
+            .collect::<Vec<_>>();
+        for handles in missing.chunks(HANDLES_PER_REQUEST) {
+            for user in cf_api(User::info(http, handles)).await? {
+                self.ratings
+                    .insert(user.handle, user.rating.unwrap_or(DEFAULT_RATING));
+            }
+        }
+
+        let contestants = self.contestants(&rows);
+        Ok(tokio::task::spawn_blocking(move || predict(&contestants)).await?)
+    }
+
+    fn contestants(&self, rows: &[RanklistRow]) -> Vec<Contestant> {
+        // Tied contestants share the lowest position among them.
+        let mut tied = HashMap::<u64, u64>::new();
+        for row in rows {
+            *tied.entry(row.rank).or_default() += 1;
+        }
+        rows.iter()
+            .map(|row| {
+                let handle = row.party.members[0].handle.clone();
+                Contestant {
+                    rank: row.rank + tied[&row.rank] - 1,
+                    rating: self.ratings.get(&handle).copied().unwrap_or(DEFAULT_RATING),
+                    handle,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contestant(handle: &str, rank: u64, rating: i64) -> Contestant {
+        Contestant {
+            handle: handle.to_owned(),
+            rank,
+            rating,
+        }
+    }
+
+    #[test]
+    fn test_equal_ratings() {
+        let deltas = predict(&[
+            contestant("a", 1, 1500),
+            contestant("b", 2, 1500),
+            contestant("c", 3, 1500),
+        ]);
+        assert!(deltas["a"] > 0);
+        assert!(deltas["a"] > deltas["b"]);
+        assert!(deltas["b"] > deltas["c"]);
+        assert!(deltas["c"] < 0);
+        assert!(deltas.values().sum::<i64>() <= 0);
+    }
+
+    #[test]
+    fn test_upset() {
+        let deltas = predict(&[contestant("low", 1, 1200), contestant("high", 2, 2400)]);
+        assert!(deltas["low"] > 100);
+        assert!(deltas["high"] < -100);
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use youmubot_prelude::{ratelimit::Ratelimit, *};
+
+type HttpClient = <HTTPClient as TypeMapKey>::Value;
+
+/// Codeforces allows one API call every two seconds.
+const CALL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+/// How many submissions are fetched at once when looking for a single submission.
+const PAGE_SIZE: u64 = 500;
+/// How many requests we make at most when looking for the submissions linked in a single message.
+const MAX_PROBES: usize = 8;
+/// How long found submissions are cached. Only judged submissions are cached.
+const CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+/// Stale submissions are removed from the cache once it has this many.
+const MAX_CACHED: usize = 1024;
+
+/// The verdict of a submission.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Verdict {
+    Failed,
+    Ok,
+    Partial,
+    CompilationError,
+    RuntimeError,
+    WrongAnswer,
+    PresentationError,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    IdlenessLimitExceeded,
+    SecurityViolated,
+    Crashed,
+    InputPreparationCrashed,
+    Challenged,
+    Skipped,
+    Testing,
+    Rejected,
+    #[serde(other)]
+    Unknown,
+}
+
+impl Verdict {
+    /// Whether the submission is still being judged.
+    pub fn is_pending(self) -> bool {
+        matches!(self, Verdict::Testing)
+    }
+
+    /// Whether the submission counts as a rejected attempt in ICPC-style scoring.
+    pub fn is_rejected_attempt(self) -> bool {
+        !matches!(
+            self,
+            Verdict::Ok
+                | Verdict::Testing
+                | Verdict::CompilationError
+                | Verdict::Skipped
+                | Verdict::Unknown
+        )
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Verdict::Failed => "Failed",
+            Verdict::Ok => "Accepted",
+            Verdict::Partial => "Partial",
+            Verdict::CompilationError => "Compilation error",
+            Verdict::RuntimeError => "Runtime error",
+            Verdict::WrongAnswer => "Wrong answer",
+            Verdict::PresentationError => "Presentation error",
+            Verdict::TimeLimitExceeded => "Time limit exceeded",
+            Verdict::MemoryLimitExceeded => "Memory limit exceeded",
+            Verdict::IdlenessLimitExceeded => "Idleness limit exceeded",
+            Verdict::SecurityViolated => "Security violated",
+            Verdict::Crashed => "Crashed",
+            Verdict::InputPreparationCrashed => "Input preparation crashed",
+            Verdict::Challenged => "Hacked",
+            Verdict::Skipped => "Skipped",
+            Verdict::Testing => "In queue",
+            Verdict::Rejected => "Rejected",
+            Verdict::Unknown => "Unknown",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The problem a submission was made to.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionProblem {
+    pub contest_id: Option<u64>,
+    pub index: String,
+    pub name: String,
+}
+
+/// A member of the party that made a submission.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AuthorMember {
+    pub handle: String,
+}
+
+/// The party that made a submission.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Author {
+    pub members: Vec<AuthorMember>,
+    #[serde(rename = "teamName")]
+    pub team_name: Option<String>,
+}
+
+/// A single Codeforces submission.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub id: u64,
+    pub contest_id: Option<u64>,
+    pub creation_time_seconds: i64,
+    pub problem: SubmissionProblem,
+    pub author: Author,
+    pub programming_language: String,
+    pub verdict: Option<Verdict>,
+    pub passed_test_count: u64,
+    pub time_consumed_millis: u64,
+    pub memory_consumed_bytes: u64,
+}
+
+impl Submission {
+    /// The verdict of the submission, treating missing verdicts as pending.
+    pub fn verdict(&self) -> Verdict {
+        self.verdict.unwrap_or(Verdict::Testing)
+    }
+}
+
+/// The client for the API calls not covered by the `codeforces` crate, rate-limited
+/// to stay within the Codeforces API limits.
+#[derive(Debug, Clone)]
+pub(crate) struct Client {
+    http: Arc<Ratelimit<HttpClient>>,
+    api_url: Arc<str>,
+    found: Arc<DashMap<u64, (Submission, Instant)>>,
+}
+
+impl TypeMapKey for Client {
+    type Value = Client;
+}
+
+impl Client {
+    /// Creates a client calling the API at `api_url`, e.g. `https://codeforces.com/api`.
+    pub fn new(http: HttpClient, api_url: &str) -> Self {
+        Self {
+            http: Arc::new(Ratelimit::new(http, 1, CALL_INTERVAL)),
+            api_url: api_url.trim_end_matches('/').into(),
+            found: Arc::new(DashMap::new()),
+        }
+    }
+
+    fn cached(&self, submission_id: u64) -> Option<Submission> {
+        self.found
+            .get(&submission_id)
+            .filter(|v| v.1.elapsed() < CACHE_TTL)
+            .map(|v| v.0.clone())
+    }
+
+    fn cache(&self, submission: &Submission) {
+        if submission.verdict().is_pending() {
+            return;
+        }
+        if self.found.len() >= MAX_CACHED {
+            self.found.retain(|_, v| v.1.elapsed() < CACHE_TTL);
+            if self.found.len() >= MAX_CACHED {
+                self.found.clear();
+            }
+        }
+        self.found
+            .insert(submission.id, (submission.clone(), Instant::now()));
+    }
+}
+
+/// The requests that can still be made to find the submissions linked in a message.
+pub(crate) struct Probes(AtomicUsize);
+
+impl Probes {
+    pub fn new() -> Self {
+        Self(AtomicUsize::new(MAX_PROBES))
+    }
+
+    fn take(&self) -> Result<()> {
+        self.0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| v.checked_sub(1))
+            .map(|_| ())
+            .map_err(|_| Error::msg("too many requests needed to find the submissions"))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "status")]
+enum Response<T> {
+    #[serde(rename = "OK")]
+    Ok { result: T },
+    #[serde(rename = "FAILED")]
+    Failed { comment: String },
+}
+
+async fn request<T: serde::de::DeserializeOwned>(
+    client: &Client,
+    method: &str,
+    query: &[(&str, String)],
+) -> Result<T> {
+    let http = client.http.borrow().await?;
+    metrics::api_request("codeforces");
+    let resp: Result<T> = async {
+        let resp: Response<T> = http
+            .get(format!("{}/{}", client.api_url, method))
+            .query(query)
+            .send()
+            .await?
+            .json()
+            .await?;
+        match resp {
+            Response::Ok { result } => Ok(result),
+            Response::Failed { comment } => Err(Error::msg(comment)),
+        }
+    }
+    .await;
+    if resp.is_err() {
+        metrics::api_failure("codeforces");
+    }
+    resp
+}
+
+/// Gets the submissions of an user made at or after `since` (as a unix timestamp), most recent first.
+///
+/// The submissions are fetched `count` at a time, until one made before `since` is found.
+pub async fn user_status(
+    client: &Client,
+    handle: &str,
+    since: i64,
+    count: u32,
+) -> Result<Vec<Submission>> {
+    let mut submissions = Vec::new();
+    loop {
+        let page: Vec<Submission> = request(
+            client,
+            "user.status",
+            &[
+                ("handle", handle.to_owned()),
+                ("from", (submissions.len() + 1).to_string()),
+                ("count", count.to_string()),
+            ],
+        )
+        .await?;
+        let done = page.len() < count as usize
+            || page.last().is_none_or(|s| s.creation_time_seconds < since);
+        submissions.extend(
+            page.into_iter()
+                .take_while(|s| s.creation_time_seconds >= since),
+        );
+        if done {
+            return Ok(submissions);
+        }
+    }
+}
+
+async fn contest_status(
+    client: &Client,
+    probes: &Probes,
+    contest_id: u64,
+    from: u64,
+    count: u64,
+) -> Result<Vec<Submission>> {
+    probes.take()?;
+    request(
+        client,
+        "contest.status",
+        &[
+            ("contestId", contest_id.to_string()),
+            ("from", from.to_string()),
+            ("count", count.to_string()),
+        ],
+    )
+    .await
+}
+
+/// Finds a submission of a contest by its id.
+///
+/// There is no API to get a single submission, so we search the contest status,
+/// which lists the submissions from the newest to the oldest.
+/// Every request made is taken from `probes`.
+pub async fn find_submission(
+    client: &Client,
+    probes: &Probes,
+    contest_id: u64,
+    submission_id: u64,
+) -> Result<Option<Submission>> {
+    if let Some(s) = client.cached(submission_id) {
+        return Ok(Some(s));
+    }
+    let find = |page: Vec<Submission>| {
+        let found = page.into_iter().find(|s| s.id == submission_id);
+        if let Some(s) = &found {
+            client.cache(s);
+        }
+        found
+    };
+    // Most links are to recent submissions, which are on the first page.
+    let first = contest_status(client, probes, contest_id, 1, PAGE_SIZE).await?;
+    match first.last() {
+        Some(s) if first.len() as u64 == PAGE_SIZE && s.id > submission_id => (),
+        _ => return Ok(find(first)),
+    }
+    let probe = |from: u64| async move {
+        contest_status(client, probes, contest_id, from, 1)
+            .await
+            .map(|v| v.into_iter().next())
+    };
+    // The submission lies within positions [lo, hi].
+    // Gallop until we find a position that is not newer than the submission.
+    let (mut lo, mut step) = (PAGE_SIZE + 1, PAGE_SIZE);
+    let mut hi = PAGE_SIZE + step;
+    while let Some(s) = probe(hi).await? {
+        if s.id <= submission_id {
+            break;
+        }
+        lo = hi + 1;
+        step *= 2;
+        hi += step;
+    }
+    // Narrow down until the range fits in a page.
+    while hi - lo >= PAGE_SIZE {
+        let mid = lo + (hi - lo) / 2;
+        match probe(mid).await? {
+            Some(s) if s.id > submission_id => lo = mid + 1,
+            _ => hi = mid,
+        }
+    }
+    Ok(find(
+        contest_status(client, probes, contest_id, lo, hi - lo + 1).await?,
+    ))
+}
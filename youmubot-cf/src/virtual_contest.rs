@@ -0,0 +1,556 @@
+use crate::{
+    db::CfSavedUsers,
+    hook::ContestCache,
+    submissions::{self, Submission, Verdict},
+};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serenity::{
+    builder::{CreateMessage, EditMessage},
+    model::{
+        guild::Member,
+        id::{ChannelId, GuildId, UserId},
+    },
+    utils::MessageBuilder,
+};
+use std::collections::{HashMap, HashSet as Set};
+use std::sync::{Arc, Mutex as SyncMutex};
+use youmubot_prelude::table_format::{table_formatting_unsafe, Align};
+use youmubot_prelude::*;
+
+/// Penalty minutes added for every rejected attempt on a solved problem.
+const PENALTY_PER_ATTEMPT: i64 = 20;
+/// How many submissions to fetch per request when polling a participant.
+const SUBMISSIONS_PER_REQUEST: u32 = 50;
+/// How many participants are polled at the same time.
+const CONCURRENT_POLLS: usize = 4;
+
+lazy_static! {
+    static ref PROBLEM_ID: Regex =
+        Regex::new(r"^(?P<contest>\d+)(?P<problem>[A-Za-z]\d*)$").unwrap();
+}
+
+/// A problem in a virtual contest.
+#[derive(Debug, Clone)]
+pub(crate) struct VirtualProblem {
+    pub contest_id: u64,
+    pub index: String,
+    pub name: String,
+}
+
+impl VirtualProblem {
+    fn matches(&self, s: &Submission) -> bool {
+        s.problem.contest_id == Some(self.contest_id) && s.problem.index == self.index
+    }
+}
+
+/// The set of problems a virtual contest is made of.
+pub(crate) struct ProblemSet {
+    pub title: String,
+    pub problems: Vec<VirtualProblem>,
+    /// The duration of the original contest, if the set is made from one.
+    pub duration: Option<std::time::Duration>,
+}
+
+impl ProblemSet {
+    /// Parse a problem set from either a contest id (`1900`) or a list of problems (`1900A 1234B`).
+    pub async fn parse(contest_cache: &ContestCache, input: &str) -> Result<Self> {
+        let input = input.trim();
+        if let Ok(contest_id) = input.parse::<u64>() {
+            let (contest, problems) = contest_cache.get(contest_id).await?;
+            let problems = problems.ok_or_else(|| Error::msg("Contest hasn't started yet"))?;
+            return Ok(Self {
+                title: contest.name,
+                problems: problems
+                    .into_iter()
+                    .map(|p| VirtualProblem {
+                        contest_id,
+                        index: p.index,
+                        name: p.name,
+                    })
+                    .collect(),
+                duration: Some(std::time::Duration::from_secs(contest.duration_seconds)),
+            });
+        }
+        let mut problems = Vec::new();
+        for token in input.split_whitespace() {
+            let cap = PROBLEM_ID
+                .captures(token)
+                .ok_or_else(|| Error::msg(format!("`{}` is not a valid problem id", token)))?;
+            let contest_id: u64 = cap["contest"].parse()?;
+            let index = cap["problem"].to_uppercase();
+            let (_, contest_problems) = contest_cache.get(contest_id).await?;
+            let problem = contest_problems
+                .unwrap_or_default()
+                .into_iter()
+                .find(|p| p.index == index)
+                .ok_or_else(|| Error::msg(format!("Problem `{}` not found", token)))?;
+            problems.push(VirtualProblem {
+                contest_id,
+                index,
+                name: problem.name,
+            });
+        }
+        if problems.is_empty() {
+            return Err(Error::msg("No problems given"));
+        }
+        Ok(Self {
+            title: "Mashup".to_owned(),
+            problems,
+            duration: None,
+        })
+    }
+}
+
+/// The result of a participant on a single problem.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ProblemStatus {
+    /// Seconds since the start of the contest the problem was solved at.
+    pub solved_at: Option<i64>,
+    /// Number of rejected attempts before solving (or in total, if unsolved).
+    pub rejected: u32,
+    /// Whether there is a submission still being judged.
+    pub pending: bool,
+}
+
+impl ProblemStatus {
+    /// The penalty in minutes of this problem, if solved.
+    pub fn penalty(&self) -> i64 {
+        self.solved_at
+            .map(|t| t / 60 + PENALTY_PER_ATTEMPT * self.rejected as i64)
+            .unwrap_or(0)
+    }
+
+    fn cell(&self) -> String {
+        match (self.solved_at, self.rejected, self.pending) {
+            (Some(_), 0, _) => "+".to_owned(),
+            (Some(_), r, _) => format!("+{}", r),
+            (None, _, true) => "?".to_owned(),
+            (None, 0, false) => ".".to_owned(),
+            (None, r, false) => format!("-{}", r),
+        }
+    }
+}
+
+/// Compute ICPC-style problem statuses from a list of submissions.
+pub(crate) fn score_submissions(
+    problems: &[VirtualProblem],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    submissions: &[Submission],
+) -> Vec<ProblemStatus> {
+    let mut subs = submissions
+        .iter()
+        .filter(|s| {
+            s.creation_time_seconds >= start.timestamp()
+                && s.creation_time_seconds < end.timestamp()
+        })
+        .collect::<Vec<_>>();
+    subs.sort_by_key(|s| (s.creation_time_seconds, s.id));
+    problems
+        .iter()
+        .map(|problem| {
+            let mut status = ProblemStatus::default();
+            for s in subs.iter().filter(|s| problem.matches(s)) {
+                let verdict = s.verdict();
+                if verdict == Verdict::Ok {
+                    status.solved_at = Some(s.creation_time_seconds - start.timestamp());
+                    break;
+                } else if verdict.is_pending() {
+                    status.pending = true;
+                } else if verdict.is_rejected_attempt() {
+                    status.rejected += 1;
+                }
+            }
+            status
+        })
+        .collect()
+}
+
+struct Participant {
+    member: Member,
+    handle: String,
+    problems: Vec<ProblemStatus>,
+}
+
+impl Participant {
+    fn solved(&self) -> usize {
+        self.problems
+            .iter()
+            .filter(|p| p.solved_at.is_some())
+            .count()
+    }
+
+    fn penalty(&self) -> i64 {
+        self.problems.iter().map(|p| p.penalty()).sum()
+    }
+}
+
+/// The structure storing the channels with a running virtual contest.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VirtualContests {
+    running: Arc<SyncMutex<Set<ChannelId>>>,
+}
+
+impl TypeMapKey for VirtualContests {
+    type Value = VirtualContests;
+}
+
+struct RunningContest(ChannelId, Arc<SyncMutex<Set<ChannelId>>>);
+
+impl Drop for RunningContest {
+    fn drop(&mut self) {
+        self.1.lock().unwrap().remove(&self.0);
+    }
+}
+
+impl VirtualContests {
+    fn start(&self, channel: ChannelId) -> Option<RunningContest> {
+        let mut s = self.running.lock().unwrap();
+        if s.insert(channel) {
+            Some(RunningContest(channel, self.running.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Run a virtual contest on a channel, blocking until it ends.
+pub(crate) async fn run_virtual_contest(
+    ctx: &Context,
+    guild: GuildId,
+    channel: ChannelId,
+    problem_set: ProblemSet,
+    duration: std::time::Duration,
+    participants: Option<Vec<UserId>>,
+) -> Result<()> {
+    let (http, member_cache, saved_users, running) = {
+        let data = ctx.data.read().await;
+        let saved_users = CfSavedUsers::open(&data).borrow()?.clone();
+        (
+            data.get::<submissions::Client>().unwrap().clone(),
+            data.get::<MemberCache>().unwrap().clone(),
+            saved_users,
+            data.get::<VirtualContests>().unwrap().clone(),
+        )
+    };
+    let _lock = match running.start(channel) {
+        Some(v) => v,
+        None => {
+            channel
+                .send_message(
+                    ctx,
+                    CreateMessage::new()
+                        .content("A virtual contest is already running in this channel!"),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let num_problems = problem_set.problems.len();
+    let mut participants: HashMap<UserId, Participant> = saved_users
+        .into_iter()
+        .filter(|(user_id, _)| {
+            participants
+                .as_ref()
+                .map(|p| p.contains(user_id))
+                .unwrap_or(true)
+        })
+        .map(|(user_id, cfu)| {
+            let member_cache = &member_cache;
+            async move {
+                member_cache.query(ctx, user_id, guild).await.map(|member| {
+                    (
+                        user_id,
+                        Participant {
+                            member,
+                            handle: cfu.handle,
+                            problems: vec![ProblemStatus::default(); num_problems],
+                        },
+                    )
+                })
+            }
+        })
+        .collect::<stream::FuturesUnordered<_>>()
+        .filter_map(future::ready)
+        .collect()
+        .await;
+
+    if participants.is_empty() {
+        channel
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .content("No participants with a saved Codeforces account were found!"),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let start = Utc::now();
+    let end = start + chrono::Duration::from_std(duration)?;
+
+    let mut msg = channel
+        .send_message(
+            ctx,
+            CreateMessage::new().content(
+                MessageBuilder::new()
+                    .push("Virtual contest ")
+                    .push_bold_safe(&problem_set.title)
+                    .push(" has started with ")
+                    .push_bold(format!("{}", participants.len()))
+                    .push(" participants! Good luck! 🏁")
+                    .build(),
+            ),
+        )
+        .await?;
+    msg.pin(ctx).await.ok();
+
+    loop {
+        let finished = Utc::now() >= end;
+        stream::iter(participants.values_mut())
+            .for_each_concurrent(CONCURRENT_POLLS, |p| {
+                let http = &http;
+                let problems = &problem_set.problems[..];
+                async move {
+                    match submissions::user_status(
+                        http,
+                        &p.handle,
+                        start.timestamp(),
+                        SUBMISSIONS_PER_REQUEST,
+                    )
+                    .await
+                    {
+                        Ok(subs) => p.problems = score_submissions(problems, start, end, &subs),
+                        Err(e) => eprintln!("Cannot fetch submissions of {}: {}", p.handle, e),
+                    }
+                }
+            })
+            .await;
+        msg.edit(
+            ctx,
+            EditMessage::new().content(standings_message(
+                &problem_set,
+                participants.values(),
+                end,
+                finished,
+            )),
+        )
+        .await
+        .pls_ok();
+        if finished {
+            break;
+        }
+        let wait = (end - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+            .min(std::time::Duration::from_secs(60));
+        tokio::time::sleep(wait).await;
+    }
+
+    msg.unpin(ctx).await.ok();
+    let winner = participants
+        .values()
+        .min_by_key(|p| (std::cmp::Reverse(p.solved()), p.penalty()))
+        .filter(|p| p.solved() > 0);
+    let mut content = MessageBuilder::new();
+    content
+        .push("Virtual contest ")
+        .push_bold_safe(&problem_set.title)
+        .push(" has ended! ");
+    match winner {
+        Some(w) => content
+            .push("Congratulations to ")
+            .push(w.member.mention().to_string())
+            .push(format!(
+                " for winning with **{}** solved and **{}** penalty! 🎉",
+                w.solved(),
+                w.penalty()
+            )),
+        None => content.push("No one solved anything... 😭"),
+    };
+    channel
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .content(content.build())
+                .reference_message(&msg),
+        )
+        .await?;
+    Ok(())
+}
+
+fn standings_message<'a>(
+    problem_set: &ProblemSet,
+    participants: impl Iterator<Item = &'a Participant>,
+    end: DateTime<Utc>,
+    finished: bool,
+) -> String {
+    use Align::*;
+    let mut participants = participants.collect::<Vec<_>>();
+    participants.sort_by_key(|p| (std::cmp::Reverse(p.solved()), p.penalty()));
+
+    let headers = [
+        vec!["Rank", "Handle", "User", "Solved", "Penalty"],
+        problem_set
+            .problems
+            .iter()
+            .map(|p| p.index.as_str())
+            .collect(),
+    ]
+    .concat();
+    let aligns = [
+        vec![Right, Left, Left, Right, Right],
+        problem_set.problems.iter().map(|_| Middle).collect(),
+    ]
+    .concat();
+    let mut rank = 0;
+    let mut last_score = None;
+    let rows = participants
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let score = (p.solved(), p.penalty());
+            if last_score != Some(score) {
+                rank = i + 1;
+                last_score = Some(score);
+            }
+            [
+                vec![
+                    format!("#{}", rank),
+                    p.handle.clone(),
+                    p.member.distinct(),
+                    p.solved().to_string(),
+                    p.penalty().to_string(),
+                ],
+                p.problems.iter().map(|s| s.cell()).collect(),
+            ]
+            .concat()
+        })
+        .collect::<Vec<_>>();
+
+    let mut m = MessageBuilder::new();
+    m.push_bold_safe(&problem_set.title).push(" | ");
+    if finished {
+        m.push_line("**Final standings**");
+    } else {
+        m.push_line(format!("ends {}", end.format("<t:%s:R>")));
+    }
+    for p in &problem_set.problems {
+        m.push(format!("`{}`: ", p.index))
+            .push_safe(&p.name)
+            .push_line(format!(
+                " (<https://codeforces.com/contest/{}/problem/{}>)",
+                p.contest_id, p.index
+            ));
+    }
+    m.push_line(table_formatting_unsafe(&headers, &aligns, rows));
+    m.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::submissions::{Author, SubmissionProblem};
+    use chrono::TimeZone;
+
+    const START: i64 = 1_700_000_000;
+
+    fn problem(index: &str) -> VirtualProblem {
+        VirtualProblem {
+            contest_id: 1900,
+            index: index.to_owned(),
+            name: format!("Problem {}", index),
+        }
+    }
+
+    fn submission(id: u64, index: &str, minute: i64, verdict: Option<Verdict>) -> Submission {
+        Submission {
+            id,
+            contest_id: Some(1900),
+            creation_time_seconds: START + minute * 60,
+            problem: SubmissionProblem {
+                contest_id: Some(1900),
+                index: index.to_owned(),
+                name: format!("Problem {}", index),
+            },
+            author: Author {
+                members: vec![],
+                team_name: None,
+            },
+            programming_language: "C++".to_owned(),
+            verdict,
+            passed_test_count: 0,
+            time_consumed_millis: 0,
+            memory_consumed_bytes: 0,
+        }
+    }
+
+    fn score(problems: &[VirtualProblem], submissions: &[Submission]) -> Vec<ProblemStatus> {
+        let start = Utc.timestamp_opt(START, 0).unwrap();
+        score_submissions(
+            problems,
+            start,
+            start + chrono::Duration::hours(2),
+            submissions,
+        )
+    }
+
+    #[test]
+    fn test_solved_after_rejections() {
+        // Submissions come from the API newest first.
+        let status = score(
+            &[problem("A")],
+            &[
+                submission(4, "A", 50, Some(Verdict::WrongAnswer)),
+                submission(3, "A", 30, Some(Verdict::Ok)),
+                submission(2, "A", 20, Some(Verdict::CompilationError)),
+                submission(1, "A", 10, Some(Verdict::WrongAnswer)),
+            ],
+        );
+        assert_eq!(
+            status,
+            vec![ProblemStatus {
+                solved_at: Some(30 * 60),
+                rejected: 1,
+                pending: false,
+            }]
+        );
+        assert_eq!(status[0].penalty(), 30 + PENALTY_PER_ATTEMPT);
+    }
+
+    #[test]
+    fn test_unsolved_and_pending() {
+        let status = score(
+            &[problem("A"), problem("B"), problem("C")],
+            &[
+                submission(3, "B", 40, None),
+                submission(2, "A", 20, Some(Verdict::TimeLimitExceeded)),
+                submission(1, "A", 10, Some(Verdict::WrongAnswer)),
+            ],
+        );
+        assert_eq!(status[0].rejected, 2);
+        assert_eq!(status[0].solved_at, None);
+        assert_eq!(status[0].penalty(), 0);
+        assert!(status[1].pending);
+        assert_eq!(status[2], ProblemStatus::default());
+    }
+
+    #[test]
+    fn test_ignores_outside_submissions() {
+        let mut other_contest = submission(4, "A", 30, Some(Verdict::Ok));
+        other_contest.problem.contest_id = Some(1901);
+        let status = score(
+            &[problem("A")],
+            &[
+                submission(5, "A", 121, Some(Verdict::Ok)),
+                other_contest,
+                submission(3, "A", -1, Some(Verdict::Ok)),
+                submission(2, "A", -5, Some(Verdict::WrongAnswer)),
+            ],
+        );
+        assert_eq!(status, vec![ProblemStatus::default()]);
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::Utc;
+use poise::CreateReply;
+use regex::Regex;
+use serenity::{
+    all::{ChannelId, MessageId, User, UserId},
+    builder::GetMessages,
+    model::channel::Message,
+};
+use std::time::Duration;
+use youmubot_prelude::{ratelimit::Ratelimit, *};
+
+use crate::HasCoreEnv;
+
+/// The most messages that are looked at in one go.
+const SCAN_LIMIT: usize = 1000;
+/// Discord refuses to bulk delete messages older than this.
+const BULK_DELETE_AGE: chrono::Duration = chrono::Duration::days(14);
+/// The most old messages deleted one by one in a clean, so that it finishes
+/// well before the interaction token expires (after 15 minutes).
+const MAX_SINGLE_DELETES: usize = 300;
+
+/// Which messages to clean.
+#[derive(Debug, Default)]
+struct Filter {
+    author: Option<UserId>,
+    contains: Option<String>,
+    pattern: Option<Regex>,
+    bots_only: bool,
+    attachments_only: bool,
+}
+
+impl Filter {
+    fn matches(&self, m: &Message) -> bool {
+        self.author.is_none_or(|a| m.author.id == a)
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|t| m.content.to_lowercase().contains(&t.to_lowercase()))
+            && self.pattern.as_ref().is_none_or(|p| p.is_match(&m.content))
+            && (!self.bots_only || m.author.bot)
+            && (!self.attachments_only || !m.attachments.is_empty())
+    }
+}
+
+/// How many messages were deleted by a clean.
+#[derive(Debug, Default)]
+pub(super) struct Cleaned {
+    pub scanned: usize,
+    pub bulk: usize,
+    pub single: usize,
+    pub failed: usize,
+    /// Old messages left alone, as there were too many to delete one by one.
+    pub skipped: usize,
+}
+
+/// Deletes the given messages from the channel.
+///
+/// Messages too old to be bulk deleted are deleted one by one, at most one per second,
+/// up to [`MAX_SINGLE_DELETES`] of them.
+pub(super) async fn delete_messages(
+    ctx: &Context,
+    channel: ChannelId,
+    messages: Vec<MessageId>,
+    cleaned: &mut Cleaned,
+) -> Result<()> {
+    let threshold = Utc::now() - BULK_DELETE_AGE;
+    let (recent, old): (Vec<_>, Vec<_>) = messages
+        .into_iter()
+        .partition(|m| *m.created_at() > threshold);
+
+    for chunk in recent.chunks(100) {
+        channel.delete_messages(&ctx.http, chunk).await?;
+        cleaned.bulk += chunk.len();
+    }
+
+    let http = Ratelimit::new(&ctx.http, 1, Duration::from_secs(1));
+    cleaned.skipped += old.len().saturating_sub(MAX_SINGLE_DELETES);
+    for id in old.into_iter().take(MAX_SINGLE_DELETES) {
+        match channel.delete_message(&*http.borrow().await?, id).await {
+            Ok(()) => cleaned.single += 1,
+            Err(_) => cleaned.failed += 1,
+        }
+    }
+    Ok(())
+}
+
+/// Clean messages from the current channel, with filters.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_MESSAGES")]
+pub async fn clean<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The number of matching messages to delete (defaults to 10)"]
+    #[min = 1]
+    #[max = 1000]
+    count: Option<usize>,
+    #[description = "Only delete messages from this user"] author: Option<User>,
+    #[description = "Only delete messages containing this text (case insensitive)"]
+    contains: Option<String>,
+    #[description = "Only delete messages matching this regular expression"] regex: Option<String>,
+    #[description = "Only delete messages from bots"] bots: Option<bool>,
+    #[description = "Only delete messages with attachments"] attachments: Option<bool>,
+    #[description = "Only delete messages before this message ID"] before: Option<String>,
+    #[description = "Only delete messages after this message ID"] after: Option<String>,
+) -> Result<()> {
+    let count = count.unwrap_or(10);
+    let parse_id = |v: Option<String>| {
+        v.map(|v| {
+            v.trim()
+                .parse::<u64>()
+                .map(MessageId::new)
+                .map_err(|_| Error::msg(format!("`{}` is not a message ID", v)))
+        })
+        .transpose()
+    };
+    let before = parse_id(before)?;
+    let after = parse_id(after)?;
+    let filter = Filter {
+        author: author.map(|u| u.id),
+        contains,
+        pattern: regex
+            .map(|r| Regex::new(&r))
+            .transpose()
+            .map_err(|e| Error::msg(format!("Invalid regular expression: {}", e)))?,
+        bots_only: bots.unwrap_or(false),
+        attachments_only: attachments.unwrap_or(false),
+    };
+    ctx.defer_ephemeral().await?;
+
+    let channel = ctx.channel_id();
+    let mut cleaned = Cleaned::default();
+    let mut matched = Vec::new();
+    let mut cursor = before;
+    'scan: while matched.len() < count && cleaned.scanned < SCAN_LIMIT {
+        let mut req = GetMessages::new().limit(100);
+        if let Some(cursor) = cursor {
+            req = req.before(cursor);
+        }
+        let messages = channel.messages(ctx.http(), req).await?;
+        let Some(last) = messages.last() else {
+            break;
+        };
+        cursor = Some(last.id);
+        for m in messages {
+            if after.is_some_and(|a| m.id <= a) {
+                break 'scan;
+            }
+            cleaned.scanned += 1;
+            if filter.matches(&m) {
+                matched.push(m.id);
+                if matched.len() == count {
+                    break 'scan;
+                }
+            }
+        }
+    }
+
+    delete_messages(ctx.serenity_context(), channel, matched, &mut cleaned).await?;
+
+    let mut summary = format!(
+        "🌋 Deleted **{}** out of {} scanned messages.",
+        cleaned.bulk + cleaned.single,
+        cleaned.scanned
+    );
+    if cleaned.single > 0 {
+        summary += &format!(
+            "\n{} of them were older than 14 days and deleted one by one.",
+            cleaned.single
+        );
+    }
+    if cleaned.skipped > 0 {
+        summary += &format!(
+            "\n{} more messages older than 14 days were left alone, run the command again to delete them.",
+            cleaned.skipped
+        );
+    }
+    if cleaned.failed > 0 {
+        summary += &format!("\n⚠️ {} messages could not be deleted.", cleaned.failed);
+    }
+    ctx.send(CreateReply::default().ephemeral(true).content(summary))
+        .await?;
+    Ok(())
+}
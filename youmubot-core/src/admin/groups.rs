@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use dashmap::DashSet;
+use poise::ChoiceParameter;
+use serenity::all::{Cache, ChannelId, GuildChannel, GuildId};
+use youmubot_db_sql::models::disabled_groups as model;
+use youmubot_prelude::*;
+
+use crate::HasCoreEnv;
+
+/// A command group that can be disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ChoiceParameter)]
+pub enum CommandGroup {
+    #[name = "osu!"]
+    Osu,
+    #[name = "Codeforces"]
+    Codeforces,
+    #[name = "AtCoder"]
+    AtCoder,
+    #[name = "Fun"]
+    Fun,
+    #[name = "Community"]
+    Community,
+}
+
+impl CommandGroup {
+    /// The key of the group, as used by the command checks.
+    pub fn key(self) -> &'static str {
+        match self {
+            CommandGroup::Osu => "osu",
+            CommandGroup::Codeforces => "codeforces",
+            CommandGroup::AtCoder => "atcoder",
+            CommandGroup::Fun => "fun",
+            CommandGroup::Community => "community",
+        }
+    }
+}
+
+/// Is the command group disabled in this channel?
+///
+/// Threads follow the channel they are in.
+pub fn is_disabled(
+    env: &impl HasCoreEnv,
+    cache: &Cache,
+    guild: GuildId,
+    channel: ChannelId,
+    group: &str,
+) -> bool {
+    let disabled = &env.core_env().disabled_groups.list;
+    let is_disabled_in = |c: Option<ChannelId>| disabled.contains(&(guild, c, group.to_owned()));
+    is_disabled_in(None)
+        || is_disabled_in(Some(channel))
+        || thread_parent(cache, guild, channel).is_some_and(|p| is_disabled_in(Some(p)))
+}
+
+/// The channel a thread is in, if the channel is a thread we know of.
+fn thread_parent(cache: &Cache, guild: GuildId, channel: ChannelId) -> Option<ChannelId> {
+    cache
+        .guild(guild)?
+        .threads
+        .iter()
+        .find(|t| t.id == channel)
+        .and_then(|t| t.parent_id)
+}
+
+/// Commandgroup: enable or disable groups of commands in the server or in a channel.
+#[poise::command(
+    slash_command,
+    subcommands("disable", "enable", "list"),
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn commandgroup<U: HasCoreEnv>(_ctx: CmdContext<'_, U>) -> Result<()> {
+    Ok(())
+}
+
+/// Disable a group of commands.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+async fn disable<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The command group"] group: CommandGroup,
+    #[description = "Only disable it in this channel"] channel: Option<GuildChannel>,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    let channel = channel.map(|c| c.id);
+    ctx.defer().await?;
+    env.disabled_groups
+        .disable(&env.prelude, guild, channel, group)
+        .await?;
+    ctx.say(format!(
+        "Commands of **{}** are now disabled {}.",
+        group.name(),
+        where_(channel)
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Enable a group of commands again.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+async fn enable<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The command group"] group: CommandGroup,
+    #[description = "Only enable it in this channel"] channel: Option<GuildChannel>,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    let channel = channel.map(|c| c.id);
+    ctx.defer().await?;
+    let msg = if env
+        .disabled_groups
+        .enable(&env.prelude, guild, channel, group)
+        .await?
+    {
+        format!(
+            "Commands of **{}** are enabled {} again.",
+            group.name(),
+            where_(channel)
+        )
+    } else {
+        format!(
+            "Commands of **{}** were not disabled {}.",
+            group.name(),
+            where_(channel)
+        )
+    };
+    ctx.say(msg).await?;
+    Ok(())
+}
+
+/// List the disabled groups of commands in this server.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+async fn list<U: HasCoreEnv>(ctx: CmdContext<'_, U>) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    ctx.defer().await?;
+    let mut groups = env
+        .disabled_groups
+        .list
+        .iter()
+        .filter(|v| v.0 == guild)
+        .map(|v| format!("- **{}** {}", group_name(&v.2), where_(v.1)))
+        .collect::<Vec<_>>();
+    groups.sort();
+    let msg = if groups.is_empty() {
+        "No command groups are disabled in this server!".to_owned()
+    } else {
+        format!("Disabled command groups:\n{}", groups.join("\n"))
+    };
+    ctx.say(msg).await?;
+    Ok(())
+}
+
+/// The display name of a group key.
+fn group_name(key: &str) -> &str {
+    (0..)
+        .map_while(CommandGroup::from_index)
+        .find(|group| group.key() == key)
+        .map_or(key, |group| group.name())
+}
+
+fn where_(channel: Option<ChannelId>) -> String {
+    match channel {
+        Some(c) => format!("in {}", c.mention()),
+        None => "in this server".to_owned(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DisabledGroups {
+    list: Arc<DashSet<(GuildId, Option<ChannelId>, String)>>,
+}
+
+impl DisabledGroups {
+    pub async fn from_db(env: &Env) -> Result<Self> {
+        let list = model::DisabledGroup::get_all(&env.sql).await?;
+        let set: DashSet<_> = list
+            .into_iter()
+            .map(|v| {
+                (
+                    GuildId::new(v.guild_id as u64),
+                    v.channel_id.map(|c| ChannelId::new(c as u64)),
+                    v.group_name,
+                )
+            })
+            .collect();
+        Ok(Self {
+            list: Arc::new(set),
+        })
+    }
+
+    pub async fn disable(
+        &self,
+        env: &Env,
+        guild: GuildId,
+        channel: Option<ChannelId>,
+        group: CommandGroup,
+    ) -> Result<()> {
+        Self::to_model(guild, channel, group).add(&env.sql).await?;
+        self.list.insert((guild, channel, group.key().to_owned()));
+        Ok(())
+    }
+
+    pub async fn enable(
+        &self,
+        env: &Env,
+        guild: GuildId,
+        channel: Option<ChannelId>,
+        group: CommandGroup,
+    ) -> Result<bool> {
+        Self::to_model(guild, channel, group)
+            .remove(&env.sql)
+            .await?;
+        Ok(self
+            .list
+            .remove(&(guild, channel, group.key().to_owned()))
+            .is_some())
+    }
+
+    fn to_model(
+        guild: GuildId,
+        channel: Option<ChannelId>,
+        group: CommandGroup,
+    ) -> model::DisabledGroup {
+        model::DisabledGroup {
+            guild_id: guild.get() as i64,
+            channel_id: channel.map(|c| c.get() as i64),
+            group_name: group.key().to_owned(),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
-use serenity::all::User;
+use serenity::all::{GuildId, User};
 use youmubot_db_sql::models::ignore_list as model;
 use youmubot_prelude::*;
 
@@ -13,6 +13,11 @@ pub fn should_ignore(env: &impl HasCoreEnv, id: UserId) -> bool {
     env.core_env().ignore.query(id).is_some()
 }
 
+// Should we ignore this user in the given guild?
+pub fn should_ignore_in(env: &impl HasCoreEnv, guild: GuildId, id: UserId) -> bool {
+    env.core_env().guild_ignore.list.contains_key(&(guild, id))
+}
+
 /// Ignore: make Youmu ignore all commands from an user.
 #[poise::command(
     slash_command,
@@ -93,6 +98,100 @@ async fn list<U: HasCoreEnv>(ctx: CmdContext<'_, U>) -> Result<()> {
     Ok(())
 }
 
+/// Guildignore: make Youmu ignore all commands from an user in this server.
+#[poise::command(
+    slash_command,
+    subcommands("guild_add", "guild_remove", "guild_list"),
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn guildignore<U: HasCoreEnv>(_ctx: CmdContext<'_, U>) -> Result<()> {
+    Ok(())
+}
+
+/// Add an user to the server's ignore list.
+#[poise::command(
+    slash_command,
+    rename = "add",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn guild_add<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "Discord username"] discord_name: User,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    ctx.defer().await?;
+    env.guild_ignore
+        .add(&env.prelude, guild, UserId(discord_name.id))
+        .await?;
+    let msg = format!("User **{}** ignored in this server!", discord_name.name);
+    ctx.say(msg).await?;
+    Ok(())
+}
+
+/// Remove an user from the server's ignore list.
+#[poise::command(
+    slash_command,
+    rename = "remove",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn guild_remove<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "Discord username"] discord_name: User,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    ctx.defer().await?;
+    env.guild_ignore
+        .remove(&env.prelude, guild, UserId(discord_name.id))
+        .await?;
+    let msg = format!(
+        "User **{}** removed from this server's ignore list!",
+        discord_name.name
+    );
+    ctx.say(msg).await?;
+    Ok(())
+}
+
+/// List users ignored in this server.
+#[poise::command(
+    slash_command,
+    rename = "list",
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+async fn guild_list<U: HasCoreEnv>(ctx: CmdContext<'_, U>) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    ctx.defer().await?;
+    let users = env
+        .guild_ignore
+        .list
+        .iter()
+        .filter(|v| v.key().0 == guild)
+        .map(|v| {
+            format!(
+                "- {}, since <t:{}:R>",
+                v.key().1 .0.mention(),
+                v.value().timestamp(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let users = if users.is_empty() {
+        "No one is being ignored!"
+    } else {
+        &users[..]
+    };
+
+    let msg = format!("Ignored users in this server:\n{}", users);
+    ctx.say(msg).await?;
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct IgnoredUsers {
     list: Arc<DashMap<UserId, IgnoredUser>>,
@@ -147,3 +246,36 @@ pub(crate) struct IgnoredUser {
     pub username: String,
     pub ignored_since: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone)]
+pub(crate) struct GuildIgnoredUsers {
+    list: Arc<DashMap<(GuildId, UserId), DateTime<Utc>>>,
+}
+
+impl GuildIgnoredUsers {
+    pub async fn from_db(env: &Env) -> Result<Self> {
+        let list = model::GuildIgnoredUser::get_all(&env.sql).await?;
+        let mp: DashMap<_, _> = list
+            .into_iter()
+            .map(|v| {
+                (
+                    (GuildId::new(v.guild_id as u64), (v.user_id as u64).into()),
+                    v.ignored_since,
+                )
+            })
+            .collect();
+        Ok(Self { list: Arc::new(mp) })
+    }
+
+    pub async fn add(&self, env: &Env, guild: GuildId, id: UserId) -> Result<()> {
+        let iu =
+            model::GuildIgnoredUser::add(&env.sql, guild.get() as i64, id.0.get() as i64).await?;
+        self.list.insert((guild, id), iu.ignored_since);
+        Ok(())
+    }
+
+    pub async fn remove(&self, env: &Env, guild: GuildId, id: UserId) -> Result<bool> {
+        model::GuildIgnoredUser::remove(&env.sql, guild.get() as i64, id.0.get() as i64).await?;
+        Ok(self.list.remove(&(guild, id)).is_some())
+    }
+}
@@ -9,17 +9,29 @@ use serenity::{
 };
 use youmubot_prelude::*;
 
+pub mod clean;
+pub mod groups;
 pub mod ignore;
+pub mod moderation;
+pub mod prefix;
+
+use moderation::{
+    log_case, ModAction, MUTE_COMMAND, TEMPBAN_COMMAND, UNBAN_COMMAND, UNMUTE_COMMAND, WARN_COMMAND,
+};
+use prefix::PREFIX_COMMAND;
+
+pub use moderation::{modlog, ModJobs};
+pub use prefix::GuildPrefixes;
 
 #[group]
 #[description = "Administrative commands for the server."]
-#[commands(clean, ban, kick)]
+#[commands(clean, ban, tempban, unban, kick, warn, mute, unmute, prefix)]
 struct Admin;
 
 #[command]
 #[aliases("cleanall")]
 #[required_permissions(MANAGE_MESSAGES)]
-#[description = "Clean at most X latest messages from the current channel (only clean Youmu's messages in DMs). Defaults to 10."]
+#[description = "Clean at most X latest messages from the current channel (only clean Youmu's messages in DMs). Defaults to 10.\nUse the `/clean` slash command to filter the messages to clean."]
 #[usage = "clean 50"]
 #[min_args(0)]
 #[max_args(1)]
@@ -42,9 +54,13 @@ async fn clean(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
                 .await?;
         }
         _ => {
-            msg.channel_id
-                .delete_messages(&ctx.http, messages.into_iter())
-                .await?;
+            clean::delete_messages(
+                ctx,
+                msg.channel_id,
+                messages.into_iter().map(|m| m.id).collect(),
+                &mut clean::Cleaned::default(),
+            )
+            .await?;
         }
     };
     msg.react(&ctx, '🌋').await?;
@@ -64,31 +80,38 @@ async fn clean(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 #[max_args(2)]
 #[only_in("guilds")]
 async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
     let user = args.single::<UserId>()?.0.to_user(&ctx).await?;
-    let reason = args.single::<String>().map(|v| format!("`{}`", v)).ok();
+    let reason = args.single::<String>().ok();
     let dmds = args.single::<u8>().unwrap_or(0);
 
-    match reason {
+    match &reason {
         Some(reason) => {
             msg.reply(
                 &ctx,
                 format!("🔨 Banning user {} for reason `{}`.", user.tag(), reason),
             )
             .await?;
-            msg.guild_id
-                .ok_or_else(|| Error::msg("Can't get guild from message?"))? // we had a contract
-                .ban_with_reason(&ctx.http, user, dmds, &reason)
+            guild
+                .ban_with_reason(&ctx.http, user.id, dmds, reason)
                 .await?;
         }
         None => {
             msg.reply(&ctx, format!("🔨 Banning user {}.", user.tag()))
                 .await?;
-            msg.guild_id
-                .ok_or_else(|| Error::msg("Can't get guild from message?"))? // we had a contract
-                .ban(&ctx.http, user, dmds)
-                .await?;
+            guild.ban(&ctx.http, user.id, dmds).await?;
         }
     }
+    log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Ban,
+        reason.as_deref(),
+        None,
+    )
+    .await?;
 
     Ok(())
 }
@@ -96,19 +119,31 @@ async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 #[command]
 #[required_permissions(ADMINISTRATOR)]
 #[description = "Kick an user."]
-#[usage = "@user#1234"]
-#[num_args(1)]
+#[usage = "@user#1234/[reason = none]"]
+#[min_args(1)]
 #[only_in("guilds")]
 async fn kick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
     let user = args.single::<UserId>()?.0.to_user(&ctx).await?;
+    let reason = moderation::reason(&args);
 
     msg.reply(&ctx, format!("🔫 Kicking user {}.", user.tag()))
         .await?;
 
-    msg.guild_id
-        .ok_or("Can't get guild from message?")? // we had a contract
-        .kick(&ctx.http, user)
-        .await?;
+    match &reason {
+        Some(reason) => guild.kick_with_reason(&ctx.http, user.id, reason).await?,
+        None => guild.kick(&ctx.http, user.id).await?,
+    }
+    log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Kick,
+        reason.as_deref(),
+        None,
+    )
+    .await?;
 
     Ok(())
 }
@@ -0,0 +1,450 @@
+use chrono::Utc;
+use dashmap::DashSet;
+use poise::CreateReply;
+use serenity::{
+    all::{GuildId, User, UserId},
+    builder::{CreateMessage, EditMember},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use youmubot_db_sql::models::moderation::{ModCase, ModJob};
+use youmubot_prelude::{Duration as ParseDuration, UserId as ParseUserId, *};
+
+use crate::HasCoreEnv;
+
+/// The longest time Discord lets a member be timed out for.
+const MAX_MUTE: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+/// The longest temporary ban. Anything longer should just be a ban.
+const MAX_TEMPBAN: Duration = Duration::from_secs(366 * 24 * 60 * 60);
+
+/// A moderation action, as recorded in the case log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModAction {
+    Warn = 0,
+    Mute = 1,
+    Unmute = 2,
+    Kick = 3,
+    Ban = 4,
+    Unban = 5,
+}
+
+impl ModAction {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => ModAction::Mute,
+            2 => ModAction::Unmute,
+            3 => ModAction::Kick,
+            4 => ModAction::Ban,
+            5 => ModAction::Unban,
+            _ => ModAction::Warn,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            ModAction::Warn => "⚠️ Warning",
+            ModAction::Mute => "🔇 Mute",
+            ModAction::Unmute => "🔊 Unmute",
+            ModAction::Kick => "🔫 Kick",
+            ModAction::Ban => "🔨 Ban",
+            ModAction::Unban => "🕊️ Unban",
+        }
+    }
+}
+
+/// Records a case in the guild's case log, returning its number.
+///
+/// Bans and unbans replace any pending unban of the user, and timed bans schedule a new one.
+pub(crate) async fn log_case(
+    ctx: &Context,
+    guild: GuildId,
+    user: UserId,
+    moderator: UserId,
+    action: ModAction,
+    reason: Option<&str>,
+    duration: Option<Duration>,
+) -> Result<i64> {
+    let (pool, jobs) = {
+        let data = ctx.data.read().await;
+        let pool = data.get::<SQLClient>().unwrap().clone();
+        (pool, data.get::<ModJobs>().unwrap().clone())
+    };
+    let now = Utc::now();
+    let expires_at = duration
+        .map(|d| {
+            chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| now.checked_add_signed(d))
+                .ok_or_else(|| Error::msg("The duration is too long."))
+        })
+        .transpose()?;
+    let mut case = ModCase {
+        guild_id: guild.get() as i64,
+        case_id: 0,
+        user_id: user.get() as i64,
+        moderator_id: moderator.get() as i64,
+        action: action as u8,
+        reason: reason.map(|v| v.to_owned()),
+        created_at: now,
+        expires_at,
+    };
+
+    let mut tx = pool.begin().await?;
+    case.insert(&mut tx).await?;
+    let mut job = None;
+    if matches!(action, ModAction::Ban | ModAction::Unban) {
+        ModJob::cancel(case.guild_id, case.user_id, &mut *tx).await?;
+    }
+    if let (ModAction::Ban, Some(due)) = (action, case.expires_at) {
+        let mut j = ModJob {
+            id: 0,
+            guild_id: case.guild_id,
+            user_id: case.user_id,
+            case_id: case.case_id,
+            due,
+        };
+        j.insert(&mut *tx).await?;
+        job = Some(j);
+    }
+    tx.commit().await?;
+
+    if let Some(job) = job {
+        jobs.spawn(ctx.clone(), job);
+    }
+    Ok(case.case_id)
+}
+
+/// Keeps track of the scheduled unbans being waited on, so that each is only run once.
+#[derive(Debug, Default, Clone)]
+pub struct ModJobs {
+    running: Arc<DashSet<i64>>,
+}
+
+impl TypeMapKey for ModJobs {
+    type Value = ModJobs;
+}
+
+impl ModJobs {
+    /// Resumes all pending jobs. Overdue jobs are run right away.
+    pub async fn init(ctx: &Context) -> Result<()> {
+        let (pool, jobs) = {
+            let data = ctx.data.read().await;
+            let pool = data.get::<SQLClient>().unwrap().clone();
+            (pool, data.get::<ModJobs>().unwrap().clone())
+        };
+        for job in ModJob::all(&pool).await? {
+            jobs.spawn(ctx.clone(), job);
+        }
+        Ok(())
+    }
+
+    fn spawn(&self, ctx: Context, job: ModJob) {
+        if !self.running.insert(job.id) {
+            return;
+        }
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            let id = job.id;
+            run_job(ctx, job).await.pls_ok();
+            running.remove(&id);
+        });
+    }
+}
+
+/// Waits until the job is due, then lifts the ban.
+async fn run_job(ctx: Context, job: ModJob) -> Result<()> {
+    let wait = (job.due - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(wait).await;
+
+    let pool = ctx.data.read().await.get::<SQLClient>().unwrap().clone();
+    // The ban might have been lifted or replaced in the meantime.
+    if !ModJob::delete(job.id, &pool).await? {
+        return Ok(());
+    }
+    let guild = GuildId::new(job.guild_id as u64);
+    let user = UserId::new(job.user_id as u64);
+    guild.unban(&ctx, user).await?;
+    let self_id = ctx.http.get_current_user().await?.id;
+    log_case(
+        &ctx,
+        guild,
+        user,
+        self_id,
+        ModAction::Unban,
+        Some(&format!("Temporary ban (case #{}) ran out", job.case_id)),
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Reads the rest of the arguments as the reason, if any.
+pub(super) fn reason(args: &Args) -> Option<String> {
+    Some(args.rest().trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_owned())
+}
+
+#[command]
+#[required_permissions(MODERATE_MEMBERS)]
+#[description = "Warn an user, recording it in the case log. The user is notified by DM."]
+#[usage = "@user/reason"]
+#[min_args(2)]
+#[only_in("guilds")]
+async fn warn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let user = args.single::<ParseUserId>()?.0.to_user(&ctx).await?;
+    let reason = reason(&args).ok_or_else(|| Error::msg("A warning needs a reason."))?;
+
+    let case = log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Warn,
+        Some(&reason),
+        None,
+    )
+    .await?;
+    let guild_name = guild.name(ctx).unwrap_or_else(|| "the server".to_owned());
+    user.direct_message(
+        &ctx,
+        CreateMessage::new().content(format!(
+            "⚠️ You have been warned in **{}**: {}",
+            guild_name, reason
+        )),
+    )
+    .await
+    .pls_ok();
+    msg.reply(
+        &ctx,
+        format!("⚠️ Warned user {} (case #{}).", user.tag(), case),
+    )
+    .await?;
+    Ok(())
+}
+
+#[command]
+#[required_permissions(MODERATE_MEMBERS)]
+#[description = "Time out an user for the given duration (at most 28 days)."]
+#[usage = "@user/duration/[reason = none]"]
+#[example = "@user/1h/spamming"]
+#[min_args(2)]
+#[only_in("guilds")]
+async fn mute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let user = args.single::<ParseUserId>()?.0.to_user(&ctx).await?;
+    let duration = args.single::<ParseDuration>()?;
+    if duration.0 > MAX_MUTE {
+        return Err(Error::msg("Users can only be muted for at most 28 days.").into());
+    }
+    let reason = reason(&args);
+
+    let until = Utc::now() + chrono::Duration::from_std(duration.0)?;
+    let mut edit = EditMember::new().disable_communication_until_datetime(until.into());
+    if let Some(reason) = &reason {
+        edit = edit.audit_log_reason(reason);
+    }
+    guild.edit_member(&ctx, user.id, edit).await?;
+
+    let case = log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Mute,
+        reason.as_deref(),
+        Some(duration.0),
+    )
+    .await?;
+    msg.reply(
+        &ctx,
+        format!(
+            "🔇 Muted user {} for {} (case #{}).",
+            user.tag(),
+            duration,
+            case
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+#[command]
+#[required_permissions(MODERATE_MEMBERS)]
+#[description = "Lift the time out of an user."]
+#[usage = "@user/[reason = none]"]
+#[min_args(1)]
+#[only_in("guilds")]
+async fn unmute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let user = args.single::<ParseUserId>()?.0.to_user(&ctx).await?;
+    let reason = reason(&args);
+
+    let mut edit = EditMember::new().enable_communication();
+    if let Some(reason) = &reason {
+        edit = edit.audit_log_reason(reason);
+    }
+    guild.edit_member(&ctx, user.id, edit).await?;
+
+    let case = log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Unmute,
+        reason.as_deref(),
+        None,
+    )
+    .await?;
+    msg.reply(
+        &ctx,
+        format!("🔊 Unmuted user {} (case #{}).", user.tag(), case),
+    )
+    .await?;
+    Ok(())
+}
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+#[description = "Ban an user for the given duration (at most a year). They are unbanned automatically afterwards."]
+#[usage = "@user/duration/[reason = none]"]
+#[example = "@user/7d/raiding"]
+#[min_args(2)]
+#[only_in("guilds")]
+async fn tempban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let user = args.single::<ParseUserId>()?.0.to_user(&ctx).await?;
+    let duration = args.single::<ParseDuration>()?;
+    if duration.0 > MAX_TEMPBAN {
+        return Err(Error::msg("Users can only be banned temporarily for at most a year.").into());
+    }
+    let reason = reason(&args);
+
+    msg.reply(
+        &ctx,
+        format!("🔨 Banning user {} for {}.", user.tag(), duration),
+    )
+    .await?;
+    match &reason {
+        Some(reason) => guild.ban_with_reason(&ctx, user.id, 0, reason).await?,
+        None => guild.ban(&ctx, user.id, 0).await?,
+    }
+    if let Err(e) = log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Ban,
+        reason.as_deref(),
+        Some(duration.0),
+    )
+    .await
+    {
+        // Without the case, nothing would ever lift the ban.
+        guild.unban(&ctx, user.id).await.pls_ok();
+        return Err(Error::msg(format!("Cannot record the ban, so it was lifted: {}", e)).into());
+    }
+    Ok(())
+}
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+#[description = "Unban an user, cancelling their temporary ban if there is one."]
+#[usage = "@user/[reason = none]"]
+#[min_args(1)]
+#[only_in("guilds")]
+async fn unban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let user = args.single::<ParseUserId>()?.0.to_user(&ctx).await?;
+    let reason = reason(&args);
+
+    guild.unban(&ctx, user.id).await?;
+    let case = log_case(
+        ctx,
+        guild,
+        user.id,
+        msg.author.id,
+        ModAction::Unban,
+        reason.as_deref(),
+        None,
+    )
+    .await?;
+    msg.reply(
+        &ctx,
+        format!("🕊️ Unbanned user {} (case #{}).", user.tag(), case),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Moderation case log of the server.
+#[poise::command(
+    slash_command,
+    subcommands("user"),
+    guild_only,
+    required_permissions = "MODERATE_MEMBERS"
+)]
+pub async fn modlog<U: HasCoreEnv>(_ctx: CmdContext<'_, U>) -> Result<()> {
+    Ok(())
+}
+
+/// Show all recorded cases of an user.
+#[poise::command(slash_command, guild_only, required_permissions = "MODERATE_MEMBERS")]
+async fn user<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The user to look up"] user: User,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    ctx.defer_ephemeral().await?;
+
+    let cases =
+        ModCase::by_user(guild.get() as i64, user.id.get() as i64, &env.prelude.sql).await?;
+    let content = if cases.is_empty() {
+        format!("No cases recorded for **{}**.", user.tag())
+    } else {
+        format!(
+            "Cases of **{}** ({} in total):\n{}",
+            user.tag(),
+            cases.len(),
+            format_cases(&cases)
+        )
+    };
+    ctx.send(CreateReply::default().ephemeral(true).content(content))
+        .await?;
+    Ok(())
+}
+
+/// The most characters of case lines that fit in a message, leaving room for the header.
+const MAX_CASES_LENGTH: usize = 1800;
+
+/// Lists the cases, newest first, leaving out the oldest ones if they don't fit.
+fn format_cases(cases: &[ModCase]) -> String {
+    let mut lines = Vec::new();
+    let mut length = 0;
+    for (i, case) in cases.iter().rev().enumerate() {
+        let mut line = format!(
+            "- **#{}** {} by <@{}> <t:{}:R>",
+            case.case_id,
+            ModAction::from_u8(case.action).describe(),
+            case.moderator_id,
+            case.created_at.timestamp(),
+        );
+        if let Some(expires_at) = case.expires_at {
+            line += &format!(", until <t:{}:f>", expires_at.timestamp());
+        }
+        if let Some(reason) = &case.reason {
+            line += &format!(": {}", reason);
+        }
+        if length + line.len() > MAX_CASES_LENGTH {
+            lines.push(format!("- ...and {} older cases", cases.len() - i));
+            break;
+        }
+        length += line.len() + 1;
+        lines.push(line);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serenity::{
+    all::GuildId,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+use youmubot_db_sql::{models::guild_prefix as model, Pool};
+use youmubot_prelude::*;
+
+/// The most prefixes a guild can have.
+const MAX_PREFIXES: usize = 5;
+/// The longest a prefix can be.
+const MAX_PREFIX_LENGTH: usize = 10;
+
+/// The custom command prefixes of each guild.
+#[derive(Debug, Clone, Default)]
+pub struct GuildPrefixes {
+    list: Arc<DashMap<GuildId, Vec<String>>>,
+}
+
+impl TypeMapKey for GuildPrefixes {
+    type Value = GuildPrefixes;
+}
+
+impl GuildPrefixes {
+    pub async fn from_db(pool: &Pool) -> Result<Self> {
+        let list = model::GuildPrefixes::get_all(pool)
+            .await?
+            .into_iter()
+            .map(|v| (GuildId::new(v.guild_id as u64), v.prefixes))
+            .collect();
+        Ok(Self {
+            list: Arc::new(list),
+        })
+    }
+
+    /// Returns the custom prefixes of the guild, if it has any.
+    pub fn get(&self, guild: GuildId) -> Option<Vec<String>> {
+        self.list.get(&guild).map(|v| v.clone())
+    }
+
+    /// Sets the prefixes of the guild. An empty list goes back to the default prefixes.
+    async fn set(&self, pool: &Pool, guild: GuildId, prefixes: Vec<String>) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        model::GuildPrefixes {
+            guild_id: guild.get() as i64,
+            prefixes: prefixes.clone(),
+        }
+        .store(&mut tx)
+        .await?;
+        tx.commit().await?;
+        if prefixes.is_empty() {
+            self.list.remove(&guild);
+        } else {
+            self.list.insert(guild, prefixes);
+        }
+        Ok(())
+    }
+}
+
+#[command]
+#[required_permissions(MANAGE_GUILD)]
+#[description = "Set the command prefixes of this server, separated by spaces. `reset` goes back to the default prefixes.\nWithout arguments, shows the current prefixes."]
+#[usage = "[prefixes... | reset]"]
+#[example = "y! yy!"]
+#[only_in("guilds")]
+async fn prefix(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap();
+    let (pool, prefixes) = {
+        let data = ctx.data.read().await;
+        let pool = data.get::<SQLClient>().unwrap().clone();
+        (pool, data.get::<GuildPrefixes>().unwrap().clone())
+    };
+    let new: Vec<String> = args
+        .rest()
+        .split_whitespace()
+        .map(|v| v.to_owned())
+        .collect();
+
+    let reply = match &new[..] {
+        [] => match prefixes.get(guild) {
+            Some(p) => format!("This server's prefixes are {}.", format_prefixes(&p)),
+            None => "This server uses the default prefixes.".to_owned(),
+        },
+        [reset] if reset == "reset" => {
+            prefixes.set(&pool, guild, vec![]).await?;
+            "This server now uses the default prefixes.".to_owned()
+        }
+        _ => {
+            if new.len() > MAX_PREFIXES {
+                return Err(Error::msg(format!(
+                    "A server can have at most {} prefixes.",
+                    MAX_PREFIXES
+                ))
+                .into());
+            }
+            if let Some(p) = new.iter().find(|p| p.chars().count() > MAX_PREFIX_LENGTH) {
+                return Err(Error::msg(format!(
+                    "Prefix `{}` is too long, prefixes can be at most {} characters.",
+                    p, MAX_PREFIX_LENGTH
+                ))
+                .into());
+            }
+            let reply = format!("This server's prefixes are now {}.", format_prefixes(&new));
+            prefixes.set(&pool, guild, new).await?;
+            reply
+        }
+    };
+    msg.reply(&ctx, reply).await?;
+    Ok(())
+}
+
+fn format_prefixes(prefixes: &[String]) -> String {
+    prefixes
+        .iter()
+        .map(|p| format!("`{}`", p))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
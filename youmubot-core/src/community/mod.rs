@@ -17,16 +17,25 @@ use serenity::{
 };
 use youmubot_prelude::*;
 
+mod reminders;
+mod role_menus;
 mod roles;
+mod starboard;
 mod votes;
 
+use role_menus::ROLEMENU_COMMAND;
 use roles::{
-    ADD_COMMAND, LIST_COMMAND, REMOVE_COMMAND, RMROLEMESSAGE_COMMAND, ROLEMESSAGE_COMMAND,
-    TOGGLE_COMMAND, UPDATEROLEMESSAGE_COMMAND,
+    ADD_COMMAND, LIST_COMMAND, REMOVE_COMMAND, RMROLEMESSAGE_COMMAND, ROLECOOLDOWN_COMMAND,
+    ROLEGROUP_COMMAND, ROLEMESSAGE_COMMAND, ROLEREQUIRE_COMMAND, TOGGLE_COMMAND,
+    UPDATEROLEMESSAGE_COMMAND,
 };
 use votes::VOTE_COMMAND;
 
+pub use reminders::{remind, Reminders};
+pub use role_menus::{handle_role_menu, RoleCooldowns};
 pub use roles::ReactionWatchers;
+pub use starboard::{starboard, Starboards};
+pub use votes::{poll, Polls};
 
 #[group]
 #[description = "Community related commands. Usually comes with some sort of delays, since it involves pinging"]
@@ -40,7 +49,11 @@ pub use roles::ReactionWatchers;
     toggle,
     rolemessage,
     rmrolemessage,
-    updaterolemessage
+    updaterolemessage,
+    rolemenu,
+    rolegroup,
+    rolerequire,
+    rolecooldown
 )]
 struct Community;
 
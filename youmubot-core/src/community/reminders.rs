@@ -0,0 +1,398 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use dashmap::DashSet;
+use poise::CreateReply;
+use serenity::all::{ChannelId, CreateAllowedMentions, CreateMessage, UserId};
+use std::sync::Arc;
+use youmubot_db_sql::models::reminder::Reminder;
+use youmubot_prelude::{Duration as ParseDuration, *};
+
+use crate::HasCoreEnv;
+
+/// The most pending reminders an user can have.
+const MAX_REMINDERS: usize = 25;
+/// The shortest interval of a recurring reminder, in seconds.
+const MIN_REPEAT_SECS: i64 = 10 * 60;
+/// The longest interval of a recurring reminder, in seconds.
+const MAX_REPEAT_SECS: i64 = 366 * 24 * 60 * 60;
+/// How far ahead a reminder can be set, in days.
+const MAX_DELAY_DAYS: i64 = 5 * 366;
+/// The longest a reminder's message can be.
+const MAX_MESSAGE_LENGTH: usize = 1500;
+/// The most characters of reminder lines that fit in a message.
+const MAX_LIST_LENGTH: usize = 1900;
+
+/// Parses the time of a reminder: either a duration from now (`1d2h`), a Discord timestamp
+/// (`<t:1700000000:R>`), or an absolute time (`2024-06-01 18:00`, in UTC unless an offset is given).
+fn parse_time(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(d) = s.parse::<ParseDuration>() {
+        return chrono::Duration::from_std(d.0)
+            .ok()
+            .and_then(|d| now.checked_add_signed(d))
+            .ok_or_else(|| Error::msg("That time is too far ahead!"));
+    }
+    if let Some(ts) = s.strip_prefix("<t:").and_then(|v| v.strip_suffix('>')) {
+        let ts = ts.split(':').next().unwrap_or_default();
+        if let Some(t) = ts
+            .parse::<i64>()
+            .ok()
+            .and_then(|v| Utc.timestamp_opt(v, 0).single())
+        {
+            return Ok(t);
+        }
+    }
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M %:z", "%Y-%m-%d %H:%M %z"] {
+        if let Ok(t) = DateTime::parse_from_str(s, format) {
+            return Ok(t.with_timezone(&Utc));
+        }
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(t.and_utc());
+        }
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(d.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    Err(Error::msg(format!(
+        "Cannot understand `{}` as a time. Try a duration like `1d2h`, or a time like `2024-06-01 18:00 +07:00`.",
+        s
+    )))
+}
+
+/// The first occurence of a recurring reminder after `now`, skipping the ones missed.
+/// Returns `None` if the occurence cannot be represented.
+fn next_occurrence(
+    due: DateTime<Utc>,
+    repeat_secs: i64,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if due > now {
+        return Some(due);
+    }
+    let missed = (now - due).num_seconds() / repeat_secs.max(1) + 1;
+    let delay = chrono::Duration::try_seconds(repeat_secs.checked_mul(missed)?)?;
+    due.checked_add_signed(delay)
+}
+
+/// Reminders, delivered in the channel or by DM.
+#[poise::command(
+    slash_command,
+    subcommands("add", "list", "cancel"),
+    category = "community"
+)]
+pub async fn remind<U: HasCoreEnv>(_ctx: CmdContext<'_, U>) -> Result<()> {
+    Ok(())
+}
+
+/// Set up a reminder.
+#[poise::command(slash_command)]
+async fn add<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "When to remind, e.g. `1d2h`, or `2024-06-01 18:00 +07:00` (UTC by default)"]
+    when: String,
+    #[description = "What to remind about"] message: String,
+    #[description = "Repeat the reminder at this interval, e.g. `1w`"] repeat: Option<String>,
+    #[description = "Deliver the reminder by DM instead of in this channel"] dm: Option<bool>,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let now = Utc::now();
+    let due = parse_time(&when, now)?;
+    if due <= now {
+        return Err(Error::msg("That time is already in the past!"));
+    }
+    if due - now > chrono::Duration::days(MAX_DELAY_DAYS) {
+        return Err(Error::msg(format!(
+            "Reminders can be set at most {} ahead.",
+            ParseDuration::from_secs(MAX_DELAY_DAYS as u64 * 24 * 60 * 60)
+        )));
+    }
+    if message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(Error::msg(format!(
+            "The message can be at most {} characters.",
+            MAX_MESSAGE_LENGTH
+        )));
+    }
+    let repeat_secs = repeat
+        .map(|r| r.parse::<ParseDuration>())
+        .transpose()?
+        .map(|r| r.0.as_secs() as i64);
+    if repeat_secs.is_some_and(|r| r < MIN_REPEAT_SECS) {
+        return Err(Error::msg(format!(
+            "Recurring reminders can repeat at most once every {}.",
+            ParseDuration::from_secs(MIN_REPEAT_SECS as u64)
+        )));
+    }
+    if repeat_secs.is_some_and(|r| r > MAX_REPEAT_SECS) {
+        return Err(Error::msg(format!(
+            "Recurring reminders must repeat at least once every {}.",
+            ParseDuration::from_secs(MAX_REPEAT_SECS as u64)
+        )));
+    }
+    let dm = dm.unwrap_or(false) || ctx.guild_id().is_none();
+
+    let user_id = ctx.author().id.get() as i64;
+    if Reminder::by_user(user_id, &env.prelude.sql).await?.len() >= MAX_REMINDERS {
+        return Err(Error::msg(format!(
+            "You can have at most {} reminders, cancel some first.",
+            MAX_REMINDERS
+        )));
+    }
+    let mut reminder = Reminder {
+        id: 0,
+        user_id,
+        guild_id: ctx.guild_id().map(|g| g.get() as i64),
+        channel_id: (!dm).then(|| ctx.channel_id().get() as i64),
+        message,
+        created_at: now,
+        due,
+        repeat_secs,
+    };
+    reminder.insert(&env.prelude.sql).await?;
+
+    let mut reply = format!(
+        "⏰ I will remind you <t:{}:R> {} (reminder #{})",
+        due.timestamp(),
+        if dm { "by DM" } else { "in this channel" },
+        reminder.id
+    );
+    if let Some(r) = repeat_secs {
+        reply += &format!(", then every {}", ParseDuration::from_secs(r as u64));
+    }
+    reply += ".";
+    ctx.say(reply).await?;
+
+    let reminders = ctx
+        .serenity_context()
+        .data
+        .read()
+        .await
+        .get::<Reminders>()
+        .unwrap()
+        .clone();
+    reminders.spawn(ctx.serenity_context().clone(), reminder);
+    Ok(())
+}
+
+/// List your reminders.
+#[poise::command(slash_command)]
+async fn list<U: HasCoreEnv>(ctx: CmdContext<'_, U>) -> Result<()> {
+    let env = ctx.data().core_env();
+    let reminders = Reminder::by_user(ctx.author().id.get() as i64, &env.prelude.sql).await?;
+    let content = if reminders.is_empty() {
+        "You have no reminders!".to_owned()
+    } else {
+        format_reminders(&reminders)
+    };
+    ctx.send(CreateReply::default().ephemeral(true).content(content))
+        .await?;
+    Ok(())
+}
+
+/// Lists the reminders, as many as fit in a message.
+fn format_reminders(reminders: &[Reminder]) -> String {
+    let mut lines = Vec::new();
+    let mut length = 0;
+    for (i, r) in reminders.iter().enumerate() {
+        let mut line = format!(
+            "- **#{}** <t:{}:R> {}",
+            r.id,
+            r.due.timestamp(),
+            match r.channel_id {
+                Some(c) => format!("in {}", ChannelId::new(c as u64).mention()),
+                None => "by DM".to_owned(),
+            }
+        );
+        if let Some(secs) = r.repeat_secs {
+            line += &format!(", every {}", ParseDuration::from_secs(secs as u64));
+        }
+        let mut message = r.message.chars().take(60).collect::<String>();
+        if message.len() < r.message.len() {
+            message += "…";
+        }
+        line += &format!(": {}", message);
+        if length + line.len() > MAX_LIST_LENGTH {
+            lines.push(format!("- ...and {} more", reminders.len() - i));
+            break;
+        }
+        length += line.len() + 1;
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Cancel one of your reminders.
+#[poise::command(slash_command)]
+async fn cancel<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The reminder number, as shown in `/remind list`"] id: i64,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let msg = if Reminder::delete(id, ctx.author().id.get() as i64, &env.prelude.sql).await? {
+        format!("Reminder #{} cancelled.", id)
+    } else {
+        format!("You have no reminder #{}.", id)
+    };
+    ctx.send(CreateReply::default().ephemeral(true).content(msg))
+        .await?;
+    Ok(())
+}
+
+/// Keeps track of the reminders being waited on, so that each reminder is only delivered once.
+#[derive(Debug, Default, Clone)]
+pub struct Reminders {
+    running: Arc<DashSet<i64>>,
+}
+
+impl TypeMapKey for Reminders {
+    type Value = Reminders;
+}
+
+impl Reminders {
+    /// Resumes all stored reminders. Overdue reminders are delivered right away.
+    pub async fn init(ctx: &Context) -> Result<()> {
+        let (pool, reminders) = {
+            let data = ctx.data.read().await;
+            let pool = data.get::<SQLClient>().unwrap().clone();
+            (pool, data.get::<Reminders>().unwrap().clone())
+        };
+        for reminder in Reminder::all(&pool).await? {
+            reminders.spawn(ctx.clone(), reminder);
+        }
+        Ok(())
+    }
+
+    fn spawn(&self, ctx: Context, reminder: Reminder) {
+        if !self.running.insert(reminder.id) {
+            return;
+        }
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            let id = reminder.id;
+            watch(ctx, reminder).await.pls_ok();
+            running.remove(&id);
+        });
+    }
+}
+
+/// Delivers the reminder whenever it is due, until it is cancelled or has no more occurences.
+async fn watch(ctx: Context, mut reminder: Reminder) -> Result<()> {
+    let pool = ctx.data.read().await.get::<SQLClient>().unwrap().clone();
+    loop {
+        let wait = (reminder.due - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+        // The reminder might have been cancelled in the meantime.
+        let Some(r) = Reminder::get(reminder.id, &pool).await? else {
+            return Ok(());
+        };
+        deliver(&ctx, &r).await.pls_ok();
+        match r
+            .repeat_secs
+            .and_then(|secs| next_occurrence(r.due, secs, Utc::now()))
+        {
+            Some(due) => {
+                Reminder::reschedule(r.id, due, &pool).await?;
+                reminder = Reminder { due, ..r };
+            }
+            None => {
+                Reminder::delete(r.id, r.user_id, &pool).await?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn deliver(ctx: &Context, reminder: &Reminder) -> Result<()> {
+    let user = UserId::new(reminder.user_id as u64);
+    let msg = CreateMessage::new()
+        .content(format!("⏰ {} {}", user.mention(), reminder.message))
+        .allowed_mentions(CreateAllowedMentions::new().users([user]));
+    match reminder.channel_id {
+        Some(channel) => {
+            ChannelId::new(channel as u64)
+                .send_message(ctx, msg)
+                .await?;
+        }
+        None => {
+            user.direct_message(ctx, msg).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_times() {
+        let now = at("2024-06-01T12:00:00Z");
+        assert_eq!(parse_time("1d2h", now).unwrap(), at("2024-06-02T14:00:00Z"));
+        assert_eq!(
+            parse_time("<t:1717243200:R>", now).unwrap(),
+            at("2024-06-01T12:00:00Z")
+        );
+        assert_eq!(
+            parse_time("2024-06-03 18:00", now).unwrap(),
+            at("2024-06-03T18:00:00Z")
+        );
+        assert_eq!(
+            parse_time("2024-06-03 18:00 +07:00", now).unwrap(),
+            at("2024-06-03T11:00:00Z")
+        );
+        assert_eq!(
+            parse_time("2024-06-03", now).unwrap(),
+            at("2024-06-03T00:00:00Z")
+        );
+        assert!(parse_time("tomorrow-ish", now).is_err());
+        assert!(parse_time("4294967295w", now).is_err());
+    }
+
+    #[test]
+    fn skips_missed_occurrences() {
+        let due = at("2024-06-01T12:00:00Z");
+        let hour = 60 * 60;
+        assert_eq!(
+            next_occurrence(due, hour, at("2024-06-01T12:00:00Z")),
+            Some(at("2024-06-01T13:00:00Z"))
+        );
+        assert_eq!(
+            next_occurrence(due, hour, at("2024-06-01T15:30:00Z")),
+            Some(at("2024-06-01T16:00:00Z"))
+        );
+        assert_eq!(
+            next_occurrence(due, hour, at("2024-06-01T11:00:00Z")),
+            Some(due)
+        );
+        // Out of range instead of panicking.
+        assert_eq!(next_occurrence(due, i64::MAX, due), None);
+    }
+
+    #[test]
+    fn lists_fit_in_a_message() {
+        let due = at("2024-06-01T12:00:00Z");
+        let reminders = (0..MAX_REMINDERS as i64)
+            .map(|id| Reminder {
+                id: 1_000_000 + id,
+                user_id: 1,
+                guild_id: Some(2),
+                channel_id: Some(1_000_000_000_000_000_000),
+                message: "a very long reminder message ".repeat(10),
+                created_at: due,
+                due,
+                repeat_secs: Some(MAX_REPEAT_SECS),
+            })
+            .collect::<Vec<_>>();
+        let list = format_reminders(&reminders);
+        assert!(list.len() <= 2000);
+        assert!(list.ends_with("more"));
+        assert_eq!(format_reminders(&reminders[..1]).lines().count(), 1);
+    }
+}
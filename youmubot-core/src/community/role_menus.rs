@@ -0,0 +1,610 @@
+use std::{
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use serenity::{
+    all::{
+        ComponentInteractionDataKind, CreateActionRow, CreateButton, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind,
+        CreateSelectMenuOption, EditInteractionResponse, GuildId, Interaction, Member,
+    },
+    builder::CreateMessage,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{
+        channel::Message,
+        guild::Role as DiscordRole,
+        id::{MessageId, RoleId, UserId},
+    },
+    utils::MessageBuilder,
+};
+use youmubot_prelude::*;
+
+use super::roles::assignable_roles;
+use super::roles::sql;
+use crate::db::{Role, RoleMenu, RoleMenuKind};
+
+const MENU_BUTTON_PREFIX: &str = "youmubot_rolemenu_btn_";
+const MENU_SELECT: &str = "youmubot_rolemenu_select";
+/// The maximum number of roles in a menu: 5 rows of 5 buttons, or 25 select options.
+const MAX_MENU_ROLES: usize = 25;
+
+#[command("rolemenu")]
+#[description = r"Create a role menu that uses buttons or a select menu instead of reactions. All roles in the list must already be inside the set. Empty = all assignable roles.
+With `buttons`, each role gets a toggle button. With `select`, members pick their roles from a list; `select:min-max` limits how many roles can be picked at once."]
+#[usage = "{buttons | select | select:min-max} / {title} / [role] / [role] / ..."]
+#[example = "select:0-1 / Colors / Red / Green / Blue"]
+#[min_args(2)]
+#[required_permissions(MANAGE_ROLES)]
+#[only_in(guilds)]
+async fn rolemenu(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let kind = args.single_quoted::<String>()?;
+    let title = args.single_quoted::<String>()?;
+    let rolenames = args
+        .iter::<String>()
+        .quoted()
+        .collect::<Result<Vec<_>, _>>()?;
+    let roles = match assignable_roles(ctx, m, rolenames).await? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    if roles.is_empty() || roles.len() > MAX_MENU_ROLES {
+        m.reply(
+            &ctx,
+            format!("A role menu needs between 1 and {} roles.", MAX_MENU_ROLES),
+        )
+        .await?;
+        return Ok(());
+    }
+    let kind = match parse_kind(&kind, roles.len()) {
+        Some(v) => v,
+        None => {
+            m.reply(&ctx, format!("Invalid menu kind `{}`. Use `buttons`, `select` or `select:min-max` with 0 ≤ min ≤ max ≤ {}.", kind, roles.len())).await?;
+            return Ok(());
+        }
+    };
+
+    let guild_id = m.guild_id.unwrap();
+    let msg = m
+        .channel_id
+        .send_message(&ctx, render_menu(&title, kind, &roles))
+        .await?;
+    RoleMenu {
+        id: msg.id,
+        channel: msg.channel_id,
+        title,
+        roles: roles.iter().map(|(r, _)| r.id).collect(),
+        kind,
+    }
+    .store(&sql(ctx).await, guild_id)
+    .await?;
+    Ok(())
+}
+
+/// Parses `buttons`, `select` or `select:min-max`.
+fn parse_kind(kind: &str, roles: usize) -> Option<RoleMenuKind> {
+    let kind = kind.to_lowercase();
+    match kind.split_once(':') {
+        None if kind == "buttons" => Some(RoleMenuKind::Buttons),
+        None if kind == "select" => Some(RoleMenuKind::Select {
+            min: 0,
+            max: roles as u8,
+        }),
+        Some(("select", range)) => {
+            let (min, max) = range.split_once('-')?;
+            let (min, max) = (
+                min.trim().parse::<u8>().ok()?,
+                max.trim().parse::<u8>().ok()?,
+            );
+            (min <= max && max >= 1 && max as usize <= roles)
+                .then_some(RoleMenuKind::Select { min, max })
+        }
+        _ => None,
+    }
+}
+
+/// Renders the role menu message.
+fn render_menu(title: &str, kind: RoleMenuKind, roles: &[(Role, DiscordRole)]) -> CreateMessage {
+    let mut content = MessageBuilder::new();
+    content
+        .push_bold("Role Menu:")
+        .push(" ")
+        .push_bold_line_safe(title)
+        .push_line(match kind {
+            RoleMenuKind::Buttons => "Click a button to toggle a role.",
+            RoleMenuKind::Select { .. } => "Pick your roles from the list below.",
+        })
+        .push_line("");
+    for (role, discord_role) in roles {
+        if let Some(emoji) = &role.reaction {
+            content.push(emoji.to_string()).push(" ");
+        }
+        content
+            .push_bold_safe(&discord_role.name)
+            .push(": ")
+            .push_safe(&role.description);
+        if let Some(group) = &role.group {
+            content.push(" (group ").push_italic_safe(group).push(")");
+        }
+        if let Some(requires) = role.requires {
+            content
+                .push(" (requires ")
+                .push(requires.mention().to_string())
+                .push(")");
+        }
+        content.push_line("");
+    }
+
+    let components = match kind {
+        RoleMenuKind::Buttons => roles
+            .chunks(5)
+            .map(|row| {
+                CreateActionRow::Buttons(
+                    row.iter()
+                        .map(|(role, discord_role)| {
+                            let button =
+                                CreateButton::new(format!("{}{}", MENU_BUTTON_PREFIX, role.id))
+                                    .label(discord_role.name.chars().take(80).collect::<String>())
+                                    .style(serenity::all::ButtonStyle::Secondary);
+                            match &role.reaction {
+                                Some(emoji) => button.emoji(emoji.clone()),
+                                None => button,
+                            }
+                        })
+                        .collect(),
+                )
+            })
+            .collect(),
+        RoleMenuKind::Select { min, max } => {
+            let options = roles
+                .iter()
+                .map(|(role, discord_role)| {
+                    let option = CreateSelectMenuOption::new(
+                        discord_role.name.chars().take(100).collect::<String>(),
+                        role.id.to_string(),
+                    )
+                    .description(role.description.chars().take(100).collect::<String>());
+                    match &role.reaction {
+                        Some(emoji) => option.emoji(emoji.clone()),
+                        None => option,
+                    }
+                })
+                .collect();
+            vec![CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(MENU_SELECT, CreateSelectMenuKind::String { options })
+                    .placeholder("Pick your roles")
+                    .min_values(min)
+                    .max_values(max),
+            )]
+        }
+    };
+    CreateMessage::new()
+        .content(content.build())
+        .components(components)
+}
+
+/// Remembers when each member last toggled each role from a role menu.
+#[derive(Debug, Default)]
+pub struct RoleCooldowns(DashMap<(UserId, RoleId), Instant>);
+
+impl TypeMapKey for RoleCooldowns {
+    type Value = RoleCooldowns;
+}
+
+impl RoleCooldowns {
+    /// Returns the time left before the member can toggle the role again.
+    fn remaining(&self, user: UserId, role: &Role) -> Option<Duration> {
+        let cooldown = Duration::from_secs(role.cooldown?);
+        let last = *self.0.get(&(user, role.id))?;
+        cooldown
+            .checked_sub(last.elapsed())
+            .filter(|v| !v.is_zero())
+    }
+
+    fn touch(&self, user: UserId, role: RoleId) {
+        self.0.insert((user, role), Instant::now());
+    }
+}
+
+/// What the member picked from the menu.
+enum Pick {
+    /// A button was clicked.
+    Toggle(RoleId),
+    /// The roles selected in the select menu.
+    Set(Vec<RoleId>),
+}
+
+/// Handles clicks and selections on role menus.
+pub fn handle_role_menu<'a>(
+    ctx: &'a Context,
+    interaction: &'a Interaction,
+) -> Pin<Box<dyn future::Future<Output = Result<()>> + Send + 'a>> {
+    Box::pin(async move {
+        let Some(comp) = interaction.as_message_component() else {
+            return Ok(());
+        };
+        let pick = match &comp.data.kind {
+            ComponentInteractionDataKind::Button => {
+                match comp
+                    .data
+                    .custom_id
+                    .strip_prefix(MENU_BUTTON_PREFIX)
+                    .and_then(|id| id.parse::<u64>().ok())
+                {
+                    Some(id) => Pick::Toggle(RoleId::new(id)),
+                    None => return Ok(()),
+                }
+            }
+            ComponentInteractionDataKind::StringSelect { values }
+                if comp.data.custom_id == MENU_SELECT =>
+            {
+                Pick::Set(
+                    values
+                        .iter()
+                        .filter_map(|v| v.parse::<u64>().ok())
+                        .map(RoleId::new)
+                        .collect(),
+                )
+            }
+            _ => return Ok(()),
+        };
+        let (Some(guild), Some(member)) = (comp.guild_id, comp.member.as_ref()) else {
+            return Ok(());
+        };
+        comp.create_response(
+            &ctx,
+            CreateInteractionResponse::Defer(
+                CreateInteractionResponseMessage::new().ephemeral(true),
+            ),
+        )
+        .await?;
+        let reply = apply_pick(ctx, guild, comp.message.id, member, pick)
+            .await
+            .unwrap_or_else(|e| format!("Cannot update your roles: {}", e));
+        comp.edit_response(&ctx, EditInteractionResponse::new().content(reply))
+            .await?;
+        Ok(())
+    })
+}
+
+/// The changes a pick makes to the member's roles.
+#[derive(Debug, PartialEq, Eq)]
+struct RoleChanges {
+    add: Vec<RoleId>,
+    remove: Vec<RoleId>,
+    /// The roles that cannot be changed, with the reason why.
+    skipped: Vec<String>,
+}
+
+/// Works out the changes of the pick to the `current` roles of a member,
+/// respecting groups, prerequisites and cooldowns.
+/// `cooldown` gives the reason a role cannot be changed yet, if it is on cooldown.
+/// Returns `None` if the picked role is no longer in the menu.
+fn role_changes(
+    current: &HashSet<RoleId>,
+    menu: &[RoleId],
+    assignables: &HashMap<RoleId, Role>,
+    pick: Pick,
+    cooldown: impl Fn(&Role) -> Option<String>,
+) -> Option<RoleChanges> {
+    let in_menu = |r: &RoleId| menu.contains(r) && assignables.contains_key(r);
+
+    let (mut add, mut remove): (Vec<RoleId>, Vec<RoleId>) = match pick {
+        Pick::Toggle(role) if !in_menu(&role) => return None,
+        Pick::Toggle(role) if current.contains(&role) => (vec![], vec![role]),
+        Pick::Toggle(role) => (vec![role], vec![]),
+        Pick::Set(picked) => {
+            let picked: Vec<RoleId> = picked.into_iter().filter(|r| in_menu(r)).collect();
+            (
+                picked
+                    .iter()
+                    .copied()
+                    .filter(|r| !current.contains(r))
+                    .collect(),
+                menu.iter()
+                    .copied()
+                    .filter(|r| current.contains(r) && in_menu(r) && !picked.contains(r))
+                    .collect(),
+            )
+        }
+    };
+
+    let mut skipped = Vec::new();
+    // Only one role of each group can be picked.
+    let mut groups = HashSet::new();
+    add.retain(|r| match &assignables[r].group {
+        Some(group) if !groups.insert(group.clone()) => {
+            skipped.push(format!(
+                "{} (only one role of group **{}** can be held)",
+                r.mention(),
+                group
+            ));
+            false
+        }
+        _ => true,
+    });
+    // Picking a role swaps out the other roles of its group.
+    let swaps = |r: &RoleId| -> Vec<RoleId> {
+        let Some(group) = &assignables[r].group else {
+            return vec![];
+        };
+        current
+            .iter()
+            .copied()
+            .filter(|other| {
+                other != r
+                    && assignables
+                        .get(other)
+                        .is_some_and(|o| o.group.as_ref() == Some(group))
+            })
+            .collect()
+    };
+    // Cooldowns apply to every role that changes, including the swapped out ones.
+    let cooldown = |r: &RoleId| cooldown(&assignables[r]);
+    remove.retain(|r| match cooldown(r) {
+        Some(why) => {
+            skipped.push(format!("{} ({})", r.mention(), why));
+            false
+        }
+        None => true,
+    });
+    add.retain(|r| {
+        let why = cooldown(r).or_else(|| {
+            swaps(r).iter().find_map(|other| {
+                cooldown(other).map(|why| format!("{} is {}", other.mention(), why))
+            })
+        });
+        match why {
+            Some(why) => {
+                skipped.push(format!("{} ({})", r.mention(), why));
+                false
+            }
+            None => true,
+        }
+    });
+    // Prerequisites are checked against the roles the member ends up with.
+    loop {
+        let removed: HashSet<RoleId> = remove
+            .iter()
+            .copied()
+            .chain(add.iter().flat_map(swaps))
+            .collect();
+        let has = |r: &RoleId| (current.contains(r) || add.contains(r)) && !removed.contains(r);
+        let requires = |r: &RoleId| assignables.get(r).and_then(|r| r.requires);
+        // New roles need their prerequisites...
+        if let Some(i) = add
+            .iter()
+            .position(|r| requires(r).is_some_and(|q| !has(&q)))
+        {
+            let r = add.remove(i);
+            skipped.push(format!(
+                "{} (requires {})",
+                r.mention(),
+                requires(&r).unwrap().mention()
+            ));
+            continue;
+        }
+        // ...and prerequisites of the kept roles cannot be dropped.
+        let broken = current.iter().filter(|r| has(r)).find_map(|r| {
+            let required = requires(r)?;
+            (current.contains(&required) && !has(&required)).then_some((*r, required))
+        });
+        let Some((role, required)) = broken else {
+            break;
+        };
+        if remove.contains(&required) {
+            remove.retain(|r| *r != required);
+            skipped.push(format!(
+                "{} (required by {})",
+                required.mention(),
+                role.mention()
+            ));
+        } else {
+            let i = add
+                .iter()
+                .position(|r| swaps(r).contains(&required))
+                .unwrap();
+            let r = add.remove(i);
+            skipped.push(format!(
+                "{} (would remove {}, which is required by {})",
+                r.mention(),
+                required.mention(),
+                role.mention()
+            ));
+        }
+    }
+    for other in add.iter().flat_map(swaps).collect::<Vec<_>>() {
+        if !remove.contains(&other) {
+            remove.push(other);
+        }
+    }
+
+    Some(RoleChanges {
+        add,
+        remove,
+        skipped,
+    })
+}
+
+/// Applies the pick to the member's roles, respecting groups, prerequisites and cooldowns.
+/// Returns a summary for the member.
+async fn apply_pick(
+    ctx: &Context,
+    guild: GuildId,
+    message: MessageId,
+    member: &Member,
+    pick: Pick,
+) -> Result<String> {
+    let pool = sql(ctx).await;
+    let menu = match RoleMenu::get(&pool, message).await? {
+        Some((menu_guild, menu)) if menu_guild == guild => menu,
+        _ => return Ok("This role menu is no longer available.".to_owned()),
+    };
+    let assignables: HashMap<RoleId, Role> = Role::list(&pool, guild).await?;
+    let data = ctx.data.read().await;
+    let cooldowns = data.get::<RoleCooldowns>().unwrap();
+    let user = member.user.id;
+    let current: HashSet<RoleId> = member.roles.iter().copied().collect();
+    let cooldown = |r: &Role| {
+        cooldowns.remaining(user, r).map(|left| {
+            let until = chrono::Utc::now() + chrono::Duration::from_std(left).unwrap_or_default();
+            format!("on cooldown, try again {}", until.format("<t:%s:R>"))
+        })
+    };
+    let Some(RoleChanges {
+        add,
+        remove,
+        skipped,
+    }) = role_changes(&current, &menu.roles, &assignables, pick, cooldown)
+    else {
+        return Ok("This role is no longer in the menu.".to_owned());
+    };
+
+    for r in &add {
+        member.add_role(&ctx, r).await?;
+        cooldowns.touch(user, *r);
+    }
+    for r in &remove {
+        member.remove_role(&ctx, r).await?;
+        cooldowns.touch(user, *r);
+    }
+
+    let list = |roles: &[RoleId]| {
+        roles
+            .iter()
+            .map(|r| r.mention().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut reply = Vec::new();
+    if !add.is_empty() {
+        reply.push(format!("Added: {}", list(&add)));
+    }
+    if !remove.is_empty() {
+        reply.push(format!("Removed: {}", list(&remove)));
+    }
+    if !skipped.is_empty() {
+        reply.push(format!("Skipped: {}", skipped.join(", ")));
+    }
+    if reply.is_empty() {
+        reply.push("Your roles are unchanged.".to_owned());
+    }
+    Ok(reply.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: RoleId = RoleId::new(1);
+    const BLUE: RoleId = RoleId::new(2);
+    const MEMBER: RoleId = RoleId::new(3);
+    const VIP: RoleId = RoleId::new(4);
+
+    fn assignables() -> HashMap<RoleId, Role> {
+        let role = |id: RoleId, group: Option<&str>, requires: Option<RoleId>| Role {
+            id,
+            description: String::new(),
+            reaction: None,
+            group: group.map(|g| g.to_owned()),
+            requires,
+            cooldown: Some(60),
+        };
+        [
+            role(RED, Some("color"), None),
+            role(BLUE, Some("color"), None),
+            role(MEMBER, None, None),
+            role(VIP, None, Some(MEMBER)),
+        ]
+        .into_iter()
+        .map(|r| (r.id, r))
+        .collect()
+    }
+
+    fn changes(current: &[RoleId], pick: Pick, on_cooldown: &[RoleId]) -> RoleChanges {
+        role_changes(
+            &current.iter().copied().collect(),
+            &[RED, BLUE, MEMBER, VIP],
+            &assignables(),
+            pick,
+            |r: &Role| {
+                on_cooldown
+                    .contains(&r.id)
+                    .then(|| "on cooldown".to_owned())
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn swaps_roles_of_a_group() {
+        let c = changes(&[RED], Pick::Toggle(BLUE), &[]);
+        assert_eq!((c.add, c.remove), (vec![BLUE], vec![RED]));
+        assert!(c.skipped.is_empty());
+
+        let c = changes(&[], Pick::Set(vec![RED, BLUE]), &[]);
+        assert_eq!((c.add, c.remove), (vec![RED], vec![]));
+        assert_eq!(c.skipped.len(), 1);
+    }
+
+    #[test]
+    fn cooldowns_apply_to_swapped_roles() {
+        let c = changes(&[RED], Pick::Toggle(BLUE), &[RED]);
+        assert!(c.add.is_empty() && c.remove.is_empty());
+        assert_eq!(
+            c.skipped,
+            vec![format!(
+                "{} ({} is on cooldown)",
+                BLUE.mention(),
+                RED.mention()
+            )]
+        );
+
+        let c = changes(&[RED], Pick::Toggle(RED), &[RED]);
+        assert!(c.remove.is_empty());
+        assert_eq!(c.skipped.len(), 1);
+    }
+
+    #[test]
+    fn prerequisites_are_checked_on_the_final_roles() {
+        let c = changes(&[], Pick::Toggle(VIP), &[]);
+        assert!(c.add.is_empty());
+        assert_eq!(
+            c.skipped,
+            vec![format!("{} (requires {})", VIP.mention(), MEMBER.mention())]
+        );
+
+        // Picked together with its prerequisite.
+        let c = changes(&[], Pick::Set(vec![MEMBER, VIP]), &[]);
+        assert_eq!(c.add, vec![MEMBER, VIP]);
+
+        // A prerequisite of a kept role cannot be dropped...
+        let c = changes(&[MEMBER, VIP], Pick::Set(vec![VIP]), &[]);
+        assert!(c.remove.is_empty());
+        assert_eq!(
+            c.skipped,
+            vec![format!(
+                "{} (required by {})",
+                MEMBER.mention(),
+                VIP.mention()
+            )]
+        );
+        // ...but can be dropped along with it.
+        let c = changes(&[MEMBER, VIP], Pick::Set(vec![]), &[]);
+        assert_eq!(c.remove, vec![MEMBER, VIP]);
+    }
+
+    #[test]
+    fn rejects_roles_not_in_the_menu() {
+        let c = role_changes(
+            &HashSet::new(),
+            &[RED],
+            &assignables(),
+            Pick::Toggle(BLUE),
+            |_: &Role| None,
+        );
+        assert_eq!(c, None);
+    }
+}
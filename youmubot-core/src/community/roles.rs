@@ -13,7 +13,12 @@ use youmubot_prelude::table_format::Align::Right;
 use youmubot_prelude::table_format::{table_formatting, Align};
 use youmubot_prelude::*;
 
-use crate::db::Roles as DB;
+use crate::db::Role as AssignableRole;
+
+/// Gets the SQL pool from the context.
+pub(super) async fn sql(ctx: &Context) -> youmubot_db_sql::Pool {
+    ctx.data.read().await.get::<SQLClient>().unwrap().clone()
+}
 
 #[command("listroles")]
 #[description = "List all available roles in the server."]
@@ -21,13 +26,8 @@ use crate::db::Roles as DB;
 #[only_in(guilds)]
 async fn list(ctx: &Context, m: &Message, _: Args) -> CommandResult {
     let guild_id = m.guild_id.unwrap(); // only_in(guilds)
-    let data = ctx.data.read().await;
-    let db = DB::open(&data);
-    let roles = db
-        .borrow()?
-        .get(&guild_id)
-        .filter(|v| !v.roles.is_empty())
-        .cloned();
+    let roles =
+        Some(AssignableRole::list(&sql(ctx).await, guild_id).await?).filter(|v| !v.is_empty());
     match roles {
         None => {
             m.reply(&ctx, "No roles available for assigning.").await?;
@@ -35,7 +35,6 @@ async fn list(ctx: &Context, m: &Message, _: Args) -> CommandResult {
         Some(v) => {
             let roles = guild_id.to_partial_guild(&ctx).await?.roles;
             let roles: Vec<_> = v
-                .roles
                 .into_iter()
                 .filter_map(|(_, role)| roles.get(&role.id).cloned().map(|r| (r, role.description)))
                 .collect();
@@ -109,11 +108,9 @@ async fn toggle(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
             m.reply(&ctx, "No such role exists").await?;
         }
         Some(role)
-            if !DB::open(&*ctx.data.read().await)
-                .borrow()?
-                .get(&guild_id)
-                .map(|g| g.roles.contains_key(&role.id))
-                .unwrap_or(false) =>
+            if !AssignableRole::list(&sql(ctx).await, guild_id)
+                .await?
+                .contains_key(&role.id) =>
         {
             m.reply(&ctx, "This role is not self-assignable. Check the `listroles` command to see which role can be assigned.").await?;
         }
@@ -143,7 +140,6 @@ async fn toggle(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
 #[only_in(guilds)]
 async fn add(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let role = args.single_quoted::<String>()?;
-    let data = ctx.data.read().await;
     let description = args.single_quoted::<String>()?;
     let reaction = match args.single::<ReactionType>() {
         Ok(v) => match &v {
@@ -168,19 +164,21 @@ async fn add(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
             m.reply(&ctx, "No such role exists").await?;
         }
         Some(role) => {
-            DB::open(&data)
-                .borrow_mut()?
-                .entry(guild_id)
-                .or_default()
-                .roles
-                .insert(
-                    role.id,
-                    crate::db::Role {
-                        id: role.id,
-                        description,
-                        reaction,
-                    },
-                );
+            let pool = sql(ctx).await;
+            // Keep the role menu options of the old entry.
+            let old = AssignableRole::list(&pool, guild_id)
+                .await?
+                .remove(&role.id);
+            AssignableRole {
+                id: role.id,
+                description,
+                reaction,
+                group: old.as_ref().and_then(|r| r.group.clone()),
+                requires: old.as_ref().and_then(|r| r.requires),
+                cooldown: old.as_ref().and_then(|r| r.cooldown),
+            }
+            .store(&pool, guild_id)
+            .await?;
             m.react(&ctx, '👌').await?;
         }
     };
@@ -196,7 +194,6 @@ async fn add(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
 #[only_in(guilds)]
 async fn remove(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let role = args.single_quoted::<String>()?;
-    let data = ctx.data.read().await;
     let guild_id = m.guild_id.unwrap();
     let roles = guild_id.to_partial_guild(&ctx).await?.roles;
     let role = role_from_string(&role, &roles);
@@ -204,29 +201,114 @@ async fn remove(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
         None => {
             m.reply(&ctx, "No such role exists").await?;
         }
-        Some(role)
-            if !DB::open(&data)
-                .borrow()?
-                .get(&guild_id)
-                .map(|g| g.roles.contains_key(&role.id))
-                .unwrap_or(false) =>
-        {
-            m.reply(&ctx, "This role does not exist in the assignable list.")
-                .await?;
-        }
         Some(role) => {
-            DB::open(&data)
-                .borrow_mut()?
-                .entry(guild_id)
-                .or_default()
-                .roles
-                .remove(&role.id);
-            m.react(&ctx, '👌').await?;
+            if AssignableRole::remove(&sql(ctx).await, role.id).await? {
+                m.react(&ctx, '👌').await?;
+            } else {
+                m.reply(&ctx, "This role does not exist in the assignable list.")
+                    .await?;
+            }
         }
     };
     Ok(())
 }
 
+#[command("rolegroup")]
+#[description = "Put assignable roles into an exclusive group: a member can only hold one role of each group, and picking another one from a role menu swaps them. Use `none` as the group to take the roles out of their groups."]
+#[usage = "{group-name | none} / {role-name-or-id} / [role-name-or-id] / ..."]
+#[example = "Colors / Red / Green / Blue"]
+#[min_args(2)]
+#[required_permissions(MANAGE_ROLES)]
+#[only_in(guilds)]
+async fn rolegroup(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let group = args.single_quoted::<String>()?;
+    let group = Some(group).filter(|g| !g.eq_ignore_ascii_case("none"));
+    let rolenames = args
+        .iter::<String>()
+        .quoted()
+        .collect::<Result<Vec<_>, _>>()?;
+    let roles = match assignable_roles(ctx, m, rolenames).await? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    update_roles(ctx, m, roles.iter().map(|(r, _)| r.id), |r| {
+        r.group = group.clone()
+    })
+    .await
+}
+
+#[command("rolerequire")]
+#[description = "Require members to have another role before picking an assignable role from a role menu. Leave the required role empty to remove the requirement."]
+#[usage = "{role-name-or-id} / [required-role-name-or-id = none]"]
+#[example = "Raid Leader / Verified"]
+#[min_args(1)]
+#[max_args(2)]
+#[required_permissions(MANAGE_ROLES)]
+#[only_in(guilds)]
+async fn rolerequire(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let role = args.single_quoted::<String>()?;
+    let role = match assignable_roles(ctx, m, vec![role]).await? {
+        Some(v) => v[0].0.id,
+        None => return Ok(()),
+    };
+    let requires = match args.single_quoted::<String>().ok() {
+        Some(name) => {
+            let roles = m.guild_id.unwrap().to_partial_guild(&ctx).await?.roles;
+            match role_from_string(&name, &roles) {
+                Some(r) => Some(r.id),
+                None => {
+                    m.reply(&ctx, format!("Role `{}` not found", name)).await?;
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+    update_roles(ctx, m, std::iter::once(role), |r| r.requires = requires).await
+}
+
+#[command("rolecooldown")]
+#[description = "Set how long a member has to wait between two toggles of an assignable role from a role menu. Leave the duration empty to remove the cooldown."]
+#[usage = "{role-name-or-id} / [duration = none]"]
+#[example = "Red / 1h"]
+#[min_args(1)]
+#[max_args(2)]
+#[required_permissions(MANAGE_ROLES)]
+#[only_in(guilds)]
+async fn rolecooldown(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let role = args.single_quoted::<String>()?;
+    let role = match assignable_roles(ctx, m, vec![role]).await? {
+        Some(v) => v[0].0.id,
+        None => return Ok(()),
+    };
+    let cooldown = if args.is_empty() {
+        None
+    } else {
+        Some(args.single::<Duration>()?.0.as_secs())
+    };
+    update_roles(ctx, m, std::iter::once(role), |r| r.cooldown = cooldown).await
+}
+
+/// Applies `f` to the given assignable roles and acknowledges the command.
+async fn update_roles(
+    ctx: &Context,
+    m: &Message,
+    roles: impl Iterator<Item = RoleId>,
+    mut f: impl FnMut(&mut AssignableRole),
+) -> CommandResult {
+    let guild_id = m.guild_id.unwrap();
+    let pool = sql(ctx).await;
+    let mut list = AssignableRole::list(&pool, guild_id).await?;
+    for role in roles {
+        if let Some(role) = list.get_mut(&role) {
+            f(role);
+            role.store(&pool, guild_id).await?;
+        }
+    }
+    m.react(&ctx, '👌').await?;
+    Ok(())
+}
+
 /// Parse a string as a role.
 fn role_from_string(role: &str, roles: &std::collections::HashMap<RoleId, Role>) -> Option<Role> {
     match role.parse::<u64>() {
@@ -263,21 +345,45 @@ async fn parse(
     ctx: &Context,
     m: &Message,
     mut args: Args,
-) -> Result<Option<(String, Vec<(crate::db::Role, Role, ReactionType)>)>> {
+) -> Result<Option<(String, Vec<(AssignableRole, Role, ReactionType)>)>> {
     let title = args.single_quoted::<String>().unwrap();
-    let data = ctx.data.read().await;
-    let guild_id = m.guild_id.unwrap();
-    let assignables = DB::open(&data)
-        .borrow()?
-        .get(&guild_id)
-        .filter(|v| !v.roles.is_empty())
-        .map(|r| r.roles.clone())
-        .unwrap_or_default();
-    let mut rolenames = args
+    let rolenames = args
         .iter::<String>()
         .quoted()
         .collect::<Result<Vec<_>, _>>()
         .unwrap();
+    let mut roles = Vec::new();
+    for (role, discord_role) in match assignable_roles(ctx, m, rolenames).await? {
+        Some(v) => v,
+        None => return Ok(None),
+    } {
+        match role.reaction.clone() {
+            Some(emote) => roles.push((role, discord_role, emote)),
+            None => {
+                m.reply(
+                    &ctx,
+                    format!(
+                        "Role `{}` does not have a assignable emote.",
+                        discord_role.name
+                    ),
+                )
+                .await?;
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some((title, roles)))
+}
+
+/// Resolves the given role names into assignable roles, replying with an error if one is not.
+/// Empty = all assignable roles.
+pub(super) async fn assignable_roles(
+    ctx: &Context,
+    m: &Message,
+    mut rolenames: Vec<String>,
+) -> Result<Option<Vec<(AssignableRole, Role)>>> {
+    let guild_id = m.guild_id.unwrap();
+    let assignables = AssignableRole::list(&sql(ctx).await, guild_id).await?;
     let rolelist = guild_id.to_partial_guild(&ctx).await?.roles;
     let mut roles = Vec::new();
     if rolenames.is_empty() {
@@ -292,27 +398,16 @@ async fn parse(
                 return Ok(None);
             }
         };
-        let role = match assignables.get(&role.id) {
-            Some(r) => match &r.reaction {
-                Some(emote) => (r.clone(), role.clone(), emote.clone()),
-                None => {
-                    m.reply(
-                        &ctx,
-                        format!("Role `{}` does not have a assignable emote.", rolename),
-                    )
-                    .await?;
-                    return Ok(None);
-                }
-            },
+        match assignables.get(&role.id) {
+            Some(r) => roles.push((r.clone(), role)),
             None => {
                 m.reply(&ctx, format!("Role `{}` is not assignable.", rolename))
                     .await?;
                 return Ok(None);
             }
         };
-        roles.push(role);
     }
-    Ok(Some((title, roles)))
+    Ok(Some(roles))
 }
 
 #[command("updaterolemessage")]
@@ -342,7 +437,7 @@ async fn updaterolemessage(ctx: &Context, m: &Message, args: Args) -> CommandRes
     if data
         .get::<ReactionWatchers>()
         .unwrap()
-        .remove(ctx, guild_id, message.id)
+        .remove(message.id)
         .await?
     {
         data.get::<ReactionWatchers>()
@@ -359,14 +454,14 @@ async fn updaterolemessage(ctx: &Context, m: &Message, args: Args) -> CommandRes
 }
 
 #[command("rmrolemessage")]
-#[description = "Delete a role message handler."]
+#[description = "Delete a role message handler or a role menu."]
 #[usage = "(reply to the message to delete)"]
 #[num_args(0)]
 #[required_permissions(MANAGE_ROLES)]
 #[only_in(guilds)]
 async fn rmrolemessage(ctx: &Context, m: &Message, _args: Args) -> CommandResult {
     let data = ctx.data.read().await;
-    let guild_id = m.guild_id.unwrap();
+    let pool = data.get::<SQLClient>().unwrap();
 
     let message = match &m.referenced_message {
         Some(m) => m,
@@ -379,8 +474,9 @@ async fn rmrolemessage(ctx: &Context, m: &Message, _args: Args) -> CommandResult
     if !data
         .get::<ReactionWatchers>()
         .unwrap()
-        .remove(ctx, guild_id, message.id)
+        .remove(message.id)
         .await?
+        && !crate::db::remove_role_message(pool, message.id).await?
     {
         m.reply(&ctx, "Message does not come with a reaction handler")
             .await
@@ -403,30 +499,25 @@ mod reaction_watcher {
         },
     };
 
-    use youmubot_prelude::*;
+    use youmubot_db_sql::Pool;
+    use youmubot_prelude::{shutdown::Shutdown, *};
 
-    use crate::db::{Role, RoleMessage, Roles};
+    use crate::db::{remove_role_message, Role, RoleMessage};
 
     /// A set of watchers.
     #[derive(Debug)]
     pub struct Watchers {
+        pool: Pool,
         watchers: DashMap<MessageId, Watcher>,
 
-        init: Mutex<Vec<(GuildId, MessageId, RoleMessage)>>,
+        init: Mutex<Vec<(GuildId, RoleMessage)>>,
     }
 
     impl Watchers {
-        pub fn new(data: &TypeMap) -> Result<Self> {
-            let init = Roles::open(data)
-                .borrow()?
-                .iter()
-                .flat_map(|(&guild, rs)| {
-                    rs.reaction_messages
-                        .iter()
-                        .map(move |(m, r)| (guild, *m, r.clone()))
-                })
-                .collect();
+        pub async fn new(pool: Pool) -> Result<Self> {
+            let init = RoleMessage::all(&pool).await?;
             Ok(Self {
+                pool,
                 init: Mutex::new(init),
                 watchers: DashMap::new(),
             })
@@ -435,7 +526,7 @@ mod reaction_watcher {
             let mut init = self.init.lock().await;
             for (msg, watcher) in init
                 .drain(..)
-                .map(|(guild, msg, rm)| (msg, Watcher::spawn(ctx.clone(), guild, rm.id)))
+                .map(|(guild, rm)| (rm.id, Watcher::spawn(ctx.clone(), guild, rm)))
             {
                 self.watchers.insert(msg, watcher);
             }
@@ -493,42 +584,24 @@ mod reaction_watcher {
                 msg.react(&ctx, emoji.clone()).await.ok();
             }
             // Store the message into the list.
-            {
-                let data = ctx.data.read().await;
-                Roles::open(&data)
-                    .borrow_mut()?
-                    .entry(guild)
-                    .or_default()
-                    .reaction_messages
-                    .insert(
-                        msg.id,
-                        RoleMessage {
-                            id: msg.id,
-                            title,
-                            roles: roles.into_iter().map(|(a, _, b)| (a, b)).collect(),
-                        },
-                    );
-            }
+            let rm = RoleMessage {
+                id: msg.id,
+                channel: Some(msg.channel_id),
+                title,
+                roles: roles.into_iter().map(|(a, _, b)| (a.id, b)).collect(),
+            };
+            rm.store(&self.pool, guild).await?;
             // Spawn the handler
-            self.watchers
-                .insert(msg.id, Watcher::spawn(ctx, guild, msg.id));
+            self.watchers.insert(msg.id, Watcher::spawn(ctx, guild, rm));
             Ok(())
         }
 
-        pub async fn remove(
-            &self,
-            ctx: &Context,
-            guild: GuildId,
-            message: MessageId,
-        ) -> Result<bool> {
-            let data = ctx.data.read().await;
-            Roles::open(&data)
-                .borrow_mut()?
-                .entry(guild)
-                .or_default()
-                .reaction_messages
-                .remove(&message);
-            Ok(self.watchers.remove(&message).is_some())
+        pub async fn remove(&self, message: MessageId) -> Result<bool> {
+            if self.watchers.remove(&message).is_none() {
+                return Ok(false);
+            }
+            remove_role_message(&self.pool, message).await?;
+            Ok(true)
         }
     }
 
@@ -543,13 +616,14 @@ mod reaction_watcher {
     }
 
     impl Watcher {
-        pub fn spawn(ctx: Context, guild: GuildId, message: MessageId) -> Self {
+        pub fn spawn(ctx: Context, guild: GuildId, message: RoleMessage) -> Self {
             let (send, recv) = flume::bounded(0);
             tokio::spawn(Self::handle(ctx, recv, guild, message));
             Watcher { cancel: send }
         }
 
-        async fn handle(ctx: Context, recv: Receiver<()>, guild: GuildId, message: MessageId) {
+        async fn handle(ctx: Context, recv: Receiver<()>, guild: GuildId, rm: RoleMessage) {
+            let message = rm.id;
             let mut recv = recv.into_recv_async();
             let mut collect = serenity::collector::collect(&ctx.shard, move |event| {
                 match event {
@@ -562,20 +636,24 @@ mod reaction_watcher {
             // serenity::collector::CollectReaction::new(&ctx)
             //     .message_id(message)
             //     .removed(true)
+            let shutdown = ctx
+                .data
+                .read()
+                .await
+                .get::<Shutdown>()
+                .cloned()
+                .unwrap_or_default();
             loop {
-                let (reaction, is_add) = match future::select(recv, collect.next()).await {
-                    future::Either::Left(_) => break,
-                    future::Either::Right((r, new_recv)) => {
-                        recv = new_recv;
-                        match r {
-                            Some(r) => r,
-                            None => continue,
-                        }
-                    }
+                let (reaction, is_add) = tokio::select! {
+                    _ = &mut recv => break,
+                    _ = shutdown.started() => break,
+                    r = collect.next() => match r {
+                        Some(r) => r,
+                        None => continue,
+                    },
                 };
                 eprintln!("{:?} {}", reaction, is_add);
-                if let Err(e) = Self::handle_reaction(&ctx, guild, message, &reaction, is_add).await
-                {
+                if let Err(e) = Self::handle_reaction(&ctx, guild, &rm, &reaction, is_add).await {
                     eprintln!("Handling {:?}: {}", reaction, e);
                     break;
                 }
@@ -585,11 +663,10 @@ mod reaction_watcher {
         async fn handle_reaction(
             ctx: &Context,
             guild: GuildId,
-            message: MessageId,
+            rm: &RoleMessage,
             reaction: &Reaction,
             is_add: bool,
         ) -> Result<()> {
-            let data = ctx.data.read().await;
             // Collect user
             let user_id = match reaction.user_id {
                 Some(id) => id,
@@ -603,22 +680,13 @@ mod reaction_watcher {
                 return Ok(());
             }
             // Get the role list.
-            let role = Roles::open(&data)
-                .borrow()?
-                .get(&guild)
-                .ok_or_else(|| Error::msg("guild no longer has role list"))?
-                .reaction_messages
-                .get(&message)
-                .map(|msg| &msg.roles[..])
-                .ok_or_else(|| Error::msg("message is no longer a role list handler"))?
-                .iter()
-                .find_map(|(role, role_reaction)| {
-                    if &reaction.emoji == role_reaction {
-                        Some(role.id)
-                    } else {
-                        None
-                    }
-                });
+            let role = rm.roles.iter().find_map(|(role, role_reaction)| {
+                if &reaction.emoji == role_reaction {
+                    Some(*role)
+                } else {
+                    None
+                }
+            });
             let role = match role {
                 Some(id) => id,
                 None => return Ok(()),
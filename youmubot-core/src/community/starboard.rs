@@ -0,0 +1,393 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use dashmap::DashMap;
+use serenity::all::{
+    ChannelId, ChannelType, CreateEmbed, CreateEmbedAuthor, CreateMessage, EditMessage, Event,
+    GuildChannel, GuildId, Message, MessageId, PermissionOverwriteType, Permissions, ReactionType,
+};
+use youmubot_db_sql::{
+    models::starboard::{Starboard as StarboardModel, StarredMessage},
+    Pool,
+};
+use youmubot_prelude::*;
+
+use crate::HasCoreEnv;
+
+const STAR_COLOR: u32 = 0xffac33;
+
+/// The starboard settings of a guild.
+#[derive(Debug, Clone)]
+struct Starboard {
+    channel: ChannelId,
+    emoji: ReactionType,
+    threshold: u64,
+}
+
+/// Keeps the starboard settings of all guilds, and watches reactions for them.
+#[derive(Debug, Clone, Default)]
+pub struct Starboards {
+    boards: Arc<DashMap<GuildId, Starboard>>,
+    running: Arc<AtomicBool>,
+}
+
+impl TypeMapKey for Starboards {
+    type Value = Starboards;
+}
+
+impl Starboards {
+    pub async fn from_db(pool: &Pool) -> Result<Self> {
+        let boards = StarboardModel::all(pool)
+            .await?
+            .into_iter()
+            .filter_map(|b| {
+                Some((
+                    GuildId::new(b.guild_id as u64),
+                    Starboard {
+                        channel: ChannelId::new(b.channel_id as u64),
+                        emoji: ReactionType::try_from(b.emoji).ok()?,
+                        threshold: b.threshold as u64,
+                    },
+                ))
+            })
+            .collect();
+        Ok(Self {
+            boards: Arc::new(boards),
+            running: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Starts watching reactions, unless we already are.
+    pub async fn init(ctx: &Context) {
+        let (pool, boards) = {
+            let data = ctx.data.read().await;
+            let pool = data.get::<SQLClient>().unwrap().clone();
+            (pool, data.get::<Starboards>().unwrap().clone())
+        };
+        if boards.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            boards.watch(&ctx, &pool).await;
+            boards.running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    async fn watch(&self, ctx: &Context, pool: &Pool) {
+        let mut collect = serenity::collector::collect(&ctx.shard, |event| match event {
+            Event::ReactionAdd(r) => Some((
+                r.reaction.guild_id?,
+                r.reaction.channel_id,
+                r.reaction.message_id,
+                Some(r.reaction.emoji.clone()),
+            )),
+            Event::ReactionRemove(r) => Some((
+                r.reaction.guild_id?,
+                r.reaction.channel_id,
+                r.reaction.message_id,
+                Some(r.reaction.emoji.clone()),
+            )),
+            Event::ReactionRemoveEmoji(r) => Some((
+                r.reaction.guild_id?,
+                r.reaction.channel_id,
+                r.reaction.message_id,
+                Some(r.reaction.emoji.clone()),
+            )),
+            Event::ReactionRemoveAll(r) => Some((r.guild_id?, r.channel_id, r.message_id, None)),
+            _ => None,
+        });
+        // Reactions are handled one at a time, so a message is never reposted twice.
+        while let Some((guild, channel, message, emoji)) = collect.next().await {
+            if let Err(e) = self.handle(ctx, pool, guild, channel, message, emoji).await {
+                eprintln!("Starboard: handling message {}: {}", message, e);
+            }
+        }
+    }
+
+    async fn handle(
+        &self,
+        ctx: &Context,
+        pool: &Pool,
+        guild: GuildId,
+        channel: ChannelId,
+        message: MessageId,
+        emoji: Option<ReactionType>,
+    ) -> Result<()> {
+        let Some(board) = self.boards.get(&guild).map(|b| b.clone()) else {
+            return Ok(());
+        };
+        if channel == board.channel || emoji.is_some_and(|e| !same_emoji(&e, &board.emoji)) {
+            return Ok(());
+        }
+        let msg = channel.message(ctx, message).await?;
+        let count = msg
+            .reactions
+            .iter()
+            .find(|r| same_emoji(&r.reaction_type, &board.emoji))
+            .map(|r| r.count)
+            .unwrap_or(0);
+        let starred = StarredMessage::get(message.get() as i64, pool).await?;
+
+        match starred {
+            Some(starred) if count < board.threshold => {
+                board
+                    .channel
+                    .delete_message(ctx, MessageId::new(starred.starboard_message_id as u64))
+                    .await
+                    .pls_ok();
+                StarredMessage::delete(starred.message_id, pool).await?;
+            }
+            Some(starred) => {
+                if starred.count as u64 != count {
+                    board
+                        .channel
+                        .edit_message(
+                            ctx,
+                            MessageId::new(starred.starboard_message_id as u64),
+                            EditMessage::new().content(header(&board, count, channel)),
+                        )
+                        .await?;
+                    StarredMessage {
+                        count: count as i64,
+                        ..starred
+                    }
+                    .store(pool)
+                    .await?;
+                }
+            }
+            None if count >= board.threshold => {
+                if !can_repost(ctx, guild, channel, &board).await? {
+                    return Ok(());
+                }
+                let post = board
+                    .channel
+                    .send_message(
+                        ctx,
+                        CreateMessage::new()
+                            .content(header(&board, count, channel))
+                            .embed(repost(&msg)),
+                    )
+                    .await?;
+                StarredMessage {
+                    message_id: message.get() as i64,
+                    guild_id: guild.get() as i64,
+                    channel_id: channel.get() as i64,
+                    starboard_message_id: post.id.get() as i64,
+                    count: count as i64,
+                }
+                .store(pool)
+                .await?;
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    async fn set(&self, pool: &Pool, guild: GuildId, board: Starboard) -> Result<()> {
+        let model = StarboardModel {
+            guild_id: guild.get() as i64,
+            channel_id: board.channel.get() as i64,
+            emoji: board.emoji.to_string(),
+            threshold: board.threshold as i64,
+        };
+        let mut tx = pool.begin().await?;
+        // Reposts in the old channel are not kept track of anymore.
+        if self
+            .boards
+            .get(&guild)
+            .is_some_and(|b| b.channel != board.channel)
+        {
+            StarboardModel::delete(model.guild_id, &mut *tx).await?;
+        }
+        model.store(&mut *tx).await?;
+        tx.commit().await?;
+        self.boards.insert(guild, board);
+        Ok(())
+    }
+
+    async fn remove(&self, pool: &Pool, guild: GuildId) -> Result<bool> {
+        StarboardModel::delete(guild.get() as i64, pool).await?;
+        Ok(self.boards.remove(&guild).is_some())
+    }
+}
+
+/// Whether messages from the channel can be reposted to the board.
+///
+/// Messages from NSFW channels never are, and messages from channels hidden from @everyone
+/// only are if the board is hidden too.
+async fn can_repost(
+    ctx: &Context,
+    guild: GuildId,
+    channel: ChannelId,
+    board: &Starboard,
+) -> Result<bool> {
+    let guild_channel = |c: ChannelId| async move {
+        c.to_channel(ctx)
+            .await?
+            .guild()
+            .ok_or_else(|| Error::msg("not a server channel"))
+    };
+    let source = guild_channel(channel).await?;
+    let is_private_thread = source.kind == ChannelType::PrivateThread;
+    // Threads follow the channel they are in.
+    let source = match (source.kind, source.parent_id) {
+        (
+            ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread,
+            Some(parent),
+        ) => guild_channel(parent).await?,
+        _ => source,
+    };
+    if source.nsfw {
+        return Ok(false);
+    }
+    let cached = ctx
+        .cache
+        .guild(guild)
+        .and_then(|g| g.roles.get(&guild.everyone_role()).map(|r| r.permissions));
+    let everyone = match cached {
+        Some(p) => p,
+        None => guild
+            .roles(ctx)
+            .await?
+            .get(&guild.everyone_role())
+            .map(|r| r.permissions)
+            .unwrap_or_default(),
+    };
+    if !is_private_thread && visible_to_everyone(guild, everyone, &source) {
+        return Ok(true);
+    }
+    let board = guild_channel(board.channel).await?;
+    Ok(!visible_to_everyone(guild, everyone, &board))
+}
+
+/// Whether @everyone can view the channel, given their server-wide permissions.
+fn visible_to_everyone(guild: GuildId, everyone: Permissions, channel: &GuildChannel) -> bool {
+    if everyone.administrator() {
+        return true;
+    }
+    let permissions = channel
+        .permission_overwrites
+        .iter()
+        .find(|o| o.kind == PermissionOverwriteType::Role(guild.everyone_role()))
+        .map_or(everyone, |o| (everyone & !o.deny) | o.allow);
+    permissions.view_channel()
+}
+
+/// Compares two emojis, ignoring the names of custom emojis.
+fn same_emoji(a: &ReactionType, b: &ReactionType) -> bool {
+    match (a, b) {
+        (ReactionType::Custom { id: a, .. }, ReactionType::Custom { id: b, .. }) => a == b,
+        (ReactionType::Unicode(a), ReactionType::Unicode(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn header(board: &Starboard, count: u64, channel: ChannelId) -> String {
+    format!("{} **{}** | {}", board.emoji, count, channel.mention())
+}
+
+/// Builds the repost of a message: its content, a jump link and its attachments.
+fn repost(msg: &Message) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::new(&msg.author.name).icon_url(msg.author.face()))
+        .description(&msg.content)
+        .timestamp(msg.timestamp)
+        .color(STAR_COLOR);
+    let image = msg.attachments.iter().find(|a| {
+        a.content_type
+            .as_deref()
+            .is_some_and(|t| t.starts_with("image/"))
+    });
+    if let Some(image) = image {
+        embed = embed.image(&image.url);
+    }
+    let others = msg
+        .attachments
+        .iter()
+        .filter(|a| image.is_none_or(|i| i.id != a.id))
+        .map(|a| format!("[{}]({})", a.filename, a.url))
+        .collect::<Vec<_>>();
+    if !others.is_empty() {
+        embed = embed.field("Attachments", others.join("\n"), false);
+    }
+    embed.field(
+        "Source",
+        format!("[Jump to message!]({})", msg.link()),
+        false,
+    )
+}
+
+/// Starboard: repost messages with enough reactions to a channel.
+#[poise::command(
+    slash_command,
+    subcommands("set", "disable"),
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    category = "community"
+)]
+pub async fn starboard<U: HasCoreEnv>(_ctx: CmdContext<'_, U>) -> Result<()> {
+    Ok(())
+}
+
+/// Set up the starboard of this server.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+async fn set<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The channel to repost messages to"] channel: GuildChannel,
+    #[description = "The counted reaction (default: ⭐)"] emoji: Option<String>,
+    #[description = "The number of reactions needed (default: 3)"]
+    #[min = 1]
+    threshold: Option<u64>,
+) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    let emoji = ReactionType::try_from(emoji.as_deref().unwrap_or("⭐").trim())
+        .map_err(|_| Error::msg("That is not an emoji!"))?;
+    let board = Starboard {
+        channel: channel.id,
+        emoji,
+        threshold: threshold.unwrap_or(3),
+    };
+    let reply = format!(
+        "Messages with {} {} will now be reposted to {}.",
+        board.threshold,
+        board.emoji,
+        board.channel.mention()
+    );
+    let boards = ctx
+        .serenity_context()
+        .data
+        .read()
+        .await
+        .get::<Starboards>()
+        .unwrap()
+        .clone();
+    boards.set(&env.prelude.sql, guild, board).await?;
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Disable the starboard of this server.
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+async fn disable<U: HasCoreEnv>(ctx: CmdContext<'_, U>) -> Result<()> {
+    let env = ctx.data().core_env();
+    let guild = ctx.guild_id().unwrap();
+    let boards = ctx
+        .serenity_context()
+        .data
+        .read()
+        .await
+        .get::<Starboards>()
+        .unwrap()
+        .clone();
+    let reply = if boards.remove(&env.prelude.sql, guild).await? {
+        "The starboard is disabled."
+    } else {
+        "This server has no starboard!"
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
@@ -1,12 +1,85 @@
-use serenity::all::{Message, ReactionType, UserId};
-use serenity::builder::{CreateEmbed, CreateEmbedAuthor, CreateMessage};
+use dashmap::DashSet;
+use poise::{ChoiceParameter, CreateReply};
+use serenity::all::{
+    ButtonStyle, ChannelId, ComponentInteraction, Message, MessageId, ReactionType, User, UserId,
+};
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+    CreateInteractionResponseFollowup, CreateMessage, EditMessage,
+};
 use serenity::framework::standard::macros::command;
 use serenity::framework::standard::{Args, CommandError as Error, CommandResult};
-use serenity::{self, collector, utils::MessageBuilder};
-use std::collections::{HashMap as Map, HashSet as Set};
-use std::time::Duration;
+use serenity::{self, utils::MessageBuilder};
+use std::collections::{BTreeMap, HashMap as Map};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use youmubot_db_sql::models::poll::{Poll, PollChoice};
 use youmubot_prelude::{Duration as ParseDuration, *};
 
+use crate::HasCoreEnv;
+
+/// The ballots casted so far, as choice indices in order of preference.
+type Ballots = BTreeMap<UserId, Vec<usize>>;
+
+/// How votes are casted and counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ChoiceParameter)]
+pub enum PollKind {
+    #[default]
+    #[name = "Single choice"]
+    Single,
+    #[name = "Multiple choices"]
+    Multiple,
+    #[name = "Ranked choices (instant runoff)"]
+    Ranked,
+}
+
+impl PollKind {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => PollKind::Single,
+            2 => PollKind::Ranked,
+            _ => PollKind::Multiple,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            PollKind::Single => 0,
+            PollKind::Multiple => 1,
+            PollKind::Ranked => 2,
+        }
+    }
+
+    /// Applies a click on `choice` to the ballot.
+    fn cast(self, mut ballot: Vec<usize>, choice: usize) -> Vec<usize> {
+        match self {
+            PollKind::Single if ballot == [choice] => vec![],
+            PollKind::Single => vec![choice],
+            PollKind::Multiple | PollKind::Ranked => {
+                match ballot.iter().position(|&c| c == choice) {
+                    Some(pos) => {
+                        ballot.remove(pos);
+                    }
+                    None => ballot.push(choice),
+                }
+                ballot
+            }
+        }
+    }
+
+    fn instructions(self) -> &'static str {
+        match self {
+            PollKind::Single => "Pick one answer. Clicking it again takes your vote back.",
+            PollKind::Multiple => {
+                "Pick as many answers as you like. Clicking an answer again takes it back."
+            }
+            PollKind::Ranked => {
+                "Click the answers in order of preference. Clicking an answer again takes it out of your ranking."
+            }
+        }
+    }
+}
+
 #[command]
 #[description = "🎌 Cast a poll upon everyone and ask them for opinions!"]
 #[usage = "[duration] / [question] / [answer #1 = Yes!] / [answer #2 = No!] ..."]
@@ -18,188 +91,653 @@ use youmubot_prelude::{Duration as ParseDuration, *};
 pub async fn vote(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     // Parse stuff first
     let args = args.quoted();
-    let _duration = args.single::<ParseDuration>()?;
-    let duration = &_duration.0;
-    if *duration < Duration::from_secs(2) || *duration > Duration::from_secs(60 * 60 * 24) {
-        msg.reply(ctx, format!("😒 Invalid duration ({}). The voting time should be between **2 minutes** and **1 day**.", _duration)).await?;
+    let duration = args.single::<ParseDuration>()?;
+    if let Some(err) = check_duration(duration) {
+        msg.reply(ctx, err).await?;
         return Ok(());
     }
     let question = args.single::<String>()?;
-    let choices = if args.is_empty() {
+    let answers: Vec<_> = args.iter().quoted().trimmed().map(|v| v.unwrap()).collect();
+    let choices = match make_choices(answers) {
+        Ok(v) => v,
+        Err(err) => {
+            msg.reply(ctx, err).await?;
+            return Ok(());
+        }
+    };
+
+    let poll = new_poll(
+        msg.guild_id.unwrap(),
+        msg.channel_id,
+        &msg.author,
+        question,
+        *msg.timestamp,
+        duration,
+        PollKind::Multiple,
+        false,
+        choices,
+    );
+
+    // Ok... now we post up a nice voting panel.
+    let (embed, components) = render_panel(&poll, &msg.author, &Ballots::new());
+    let panel = msg
+        .channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .content("@here")
+                .embed(embed)
+                .components(components),
+        )
+        .await?;
+    msg.delete(&ctx).await?;
+
+    start(
+        ctx,
+        Poll {
+            message_id: panel.id.get() as i64,
+            ..poll
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+/// 🎌 Cast a poll upon everyone and ask them for opinions!
+#[poise::command(
+    slash_command,
+    guild_only,
+    channel_cooldown = 120,
+    category = "community"
+)]
+pub async fn poll<U: HasCoreEnv>(
+    ctx: CmdContext<'_, U>,
+    #[description = "The question to ask"] question: String,
+    #[description = "The answers, separated by `/` (default: Yes! / No!)"] answers: Option<String>,
+    #[description = "How long the poll lasts, e.g. `1h30m` (default: 1 hour)"] duration: Option<
+        String,
+    >,
+    #[description = "How votes are casted and counted (default: single choice)"] kind: Option<
+        PollKind,
+    >,
+    #[description = "Hide who voted for what (default: no)"] anonymous: Option<bool>,
+) -> Result<()> {
+    let duration = match duration {
+        Some(d) => d.parse::<ParseDuration>()?,
+        None => ParseDuration(DEFAULT_DURATION),
+    };
+    let answers: Vec<String> = answers
+        .iter()
+        .flat_map(|a| a.split('/'))
+        .map(|a| a.trim().to_owned())
+        .filter(|a| !a.is_empty())
+        .collect();
+    let choices = check_duration(duration)
+        .map(Err)
+        .unwrap_or_else(|| make_choices(answers));
+    let choices = match choices {
+        Ok(v) => v,
+        Err(err) => {
+            ctx.send(CreateReply::default().content(err).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let poll = new_poll(
+        ctx.guild_id().unwrap(),
+        ctx.channel_id(),
+        ctx.author(),
+        question,
+        *ctx.created_at(),
+        duration,
+        kind.unwrap_or_default(),
+        anonymous.unwrap_or(false),
+        choices,
+    );
+    let (embed, components) = render_panel(&poll, ctx.author(), &Ballots::new());
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .content("@here")
+                .embed(embed)
+                .components(components),
+        )
+        .await?;
+    let panel = reply.message().await?;
+
+    start(
+        ctx.serenity_context(),
+        Poll {
+            message_id: panel.id.get() as i64,
+            ..poll
+        },
+    )
+    .await
+}
+
+/// Returns an error message if the duration is out of range.
+fn check_duration(duration: ParseDuration) -> Option<String> {
+    if duration.0 < Duration::from_secs(2) || duration.0 > Duration::from_secs(60 * 60 * 24) {
+        Some(format!("😒 Invalid duration ({}). The voting time should be between **2 minutes** and **1 day**.", duration))
+    } else {
+        None
+    }
+}
+
+/// Assigns an emote to each answer, or returns an error message.
+fn make_choices(answers: Vec<String>) -> Result<Vec<PollChoice>, String> {
+    let choices = if answers.is_empty() {
         vec![
             ("😍".to_owned(), "Yes! 😍".to_owned()),
             ("🤢".to_owned(), "No! 🤢".to_owned()),
         ]
     } else {
-        let choices: Vec<_> = args.iter().quoted().trimmed().map(|v| v.unwrap()).collect();
-        if choices.len() < 2 {
+        if answers.len() < 2 {
             // Where are the choices?
-            msg.reply(
-                ctx,
-                "😒 Can't have a nice voting session if you only have one choice.",
-            )
-            .await?;
-            return Ok(());
+            return Err(
+                "😒 Can't have a nice voting session if you only have one choice.".to_owned(),
+            );
         }
-        if choices.len() > MAX_CHOICES {
+        if answers.len() > MAX_CHOICES {
             // Too many choices!
-            msg.reply(
-                ctx,
-                format!(
-                    "😵 Too many choices... We only support {} choices at the moment!",
-                    MAX_CHOICES
-                ),
-            )
-            .await?;
-            return Ok(());
+            return Err(format!(
+                "😵 Too many choices... We only support {} choices at the moment!",
+                MAX_CHOICES
+            ));
         }
-
-        pick_n_reactions(choices.len())?
+        pick_n_reactions(answers.len())
+            .map_err(|e| e.to_string())?
             .into_iter()
-            .zip(choices.into_iter())
+            .zip(answers)
             .collect()
     };
+    Ok(choices
+        .into_iter()
+        .map(|(emote, description)| PollChoice { emote, description })
+        .collect())
+}
 
-    let fields: Vec<_> = {
-        choices
-            .iter()
-            .map(|(reaction, choice)| {
-                (
-                    MessageBuilder::new().push_bold_safe(choice).build(),
-                    format!("React with {}", reaction),
-                    true,
-                )
-            })
-            .collect()
+#[allow(clippy::too_many_arguments)]
+fn new_poll(
+    guild: serenity::all::GuildId,
+    channel: ChannelId,
+    author: &User,
+    question: String,
+    asked: chrono::DateTime<chrono::Utc>,
+    duration: ParseDuration,
+    kind: PollKind,
+    anonymous: bool,
+    choices: Vec<PollChoice>,
+) -> Poll {
+    Poll {
+        id: 0,
+        guild_id: guild.get() as i64,
+        channel_id: channel.get() as i64,
+        message_id: 0,
+        author_id: author.id.get() as i64,
+        question,
+        asked_at: asked,
+        deadline: asked + chrono::Duration::from_std(duration.0).unwrap(),
+        kind: kind.to_u8(),
+        anonymous,
+        choices,
+    }
+}
+
+/// Stores the poll, so that it survives restarts, and starts collecting votes.
+async fn start(ctx: &Context, mut poll: Poll) -> Result<()> {
+    let (pool, polls) = {
+        let data = ctx.data.read().await;
+        let pool = data.get::<SQLClient>().unwrap().clone();
+        (pool, data.get::<Polls>().unwrap().clone())
     };
+    let mut tx = pool.begin().await?;
+    poll.insert(&mut tx).await?;
+    tx.commit().await?;
+    polls.spawn(ctx.clone(), poll);
+    Ok(())
+}
 
-    // Ok... now we post up a nice voting panel.
-    let channel = msg.channel_id;
-    let author = msg.author.clone();
-    let asked = msg.timestamp;
-    let until = *asked + (chrono::Duration::from_std(*duration).unwrap());
-    let panel = channel.send_message(&ctx,
-        CreateMessage::new().content("@here").embed( 
-            CreateEmbed::new().author( {
-                CreateEmbedAuthor::new(&author.name).icon_url(author.avatar_url().unwrap_or_else(|| "".to_owned()))
-            })
-            .title(format!("Please vote! Poll ends {}", until.format("<t:%s:R>")))
-            .thumbnail("https://images-ext-2.discordapp.net/external/BK7injOyt4XT8yNfbCDV4mAkwoRy49YPfq-3IwCc_9M/http/cdn.i.ntere.st/p/9197498/image")
-            .description(
-                MessageBuilder::new().push_bold_line_safe(&question).push("\nThis question was asked by ").push(author.mention().to_string()).build())
-            .fields(fields.into_iter())
-        )
-    ).await?;
-    msg.delete(&ctx).await?;
+/// Keeps track of the polls being watched, so that each poll is only watched once.
+#[derive(Debug, Default, Clone)]
+pub struct Polls {
+    running: Arc<DashSet<i64>>,
+}
 
-    // React on all the choices
-    for (emote, _) in &choices {
-        panel
-            .react(&ctx, ReactionType::try_from(&emote[..]).unwrap())
-            .map_ok(|_| ())
-            .await?;
+impl TypeMapKey for Polls {
+    type Value = Polls;
+}
+
+impl Polls {
+    /// Resumes all stored polls. Overdue polls are finalized right away.
+    pub async fn init(ctx: &Context) -> Result<()> {
+        let (pool, polls) = {
+            let data = ctx.data.read().await;
+            let pool = data.get::<SQLClient>().unwrap().clone();
+            (pool, data.get::<Polls>().unwrap().clone())
+        };
+        for poll in Poll::all(&pool).await? {
+            polls.spawn(ctx.clone(), poll);
+        }
+        Ok(())
     }
 
-    // A handler for votes.
-    let user_reactions: Map<String, Set<UserId>> = choices
-        .iter()
-        .map(|(emote, _)| (emote.clone(), Set::new()))
-        .collect();
+    /// Watches the poll until its deadline, then announces the results.
+    fn spawn(&self, ctx: Context, poll: Poll) {
+        if !self.running.insert(poll.id) {
+            return;
+        }
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            let id = poll.id;
+            watch(ctx, poll).await.pls_ok();
+            running.remove(&id);
+        });
+    }
+}
 
-    // Collect reactions...
-    let message_id = panel.id;
-    let user_reactions = collector::collect(&ctx.shard, move |event| {
-        match event {
-            serenity::all::Event::ReactionAdd(r) => Some((r.reaction.clone(), true)),
-            serenity::all::Event::ReactionRemove(r) => Some((r.reaction.clone(), false)),
-            _ => None,
-        }
-        .filter(|(r, _)| r.message_id == message_id)
-    })
-    .take_until(tokio::time::timeout(*duration, future::ready(())))
-    .fold(user_reactions, |mut set, (reaction, is_add)| async move {
-        let users = if let ReactionType::Unicode(ref s) = reaction.emoji {
-            if let Some(users) = set.get_mut(s.as_str()) {
-                users
-            } else {
-                return set;
-            }
+const CHOICE_BUTTON_PREFIX: &str = "youmubot_poll_choice_";
+const CLEAR_BUTTON: &str = "youmubot_poll_clear";
+const DEFAULT_DURATION: Duration = Duration::from_secs(60 * 60);
+/// The minimum time between two updates of the live results.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(3);
+const BAR_WIDTH: usize = 16;
+
+/// Records votes on the poll until the deadline, then finalizes it.
+async fn watch(ctx: Context, poll: Poll) -> Result<()> {
+    let pool = ctx.data.read().await.get::<SQLClient>().unwrap().clone();
+    let channel = ChannelId::new(poll.channel_id as u64);
+    let message = MessageId::new(poll.message_id as u64);
+    let author = UserId::new(poll.author_id as u64).to_user(&ctx).await?;
+
+    let mut ballots = Ballots::new();
+    for vote in Poll::votes(poll.id, &pool).await? {
+        if let Some(choice) = poll.choices.iter().position(|c| c.emote == vote.emote) {
+            ballots
+                .entry(UserId::new(vote.user_id as u64))
+                .or_default()
+                .push(choice);
+        }
+    }
+
+    let recv = InteractionCollector::create(&ctx, message).await?;
+    // Always render once, so the panel is up-to-date after a restart.
+    let mut dirty = true;
+    let mut last_update: Option<Instant> = None;
+    while let Ok(remaining) = (poll.deadline - chrono::Utc::now()).to_std() {
+        let since_update = last_update.map(|t| t.elapsed()).unwrap_or(UPDATE_INTERVAL);
+        if dirty && since_update >= UPDATE_INTERVAL {
+            let (embed, components) = render_panel(&poll, &author, &ballots);
+            channel
+                .edit_message(
+                    &ctx,
+                    message,
+                    EditMessage::new().embed(embed).components(components),
+                )
+                .await
+                .pls_ok();
+            dirty = false;
+            last_update = Some(Instant::now());
+        }
+        let timeout = if dirty {
+            remaining.min(UPDATE_INTERVAL.saturating_sub(since_update))
         } else {
-            return set;
+            remaining
         };
-        let user_id = match reaction.user_id {
-            Some(v) => v,
-            None => return set,
+        let Some(interaction) = recv.next_interaction(timeout).await else {
+            continue;
         };
-        if is_add {
-            users.insert(user_id);
-        } else {
-            users.remove(&user_id);
+        if let Some(reply) = cast_vote(&pool, &poll, &mut ballots, &interaction)
+            .await
+            .pls_ok()
+        {
+            dirty = true;
+            interaction
+                .create_followup(
+                    &ctx,
+                    CreateInteractionResponseFollowup::new()
+                        .content(reply)
+                        .ephemeral(true),
+                )
+                .await
+                .pls_ok();
         }
-        set
-    })
-    .await;
+    }
+    drop(recv);
+    finalize(&ctx, &pool, &poll, &author, &ballots).await
+}
 
-    // Handle choices
-    let choice_map = choices.into_iter().collect::<Map<_, _>>();
-    let mut result: Vec<(String, Vec<UserId>)> = user_reactions
-        .into_iter()
-        .filter(|(_, users)| !users.is_empty())
-        .map(|(emote, users)| (emote, users.into_iter().collect()))
+/// Applies the button click to the voter's ballot, returning a message for the voter.
+async fn cast_vote(
+    pool: &youmubot_db_sql::Pool,
+    poll: &Poll,
+    ballots: &mut Ballots,
+    interaction: &ComponentInteraction,
+) -> Result<String> {
+    let custom_id = interaction.data.custom_id.as_str();
+    let user = interaction.user.id;
+    let ballot = ballots.get(&user).cloned().unwrap_or_default();
+    let ballot = if custom_id == CLEAR_BUTTON {
+        vec![]
+    } else {
+        let choice = custom_id
+            .strip_prefix(CHOICE_BUTTON_PREFIX)
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&v| v < poll.choices.len())
+            .ok_or_else(|| error!("unknown poll button {}", custom_id))?;
+        PollKind::from_u8(poll.kind).cast(ballot, choice)
+    };
+
+    let emotes: Vec<&str> = ballot
+        .iter()
+        .map(|&c| poll.choices[c].emote.as_str())
         .collect();
+    let mut tx = pool.begin().await?;
+    Poll::set_ballot(poll.id, user.get() as i64, &emotes, &mut tx).await?;
+    tx.commit().await?;
 
-    result.sort_unstable_by(|(_, v), (_, w)| w.len().cmp(&v.len()));
+    let reply = if ballot.is_empty() {
+        "Your vote has been taken back.".to_owned()
+    } else {
+        let mut content = MessageBuilder::new();
+        content.push(match PollKind::from_u8(poll.kind) {
+            PollKind::Ranked => "Your ranking:",
+            _ => "You voted for:",
+        });
+        for (rank, &choice) in ballot.iter().enumerate() {
+            content
+                .push(format!("\n{}. ", rank + 1))
+                .push(&poll.choices[choice].emote)
+                .push(" ")
+                .push_bold_safe(&poll.choices[choice].description);
+        }
+        content.build()
+    };
+    if ballot.is_empty() {
+        ballots.remove(&user);
+    } else {
+        ballots.insert(user, ballot);
+    }
+    Ok(reply)
+}
 
-    if result.is_empty() {
-        msg.reply(
-            &ctx,
-            MessageBuilder::new()
-                .push("no one answer your question ")
-                .push_bold_safe(&question)
-                .push(", sorry 😭")
-                .build(),
-        )
-        .await?;
-        return Ok(());
+/// Counts the votes of each choice. Ranked ballots only count their first preference.
+fn vote_counts(poll: &Poll, ballots: &Ballots) -> Vec<usize> {
+    let mut counts = vec![0; poll.choices.len()];
+    for ballot in ballots.values() {
+        let ballot = match PollKind::from_u8(poll.kind) {
+            PollKind::Ranked => &ballot[..ballot.len().min(1)],
+            _ => &ballot[..],
+        };
+        for &choice in ballot {
+            counts[choice] += 1;
+        }
     }
+    counts
+}
 
-    channel
-        .send_message(
-            &ctx,
-            CreateMessage::new().content({
-                let mut content = MessageBuilder::new();
-                content
-                    .push("@here, ")
-                    .push(asked.format("<t:%s:R>, ").to_string())
-                    .push(author.mention().to_string())
-                    .push(" asked ")
-                    .push_bold_safe(&question)
-                    .push(", and here are the results!");
-                result.into_iter().for_each(|(emote, votes)| {
-                    content
-                        .push("\n - ")
-                        .push_bold(format!("{}", votes.len()))
-                        .push(" voted for ")
-                        .push(&emote)
-                        .push(" ")
-                        .push_bold_safe(choice_map.get(&emote).unwrap())
-                        .push(": ")
-                        .push(
-                            votes
-                                .into_iter()
-                                .map(|v| v.mention().to_string())
-                                .collect::<Vec<_>>()
-                                .join(", "),
-                        );
-                });
-                content.build()
-            }),
-        )
-        .await?;
-    panel.delete(&ctx).await?;
+/// Renders the voting panel, with a live results chart and a button for each choice.
+fn render_panel(
+    poll: &Poll,
+    author: &User,
+    ballots: &Ballots,
+) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let kind = PollKind::from_u8(poll.kind);
+    let counts = vote_counts(poll, ballots);
+    let voters = ballots.len();
+
+    let mut description = MessageBuilder::new();
+    description
+        .push_bold_line_safe(&poll.question)
+        .push("\nThis question was asked by ")
+        .push_line(author.mention().to_string())
+        .push("\n")
+        .push(kind.instructions());
+    if poll.anonymous {
+        description.push(" Votes are anonymous.");
+    }
+    description.push_line("\n").push_bold_line(match kind {
+        PollKind::Ranked => "First preferences",
+        _ => "Results",
+    });
+    for (choice, &count) in poll.choices.iter().zip(&counts) {
+        let filled = (count * BAR_WIDTH + voters / 2)
+            .checked_div(voters)
+            .unwrap_or(0);
+        let percent = (count * 100).checked_div(voters).unwrap_or(0);
+        description
+            .push(&choice.emote)
+            .push(" ")
+            .push_bold_line_safe(&choice.description)
+            .push_mono(format!(
+                "{}{}",
+                "█".repeat(filled),
+                "░".repeat(BAR_WIDTH - filled)
+            ))
+            .push_line(format!(" {} ({}%)", count, percent));
+    }
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::new(&author.name).icon_url(author.face()))
+        .title(format!("Please vote! Poll ends {}", poll.deadline.format("<t:%s:R>")))
+        .thumbnail("https://images-ext-2.discordapp.net/external/BK7injOyt4XT8yNfbCDV4mAkwoRy49YPfq-3IwCc_9M/http/cdn.i.ntere.st/p/9197498/image")
+        .description(description.build())
+        .footer(CreateEmbedFooter::new(format!(
+            "{} voter{}",
+            voters,
+            if voters == 1 { "" } else { "s" }
+        )));
+
+    let buttons = poll
+        .choices
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| {
+            CreateButton::new(format!("{}{}", CHOICE_BUTTON_PREFIX, i))
+                .label(choice.description.chars().take(80).collect::<String>())
+                .emoji(ReactionType::Unicode(choice.emote.clone()))
+                .style(ButtonStyle::Secondary)
+        })
+        .chain(std::iter::once(
+            CreateButton::new(CLEAR_BUTTON)
+                .label("Take back my vote")
+                .style(ButtonStyle::Danger),
+        ))
+        .collect::<Vec<_>>();
+    let components = buttons
+        .chunks(5)
+        .map(|row| CreateActionRow::Buttons(row.to_vec()))
+        .collect();
+    (embed, components)
+}
 
+/// Announces the results of the poll and removes it.
+async fn finalize(
+    ctx: &Context,
+    pool: &youmubot_db_sql::Pool,
+    poll: &Poll,
+    author: &User,
+    ballots: &Ballots,
+) -> Result<()> {
+    let channel = ChannelId::new(poll.channel_id as u64);
+    if ballots.is_empty() {
+        channel
+            .say(
+                &ctx,
+                MessageBuilder::new()
+                    .push(author.mention().to_string())
+                    .push(", no one answer your question ")
+                    .push_bold_safe(&poll.question)
+                    .push(", sorry 😭")
+                    .build(),
+            )
+            .await?;
+    } else {
+        let mut content = MessageBuilder::new();
+        content
+            .push("@here, ")
+            .push(poll.asked_at.format("<t:%s:R>, ").to_string())
+            .push(author.mention().to_string())
+            .push(" asked ")
+            .push_bold_safe(&poll.question)
+            .push(", and here are the results!");
+        match PollKind::from_u8(poll.kind) {
+            PollKind::Ranked => push_runoff_results(&mut content, poll, ballots),
+            _ => push_results(&mut content, poll, ballots),
+        }
+        channel
+            .send_message(&ctx, CreateMessage::new().content(content.build()))
+            .await?;
+    }
+    // The panel might have been deleted already.
+    channel
+        .delete_message(&ctx, MessageId::new(poll.message_id as u64))
+        .await
+        .ok();
+    Poll::delete(poll.id, pool).await?;
     Ok(())
-    // unimplemented!();
+}
+
+fn push_choice(content: &mut MessageBuilder, choice: &PollChoice) {
+    content
+        .push(&choice.emote)
+        .push(" ")
+        .push_bold_safe(&choice.description);
+}
+
+fn mentions(users: impl IntoIterator<Item = UserId>) -> String {
+    users
+        .into_iter()
+        .map(|v| v.mention().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Lists the number of votes of each choice, most voted first.
+fn push_results(content: &mut MessageBuilder, poll: &Poll, ballots: &Ballots) {
+    let mut voters: Map<usize, Vec<UserId>> = Map::new();
+    for (&user, ballot) in ballots {
+        for &choice in ballot {
+            voters.entry(choice).or_default().push(user);
+        }
+    }
+    // Keep the given order on ties
+    let mut result: Vec<(usize, Vec<UserId>)> = (0..poll.choices.len())
+        .filter_map(|choice| voters.remove(&choice).map(|users| (choice, users)))
+        .collect();
+    result.sort_by(|(_, v), (_, w)| w.len().cmp(&v.len()));
+
+    for (choice, users) in result {
+        content
+            .push("\n - ")
+            .push_bold(format!("{}", users.len()))
+            .push(" voted for ");
+        push_choice(content, &poll.choices[choice]);
+        if !poll.anonymous {
+            content.push(": ").push(mentions(users));
+        }
+    }
+}
+
+/// Runs an instant runoff on the ranked ballots and lists every round.
+fn push_runoff_results(content: &mut MessageBuilder, poll: &Poll, ballots: &Ballots) {
+    let ranked: Vec<&[usize]> = ballots.values().map(|b| &b[..]).collect();
+    let runoff = instant_runoff(poll.choices.len(), &ranked);
+    match &runoff.winners[..] {
+        [] => {}
+        [winner] => {
+            content.push("\nThe winner is ");
+            push_choice(content, &poll.choices[*winner]);
+            content.push("!");
+        }
+        tied => {
+            content.push("\nIt's a tie between ");
+            for (i, &choice) in tied.iter().enumerate() {
+                if i > 0 {
+                    content.push(", ");
+                }
+                push_choice(content, &poll.choices[choice]);
+            }
+            content.push("!");
+        }
+    }
+    for (round, counts) in runoff.rounds.iter().enumerate() {
+        content.push(format!("\n - Round {}: ", round + 1));
+        for (i, &(choice, count)) in counts.iter().enumerate() {
+            if i > 0 {
+                content.push(", ");
+            }
+            push_choice(content, &poll.choices[choice]);
+            content.push(format!(" ({})", count));
+        }
+    }
+    if !poll.anonymous {
+        content.push("\nBallots:");
+        for (user, ballot) in ballots {
+            content
+                .push("\n - ")
+                .push(user.mention().to_string())
+                .push(": ")
+                .push(
+                    ballot
+                        .iter()
+                        .map(|&c| poll.choices[c].emote.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" > "),
+                );
+        }
+    }
+}
+
+/// The outcome of an instant runoff.
+#[derive(Debug, PartialEq, Eq)]
+struct Runoff {
+    /// The first preference counts of the choices still in the race, for each round.
+    rounds: Vec<Vec<(usize, usize)>>,
+    /// The winner, or all remaining choices on a tie.
+    winners: Vec<usize>,
+}
+
+/// Counts ranked ballots with instant runoff: the choices with the fewest first preferences
+/// are eliminated until one choice holds a majority of the ballots still in play.
+fn instant_runoff(choices: usize, ballots: &[&[usize]]) -> Runoff {
+    let mut remaining: Vec<usize> = (0..choices).collect();
+    let mut rounds = vec![];
+    loop {
+        let mut counts: Vec<(usize, usize)> = remaining.iter().map(|&c| (c, 0)).collect();
+        for ballot in ballots {
+            if let Some(top) = ballot.iter().find(|c| remaining.contains(c)) {
+                if let Some(count) = counts.iter_mut().find(|(c, _)| c == top) {
+                    count.1 += 1;
+                }
+            }
+        }
+        let total: usize = counts.iter().map(|(_, n)| n).sum();
+        if total == 0 {
+            return Runoff {
+                rounds,
+                winners: vec![],
+            };
+        }
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let (top, top_count) = counts[0];
+        let min = counts.iter().map(|(_, n)| *n).min().unwrap();
+        rounds.push(counts.iter().copied().filter(|(_, n)| *n > 0).collect());
+        if top_count * 2 > total {
+            return Runoff {
+                rounds,
+                winners: vec![top],
+            };
+        }
+        if counts.iter().all(|(_, n)| *n == min) {
+            return Runoff {
+                rounds,
+                winners: remaining,
+            };
+        }
+        remaining.retain(|c| counts.iter().any(|(d, n)| d == c && *n > min));
+    }
 }
 
 // Pick a set of random n reactions!
@@ -230,4 +768,42 @@ const REACTIONS: [&str; 90] = [
 
 // Assertions
 static_assertions::const_assert!(MAX_CHOICES <= REACTIONS.len());
-static_assertions::const_assert!(MAX_CHOICES <= REACTIONS.len());
+// Every choice and the clear button must fit in the 5 rows of 5 buttons of a message.
+static_assertions::const_assert!(MAX_CHOICES < 25);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runoff_majority_in_first_round() {
+        let ballots: [&[usize]; 3] = [&[0, 1], &[0], &[1, 0]];
+        let runoff = instant_runoff(3, &ballots);
+        assert_eq!(runoff.winners, vec![0]);
+        assert_eq!(runoff.rounds, vec![vec![(0, 2), (1, 1)]]);
+    }
+
+    #[test]
+    fn runoff_transfers_eliminated_votes() {
+        let ballots: [&[usize]; 5] = [&[0], &[0], &[1], &[1], &[2, 1]];
+        let runoff = instant_runoff(3, &ballots);
+        assert_eq!(runoff.winners, vec![1]);
+        assert_eq!(runoff.rounds.len(), 2);
+        assert_eq!(runoff.rounds[1], vec![(1, 3), (0, 2)]);
+    }
+
+    #[test]
+    fn runoff_tie() {
+        let ballots: [&[usize]; 2] = [&[0, 1], &[1, 0]];
+        let runoff = instant_runoff(2, &ballots);
+        assert_eq!(runoff.winners, vec![0, 1]);
+        assert!(instant_runoff(2, &[]).winners.is_empty());
+    }
+
+    #[test]
+    fn single_choice_replaces_vote() {
+        assert_eq!(PollKind::Single.cast(vec![1], 0), vec![0]);
+        assert_eq!(PollKind::Single.cast(vec![0], 0), Vec::<usize>::new());
+        assert_eq!(PollKind::Ranked.cast(vec![2, 0, 1], 0), vec![2, 1]);
+    }
+}
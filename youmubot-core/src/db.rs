@@ -1,72 +1,77 @@
 use serde::{Deserialize, Serialize};
 use serenity::model::{
     channel::ReactionType,
-    id::{MessageId, RoleId},
+    id::{ChannelId, GuildId, MessageId, RoleId},
 };
 use std::collections::HashMap;
-use youmubot_db::{GuildMap, DB};
+use youmubot_db_sql::{models::roles as model, Pool};
 use youmubot_prelude::*;
 
-/// A list of assignable roles for all servers.
-pub type Roles = DB<GuildMap<RoleList>>;
-
-/// Represents a server's role list.
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct RoleList {
-    /// `reaction_message` handles the reaction-handling message.
-    pub reaction_messages: HashMap<MessageId, RoleMessage>,
-    pub roles: HashMap<RoleId, Role>,
-}
-
-/// Load the file list, handling migration from v1.
-pub fn load_role_list(
-    map: &mut TypeMap,
+/// Imports the role list from the old YAML files into SQL, handling migration from v1.
+/// The imported files are renamed, so this only happens once.
+pub async fn import_role_list(
+    pool: &Pool,
     path: impl AsRef<std::path::Path>,
     v1_path: impl AsRef<std::path::Path>,
 ) -> Result<()> {
-    // Try to load v2 first
-    let v2 = Roles::load_from_path(path.as_ref());
-    let v2 = match v2 {
-        Ok(v2) => {
-            map.insert::<Roles>(v2);
-            return Ok(());
-        }
-        Err(v2) => v2,
-    };
-    // Try migrating from v1.
-    match legacy::RolesV1::load_from_path(v1_path.as_ref()) {
-        Ok(v1) => {
-            Roles::insert_into(map, path)?;
-            *Roles::open(map).borrow_mut()? = v1
-                .get_data(true)?
-                .into_iter()
+    let (path, v1_path) = (path.as_ref(), v1_path.as_ref());
+    let (list, imported): (legacy::GuildMap<legacy::RoleList>, _) = if path.exists() {
+        (
+            legacy::RolesV2::load_from_path(path)
+                .and_then(|v| v.get_data(true))
+                .map_err(|e| Error::msg(format!("cannot load role list v2: {}", e)))?,
+            path,
+        )
+    } else if v1_path.exists() {
+        let v1 = legacy::RolesV1::load_from_path(v1_path)
+            .and_then(|v| v.get_data(true))
+            .map_err(|e| Error::msg(format!("cannot load role list v1: {}", e)))?;
+        (
+            v1.into_iter()
                 .map(|(guild, roles)| {
                     (
                         guild,
-                        RoleList {
+                        legacy::RoleList {
                             reaction_messages: HashMap::new(),
+                            menus: HashMap::new(),
                             roles,
                         },
                     )
                 })
-                .collect();
-            std::fs::remove_file(v1_path.as_ref()).pls_ok();
-            eprintln!("Migrated roles v1 to v2.");
-            Ok(())
+                .collect(),
+            v1_path,
+        )
+    } else {
+        return Ok(());
+    };
+
+    let mut tx = pool.begin().await?;
+    for (guild, list) in list {
+        for role in list.roles.into_values() {
+            role.to_model(guild).store(&mut *tx).await?;
+        }
+        for msg in list.reaction_messages.into_values() {
+            RoleMessage {
+                id: msg.id,
+                channel: None,
+                title: msg.title,
+                roles: msg.roles.into_iter().map(|(r, e)| (r.id, e)).collect(),
+            }
+            .to_model(guild)
+            .store(&mut tx)
+            .await?;
+        }
+        for menu in list.menus.into_values() {
+            menu.to_model(guild).store(&mut tx).await?;
         }
-        Err(v1) => Err(Error::msg(format!(
-            "failed with v2 ({}) and v1 ({})",
-            v2, v1
-        ))),
     }
-}
+    tx.commit().await?;
 
-/// A single role in the list of role messages.
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct RoleMessage {
-    pub id: serenity::model::id::MessageId,
-    pub title: String,
-    pub roles: Vec<(Role, ReactionType)>,
+    let mut backup = imported.as_os_str().to_owned();
+    backup.push(".imported");
+    std::fs::rename(imported, backup)?;
+    eprintln!("Imported role list from {:?}.", imported);
+    Ok(())
 }
 
 /// Role represents an assignable role.
@@ -76,13 +81,254 @@ pub struct Role {
     pub description: String,
     #[serde(default)]
     pub reaction: Option<ReactionType>,
+    /// The exclusive group of the role. A member can only hold one role of each group.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// The role a member must already have to pick this role.
+    #[serde(default)]
+    pub requires: Option<RoleId>,
+    /// The minimum number of seconds between two toggles of the role by the same member.
+    #[serde(default)]
+    pub cooldown: Option<u64>,
 }
 
+impl Role {
+    /// Returns the assignable roles of the guild.
+    pub async fn list(pool: &Pool, guild: GuildId) -> Result<HashMap<RoleId, Role>> {
+        Ok(model::AssignableRole::by_guild(guild.get() as i64, pool)
+            .await?
+            .into_iter()
+            .map(|r| {
+                let role = Role {
+                    id: RoleId::new(r.role_id as u64),
+                    description: r.description,
+                    reaction: r.reaction.and_then(|e| ReactionType::try_from(e).ok()),
+                    group: r.role_group,
+                    requires: r.requires.map(|v| RoleId::new(v as u64)),
+                    cooldown: r.cooldown.map(|v| v as u64),
+                };
+                (role.id, role)
+            })
+            .collect())
+    }
+
+    /// Stores the role as assignable, overriding the old entry.
+    pub async fn store(&self, pool: &Pool, guild: GuildId) -> Result<()> {
+        self.to_model(guild).store(pool).await?;
+        Ok(())
+    }
+
+    /// Removes the role from the assignable list, returning whether it was there.
+    pub async fn remove(pool: &Pool, role: RoleId) -> Result<bool> {
+        Ok(model::AssignableRole::delete(role.get() as i64, pool).await?)
+    }
+
+    fn to_model(&self, guild: GuildId) -> model::AssignableRole {
+        model::AssignableRole {
+            role_id: self.id.get() as i64,
+            guild_id: guild.get() as i64,
+            description: self.description.clone(),
+            reaction: self.reaction.as_ref().map(|e| e.to_string()),
+            role_group: self.group.clone(),
+            requires: self.requires.map(|v| v.get() as i64),
+            cooldown: self.cooldown.map(|v| v as i64),
+        }
+    }
+}
+
+/// A message that hands out roles by reactions.
+#[derive(Debug, Clone)]
+pub struct RoleMessage {
+    pub id: MessageId,
+    pub channel: Option<ChannelId>,
+    pub title: String,
+    pub roles: Vec<(RoleId, ReactionType)>,
+}
+
+const KIND_REACTIONS: u8 = 0;
+const KIND_BUTTONS: u8 = 1;
+const KIND_SELECT: u8 = 2;
+
+impl RoleMessage {
+    /// Returns all reaction role messages.
+    pub async fn all(pool: &Pool) -> Result<Vec<(GuildId, RoleMessage)>> {
+        Ok(model::RoleMessage::by_kind(KIND_REACTIONS, pool)
+            .await?
+            .into_iter()
+            .map(|m| {
+                (
+                    GuildId::new(m.guild_id as u64),
+                    RoleMessage {
+                        id: MessageId::new(m.message_id as u64),
+                        channel: m.channel_id.map(|v| ChannelId::new(v as u64)),
+                        title: m.title,
+                        roles: m
+                            .roles
+                            .into_iter()
+                            .filter_map(|r| {
+                                let emote = ReactionType::try_from(r.reaction?).ok()?;
+                                Some((RoleId::new(r.role_id as u64), emote))
+                            })
+                            .collect(),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Stores the role message, overriding the old entry.
+    pub async fn store(&self, pool: &Pool, guild: GuildId) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        self.to_model(guild).store(&mut tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn to_model(&self, guild: GuildId) -> model::RoleMessage {
+        model::RoleMessage {
+            message_id: self.id.get() as i64,
+            guild_id: guild.get() as i64,
+            channel_id: self.channel.map(|v| v.get() as i64),
+            title: self.title.clone(),
+            kind: KIND_REACTIONS,
+            select_min: 0,
+            select_max: 0,
+            roles: self
+                .roles
+                .iter()
+                .map(|(r, e)| model::RoleMessageRole {
+                    role_id: r.get() as i64,
+                    reaction: Some(e.to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Deletes a role message or a role menu, returning whether it existed.
+pub async fn remove_role_message(pool: &Pool, message: MessageId) -> Result<bool> {
+    Ok(model::RoleMessage::delete(message.get() as i64, pool).await?)
+}
+
+/// A role menu, handled by message components.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoleMenu {
+    pub id: MessageId,
+    pub channel: ChannelId,
+    pub title: String,
+    pub roles: Vec<RoleId>,
+    pub kind: RoleMenuKind,
+}
+
+/// How roles are picked from a role menu.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleMenuKind {
+    /// One toggle button for each role.
+    Buttons,
+    /// A select menu, where between `min` and `max` roles are picked at once.
+    Select { min: u8, max: u8 },
+}
+
+impl RoleMenu {
+    /// Returns the role menu on the given message, along with its guild.
+    pub async fn get(pool: &Pool, message: MessageId) -> Result<Option<(GuildId, RoleMenu)>> {
+        let mut tx = pool.begin().await?;
+        let Some(m) = model::RoleMessage::by_id(message.get() as i64, &mut tx).await? else {
+            return Ok(None);
+        };
+        let kind = match m.kind {
+            KIND_BUTTONS => RoleMenuKind::Buttons,
+            KIND_SELECT => RoleMenuKind::Select {
+                min: m.select_min,
+                max: m.select_max,
+            },
+            _ => return Ok(None),
+        };
+        let Some(channel) = m.channel_id else {
+            return Ok(None);
+        };
+        Ok(Some((
+            GuildId::new(m.guild_id as u64),
+            RoleMenu {
+                id: message,
+                channel: ChannelId::new(channel as u64),
+                title: m.title,
+                roles: m
+                    .roles
+                    .into_iter()
+                    .map(|r| RoleId::new(r.role_id as u64))
+                    .collect(),
+                kind,
+            },
+        )))
+    }
+
+    /// Stores the role menu, overriding the old entry.
+    pub async fn store(&self, pool: &Pool, guild: GuildId) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        self.to_model(guild).store(&mut tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn to_model(&self, guild: GuildId) -> model::RoleMessage {
+        let (kind, select_min, select_max) = match self.kind {
+            RoleMenuKind::Buttons => (KIND_BUTTONS, 0, 0),
+            RoleMenuKind::Select { min, max } => (KIND_SELECT, min, max),
+        };
+        model::RoleMessage {
+            message_id: self.id.get() as i64,
+            guild_id: guild.get() as i64,
+            channel_id: Some(self.channel.get() as i64),
+            title: self.title.clone(),
+            kind,
+            select_min,
+            select_max,
+            roles: self
+                .roles
+                .iter()
+                .map(|r| model::RoleMessageRole {
+                    role_id: r.get() as i64,
+                    reaction: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The role list as it was stored in YAML files.
 mod legacy {
-    use super::Role;
-    use serenity::model::id::RoleId;
+    use super::{Role, RoleMenu};
+    use serde::{Deserialize, Serialize};
+    use serenity::model::{
+        channel::ReactionType,
+        id::{MessageId, RoleId},
+    };
     use std::collections::HashMap;
-    use youmubot_db::{GuildMap, DB};
+    pub use youmubot_db::GuildMap;
+    use youmubot_db::DB;
+
     /// (Depreciated) A list of assignable roles for all servers.
     pub type RolesV1 = DB<GuildMap<HashMap<RoleId, Role>>>;
+    /// (Depreciated) A list of assignable roles and role messages for all servers.
+    pub type RolesV2 = DB<GuildMap<RoleList>>;
+
+    /// Represents a server's role list.
+    #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+    pub struct RoleList {
+        /// `reaction_message` handles the reaction-handling message.
+        pub reaction_messages: HashMap<MessageId, RoleMessage>,
+        /// `menus` handles the component-based role menus.
+        #[serde(default)]
+        pub menus: HashMap<MessageId, RoleMenu>,
+        pub roles: HashMap<RoleId, Role>,
+    }
+
+    /// A single role in the list of role messages.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct RoleMessage {
+        pub id: MessageId,
+        pub title: String,
+        pub roles: Vec<(Role, ReactionType)>,
+    }
 }
@@ -8,13 +8,18 @@ use serenity::{
     model::channel::{Channel, Message},
 };
 use std::fmt::Display;
-use youmubot_prelude::*;
+use youmubot_prelude::{
+    ratelimit::{Invoker, Limit, Ratelimits, Scope},
+    *,
+};
+
+/// Image searches post into the channel, so each channel gets at most 2 per minute.
+const IMAGE_LIMIT: Limit = Limit::new(Scope::Channel, 2, std::time::Duration::from_secs(60));
 
 #[command]
 #[checks(nsfw)]
 #[description = "🖼️ Find an image with a given tag on Danbooru[nsfw]!"]
 #[min_args(1)]
-#[bucket("images")]
 pub async fn nsfw(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     message_command(ctx, msg, args, Rating::Explicit).await
 }
@@ -22,7 +27,6 @@ pub async fn nsfw(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 #[command]
 #[description = "🖼️ Find an image with a given tag on Danbooru[safe]!"]
 #[min_args(1)]
-#[bucket("images")]
 pub async fn image(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     message_command(ctx, msg, args, Rating::Safe).await
 }
@@ -52,6 +56,11 @@ async fn message_command(
     args: Args,
     rating: Rating,
 ) -> CommandResult {
+    ctx.data.read().await.get::<Ratelimits>().unwrap().check(
+        "image",
+        IMAGE_LIMIT,
+        &Invoker::of_message(msg),
+    )?;
     let tags = args.remains().unwrap_or("touhou");
     let images = get_image(
         ctx.data.read().await.get::<HTTPClient>().unwrap(),
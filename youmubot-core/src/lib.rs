@@ -21,12 +21,21 @@ pub mod fun;
 pub struct CoreEnv {
     pub(crate) prelude: Env,
     pub(crate) ignore: admin::ignore::IgnoredUsers,
+    pub(crate) guild_ignore: admin::ignore::GuildIgnoredUsers,
+    pub(crate) disabled_groups: admin::groups::DisabledGroups,
 }
 
 impl CoreEnv {
     async fn new(prelude: Env) -> Result<Self> {
         let ignore = admin::ignore::IgnoredUsers::from_db(&prelude).await?;
-        Ok(Self { prelude, ignore })
+        let guild_ignore = admin::ignore::GuildIgnoredUsers::from_db(&prelude).await?;
+        let disabled_groups = admin::groups::DisabledGroups::from_db(&prelude).await?;
+        Ok(Self {
+            prelude,
+            ignore,
+            guild_ignore,
+            disabled_groups,
+        })
     }
 }
 
@@ -42,15 +51,25 @@ impl<T: AsRef<CoreEnv> + Send + Sync> HasCoreEnv for T {
 }
 
 /// Sets up all databases in the client.
-pub async fn setup(path: &std::path::Path, data: &mut TypeMap, prelude: Env) -> Result<CoreEnv> {
-    db::load_role_list(
-        &mut *data,
+pub async fn setup(data: &mut TypeMap, prelude: Env) -> Result<CoreEnv> {
+    let path = &prelude.config.paths.db;
+    db::import_role_list(
+        &prelude.sql,
         path.join("roles_v2.yaml"),
         path.join("roles.yaml"),
-    )?;
+    )
+    .await?;
 
     // Start reaction handlers
-    data.insert::<community::ReactionWatchers>(community::ReactionWatchers::new(&*data)?);
+    data.insert::<community::ReactionWatchers>(
+        community::ReactionWatchers::new(prelude.sql.clone()).await?,
+    );
+    data.insert::<community::Polls>(community::Polls::default());
+    data.insert::<community::RoleCooldowns>(community::RoleCooldowns::default());
+    data.insert::<community::Reminders>(community::Reminders::default());
+    data.insert::<community::Starboards>(community::Starboards::from_db(&prelude.sql).await?);
+    data.insert::<admin::ModJobs>(admin::ModJobs::default());
+    data.insert::<admin::GuildPrefixes>(admin::GuildPrefixes::from_db(&prelude.sql).await?);
 
     CoreEnv::new(prelude).await
 }
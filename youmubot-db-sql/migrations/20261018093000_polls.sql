@@ -0,0 +1,30 @@
+-- Add migration script here
+
+CREATE TABLE polls (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  guild_id BIGINT NOT NULL,
+  channel_id BIGINT NOT NULL,
+  message_id BIGINT NOT NULL UNIQUE,
+  author_id BIGINT NOT NULL,
+  question TEXT NOT NULL,
+  asked_at DATETIME NOT NULL,
+  deadline DATETIME NOT NULL
+);
+
+CREATE TABLE poll_choices (
+  poll_id INTEGER NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  emote TEXT NOT NULL,
+  description TEXT NOT NULL,
+  PRIMARY KEY (poll_id, emote)
+);
+
+CREATE TABLE poll_votes (
+  poll_id INTEGER NOT NULL,
+  emote TEXT NOT NULL,
+  user_id BIGINT NOT NULL,
+  PRIMARY KEY (poll_id, emote, user_id),
+  FOREIGN KEY (poll_id, emote) REFERENCES poll_choices(poll_id, emote) ON DELETE CASCADE
+);
+
+CREATE INDEX polls_deadline ON polls(deadline);
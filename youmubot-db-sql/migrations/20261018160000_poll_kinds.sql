@@ -0,0 +1,9 @@
+-- Add migration script here
+
+-- 0 = single choice, 1 = multiple choices, 2 = ranked choices.
+-- Polls before this were reaction-based, which allowed multiple choices.
+ALTER TABLE polls ADD COLUMN kind INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE polls ADD COLUMN anonymous BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The position of the choice in the voter's ballot, starting from 0.
+ALTER TABLE poll_votes ADD COLUMN rank INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,33 @@
+-- Add migration script here
+
+CREATE TABLE assignable_roles (
+  role_id BIGINT NOT NULL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  description TEXT NOT NULL,
+  reaction TEXT NULL,
+  role_group TEXT NULL,
+  requires BIGINT NULL,
+  cooldown BIGINT NULL
+);
+
+CREATE INDEX assignable_roles_guild ON assignable_roles(guild_id);
+
+CREATE TABLE role_messages (
+  message_id BIGINT NOT NULL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  -- Unknown for reaction messages imported from the old role list.
+  channel_id BIGINT NULL,
+  title TEXT NOT NULL,
+  -- 0 = reactions, 1 = buttons, 2 = select menu
+  kind INTEGER NOT NULL,
+  select_min INTEGER NOT NULL DEFAULT 0,
+  select_max INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE role_message_roles (
+  message_id BIGINT NOT NULL REFERENCES role_messages(message_id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  role_id BIGINT NOT NULL,
+  reaction TEXT NULL,
+  PRIMARY KEY (message_id, position)
+);
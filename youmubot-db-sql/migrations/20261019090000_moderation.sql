@@ -0,0 +1,29 @@
+-- Add migration script here
+
+CREATE TABLE mod_cases (
+  guild_id BIGINT NOT NULL,
+  -- Numbered from 1 within each guild.
+  case_id INTEGER NOT NULL,
+  user_id BIGINT NOT NULL,
+  moderator_id BIGINT NOT NULL,
+  -- 0 = warn, 1 = mute, 2 = unmute, 3 = kick, 4 = ban, 5 = unban
+  action INTEGER NOT NULL,
+  reason TEXT NULL,
+  created_at DATETIME NOT NULL,
+  -- When a timed mute or ban runs out.
+  expires_at DATETIME NULL,
+  PRIMARY KEY (guild_id, case_id)
+);
+
+CREATE INDEX mod_cases_user ON mod_cases(guild_id, user_id);
+
+-- Unbans scheduled by timed bans.
+CREATE TABLE mod_jobs (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  guild_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  case_id INTEGER NOT NULL,
+  due DATETIME NOT NULL
+);
+
+CREATE INDEX mod_jobs_due ON mod_jobs(due);
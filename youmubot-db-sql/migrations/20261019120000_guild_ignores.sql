@@ -0,0 +1,16 @@
+-- Add migration script here
+
+CREATE TABLE guild_ignored_users (
+  guild_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  ignored_since DATETIME NOT NULL DEFAULT (DATETIME('now')),
+  PRIMARY KEY (guild_id, user_id)
+);
+
+CREATE TABLE disabled_command_groups (
+  guild_id BIGINT NOT NULL,
+  -- 0 disables the group in the whole guild.
+  channel_id BIGINT NOT NULL,
+  group_name TEXT NOT NULL,
+  PRIMARY KEY (guild_id, channel_id, group_name)
+);
@@ -0,0 +1,8 @@
+-- Add migration script here
+
+CREATE TABLE guild_prefixes (
+  guild_id BIGINT NOT NULL,
+  position INTEGER NOT NULL,
+  prefix TEXT NOT NULL,
+  PRIMARY KEY (guild_id, position)
+);
@@ -0,0 +1,16 @@
+-- Add migration script here
+
+CREATE TABLE reminders (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  user_id BIGINT NOT NULL,
+  guild_id BIGINT NULL,
+  -- NULL if the reminder is delivered by DM.
+  channel_id BIGINT NULL,
+  message TEXT NOT NULL,
+  created_at DATETIME NOT NULL,
+  due DATETIME NOT NULL,
+  -- The interval of a recurring reminder, in seconds.
+  repeat_secs BIGINT NULL
+);
+
+CREATE INDEX reminders_user ON reminders(user_id);
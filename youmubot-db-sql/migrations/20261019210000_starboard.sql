@@ -0,0 +1,17 @@
+-- Add migration script here
+
+CREATE TABLE starboards (
+  guild_id BIGINT NOT NULL PRIMARY KEY,
+  channel_id BIGINT NOT NULL,
+  emoji TEXT NOT NULL,
+  threshold INTEGER NOT NULL
+);
+
+CREATE TABLE starred_messages (
+  message_id BIGINT NOT NULL PRIMARY KEY,
+  guild_id BIGINT NOT NULL REFERENCES starboards(guild_id) ON DELETE CASCADE,
+  channel_id BIGINT NOT NULL,
+  -- The repost in the starboard channel.
+  starboard_message_id BIGINT NOT NULL,
+  count INTEGER NOT NULL
+);
@@ -0,0 +1,12 @@
+-- Add migration script here
+
+CREATE TABLE announcer_channels (
+  announcer_key TEXT NOT NULL,
+  guild_id BIGINT NOT NULL,
+  channel_id BIGINT NOT NULL,
+  -- The thread inside the channel to post to, 0 posts to the channel itself.
+  thread_id BIGINT NOT NULL DEFAULT 0,
+  -- Limits what is posted to this channel, as understood by the announcer.
+  filter TEXT NULL,
+  PRIMARY KEY (announcer_key, guild_id, channel_id, thread_id)
+);
@@ -0,0 +1,17 @@
+-- Add migration script here
+
+CREATE TABLE paginators (
+  message_id BIGINT NOT NULL PRIMARY KEY,
+  channel_id BIGINT NOT NULL,
+  -- Only this user controls the message, anyone else gets their own copy. NULL lets anyone control it.
+  owner_id BIGINT NULL,
+  -- The message this is a copy of, for copies sent to users other than the owner.
+  copy_of BIGINT NULL,
+  -- Which paginator to restore, and the parameters to restore it from, as understood by the kind.
+  kind TEXT NOT NULL,
+  params TEXT NOT NULL,
+  page INT NOT NULL DEFAULT 0,
+  updated_at DATETIME NOT NULL
+);
+
+CREATE INDEX paginators_updated_at ON paginators(updated_at);
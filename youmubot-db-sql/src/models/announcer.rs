@@ -0,0 +1,121 @@
+use super::*;
+use sqlx::Executor;
+
+/// A channel an announcer posts to.
+#[derive(Clone, Debug)]
+pub struct AnnouncerChannel {
+    pub announcer_key: String,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    /// The thread of the channel to post to, if any.
+    pub thread_id: Option<i64>,
+    pub filter: Option<String>,
+}
+
+impl AnnouncerChannel {
+    /// Returns the channels of an announcer.
+    pub async fn by_key<'a, E>(announcer_key: &str, conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"SELECT announcer_key, guild_id, channel_id, thread_id, filter
+            FROM announcer_channels
+            WHERE announcer_key = ?
+            ORDER BY guild_id ASC, channel_id ASC, thread_id ASC"#,
+            announcer_key
+        )
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| AnnouncerChannel {
+            announcer_key: row.announcer_key,
+            guild_id: row.guild_id,
+            channel_id: row.channel_id,
+            thread_id: Some(row.thread_id).filter(|v| *v != 0),
+            filter: row.filter,
+        })
+        .collect())
+    }
+
+    /// Returns the announcer channels of a guild.
+    pub async fn by_guild<'a, E>(guild_id: i64, conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"SELECT announcer_key, guild_id, channel_id, thread_id, filter
+            FROM announcer_channels
+            WHERE guild_id = ?
+            ORDER BY announcer_key ASC, channel_id ASC, thread_id ASC"#,
+            guild_id
+        )
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| AnnouncerChannel {
+            announcer_key: row.announcer_key,
+            guild_id: row.guild_id,
+            channel_id: row.channel_id,
+            thread_id: Some(row.thread_id).filter(|v| *v != 0),
+            filter: row.filter,
+        })
+        .collect())
+    }
+
+    /// Stores the channel, replacing the filter of the old entry.
+    pub async fn store<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        let thread_id = self.thread_id.unwrap_or(0);
+        query!(
+            r#"INSERT INTO announcer_channels(announcer_key, guild_id, channel_id, thread_id, filter)
+               VALUES (?, ?, ?, ?, ?)
+               ON CONFLICT (announcer_key, guild_id, channel_id, thread_id) DO UPDATE SET filter = excluded.filter"#,
+            self.announcer_key,
+            self.guild_id,
+            self.channel_id,
+            thread_id,
+            self.filter,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the channel, returning whether it was there.
+    pub async fn delete<'a, E>(&self, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        let thread_id = self.thread_id.unwrap_or(0);
+        Ok(query!(
+            r#"DELETE FROM announcer_channels
+               WHERE announcer_key = ? AND guild_id = ? AND channel_id = ? AND thread_id = ?"#,
+            self.announcer_key,
+            self.guild_id,
+            self.channel_id,
+            thread_id,
+        )
+        .execute(conn)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+
+    /// Removes all channels of an announcer in a guild, returning how many there were.
+    pub async fn delete_all<'a, E>(announcer_key: &str, guild_id: i64, conn: E) -> Result<u64>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"DELETE FROM announcer_channels WHERE announcer_key = ? AND guild_id = ?"#,
+            announcer_key,
+            guild_id,
+        )
+        .execute(conn)
+        .await?
+        .rows_affected())
+    }
+}
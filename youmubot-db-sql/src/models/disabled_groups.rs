@@ -0,0 +1,70 @@
+use super::*;
+use sqlx::Executor;
+
+/// A command group disabled in a guild or one of its channels.
+#[derive(Clone, Debug)]
+pub struct DisabledGroup {
+    pub guild_id: i64,
+    /// `None` if the group is disabled in the whole guild.
+    pub channel_id: Option<i64>,
+    pub group_name: String,
+}
+
+impl DisabledGroup {
+    /// Returns all disabled groups.
+    pub async fn get_all<'a, E>(conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"SELECT guild_id, channel_id, group_name
+            FROM disabled_command_groups
+            ORDER BY guild_id ASC, channel_id ASC, group_name ASC"#
+        )
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| DisabledGroup {
+            guild_id: row.guild_id,
+            channel_id: Some(row.channel_id).filter(|v| *v != 0),
+            group_name: row.group_name,
+        })
+        .collect())
+    }
+
+    /// Disables the group.
+    pub async fn add<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        let channel_id = self.channel_id.unwrap_or(0);
+        query!(
+            r#"INSERT INTO disabled_command_groups(guild_id, channel_id, group_name) VALUES (?, ?, ?)
+               ON CONFLICT DO NOTHING"#,
+            self.guild_id,
+            channel_id,
+            self.group_name,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Enables the group again, returning whether it was disabled.
+    pub async fn remove<'a, E>(&self, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        let channel_id = self.channel_id.unwrap_or(0);
+        Ok(query!(
+            r#"DELETE FROM disabled_command_groups WHERE guild_id = ? AND channel_id = ? AND group_name = ?"#,
+            self.guild_id,
+            channel_id,
+            self.group_name,
+        )
+        .execute(conn)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+}
@@ -0,0 +1,55 @@
+use super::*;
+use sqlx::{Executor, Transaction};
+use std::collections::HashMap as Map;
+
+/// The custom command prefixes of a guild.
+#[derive(Clone, Debug)]
+pub struct GuildPrefixes {
+    pub guild_id: i64,
+    /// The prefixes, in the order they were given.
+    pub prefixes: Vec<String>,
+}
+
+impl GuildPrefixes {
+    /// Returns the prefixes of all guilds that have them set.
+    pub async fn get_all<'a, E>(conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        let mut prefixes: Map<i64, Vec<String>> = Map::new();
+        for row in query!(
+            r#"SELECT guild_id, prefix FROM guild_prefixes ORDER BY guild_id ASC, position ASC"#
+        )
+        .fetch_all(conn)
+        .await?
+        {
+            prefixes.entry(row.guild_id).or_default().push(row.prefix);
+        }
+        Ok(prefixes
+            .into_iter()
+            .map(|(guild_id, prefixes)| GuildPrefixes { guild_id, prefixes })
+            .collect())
+    }
+
+    /// Stores the prefixes, replacing the old ones. An empty list removes them.
+    pub async fn store(&self, conn: &mut Transaction<'_, Database>) -> Result<()> {
+        query!(
+            r#"DELETE FROM guild_prefixes WHERE guild_id = ?"#,
+            self.guild_id
+        )
+        .execute(&mut **conn)
+        .await?;
+        for (position, prefix) in self.prefixes.iter().enumerate() {
+            let position = position as i64;
+            query!(
+                r#"INSERT INTO guild_prefixes(guild_id, position, prefix) VALUES (?, ?, ?)"#,
+                self.guild_id,
+                position,
+                prefix
+            )
+            .execute(&mut **conn)
+            .await?;
+        }
+        Ok(())
+    }
+}
@@ -58,3 +58,65 @@ impl IgnoredUser {
         Ok(())
     }
 }
+
+/// An user ignored within a single guild.
+#[derive(Clone, Debug)]
+pub struct GuildIgnoredUser {
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub ignored_since: DateTime,
+}
+
+impl GuildIgnoredUser {
+    /// Returns a list of all users ignored in any guild.
+    pub async fn get_all<'a, E>(conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            GuildIgnoredUser,
+            r#"SELECT
+              guild_id,
+              user_id,
+              ignored_since as "ignored_since: DateTime"
+            FROM guild_ignored_users
+            ORDER BY guild_id ASC, user_id ASC"#
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Add an user to the guild's ignore list.
+    pub async fn add<'a, E>(conn: E, guild_id: i64, user_id: i64) -> Result<Self>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            GuildIgnoredUser,
+            r#"INSERT INTO guild_ignored_users(guild_id, user_id) VALUES (?, ?)
+               ON CONFLICT (guild_id, user_id) DO UPDATE SET user_id = excluded.user_id
+               RETURNING guild_id,
+              user_id,
+              ignored_since as "ignored_since: DateTime""#,
+            guild_id,
+            user_id
+        )
+        .fetch_one(conn)
+        .await?)
+    }
+
+    /// Remove an user from the guild's ignore list.
+    pub async fn remove<'a, E>(conn: E, guild_id: i64, user_id: i64) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"DELETE FROM guild_ignored_users WHERE guild_id = ? AND user_id = ?"#,
+            guild_id,
+            user_id
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
@@ -4,6 +4,15 @@ use sqlx::{query, query_as, Executor};
 /// The DateTime used in the package.
 pub type DateTime = chrono::DateTime<chrono::Utc>;
 
+pub mod announcer;
+pub mod disabled_groups;
+pub mod guild_prefix;
 pub mod ignore_list;
+pub mod moderation;
 pub mod osu;
 pub mod osu_user;
+pub mod paginator;
+pub mod poll;
+pub mod reminder;
+pub mod roles;
+pub mod starboard;
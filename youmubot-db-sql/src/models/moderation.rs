@@ -0,0 +1,144 @@
+use super::*;
+use sqlx::{query, query_as, Executor, Transaction};
+
+/// A moderation case, as kept in the guild's case log.
+#[derive(Debug, Clone)]
+pub struct ModCase {
+    pub guild_id: i64,
+    /// The number of the case within the guild.
+    pub case_id: i64,
+    pub user_id: i64,
+    pub moderator_id: i64,
+    pub action: u8,
+    pub reason: Option<String>,
+    pub created_at: DateTime,
+    /// When the punishment runs out, if it is timed.
+    pub expires_at: Option<DateTime>,
+}
+
+impl ModCase {
+    /// Stores a new case, setting its `case_id` to the next number in the guild.
+    pub async fn insert(&mut self, conn: &mut Transaction<'_, Database>) -> Result<()> {
+        self.case_id = query!(
+            r#"SELECT COALESCE(MAX(case_id), 0) + 1 as "next!: i64" FROM mod_cases WHERE guild_id = ?"#,
+            self.guild_id
+        )
+        .fetch_one(&mut **conn)
+        .await?
+        .next;
+        query!(
+            r#"INSERT INTO mod_cases(guild_id, case_id, user_id, moderator_id, action, reason, created_at, expires_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
+            self.guild_id,
+            self.case_id,
+            self.user_id,
+            self.moderator_id,
+            self.action,
+            self.reason,
+            self.created_at,
+            self.expires_at,
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns all cases of an user in the guild, oldest first.
+    pub async fn by_user<'a, E>(guild_id: i64, user_id: i64, conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            ModCase,
+            r#"SELECT
+              guild_id,
+              case_id,
+              user_id,
+              moderator_id,
+              action as "action: u8",
+              reason,
+              created_at as "created_at: DateTime",
+              expires_at as "expires_at: DateTime"
+            FROM mod_cases
+            WHERE guild_id = ? AND user_id = ?
+            ORDER BY case_id ASC"#,
+            guild_id,
+            user_id
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+}
+
+/// A scheduled unban.
+#[derive(Debug, Clone)]
+pub struct ModJob {
+    pub id: i64,
+    pub guild_id: i64,
+    pub user_id: i64,
+    /// The case that scheduled the job.
+    pub case_id: i64,
+    pub due: DateTime,
+}
+
+impl ModJob {
+    /// Returns all pending jobs.
+    pub async fn all<'a, E>(conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            ModJob,
+            r#"SELECT id, guild_id, user_id, case_id, due as "due: DateTime"
+            FROM mod_jobs
+            ORDER BY due ASC"#
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Stores a new job, setting its `id`.
+    pub async fn insert<'a, E>(&mut self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        self.id = query!(
+            r#"INSERT INTO mod_jobs(guild_id, user_id, case_id, due) VALUES (?, ?, ?, ?) RETURNING id"#,
+            self.guild_id,
+            self.user_id,
+            self.case_id,
+            self.due,
+        )
+        .fetch_one(conn)
+        .await?
+        .id;
+        Ok(())
+    }
+
+    /// Removes the job, returning whether it was still pending.
+    pub async fn delete<'a, E>(id: i64, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(r#"DELETE FROM mod_jobs WHERE id = ?"#, id)
+            .execute(conn)
+            .await?
+            .rows_affected()
+            > 0)
+    }
+
+    /// Cancels all pending jobs of an user in the guild.
+    pub async fn cancel<'a, E>(guild_id: i64, user_id: i64, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"DELETE FROM mod_jobs WHERE guild_id = ? AND user_id = ?"#,
+            guild_id,
+            user_id
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
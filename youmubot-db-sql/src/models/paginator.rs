@@ -0,0 +1,143 @@
+use super::*;
+use sqlx::Executor;
+
+/// A paginated message, stored so that it can be restored after the bot stops watching it.
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    pub message_id: i64,
+    pub channel_id: i64,
+    /// Only this user controls the message, anyone else gets their own copy.
+    pub owner_id: Option<i64>,
+    /// The message this is a copy of, for copies sent to users other than the owner.
+    pub copy_of: Option<i64>,
+    /// Which paginator to restore.
+    pub kind: String,
+    /// The parameters to restore the paginator from, as understood by the kind.
+    /// Empty for copies, which are restored from the parameters of the message they are a copy of.
+    pub params: String,
+    pub page: i64,
+    pub updated_at: DateTime,
+}
+
+impl Paginator {
+    /// Returns the paginator of a message.
+    pub async fn by_message<'a, E>(message_id: i64, conn: E) -> Result<Option<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            Paginator,
+            r#"SELECT
+              message_id,
+              channel_id,
+              owner_id,
+              copy_of,
+              kind,
+              params,
+              page,
+              updated_at as "updated_at: DateTime"
+            FROM paginators
+            WHERE message_id = ?"#,
+            message_id
+        )
+        .fetch_optional(conn)
+        .await?)
+    }
+
+    /// Stores the paginator, replacing the old one of the same message.
+    pub async fn store<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"INSERT INTO paginators(message_id, channel_id, owner_id, copy_of, kind, params, page, updated_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+               ON CONFLICT (message_id) DO UPDATE SET
+                 owner_id = excluded.owner_id,
+                 copy_of = excluded.copy_of,
+                 kind = excluded.kind,
+                 params = excluded.params,
+                 page = excluded.page,
+                 updated_at = excluded.updated_at"#,
+            self.message_id,
+            self.channel_id,
+            self.owner_id,
+            self.copy_of,
+            self.kind,
+            self.params,
+            self.page,
+            self.updated_at,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Moves the paginator of a message to another page.
+    pub async fn set_page<'a, E>(
+        message_id: i64,
+        page: i64,
+        updated_at: DateTime,
+        conn: E,
+    ) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"UPDATE paginators SET page = ?, updated_at = ? WHERE message_id = ?"#,
+            page,
+            updated_at,
+            message_id
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the paginator of a message.
+    pub async fn delete<'a, E>(message_id: i64, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(r#"DELETE FROM paginators WHERE message_id = ?"#, message_id)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the other copies of the same message that the owner of this copy has.
+    pub async fn delete_other_copies<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"DELETE FROM paginators WHERE copy_of = ? AND owner_id = ? AND message_id != ?"#,
+            self.copy_of,
+            self.owner_id,
+            self.message_id
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the paginators not used since `before`, returning how many there were.
+    /// Paginators with copies used since then are kept, as the copies are restored from them.
+    pub async fn delete_unused<'a, E>(before: DateTime, conn: E) -> Result<u64>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"DELETE FROM paginators
+               WHERE updated_at < ?
+                 AND message_id NOT IN (
+                   SELECT copy_of FROM paginators WHERE copy_of IS NOT NULL AND updated_at >= ?
+                 )"#,
+            before,
+            before
+        )
+        .execute(conn)
+        .await?
+        .rows_affected())
+    }
+}
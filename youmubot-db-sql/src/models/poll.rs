@@ -0,0 +1,179 @@
+use super::*;
+use sqlx::{query, query_as, Executor, Transaction};
+use std::collections::HashMap as Map;
+
+/// A running poll, as represented in the SQL.
+#[derive(Debug, Clone)]
+pub struct Poll {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    /// The message holding the voting panel.
+    pub message_id: i64,
+    pub author_id: i64,
+    pub question: String,
+    pub asked_at: DateTime,
+    pub deadline: DateTime,
+    /// How the votes are casted and counted.
+    pub kind: u8,
+    /// Whether the voters are hidden from the results.
+    pub anonymous: bool,
+    /// The choices, in the order they were given.
+    pub choices: Vec<PollChoice>,
+}
+
+/// A choice of a poll.
+#[derive(Debug, Clone)]
+pub struct PollChoice {
+    pub emote: String,
+    pub description: String,
+}
+
+/// A vote casted by an user.
+#[derive(Debug, Clone)]
+pub struct PollVote {
+    pub emote: String,
+    pub user_id: i64,
+    /// The position of the choice in the voter's ballot.
+    pub rank: i64,
+}
+
+impl Poll {
+    /// Returns all stored polls.
+    pub async fn all(conn: &Pool) -> Result<Vec<Self>> {
+        let mut choices: Map<i64, Vec<PollChoice>> = Map::new();
+        for row in query!(
+            r#"SELECT poll_id, emote, description
+            FROM poll_choices
+            ORDER BY poll_id ASC, position ASC"#
+        )
+        .fetch_all(conn)
+        .await?
+        {
+            choices.entry(row.poll_id).or_default().push(PollChoice {
+                emote: row.emote,
+                description: row.description,
+            });
+        }
+        Ok(query!(
+            r#"SELECT
+              id,
+              guild_id,
+              channel_id,
+              message_id,
+              author_id,
+              question,
+              asked_at as "asked_at: DateTime",
+              deadline as "deadline: DateTime",
+              kind as "kind: u8",
+              anonymous
+            FROM polls
+            ORDER BY deadline ASC"#
+        )
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| Poll {
+            id: row.id,
+            guild_id: row.guild_id,
+            channel_id: row.channel_id,
+            message_id: row.message_id,
+            author_id: row.author_id,
+            question: row.question,
+            asked_at: row.asked_at,
+            deadline: row.deadline,
+            kind: row.kind,
+            anonymous: row.anonymous,
+            choices: choices.remove(&row.id).unwrap_or_default(),
+        })
+        .collect())
+    }
+
+    /// Stores a new poll along with its choices, setting its `id`.
+    pub async fn insert(&mut self, conn: &mut Transaction<'_, Database>) -> Result<()> {
+        self.id = query!(
+            r#"INSERT INTO polls(guild_id, channel_id, message_id, author_id, question, asked_at, deadline, kind, anonymous)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+               RETURNING id"#,
+            self.guild_id,
+            self.channel_id,
+            self.message_id,
+            self.author_id,
+            self.question,
+            self.asked_at,
+            self.deadline,
+            self.kind,
+            self.anonymous,
+        )
+        .fetch_one(&mut **conn)
+        .await?
+        .id;
+        for (position, choice) in self.choices.iter().enumerate() {
+            let position = position as i64;
+            query!(
+                r#"INSERT INTO poll_choices(poll_id, position, emote, description) VALUES (?, ?, ?, ?)"#,
+                self.id,
+                position,
+                choice.emote,
+                choice.description,
+            )
+            .execute(&mut **conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns all votes casted on the poll.
+    pub async fn votes<'a, E>(id: i64, conn: E) -> Result<Vec<PollVote>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            PollVote,
+            r#"SELECT emote, user_id, rank FROM poll_votes WHERE poll_id = ? ORDER BY user_id ASC, rank ASC"#,
+            id
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Replaces the ballot of an user with the given choices, in order of preference.
+    pub async fn set_ballot(
+        id: i64,
+        user_id: i64,
+        emotes: &[&str],
+        conn: &mut Transaction<'_, Database>,
+    ) -> Result<()> {
+        query!(
+            r#"DELETE FROM poll_votes WHERE poll_id = ? AND user_id = ?"#,
+            id,
+            user_id
+        )
+        .execute(&mut **conn)
+        .await?;
+        for (rank, emote) in emotes.iter().enumerate() {
+            let rank = rank as i64;
+            query!(
+                r#"INSERT INTO poll_votes(poll_id, emote, user_id, rank) VALUES (?, ?, ?, ?)"#,
+                id,
+                emote,
+                user_id,
+                rank
+            )
+            .execute(&mut **conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the poll, along with its choices and votes.
+    pub async fn delete<'a, E>(id: i64, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(r#"DELETE FROM polls WHERE id = ?"#, id)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+use sqlx::Executor;
+
+/// A reminder, to be delivered when it is due.
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    pub id: i64,
+    pub user_id: i64,
+    pub guild_id: Option<i64>,
+    /// `None` if the reminder is delivered by DM.
+    pub channel_id: Option<i64>,
+    pub message: String,
+    pub created_at: DateTime,
+    pub due: DateTime,
+    /// The interval of a recurring reminder, in seconds.
+    pub repeat_secs: Option<i64>,
+}
+
+impl Reminder {
+    /// Returns all pending reminders.
+    pub async fn all<'a, E>(conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            Reminder,
+            r#"SELECT
+              id,
+              user_id,
+              guild_id,
+              channel_id,
+              message,
+              created_at as "created_at: DateTime",
+              due as "due: DateTime",
+              repeat_secs
+            FROM reminders
+            ORDER BY due ASC"#
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Returns the reminder with the given ID.
+    pub async fn get<'a, E>(id: i64, conn: E) -> Result<Option<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            Reminder,
+            r#"SELECT
+              id,
+              user_id,
+              guild_id,
+              channel_id,
+              message,
+              created_at as "created_at: DateTime",
+              due as "due: DateTime",
+              repeat_secs
+            FROM reminders
+            WHERE id = ?"#,
+            id
+        )
+        .fetch_optional(conn)
+        .await?)
+    }
+
+    /// Returns the pending reminders of an user.
+    pub async fn by_user<'a, E>(user_id: i64, conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            Reminder,
+            r#"SELECT
+              id,
+              user_id,
+              guild_id,
+              channel_id,
+              message,
+              created_at as "created_at: DateTime",
+              due as "due: DateTime",
+              repeat_secs
+            FROM reminders
+            WHERE user_id = ?
+            ORDER BY due ASC"#,
+            user_id
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Stores a new reminder, setting its `id`.
+    pub async fn insert<'a, E>(&mut self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        self.id = query!(
+            r#"INSERT INTO reminders(user_id, guild_id, channel_id, message, created_at, due, repeat_secs)
+               VALUES (?, ?, ?, ?, ?, ?, ?)
+               RETURNING id"#,
+            self.user_id,
+            self.guild_id,
+            self.channel_id,
+            self.message,
+            self.created_at,
+            self.due,
+            self.repeat_secs,
+        )
+        .fetch_one(conn)
+        .await?
+        .id;
+        Ok(())
+    }
+
+    /// Moves a recurring reminder to its next occurence.
+    pub async fn reschedule<'a, E>(id: i64, due: DateTime, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(r#"UPDATE reminders SET due = ? WHERE id = ?"#, due, id)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the reminder of the given user, returning whether it existed.
+    pub async fn delete<'a, E>(id: i64, user_id: i64, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"DELETE FROM reminders WHERE id = ? AND user_id = ?"#,
+            id,
+            user_id
+        )
+        .execute(conn)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+}
@@ -0,0 +1,241 @@
+use super::*;
+use sqlx::{query, query_as, Executor, Transaction};
+use std::collections::HashMap as Map;
+
+/// A self-assignable role.
+#[derive(Debug, Clone)]
+pub struct AssignableRole {
+    pub role_id: i64,
+    pub guild_id: i64,
+    pub description: String,
+    /// The representing emoji, as formatted by Discord.
+    pub reaction: Option<String>,
+    pub role_group: Option<String>,
+    pub requires: Option<i64>,
+    /// In seconds.
+    pub cooldown: Option<i64>,
+}
+
+impl AssignableRole {
+    /// Returns all assignable roles of a guild.
+    pub async fn by_guild<'a, E>(guild_id: i64, conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            AssignableRole,
+            r#"SELECT role_id, guild_id, description, reaction, role_group, requires, cooldown
+            FROM assignable_roles
+            WHERE guild_id = ?
+            ORDER BY role_id ASC"#,
+            guild_id
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Stores the role, overriding the old entry.
+    pub async fn store<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"INSERT INTO assignable_roles(role_id, guild_id, description, reaction, role_group, requires, cooldown)
+               VALUES (?, ?, ?, ?, ?, ?, ?)
+               ON CONFLICT (role_id) DO UPDATE SET
+                 guild_id = excluded.guild_id,
+                 description = excluded.description,
+                 reaction = excluded.reaction,
+                 role_group = excluded.role_group,
+                 requires = excluded.requires,
+                 cooldown = excluded.cooldown"#,
+            self.role_id,
+            self.guild_id,
+            self.description,
+            self.reaction,
+            self.role_group,
+            self.requires,
+            self.cooldown,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a role from the assignable list, returning whether it was there.
+    pub async fn delete<'a, E>(role_id: i64, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(
+            query!(r#"DELETE FROM assignable_roles WHERE role_id = ?"#, role_id)
+                .execute(conn)
+                .await?
+                .rows_affected()
+                > 0,
+        )
+    }
+}
+
+/// A message that hands out roles, either by reactions or by components.
+#[derive(Debug, Clone)]
+pub struct RoleMessage {
+    pub message_id: i64,
+    pub guild_id: i64,
+    pub channel_id: Option<i64>,
+    pub title: String,
+    /// 0 = reactions, 1 = buttons, 2 = select menu.
+    pub kind: u8,
+    pub select_min: u8,
+    pub select_max: u8,
+    /// The roles, in the order they are shown.
+    pub roles: Vec<RoleMessageRole>,
+}
+
+/// A role shown in a role message.
+#[derive(Debug, Clone)]
+pub struct RoleMessageRole {
+    pub role_id: i64,
+    pub reaction: Option<String>,
+}
+
+impl RoleMessage {
+    /// Returns all role messages of the given kind.
+    pub async fn by_kind(kind: u8, conn: &Pool) -> Result<Vec<Self>> {
+        let mut roles: Map<i64, Vec<RoleMessageRole>> = Map::new();
+        for row in query!(
+            r#"SELECT r.message_id, r.role_id, r.reaction
+            FROM role_message_roles r
+            INNER JOIN role_messages m ON m.message_id = r.message_id
+            WHERE m.kind = ?
+            ORDER BY r.message_id ASC, r.position ASC"#,
+            kind
+        )
+        .fetch_all(conn)
+        .await?
+        {
+            roles
+                .entry(row.message_id)
+                .or_default()
+                .push(RoleMessageRole {
+                    role_id: row.role_id,
+                    reaction: row.reaction,
+                });
+        }
+        Ok(query!(
+            r#"SELECT
+              message_id,
+              guild_id,
+              channel_id,
+              title,
+              kind as "kind: u8",
+              select_min as "select_min: u8",
+              select_max as "select_max: u8"
+            FROM role_messages
+            WHERE kind = ?"#,
+            kind
+        )
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| RoleMessage {
+            message_id: row.message_id,
+            guild_id: row.guild_id,
+            channel_id: row.channel_id,
+            title: row.title,
+            kind: row.kind,
+            select_min: row.select_min,
+            select_max: row.select_max,
+            roles: roles.remove(&row.message_id).unwrap_or_default(),
+        })
+        .collect())
+    }
+
+    /// Returns the role message with the given ID.
+    pub async fn by_id(
+        message_id: i64,
+        conn: &mut Transaction<'_, Database>,
+    ) -> Result<Option<Self>> {
+        let Some(row) = query!(
+            r#"SELECT
+              message_id,
+              guild_id,
+              channel_id,
+              title,
+              kind as "kind: u8",
+              select_min as "select_min: u8",
+              select_max as "select_max: u8"
+            FROM role_messages
+            WHERE message_id = ?"#,
+            message_id
+        )
+        .fetch_optional(&mut **conn)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let roles = query_as!(
+            RoleMessageRole,
+            r#"SELECT role_id, reaction FROM role_message_roles WHERE message_id = ? ORDER BY position ASC"#,
+            message_id
+        )
+        .fetch_all(&mut **conn)
+        .await?;
+        Ok(Some(RoleMessage {
+            message_id: row.message_id,
+            guild_id: row.guild_id,
+            channel_id: row.channel_id,
+            title: row.title,
+            kind: row.kind,
+            select_min: row.select_min,
+            select_max: row.select_max,
+            roles,
+        }))
+    }
+
+    /// Stores the role message along with its roles, overriding the old entry.
+    pub async fn store(&self, conn: &mut Transaction<'_, Database>) -> Result<()> {
+        Self::delete(self.message_id, &mut **conn).await?;
+        query!(
+            r#"INSERT INTO role_messages(message_id, guild_id, channel_id, title, kind, select_min, select_max)
+               VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+            self.message_id,
+            self.guild_id,
+            self.channel_id,
+            self.title,
+            self.kind,
+            self.select_min,
+            self.select_max,
+        )
+        .execute(&mut **conn)
+        .await?;
+        for (position, role) in self.roles.iter().enumerate() {
+            let position = position as i64;
+            query!(
+                r#"INSERT INTO role_message_roles(message_id, position, role_id, reaction) VALUES (?, ?, ?, ?)"#,
+                self.message_id,
+                position,
+                role.role_id,
+                role.reaction,
+            )
+            .execute(&mut **conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the role message, returning whether it existed.
+    pub async fn delete<'a, E>(message_id: i64, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query!(
+            r#"DELETE FROM role_messages WHERE message_id = ?"#,
+            message_id
+        )
+        .execute(conn)
+        .await?
+        .rows_affected()
+            > 0)
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+use sqlx::Executor;
+
+/// The starboard settings of a guild.
+#[derive(Debug, Clone)]
+pub struct Starboard {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    /// The counted reaction, as formatted by Discord.
+    pub emoji: String,
+    /// The number of reactions a message needs to be reposted.
+    pub threshold: i64,
+}
+
+impl Starboard {
+    /// Returns the starboards of all guilds.
+    pub async fn all<'a, E>(conn: E) -> Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            Starboard,
+            r#"SELECT guild_id, channel_id, emoji, threshold FROM starboards"#
+        )
+        .fetch_all(conn)
+        .await?)
+    }
+
+    /// Stores the starboard, overriding the old settings.
+    pub async fn store<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"INSERT INTO starboards(guild_id, channel_id, emoji, threshold) VALUES (?, ?, ?, ?)
+               ON CONFLICT (guild_id) DO UPDATE SET
+                 channel_id = excluded.channel_id,
+                 emoji = excluded.emoji,
+                 threshold = excluded.threshold"#,
+            self.guild_id,
+            self.channel_id,
+            self.emoji,
+            self.threshold,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the starboard of the guild, along with its reposts.
+    pub async fn delete<'a, E>(guild_id: i64, conn: E) -> Result<bool>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(
+            query!(r#"DELETE FROM starboards WHERE guild_id = ?"#, guild_id)
+                .execute(conn)
+                .await?
+                .rows_affected()
+                > 0,
+        )
+    }
+}
+
+/// A message reposted to the starboard.
+#[derive(Debug, Clone)]
+pub struct StarredMessage {
+    pub message_id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub starboard_message_id: i64,
+    /// The last known number of reactions.
+    pub count: i64,
+}
+
+impl StarredMessage {
+    /// Returns the repost of the given message, if any.
+    pub async fn get<'a, E>(message_id: i64, conn: E) -> Result<Option<Self>>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        Ok(query_as!(
+            StarredMessage,
+            r#"SELECT message_id, guild_id, channel_id, starboard_message_id, count
+            FROM starred_messages
+            WHERE message_id = ?"#,
+            message_id
+        )
+        .fetch_optional(conn)
+        .await?)
+    }
+
+    /// Stores the repost, overriding the old entry.
+    pub async fn store<'a, E>(&self, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"INSERT INTO starred_messages(message_id, guild_id, channel_id, starboard_message_id, count)
+               VALUES (?, ?, ?, ?, ?)
+               ON CONFLICT (message_id) DO UPDATE SET
+                 starboard_message_id = excluded.starboard_message_id,
+                 count = excluded.count"#,
+            self.message_id,
+            self.guild_id,
+            self.channel_id,
+            self.starboard_message_id,
+            self.count,
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Forgets the repost of the given message.
+    pub async fn delete<'a, E>(message_id: i64, conn: E) -> Result<()>
+    where
+        E: Executor<'a, Database = Database>,
+    {
+        query!(
+            r#"DELETE FROM starred_messages WHERE message_id = ?"#,
+            message_id
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
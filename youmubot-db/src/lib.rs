@@ -1,10 +1,10 @@
-use rustbreak::{deser::Yaml, FileDatabase, RustbreakError as DBError};
+use rustbreak::{deser::Yaml, PathDatabase, RustbreakError as DBError};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serenity::{
     model::id::GuildId,
     prelude::{TypeMap, TypeMapKey},
 };
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, sync::RwLock};
 
 /// GuildMap defines the guild-map type.
 /// It is basically a HashMap from a GuildId to a data structure.
@@ -12,8 +12,21 @@ pub type GuildMap<V> = HashMap<GuildId, V>;
 /// The generic DB type we will be using.
 pub struct DB<T>(std::marker::PhantomData<T>);
 
-/// A short type abbreviation for a FileDatabase.
-type Database<T> = FileDatabase<T, Yaml>;
+/// A short type abbreviation for a PathDatabase.
+///
+/// Saves are written to a temporary file which then replaces the database file,
+/// so the file is never left half-written.
+type Database<T> = PathDatabase<T, Yaml>;
+
+/// Held (for reading) while saving. Set to `true` once the databases are closed.
+static CLOSED: RwLock<bool> = RwLock::new(false);
+
+/// Waits for ongoing saves to finish, and stops any further saves.
+///
+/// This blocks, and is meant to be called once, when shutting down.
+pub fn close() {
+    *CLOSED.write().unwrap_or_else(|e| e.into_inner()) = true;
+}
 
 impl<T: std::any::Any + Send + Sync> TypeMapKey for DB<T> {
     type Value = Database<T>;
@@ -41,8 +54,8 @@ where
     }
 }
 
-/// The write guard for our FileDatabase.
-/// It wraps the FileDatabase in a write-on-drop lock.
+/// The write guard for our Database.
+/// It wraps the Database in a write-on-drop lock.
 #[derive(Debug)]
 pub struct DBWriteGuard<'a, T>
 where
@@ -68,11 +81,11 @@ impl<T> DBWriteGuard<'_, T>
 where
     T: Send + Sync + Clone + std::fmt::Debug + Serialize + DeserializeOwned,
 {
-    /// Borrows the FileDatabase.
+    /// Borrows the Database.
     pub fn borrow(&self) -> Result<std::sync::RwLockReadGuard<'_, T>, DBError> {
         self.db.borrow_data()
     }
-    /// Borrows the FileDatabase for writing.
+    /// Borrows the Database for writing.
     pub fn borrow_mut(&mut self) -> Result<std::sync::RwLockWriteGuard<'_, T>, DBError> {
         self.needs_save = true;
         self.db.borrow_data_mut()
@@ -85,7 +98,10 @@ where
 {
     fn drop(&mut self) {
         if self.needs_save {
-            if let Err(e) = self.db.save() {
+            let closed = CLOSED.read().unwrap_or_else(|e| e.into_inner());
+            if *closed {
+                eprintln!("Database is closed, changes are not saved");
+            } else if let Err(e) = self.db.save() {
                 dbg!(e);
             }
         }
@@ -16,7 +16,7 @@ use serenity::{
 };
 
 use announcer::MemberToChannels;
-use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::announcer::{AnnouncerChannel, CacheAndHttp, FilterTags};
 use youmubot_prelude::*;
 
 use crate::discord::calculate_weighted_map_age;
@@ -42,6 +42,13 @@ use super::{embeds::score_embed, BeatmapWithMode};
 pub const ANNOUNCER_KEY: &str = "osu";
 pub const ANNOUNCER_MAPPING_KEY: &str = "osu-mapping";
 const MAX_FAILURES: u8 = 64;
+/// Channels can filter the posts by the mode's name on the new site, or one of its common aliases.
+const MODE_FILTERS: FilterTags = &[
+    ("osu", &["std", "standard"]),
+    ("taiko", &[]),
+    ("fruits", &["ctb", "catch"]),
+    ("mania", &[]),
+];
 
 /// The announcer struct implementing youmubot_prelude::Announcer
 pub struct Announcer {
@@ -80,6 +87,19 @@ struct MappingAnnouncer {
 
 #[async_trait]
 impl youmubot_prelude::Announcer for Announcer {
+    fn filter_tags(&self) -> FilterTags {
+        MODE_FILTERS
+    }
+
+    fn schedule(&self) -> Schedule {
+        Schedule::every(std::time::Duration::from_secs(300))
+            .with_jitter(std::time::Duration::from_secs(30))
+            .with_backoff(
+                std::time::Duration::from_secs(60),
+                std::time::Duration::from_secs(30 * 60),
+            )
+    }
+
     async fn updates(
         &mut self,
         ctx: CacheAndHttp,
@@ -109,7 +129,7 @@ impl Announcer {
         ctx: impl CacheHttp + Clone + 'static,
         env: &OsuEnv,
         mut user: OsuUser,
-        broadcast_to: Vec<ChannelId>,
+        broadcast_to: Vec<AnnouncerChannel>,
     ) {
         if user.failures == MAX_FAILURES {
             return;
@@ -372,7 +392,7 @@ impl CollectedScore {
         ctx: impl CacheHttp,
         env: &OsuEnv,
         mention: UserId,
-        channels: &[ChannelId],
+        channels: &[AnnouncerChannel],
     ) -> Result<Vec<Message>> {
         // split the scores into ones worth sending separately
         let (individuals, mut batch) = scores
@@ -401,7 +421,7 @@ impl CollectedScore {
         ctx: impl CacheHttp,
         env: &OsuEnv,
         mention: UserId,
-        channels: &[ChannelId],
+        channels: &[AnnouncerChannel],
     ) -> Vec<Message> {
         if scores.len() <= 2 {
             scores
@@ -426,15 +446,21 @@ impl CollectedScore {
                 .collect::<Vec<_>>()
                 .await;
 
-            scores
-                .chunks(4)
-                .flat_map(|ch| {
-                    let ctx = &ctx;
-                    let embed = scores_summary_embed(ch, &ch[0].0.user);
-
-                    channels
+            let ctx = &ctx;
+            channels
+                .iter()
+                .flat_map(|c| {
+                    // Each channel only gets the plays of the modes it accepts.
+                    let scores = scores
                         .iter()
-                        .map(move |m| Self::send_batch_to(*m, mention, ctx, embed.clone()))
+                        .filter(|(s, _, _)| c.accepts(s.mode.as_str_new_site()))
+                        .collect::<Vec<_>>();
+                    scores
+                        .chunks(4)
+                        .map(|ch| scores_summary_embed(ch, &ch[0].0.user))
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .map(move |embed| Self::send_batch_to(c.target(), mention, ctx, embed))
                 })
                 .collect::<stream::FuturesUnordered<_>>()
                 .filter_map(|v| future::ready(v.pls_ok()))
@@ -467,12 +493,20 @@ impl CollectedScore {
         ctx: impl CacheHttp,
         env: &OsuEnv,
         mention: UserId,
-        channels: &[ChannelId],
+        channels: &[AnnouncerChannel],
     ) -> Result<Vec<Message>> {
+        let channels = channels
+            .iter()
+            .filter(|c| c.accepts(self.mode.as_str_new_site()))
+            .map(|c| c.target())
+            .collect::<Vec<_>>();
+        if channels.is_empty() {
+            return Ok(vec![]);
+        }
         let (bm, content) = self.get_beatmap(env).await?;
         Ok(channels
-            .iter()
-            .map(|c| self.send_message_to(mention, *c, &ctx, env, &bm, &content))
+            .into_iter()
+            .map(|c| self.send_message_to(mention, c, &ctx, env, &bm, &content))
             .collect::<stream::FuturesUnordered<_>>()
             .filter_map(|v| future::ready(v.pls_ok()))
             .collect::<Vec<_>>()
@@ -593,13 +627,31 @@ impl ScoreType {
 
 #[async_trait]
 impl youmubot_prelude::Announcer for MappingAnnouncer {
+    fn filter_tags(&self) -> FilterTags {
+        MODE_FILTERS
+    }
+
+    /// Runs as soon as the main announcer has collected the mapping events.
+    fn schedule(&self) -> Schedule {
+        Schedule::every(std::time::Duration::ZERO).with_backoff(
+            std::time::Duration::from_secs(60),
+            std::time::Duration::from_secs(30 * 60),
+        )
+    }
+
     async fn updates(
         &mut self,
         ctx: CacheAndHttp,
         _d: AppData,
         channels: MemberToChannels,
     ) -> Result<()> {
-        self.filled_recv.recv_async().await?;
+        // Don't hold up the shutdown waiting for events that won't come.
+        let filled = self.filled_recv.recv_async();
+        let stopped = self.env.prelude.shutdown.started();
+        match future::select(std::pin::pin!(filled), std::pin::pin!(stopped)).await {
+            future::Either::Left((filled, _)) => filled?,
+            future::Either::Right(_) => return Ok(()),
+        }
         self.mapping_events
             .iter_mut()
             .map(|mut r| (r.key().clone(), std::mem::take(r.value_mut())))
@@ -619,6 +671,7 @@ impl youmubot_prelude::Announcer for MappingAnnouncer {
                     maps,
                 };
                 channels.channels_of(ctx.clone(), user_id).then(move |chs| {
+                    let chs = chs.into_iter().map(|c| c.target()).collect();
                     r.ann
                         .update_user(r.ctx.clone(), r.env, r.user_id, r.maps, chs)
                 })
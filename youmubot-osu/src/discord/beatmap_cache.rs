@@ -81,7 +81,9 @@ impl BeatmapMetaCache {
 
     /// Get the given beatmap
     pub async fn get_beatmap(&self, client: &OsuClient, id: u64, mode: Mode) -> Result<Beatmap> {
-        match self.get_beatmap_db(id, mode).await? {
+        let cached = self.get_beatmap_db(id, mode).await?;
+        metrics::cache_lookup("beatmap_meta", cached.is_some());
+        match cached {
             Some(v) => Ok(v),
             None => self.insert_if_possible(client, id, Some(mode)).await,
         }
@@ -92,11 +94,13 @@ impl BeatmapMetaCache {
         for mode in [Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania].into_iter() {
             if let Ok(Some(bm)) = self.get_beatmap_db(id, mode).await {
                 if bm.mode == mode {
+                    metrics::cache_lookup("beatmap_meta", true);
                     return Ok(bm);
                 }
             }
         }
 
+        metrics::cache_lookup("beatmap_meta", false);
         self.insert_if_possible(client, id, None).await
     }
 
@@ -108,6 +112,7 @@ impl BeatmapMetaCache {
         mode: Option<Mode>,
     ) -> Result<Vec<Beatmap>> {
         let bms = models::CachedBeatmap::by_beatmapset(id as i64, &self.pool).await?;
+        metrics::cache_lookup("beatmapset_meta", !bms.is_empty());
         if !bms.is_empty() {
             return Ok(bms
                 .into_iter()
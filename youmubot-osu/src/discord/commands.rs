@@ -10,6 +10,7 @@ use link_parser::EmbedType;
 use poise::{ChoiceParameter, CreateReply};
 use serenity::all::{CreateAttachment, User};
 use server_rank::get_leaderboard_from_embed;
+use youmubot_prelude::ratelimit::Invoker;
 
 /// osu!-related command group.
 #[poise::command(
@@ -28,6 +29,7 @@ use server_rank::get_leaderboard_from_embed;
         "leaderboard",
         "clear_cache"
     ),
+    category = "osu",
     install_context = "Guild|User",
     interaction_context = "Guild|BotDm|PrivateChannel"
 )]
@@ -593,6 +595,11 @@ async fn ranks<U: HasOsuEnv>(
     #[description = "The gamemode for the rankings"] mode: Option<Mode>,
 ) -> Result<()> {
     let env = ctx.data().osu_env();
+    env.prelude.ratelimits.check(
+        "server_rank",
+        server_rank::SERVER_RANK_LIMIT,
+        &Invoker::of_context(ctx),
+    )?;
     let guild = ctx.partial_guild().await.unwrap();
     ctx.defer().await?;
     server_rank::do_server_ranks(
@@ -624,6 +631,11 @@ async fn leaderboard<U: HasOsuEnv>(
     #[description = "Score listing style"] style: Option<ScoreListStyle>,
 ) -> Result<()> {
     let env = ctx.data().osu_env();
+    env.prelude.ratelimits.check(
+        "leaderboard",
+        server_rank::LEADERBOARD_LIMIT,
+        &Invoker::of_context(ctx),
+    )?;
     let guild = ctx.partial_guild().await.unwrap();
     let style = style.unwrap_or_default();
     let order = sort.unwrap_or_default();
@@ -1,14 +1,32 @@
 pub use beatmapset::display_beatmapset;
+pub(crate) use scores::register_paginators;
 pub use scores::ScoreListStyle;
 
 mod scores {
     use poise::ChoiceParameter;
     use serenity::all::GuildId;
 
-    use youmubot_prelude::*;
+    use youmubot_prelude::{pagination::Paginators, *};
 
     use crate::{models::Score, scores::LazyBuffer};
 
+    /// Registers how to restore score lists, see [`Paginators`].
+    pub(crate) fn register_paginators(paginators: &Paginators) {
+        paginators.register(grid::KIND, grid::restore);
+        paginators.register(table::KIND, table::restore);
+    }
+
+    /// The IDs of the scores fetched so far, if they all have one.
+    async fn fetched_ids(scores: &mut impl LazyBuffer<Score>) -> Result<Option<Vec<u64>>> {
+        let count = scores.length_fetched().count();
+        Ok(scores
+            .get_range(0..count)
+            .await?
+            .iter()
+            .map(|s| s.id)
+            .collect())
+    }
+
     #[derive(Debug, Clone, Copy, PartialEq, Eq, ChoiceParameter)]
     /// The style for the scores list to be displayed.
     pub enum ScoreListStyle {
@@ -55,15 +73,37 @@ mod scores {
     }
 
     mod grid {
-        use pagination::paginate_with_first_message;
-        use serenity::all::{CreateActionRow, GuildId};
+        use pagination::{paginate_with_first_message, Persisted};
+        use serde::{Deserialize, Serialize};
+        use serenity::all::{ChannelId, CreateActionRow, GuildId};
 
         use youmubot_prelude::*;
 
         use crate::discord::interaction::score_components;
         use crate::discord::{cache::save_beatmap, BeatmapWithMode, OsuEnv};
         use crate::models::Score;
-        use crate::scores::LazyBuffer;
+        use crate::scores::{LazyBuffer, ScoresById};
+
+        pub(super) const KIND: &str = "osu_scores_grid";
+
+        /// What is stored to restore a grid.
+        #[derive(Serialize, Deserialize)]
+        struct Params {
+            scores: Vec<u64>,
+            guild_id: Option<u64>,
+            channel_id: u64,
+        }
+
+        pub(super) async fn restore(ctx: Context, params: String) -> Result<Paginate<ScoresById>> {
+            let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+            let params: Params = serde_json::from_str(&params)?;
+            Ok(Paginate {
+                scores: ScoresById::new(env.client.clone(), params.scores),
+                guild_id: params.guild_id.map(GuildId::new),
+                channel_id: ChannelId::new(params.channel_id),
+                env,
+            })
+        }
 
         pub async fn display_scores_grid(
             scores: impl LazyBuffer<Score>,
@@ -79,6 +119,7 @@ mod scores {
                 return Ok(());
             }
 
+            let timeout = env.prelude.config.pagination.timeout();
             paginate_with_first_message(
                 Paginate {
                     env,
@@ -88,7 +129,7 @@ mod scores {
                 },
                 ctx,
                 on,
-                std::time::Duration::from_secs(60),
+                timeout,
             )
             .await?;
             Ok(())
@@ -98,7 +139,7 @@ mod scores {
             env: OsuEnv,
             scores: T,
             guild_id: Option<GuildId>,
-            channel_id: serenity::all::ChannelId,
+            channel_id: ChannelId,
         }
 
         impl<T: LazyBuffer<Score>> Paginate<T> {
@@ -163,13 +204,29 @@ mod scores {
             fn len(&self) -> Option<usize> {
                 Some(self.pages_fake())
             }
+
+            async fn persist(&mut self) -> Result<Option<Persisted>> {
+                let Some(scores) = super::fetched_ids(&mut self.scores).await? else {
+                    return Ok(None);
+                };
+                let params = Params {
+                    scores,
+                    guild_id: self.guild_id.map(|v| v.get()),
+                    channel_id: self.channel_id.get(),
+                };
+                Ok(Some(Persisted {
+                    kind: KIND,
+                    params: serde_json::to_string(&params)?,
+                }))
+            }
         }
     }
 
     pub mod table {
         use std::borrow::Cow;
 
-        use pagination::paginate_with_first_message;
+        use pagination::{paginate_with_first_message, Persisted};
+        use serde::{Deserialize, Serialize};
         use serenity::all::{CreateActionRow, CreateAttachment};
 
         use youmubot_prelude::table_format::Align::{Left, Right};
@@ -179,7 +236,26 @@ mod scores {
         use crate::discord::oppai_cache::Stats;
         use crate::discord::{time_before_now, Beatmap, BeatmapInfo, OsuEnv};
         use crate::models::Score;
-        use crate::scores::LazyBuffer;
+        use crate::scores::{LazyBuffer, ScoresById};
+
+        pub(super) const KIND: &str = "osu_scores_table";
+
+        /// What is stored to restore a table.
+        #[derive(Serialize, Deserialize)]
+        struct Params {
+            header: String,
+            scores: Vec<u64>,
+        }
+
+        pub(super) async fn restore(ctx: Context, params: String) -> Result<Paginate<ScoresById>> {
+            let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+            let params: Params = serde_json::from_str(&params)?;
+            Ok(Paginate {
+                scores: ScoresById::new(env.client.clone(), params.scores),
+                header: params.header,
+                env,
+            })
+        }
 
         pub async fn display_scores_as_file(
             scores: impl LazyBuffer<Score>,
@@ -221,15 +297,17 @@ mod scores {
                 return Ok(());
             }
 
+            let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+            let timeout = env.prelude.config.pagination.timeout();
             paginate_with_first_message(
                 Paginate {
-                    env: ctx.data.read().await.get::<OsuEnv>().unwrap().clone(),
+                    env,
                     header: on.headers().unwrap_or("").to_owned(),
                     scores,
                 },
                 ctx,
                 on,
-                std::time::Duration::from_secs(60),
+                timeout,
             )
             .await?;
             Ok(())
@@ -406,6 +484,20 @@ mod scores {
                 let pages = size.count().div_ceil(ITEMS_PER_PAGE);
                 Some(pages + if size.is_total() { 0 } else { 1 })
             }
+
+            async fn persist(&mut self) -> Result<Option<Persisted>> {
+                let Some(scores) = super::fetched_ids(&mut self.scores).await? else {
+                    return Ok(None);
+                };
+                let params = Params {
+                    header: self.header.clone(),
+                    scores,
+                };
+                Ok(Some(Persisted {
+                    kind: KIND,
+                    params: serde_json::to_string(&params)?,
+                }))
+            }
         }
     }
 }
@@ -459,15 +551,11 @@ mod beatmapset {
             guild_id,
         };
 
+        let timeout = p.env.prelude.config.pagination.timeout();
         let ctx = ctx.clone();
-        pagination::paginate_with_first_message(
-            p,
-            &ctx,
-            target,
-            std::time::Duration::from_secs(60),
-        )
-        .await
-        .pls_ok();
+        pagination::paginate_with_first_message(p, &ctx, target, timeout)
+            .await
+            .pls_ok();
         Ok(true)
     }
 
@@ -561,8 +649,7 @@ mod beatmapset {
         }
 
         fn interaction_buttons(&self, mut buttons: Vec<CreateButton>) -> Vec<CreateButton> {
-            buttons.insert(
-                0,
+            buttons.push(
                 CreateButton::new(SHOW_ALL)
                     .emoji(ReactionType::try_from(SHOW_ALL_EMOTE).unwrap())
                     .label("Show all"),
@@ -570,6 +657,24 @@ mod beatmapset {
             buttons
         }
 
+        fn sections(&self) -> Vec<(String, u8)> {
+            self.maps
+                .iter()
+                .enumerate()
+                .map(|(i, map)| {
+                    (
+                        format!(
+                            "[{}] {} ({:.2}*)",
+                            map.mode.with_override(self.mode),
+                            map.difficulty_name,
+                            map.difficulty.stars
+                        ),
+                        i as u8,
+                    )
+                })
+                .collect()
+        }
+
         async fn handle_reaction(
             &mut self,
             page: u8,
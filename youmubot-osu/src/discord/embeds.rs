@@ -816,7 +816,7 @@ fn achievements_line(c: &CollectedScore) -> String {
 }
 
 pub(crate) fn scores_summary_embed(
-    cs: &[(CollectedScore, BeatmapWithMode, BeatmapContent)],
+    cs: &[&(CollectedScore, BeatmapWithMode, BeatmapContent)],
     u: &UserHeader,
 ) -> CreateEmbed {
     CreateEmbed::new()
@@ -234,7 +234,8 @@ pub fn hook<'a>(
             let use_short_link = if let Some(guild_id) = msg.guild_id {
                 announcer::announcer_of(ctx, crate::discord::announcer::ANNOUNCER_KEY, guild_id)
                     .await?
-                    == Some(msg.channel_id)
+                    .iter()
+                    .any(|c| c.target() == msg.channel_id)
             } else {
                 false
             };
@@ -53,6 +53,9 @@ mod link_parser;
 pub(crate) mod oppai_cache;
 mod server_rank;
 
+/// Where beatmap files are downloaded from, unless another osu! server is configured.
+const OSU_URL: &str = "https://osu.ppy.sh";
+
 /// The osu! client.
 pub(crate) struct OsuClient;
 
@@ -114,21 +117,18 @@ pub async fn setup(
     let last_beatmaps = OsuLastBeatmap::new(prelude.sql.clone());
 
     // API client
-    let mk_osu_client = async |usage: Usage| {
-        OsuHttpClient::new(
-            std::env::var("OSU_API_CLIENT_ID")
-                .expect("Please set OSU_API_CLIENT_ID as osu! api v2 client ID.")
-                .parse()
-                .expect("client_id should be u64"),
-            std::env::var("OSU_API_CLIENT_SECRET")
-                .expect("Please set OSU_API_CLIENT_SECRET as osu! api v2 client secret."),
-            usage,
-        )
-        .await
-        .expect("osu! should be initialized")
+    let (client_id, client_secret) = prelude.config.osu_credentials()?;
+    let client_secret = client_secret.to_owned();
+    let url = prelude.config.osu.url.clone();
+    let mk_osu_client = async |usage: Usage, rpm: u64| {
+        OsuHttpClient::new(client_id, client_secret.clone(), usage, rpm, url.as_deref()).await
     };
-    let osu_client = mk_osu_client(Usage::Foreground).await;
-    let oppai_cache = BeatmapCache::new(prelude.http.clone(), prelude.sql.clone());
+    let osu_client = mk_osu_client(Usage::Foreground, prelude.config.osu.foreground_rpm).await?;
+    let oppai_cache = BeatmapCache::new(
+        prelude.http.clone(),
+        prelude.sql.clone(),
+        url.as_deref().unwrap_or(OSU_URL),
+    );
     let beatmap_cache = BeatmapMetaCache::new(prelude.sql.clone());
 
     // Legacy data
@@ -149,10 +149,14 @@ pub async fn setup(
 
     data.insert::<OsuEnv>(env.clone());
 
+    // Paginated lists that keep working after a restart
+    display::register_paginators(&env.prelude.paginators);
+    server_rank::register_paginators(&env.prelude.paginators);
+
     // Announcer
     let ann = announcer::Announcer::new(OsuEnv {
         // give the announcer its own osu client with a separate rate limiter
-        client: mk_osu_client(Usage::Background).await,
+        client: mk_osu_client(Usage::Background, env.prelude.config.osu.background_rpm).await?,
         ..env.clone()
     });
     let map_ann = ann.mapping_announcer();
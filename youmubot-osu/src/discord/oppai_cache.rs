@@ -191,13 +191,19 @@ impl From<Mode> for rosu_pp::model::mode::GameMode {
 pub struct BeatmapCache {
     client: ratelimit::Ratelimit<reqwest::Client>,
     pool: Pool,
+    /// The osu! server the beatmap files are downloaded from.
+    url: Arc<str>,
 }
 
 impl BeatmapCache {
-    /// Create a new cache.
-    pub fn new(client: reqwest::Client, pool: Pool) -> Self {
+    /// Create a new cache, downloading beatmaps from the osu! server at `url`.
+    pub fn new(client: reqwest::Client, pool: Pool, url: &str) -> Self {
         let client = ratelimit::Ratelimit::new(client, 5, std::time::Duration::from_secs(1));
-        BeatmapCache { client, pool }
+        BeatmapCache {
+            client,
+            pool,
+            url: url.trim_end_matches('/').into(),
+        }
     }
 
     /// Clean the cache.
@@ -290,7 +296,7 @@ impl BeatmapCache {
 
     async fn download_beatmap(&self, id: u64) -> Result<BeatmapContent> {
         let (bm, content) = self
-            .download_beatmap_from_url(&format!("https://osu.ppy.sh/osu/{}", id))
+            .download_beatmap_from_url(&format!("{}/osu/{}", self.url, id))
             .await?;
 
         let mut bc = models::CachedBeatmapContent {
@@ -311,7 +317,9 @@ impl BeatmapCache {
 
     /// Get a beatmap from the cache.
     pub async fn get_beatmap(&self, id: u64) -> Result<BeatmapContent> {
-        match self.get_beatmap_db(id).await? {
+        let cached = self.get_beatmap_db(id).await?;
+        metrics::cache_lookup("beatmap_content", cached.is_some());
+        match cached {
             Some(v) => Ok(v),
             None => self.download_beatmap(id).await,
         }
@@ -8,9 +8,9 @@ use std::{
 };
 
 use chrono::DateTime;
-use pagination::paginate_with_first_message;
+use pagination::{paginate_with_first_message, Paginators, Persisted};
 use serenity::{
-    all::{CreateAttachment, CreateMessage, GuildId, Member, PartialGuild},
+    all::{CreateActionRow, CreateAttachment, CreateMessage, GuildId, Member, PartialGuild},
     framework::standard::{macros::command, Args, CommandResult},
     model::channel::Message,
     utils::MessageBuilder,
@@ -18,6 +18,7 @@ use serenity::{
 
 use youmubot_prelude::table_format::Align::{Left, Right};
 use youmubot_prelude::{
+    ratelimit::{Invoker, Limit, Scope},
     stream::FuturesUnordered,
     table_format::{table_formatting, Align},
     *,
@@ -36,6 +37,13 @@ use crate::{
 
 use super::{ModeArg, OsuEnv};
 
+/// Rankings look at every member of the server, so each user only gets a few at a time.
+pub(crate) const SERVER_RANK_LIMIT: Limit =
+    Limit::new(Scope::User, 2, std::time::Duration::from_secs(30));
+/// Leaderboards fetch the scores of every member of the server on the map.
+pub(crate) const LEADERBOARD_LIMIT: Limit =
+    Limit::new(Scope::User, 3, std::time::Duration::from_secs(20));
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, poise::ChoiceParameter)]
 pub(crate) enum RankQuery {
     #[default]
@@ -117,6 +125,9 @@ impl FromStr for RankQuery {
 #[only_in(guilds)]
 pub async fn server_rank(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    env.prelude
+        .ratelimits
+        .check("server_rank", SERVER_RANK_LIMIT, &Invoker::of_message(m))?;
     let mode = args.find::<ModeArg>().map(|v| v.0).ok();
     let query = args.find::<RankQuery>().ok();
     let guild = m
@@ -231,101 +242,91 @@ where
     let msg = mk_initial_message(header.clone()).await?;
 
     const ITEMS_PER_PAGE: usize = 10;
-    let users = Arc::new(users);
     let last_update = last_update.unwrap();
-    let total_len = users.len();
-    let total_pages = total_len.div_ceil(ITEMS_PER_PAGE);
-    paginate_with_first_message(
-        paginate_from_fn(move |page: u8, btns| {
-            let header = header.clone();
+    let total_pages = users.len().div_ceil(ITEMS_PER_PAGE);
+    let pages = users
+        .chunks(ITEMS_PER_PAGE)
+        .enumerate()
+        .map(|(page, users)| {
             use Align::*;
-            let users = users.clone();
-            Box::pin(async move {
-                let start = (page as usize) * ITEMS_PER_PAGE;
-                let end = (start + ITEMS_PER_PAGE).min(users.len());
-                if start >= end {
-                    return Ok(None);
+            let start = page * ITEMS_PER_PAGE;
+            let table = match query {
+                RankQuery::MapAge | RankQuery::MapLength => {
+                    let headers = ["#", query.col_name(), "pp", "Username", "Member"];
+                    const ALIGNS: [Align; 5] = [Right, Right, Right, Left, Left];
+
+                    let table = users
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (mem, ou))| {
+                            [
+                                format!("{}", 1 + i + start),
+                                query.extract_row(mode, ou).to_string(),
+                                RankQuery::PP.extract_row(mode, ou).to_string(),
+                                ou.username.to_string(),
+                                mem.distinct(),
+                            ]
+                        })
+                        .collect::<Vec<_>>();
+                    table_formatting(&headers, &ALIGNS, table)
                 }
-                let users = &users[start..end];
-                let table = match query {
-                    RankQuery::MapAge | RankQuery::MapLength => {
-                        let headers = ["#", query.col_name(), "pp", "Username", "Member"];
-                        const ALIGNS: [Align; 5] = [Right, Right, Right, Left, Left];
-
-                        let table = users
-                            .iter()
-                            .enumerate()
-                            .map(|(i, (mem, ou))| {
-                                [
-                                    format!("{}", 1 + i + start),
-                                    query.extract_row(mode, ou).to_string(),
-                                    RankQuery::PP.extract_row(mode, ou).to_string(),
-                                    ou.username.to_string(),
-                                    mem.distinct(),
-                                ]
-                            })
-                            .collect::<Vec<_>>();
-                        table_formatting(&headers, &ALIGNS, table)
-                    }
-                    RankQuery::PP => {
-                        const HEADERS: [&str; 6] =
-                            ["#", "pp", "Map length", "Map age", "Username", "Member"];
-                        const ALIGNS: [Align; 6] = [Right, Right, Right, Right, Left, Left];
-
-                        let table = users
-                            .iter()
-                            .enumerate()
-                            .map(|(i, (mem, ou))| {
-                                [
-                                    format!("{}", 1 + i + start),
-                                    RankQuery::PP.extract_row(mode, ou).to_string(),
-                                    RankQuery::MapLength.extract_row(mode, ou).to_string(),
-                                    RankQuery::MapAge.extract_row(mode, ou).to_string(),
-                                    ou.username.to_string(),
-                                    mem.distinct(),
-                                ]
-                            })
-                            .collect::<Vec<_>>();
-                        table_formatting(&HEADERS, &ALIGNS, table)
-                    }
-                    RankQuery::TotalPP => {
-                        const HEADERS: [&str; 4] = ["#", "Total pp", "Username", "Member"];
-                        const ALIGNS: [Align; 4] = [Right, Right, Left, Left];
-
-                        let table = users
-                            .iter()
-                            .enumerate()
-                            .map(|(i, (mem, ou))| {
-                                [
-                                    format!("{}", 1 + i + start),
-                                    query.extract_row(mode, ou).to_string(),
-                                    ou.username.to_string(),
-                                    mem.distinct(),
-                                ]
-                            })
-                            .collect::<Vec<_>>();
-                        table_formatting(&HEADERS, &ALIGNS, table)
-                    }
-                };
-                let content = MessageBuilder::new()
-                    .push_line(header)
-                    .push_line(table)
-                    .push_line(format!(
-                        "Page **{}**/**{}**. Last updated: {}",
-                        page + 1,
-                        total_pages,
-                        last_update.format("<t:%s:R>"),
-                    ))
-                    .build();
-                Ok(Some(
-                    CreateReply::default().content(content).components(btns),
+                RankQuery::PP => {
+                    const HEADERS: [&str; 6] =
+                        ["#", "pp", "Map length", "Map age", "Username", "Member"];
+                    const ALIGNS: [Align; 6] = [Right, Right, Right, Right, Left, Left];
+
+                    let table = users
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (mem, ou))| {
+                            [
+                                format!("{}", 1 + i + start),
+                                RankQuery::PP.extract_row(mode, ou).to_string(),
+                                RankQuery::MapLength.extract_row(mode, ou).to_string(),
+                                RankQuery::MapAge.extract_row(mode, ou).to_string(),
+                                ou.username.to_string(),
+                                mem.distinct(),
+                            ]
+                        })
+                        .collect::<Vec<_>>();
+                    table_formatting(&HEADERS, &ALIGNS, table)
+                }
+                RankQuery::TotalPP => {
+                    const HEADERS: [&str; 4] = ["#", "Total pp", "Username", "Member"];
+                    const ALIGNS: [Align; 4] = [Right, Right, Left, Left];
+
+                    let table = users
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (mem, ou))| {
+                            [
+                                format!("{}", 1 + i + start),
+                                query.extract_row(mode, ou).to_string(),
+                                ou.username.to_string(),
+                                mem.distinct(),
+                            ]
+                        })
+                        .collect::<Vec<_>>();
+                    table_formatting(&HEADERS, &ALIGNS, table)
+                }
+            };
+            MessageBuilder::new()
+                .push_line(&header)
+                .push_line(table)
+                .push_line(format!(
+                    "Page **{}**/**{}**. Last updated: {}",
+                    page + 1,
+                    total_pages,
+                    last_update.format("<t:%s:R>"),
                 ))
-            })
+                .build()
         })
-        .with_page_count(total_pages),
+        .collect::<Vec<_>>();
+    paginate_with_first_message(
+        RankingPages(pages),
         ctx,
         (msg, ctx),
-        std::time::Duration::from_secs(60),
+        env.prelude.config.pagination.timeout(),
     )
     .await?;
 
@@ -380,6 +381,9 @@ pub async fn show_leaderboard(ctx: &Context, msg: &Message, mut args: Args) -> C
     let style = args.single::<ScoreListStyle>().unwrap_or_default();
     let guild = msg.guild_id.expect("Guild-only command");
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    env.prelude
+        .ratelimits
+        .check("leaderboard", LEADERBOARD_LIMIT, &Invoker::of_message(msg))?;
     let Some(beatmap) = super::load_beatmap(
         &env,
         msg.channel_id,
@@ -726,18 +730,29 @@ pub async fn display_rankings_table(
     let total_len = scores.len();
     let total_pages = total_len.div_ceil(ITEMS_PER_PAGE);
     let header = to.content.clone();
+    let timeout = {
+        let data = ctx.data.read().await;
+        data.get::<OsuEnv>()
+            .unwrap()
+            .prelude
+            .config
+            .pagination
+            .timeout()
+    };
 
-    paginate_with_first_message(
-        paginate_from_fn(move |page: u8, btns| {
-            let start = (page as usize) * ITEMS_PER_PAGE;
-            let end = (start + ITEMS_PER_PAGE).min(scores.len());
-            if start >= end {
-                return Box::pin(future::ready(Ok(None)));
-            }
-            let scores = scores[start..end].to_vec();
-            let score_table =
-                rankings_to_table(&scores, start, end, has_lazer_score, show_diff, order);
-            let content = MessageBuilder::new()
+    let pages = (0..total_pages)
+        .map(|page| {
+            let start = page * ITEMS_PER_PAGE;
+            let end = (start + ITEMS_PER_PAGE).min(total_len);
+            let score_table = rankings_to_table(
+                &scores[start..end],
+                start,
+                end,
+                has_lazer_score,
+                show_diff,
+                order,
+            );
+            MessageBuilder::new()
                 .push_line(&header)
                 .push_line(score_table)
                 .push_line(format!(
@@ -745,17 +760,48 @@ pub async fn display_rankings_table(
                     page + 1,
                     total_pages,
                 ))
-                .build();
-
-            Box::pin(future::ready(Ok(Some(
-                CreateReply::default().content(content).components(btns),
-            ))))
+                .build()
         })
-        .with_page_count(total_pages),
-        ctx,
-        (to, ctx),
-        std::time::Duration::from_secs(60),
-    )
-    .await?;
+        .collect::<Vec<_>>();
+    paginate_with_first_message(RankingPages(pages), ctx, (to, ctx), timeout).await?;
     Ok(())
 }
+
+const RANKING_PAGES: &str = "osu_rankings";
+
+/// Registers how to restore rankings and leaderboards, see [`Paginators`].
+pub(crate) fn register_paginators(paginators: &Paginators) {
+    paginators.register(RANKING_PAGES, restore_ranking_pages);
+}
+
+async fn restore_ranking_pages(_: Context, params: String) -> Result<RankingPages> {
+    Ok(RankingPages(serde_json::from_str(&params)?))
+}
+
+/// The pages of a ranking table, rendered in advance so they can be restored as they were.
+struct RankingPages(Vec<String>);
+
+#[async_trait]
+impl Paginate for RankingPages {
+    async fn render(
+        &mut self,
+        page: u8,
+        btns: Vec<CreateActionRow>,
+    ) -> Result<Option<CreateReply>> {
+        Ok(self
+            .0
+            .get(page as usize)
+            .map(|content| CreateReply::default().content(content).components(btns)))
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+
+    async fn persist(&mut self) -> Result<Option<Persisted>> {
+        Ok(Some(Persisted {
+            kind: RANKING_PAGES,
+            params: serde_json::to_string(&self.0)?,
+        }))
+    }
+}
@@ -23,11 +23,19 @@ pub const MAX_TOP_SCORES_INDEX: usize = 200;
 /// Client is the client that will perform calls to the osu! api server.
 #[derive(Clone)]
 pub struct OsuClient {
-    rosu: Arc<Ratelimited<rosu_v2::Osu>>,
+    api: Arc<Ratelimited<Api>>,
 
     user_header_cache: Arc<Mutex<HashMap<u64, Option<UserHeader>>>>,
 }
 
+/// Where the API requests go.
+pub(crate) enum Api {
+    /// osu! itself, through rosu-v2.
+    Rosu(rosu_v2::Osu),
+    /// Another server, e.g. a stub of the API.
+    Custom(request::custom::CustomServer),
+}
+
 pub(crate) struct Ratelimited<T> {
     inner: T,
     usage: Usage,
@@ -35,11 +43,7 @@ pub(crate) struct Ratelimited<T> {
 }
 
 impl<T> Ratelimited<T> {
-    fn new(inner: T, usage: Usage) -> Self {
-        let rpm = match usage {
-            Usage::Background => 60,
-            Usage::Foreground => 600,
-        };
+    fn new(inner: T, usage: Usage, rpm: u64) -> Self {
         let interval = Duration::from_millis(Duration::from_mins(1).as_millis() as u64 * 2 / rpm);
         let rl = RateLimiter::builder()
             .max(20)
@@ -66,6 +70,7 @@ impl<T> Ratelimited<T> {
             self.limiter.balance()
         );
         self.limiter.acquire(tokens).await;
+        youmubot_prelude::metrics::api_request("osu");
         debug!(
             "[{:?}] acquired when bucket = {}",
             self.usage,
@@ -93,19 +98,30 @@ pub enum Usage {
 }
 
 impl OsuClient {
-    /// Create a new client from the given API key.
+    /// Create a new client from the given API key, making at most `rpm` requests per minute.
+    ///
+    /// Requests go to the server at `url` if given, and to osu! otherwise.
     pub async fn new(
         client_id: u64,
         client_secret: impl Into<String>,
         usage: Usage,
+        rpm: u64,
+        url: Option<&str>,
     ) -> Result<OsuClient> {
-        let rosu = rosu_v2::OsuBuilder::new()
-            .client_id(client_id)
-            .client_secret(client_secret)
-            .build()
-            .await?;
+        let api = match url {
+            Some(url) => Api::Custom(
+                request::custom::CustomServer::new(url, client_id, &client_secret.into()).await?,
+            ),
+            None => Api::Rosu(
+                rosu_v2::OsuBuilder::new()
+                    .client_id(client_id)
+                    .client_secret(client_secret)
+                    .build()
+                    .await?,
+            ),
+        };
         Ok(OsuClient {
-            rosu: Arc::new(Ratelimited::new(rosu, usage)),
+            api: Arc::new(Ratelimited::new(api, usage, rpm)),
             user_header_cache: Arc::new(Mutex::new(HashMap::new())),
         })
     }
@@ -202,11 +218,17 @@ impl OsuClient {
     }
 
     pub async fn score(&self, score_id: u64) -> Result<Option<Score>, Error> {
-        let s = match self.rosu.acquire_one().await.score(score_id).await {
-            Ok(v) => v,
-            Err(rosu_v2::error::OsuError::NotFound) => return Ok(None),
-            e => e?,
+        let s = match self.api.acquire_one().await {
+            Api::Rosu(osu) => match osu.score(score_id).await {
+                Ok(v) => Some(v),
+                Err(rosu_v2::error::OsuError::NotFound) => None,
+                Err(e) => {
+                    youmubot_prelude::metrics::api_failure("osu");
+                    return Err(e.into());
+                }
+            },
+            Api::Custom(server) => server.score(score_id).await?,
         };
-        Ok(Some(s.into()))
+        Ok(s.map(Into::into))
     }
 }
@@ -0,0 +1,207 @@
+//! Requests to an osu! server other than osu.ppy.sh, e.g. a stub of the API in tests.
+//!
+//! rosu-v2 always talks to osu.ppy.sh, so these requests are made here and parsed into its models.
+use rosu_v2::model::{
+    beatmap::{BeatmapExtended, BeatmapsetExtended},
+    event::Event,
+    mods::GameModsIntermode,
+    score::Score,
+    user::UserExtended,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use youmubot_prelude::*;
+
+use crate::models::{Mode, Mods};
+
+use super::UserID;
+
+/// The API version sent along with requests, the same one rosu-v2 asks for.
+const API_VERSION: &str = "20220705";
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct Scores {
+    scores: Vec<Score>,
+}
+
+/// An osu! server at a custom URL.
+pub(crate) struct CustomServer {
+    http: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl CustomServer {
+    /// Connects to the server at `url`, getting a token with the client credentials.
+    pub async fn new(url: &str, client_id: u64, client_secret: &str) -> Result<Self> {
+        let http = reqwest::Client::new();
+        let url = url.trim_end_matches('/').to_owned();
+        let token = http
+            .post(format!("{}/oauth/token", url))
+            .form(&[
+                ("client_id", client_id.to_string()),
+                ("client_secret", client_secret.to_owned()),
+                ("grant_type", "client_credentials".to_owned()),
+                ("scope", "public".to_owned()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let token: Token = serde_json::from_slice(&token)?;
+        Ok(Self {
+            http,
+            url,
+            token: token.access_token,
+        })
+    }
+
+    /// Gets `/api/v2/{path}`, or `None` if it is not found.
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Option<T>> {
+        let r: Result<Option<T>> = async {
+            let resp = self
+                .http
+                .get(format!("{}/api/v2/{}", self.url, path))
+                .bearer_auth(&self.token)
+                .header("x-api-version", API_VERSION)
+                .query(query)
+                .send()
+                .await?;
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            let body = resp.error_for_status()?.bytes().await?;
+            Ok(Some(serde_json::from_slice(&body)?))
+        }
+        .await;
+        if r.is_err() {
+            metrics::api_failure("osu");
+        }
+        r
+    }
+
+    pub async fn beatmap(&self, id: u64) -> Result<Option<BeatmapExtended>> {
+        self.get(&format!("beatmaps/{}", id), &[]).await
+    }
+
+    pub async fn beatmap_by_checksum(&self, checksum: &str) -> Result<Option<BeatmapExtended>> {
+        self.get("beatmaps/lookup", &[("checksum", checksum.to_owned())])
+            .await
+    }
+
+    pub async fn beatmapset(&self, id: u64) -> Result<Option<BeatmapsetExtended>> {
+        self.get(&format!("beatmapsets/{}", id), &[]).await
+    }
+
+    pub async fn user(&self, user: &UserID, mode: Option<Mode>) -> Result<Option<UserExtended>> {
+        let path = match mode {
+            Some(mode) => format!("users/{}/{}", user_path(user), mode.as_str_new_site()),
+            None => format!("users/{}", user_path(user)),
+        };
+        self.get(&path, &[]).await
+    }
+
+    /// All scores of an user on a beatmap.
+    pub async fn beatmap_user_scores(
+        &self,
+        beatmap_id: u64,
+        user: &UserID,
+        mode: Option<Mode>,
+    ) -> Result<Option<Vec<Score>>> {
+        let query = mode
+            .map(|m| ("mode", m.as_str_new_site().to_owned()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        let scores: Option<Scores> = self
+            .get(
+                &format!(
+                    "beatmaps/{}/scores/users/{}/all",
+                    beatmap_id,
+                    user_path(user)
+                ),
+                &query,
+            )
+            .await?;
+        Ok(scores.map(|s| s.scores))
+    }
+
+    /// The leaderboard of a beatmap.
+    pub async fn beatmap_scores(
+        &self,
+        beatmap_id: u64,
+        mode: Option<Mode>,
+        mods: Option<&Mods>,
+    ) -> Result<Option<Vec<Score>>> {
+        let mut query = vec![("type", "global".to_owned())];
+        if let Some(mode) = mode {
+            query.push(("mode", mode.as_str_new_site().to_owned()));
+        }
+        if let Some(mods) = mods {
+            for m in GameModsIntermode::from(mods.inner.clone()).iter() {
+                query.push(("mods[]", m.acronym().to_string()));
+            }
+        }
+        let scores: Option<Scores> = self
+            .get(&format!("beatmaps/{}/scores", beatmap_id), &query)
+            .await?;
+        Ok(scores.map(|s| s.scores))
+    }
+
+    /// The scores of an user of the given `kind` (`best`, `recent` or `pinned`).
+    pub async fn user_scores(
+        &self,
+        user: &UserID,
+        kind: &str,
+        mode: Option<Mode>,
+        include_fails: bool,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Option<Vec<Score>>> {
+        let mut query = vec![
+            ("limit", limit.to_string()),
+            ("offset", offset.to_string()),
+            ("include_fails", (include_fails as u8).to_string()),
+        ];
+        if let Some(mode) = mode {
+            query.push(("mode", mode.as_str_new_site().to_owned()));
+        }
+        self.get(
+            &format!("users/{}/scores/{}", user_path(user), kind),
+            &query,
+        )
+        .await
+    }
+
+    pub async fn recent_activity(
+        &self,
+        user: &UserID,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Option<Vec<Event>>> {
+        self.get(
+            &format!("users/{}/recent_activity", user_path(user)),
+            &[("limit", limit.to_string()), ("offset", offset.to_string())],
+        )
+        .await
+    }
+
+    pub async fn score(&self, id: u64) -> Result<Option<Score>> {
+        self.get(&format!("scores/{}", id), &[]).await
+    }
+}
+
+fn user_path(user: &UserID) -> String {
+    match user {
+        UserID::ID(id) => id.to_string(),
+        UserID::Username(name) => format!("@{}", name),
+    }
+}
@@ -3,10 +3,11 @@ use std::sync::Arc;
 
 use crate::models::{Mode, Mods, UserEvent};
 use crate::request::scores::FetchPure;
-use crate::OsuClient;
+use crate::{Api, OsuClient};
 use rosu_v2::error::OsuError;
 use youmubot_prelude::*;
 
+pub(crate) mod custom;
 pub(crate) mod scores;
 
 pub use scores::LazyBuffer;
@@ -55,7 +56,10 @@ fn handle_not_found<T>(v: Result<T, OsuError>) -> Result<Option<T>, OsuError> {
     match v {
         Ok(v) => Ok(Some(v)),
         Err(OsuError::NotFound) => Ok(None),
-        Err(e) => Err(e),
+        Err(e) => {
+            youmubot_prelude::metrics::api_failure("osu");
+            Err(e)
+        }
     }
 }
 
@@ -94,15 +98,11 @@ pub mod builders {
         pub(crate) async fn build(self, client: &OsuClient) -> Result<Vec<models::Beatmap>> {
             Ok(match self.kind {
                 BeatmapRequestKind::Beatmap(id) => {
-                    match handle_not_found(
-                        client
-                            .rosu
-                            .acquire_one()
-                            .await
-                            .beatmap()
-                            .map_id(id as u32)
-                            .await,
-                    )? {
+                    let bm = match client.api.acquire_one().await {
+                        Api::Rosu(osu) => handle_not_found(osu.beatmap().map_id(id as u32).await)?,
+                        Api::Custom(server) => server.beatmap(id).await?,
+                    };
+                    match bm {
                         Some(mut bm) => {
                             let set = bm.mapset.take().unwrap();
                             vec![models::Beatmap::from_rosu(bm, &set)]
@@ -111,9 +111,11 @@ pub mod builders {
                     }
                 }
                 BeatmapRequestKind::Beatmapset(id) => {
-                    let mut set = match handle_not_found(
-                        client.rosu.acquire_one().await.beatmapset(id as u32).await,
-                    )? {
+                    let set = match client.api.acquire_one().await {
+                        Api::Rosu(osu) => handle_not_found(osu.beatmapset(id as u32).await)?,
+                        Api::Custom(server) => server.beatmapset(id).await?,
+                    };
+                    let mut set = match set {
                         Some(v) => v,
                         None => return Ok(vec![]),
                     };
@@ -123,15 +125,11 @@ pub mod builders {
                         .collect()
                 }
                 BeatmapRequestKind::BeatmapHash(hash) => {
-                    let mut bm = match handle_not_found(
-                        client
-                            .rosu
-                            .acquire_one()
-                            .await
-                            .beatmap()
-                            .checksum(hash)
-                            .await,
-                    )? {
+                    let bm = match client.api.acquire_one().await {
+                        Api::Rosu(osu) => handle_not_found(osu.beatmap().checksum(hash).await)?,
+                        Api::Custom(server) => server.beatmap_by_checksum(&hash).await?,
+                    };
+                    let mut bm = match bm {
                         Some(v) => v,
                         None => return Ok(vec![]),
                     };
@@ -158,11 +156,17 @@ pub mod builders {
         }
 
         pub(crate) async fn build(self, client: &OsuClient) -> Result<Option<models::User>> {
-            let mut r = client.rosu.acquire_one().await.user(self.user);
-            if let Some(mode) = self.mode {
-                r = r.mode(mode.into());
-            }
-            let mut user = match handle_not_found(r.await)? {
+            let user = match client.api.acquire_one().await {
+                Api::Rosu(osu) => {
+                    let mut r = osu.user(self.user);
+                    if let Some(mode) = self.mode {
+                        r = r.mode(mode.into());
+                    }
+                    handle_not_found(r.await)?
+                }
+                Api::Custom(server) => server.user(&self.user, self.mode).await?,
+            };
+            let mut user = match user {
                 Some(v) => v,
                 None => return Ok(None),
             };
@@ -210,31 +214,22 @@ pub mod builders {
             osu: &crate::OsuClient,
             _offset: usize,
         ) -> Result<Vec<models::Score>> {
-            let scores = handle_not_found(match &self.user {
-                Some(user) => {
-                    let mut r = osu
-                        .rosu
-                        .acquire_one()
-                        .await
-                        .beatmap_user_scores(self.beatmap_id as u32, user.clone());
+            let api = osu.api.acquire_one().await;
+            let scores = match (&self.user, api) {
+                (Some(user), Api::Rosu(osu)) => {
+                    let mut r = osu.beatmap_user_scores(self.beatmap_id as u32, user.clone());
                     if let Some(mode) = self.mode {
                         r = r.mode(mode.into());
                     }
-                    match &self.mods {
-                        Some(mods) => r.await.map(|mut ss| {
-                            ss.retain(|s| Mods::from_gamemods(s.mods.clone()).contains(mods));
-                            ss
-                        }),
-                        None => r.await,
-                    }
+                    handle_not_found(r.await)?
                 }
-                None => {
-                    let mut r = osu
-                        .rosu
-                        .acquire_one()
-                        .await
-                        .beatmap_scores(self.beatmap_id as u32)
-                        .global();
+                (Some(user), Api::Custom(server)) => {
+                    server
+                        .beatmap_user_scores(self.beatmap_id, user, self.mode)
+                        .await?
+                }
+                (None, Api::Rosu(osu)) => {
+                    let mut r = osu.beatmap_scores(self.beatmap_id as u32).global();
                     if let Some(mode) = &self.mode {
                         r = r.mode((*mode).into());
                     }
@@ -242,10 +237,18 @@ pub mod builders {
                         r = r.mods(GameModsIntermode::from(mods.inner.clone()));
                     }
                     // r = r.limit(limit); // can't do this just yet because of offset not working
-                    Ok(r.await?.scores)
+                    handle_not_found(r.await)?.map(|v| v.scores)
+                }
+                (None, Api::Custom(server)) => {
+                    server
+                        .beatmap_scores(self.beatmap_id, self.mode, self.mods.as_ref())
+                        .await?
                 }
-            })?
-            .ok_or_else(|| error!("beatmap or user not found"))?;
+            };
+            let mut scores = scores.ok_or_else(|| error!("beatmap or user not found"))?;
+            if let (Some(_), Some(mods)) = (&self.user, &self.mods) {
+                scores.retain(|s| Mods::from_gamemods(s.mods.clone()).contains(mods));
+            }
             Ok(scores.into_iter().map(|v| v.into()).collect())
         }
 
@@ -294,24 +297,40 @@ pub mod builders {
         const SCORES_PER_PAGE: usize = 100;
 
         async fn with_offset(&self, client: &OsuClient, offset: usize) -> Result<Vec<Score>> {
-            let scores = handle_not_found({
-                let mut r = client
-                    .rosu
-                    .acquire_one()
-                    .await
-                    .user_scores(self.user.clone())
-                    .limit(Self::SCORES_PER_PAGE)
-                    .offset(offset);
-                r = match self.score_type {
-                    UserScoreType::Recent => r.recent().include_fails(self.include_fails),
-                    UserScoreType::Best => r.best(),
-                    UserScoreType::Pin => r.pinned(),
-                };
-                if let Some(mode) = self.mode {
-                    r = r.mode(mode.into());
+            let scores = match client.api.acquire_one().await {
+                Api::Rosu(osu) => {
+                    let mut r = osu
+                        .user_scores(self.user.clone())
+                        .limit(Self::SCORES_PER_PAGE)
+                        .offset(offset);
+                    r = match self.score_type {
+                        UserScoreType::Recent => r.recent().include_fails(self.include_fails),
+                        UserScoreType::Best => r.best(),
+                        UserScoreType::Pin => r.pinned(),
+                    };
+                    if let Some(mode) = self.mode {
+                        r = r.mode(mode.into());
+                    }
+                    handle_not_found(r.await)?
+                }
+                Api::Custom(server) => {
+                    let kind = match self.score_type {
+                        UserScoreType::Recent => "recent",
+                        UserScoreType::Best => "best",
+                        UserScoreType::Pin => "pinned",
+                    };
+                    server
+                        .user_scores(
+                            &self.user,
+                            kind,
+                            self.mode,
+                            self.include_fails,
+                            Self::SCORES_PER_PAGE,
+                            offset,
+                        )
+                        .await?
                 }
-                r.await
-            })?
+            }
             .ok_or_else(|| error!("user not found"))?;
             Ok(scores.into_iter().map(|v| v.into()).collect())
         }
@@ -357,19 +376,23 @@ impl FetchPure for UserEventRequest {
         client: &crate::OsuClient,
         offset: usize,
     ) -> Result<Vec<Self::Item>> {
-        Ok(handle_not_found(
-            client
-                .rosu
-                .acquire_one()
-                .await
-                .recent_activity(self.user.clone())
-                .limit(Self::ITEMS_PER_PAGE)
-                .offset(offset)
-                .await,
-        )?
-        .ok_or_else(|| error!("user not found"))?
-        .into_iter()
-        .map(Into::into)
-        .collect())
+        let events = match client.api.acquire_one().await {
+            Api::Rosu(osu) => handle_not_found(
+                osu.recent_activity(self.user.clone())
+                    .limit(Self::ITEMS_PER_PAGE)
+                    .offset(offset)
+                    .await,
+            )?,
+            Api::Custom(server) => {
+                server
+                    .recent_activity(&self.user, Self::ITEMS_PER_PAGE, offset)
+                    .await?
+            }
+        };
+        Ok(events
+            .ok_or_else(|| error!("user not found"))?
+            .into_iter()
+            .map(Into::into)
+            .collect())
     }
 }
@@ -2,7 +2,7 @@ use std::{fmt::Display, future::Future, ops::Range};
 
 use youmubot_prelude::*;
 
-use crate::OsuClient;
+use crate::{models::Score, OsuClient};
 
 pub const MAX_SCORE_PER_PAGE: usize = 1000;
 
@@ -292,3 +292,75 @@ impl<T: Fetch> Fetcher<T> {
         self.items.len()
     }
 }
+
+/// Scores fetched one by one by their IDs, as they are needed.
+///
+/// Scores that no longer exist are left out.
+pub struct ScoresById {
+    client: OsuClient,
+    ids: Vec<u64>,
+    scores: Vec<Option<Option<Score>>>,
+    /// The scores returned by the last `get_range`.
+    range: Vec<Score>,
+}
+
+impl ScoresById {
+    pub fn new(client: OsuClient, ids: Vec<u64>) -> Self {
+        Self {
+            client,
+            scores: vec![None; ids.len()],
+            ids,
+            range: vec![],
+        }
+    }
+}
+
+impl LazyBuffer<Score> for ScoresById {
+    fn length_fetched(&self) -> Size {
+        Size::Total(self.ids.len())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    async fn get(&mut self, index: usize) -> Result<Option<&Score>> {
+        Ok(self.get_range(index..(index + 1)).await?.first())
+    }
+
+    async fn get_all(mut self) -> Result<Vec<Score>> {
+        let _ = self.get_range(0..usize::MAX).await?;
+        Ok(self.range)
+    }
+
+    async fn get_range(&mut self, range: Range<usize>) -> Result<&[Score]> {
+        let range = fit_range_to_len(self.ids.len(), range);
+        let fetched = range
+            .clone()
+            .filter(|&i| self.scores[i].is_none())
+            .map(|i| {
+                let client = &self.client;
+                let id = self.ids[i];
+                async move {
+                    let r: Result<_> = Ok((i, client.score(id).await?));
+                    r
+                }
+            })
+            .collect::<stream::FuturesUnordered<_>>()
+            .try_collect::<Vec<_>>()
+            .await?;
+        for (i, score) in fetched {
+            self.scores[i] = Some(score);
+        }
+        self.range = self.scores[range]
+            .iter()
+            .filter_map(|s| s.clone().flatten())
+            .collect();
+        Ok(&self.range)
+    }
+
+    async fn find<F: FnMut(&Score) -> bool + Send>(&mut self, mut f: F) -> Result<Option<&Score>> {
+        let _ = self.get_range(0..usize::MAX).await?;
+        Ok(self.range.iter().find(|v| f(v)))
+    }
+}
@@ -1,6 +1,15 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
 use futures_util::{
     future::{join_all, ready, FutureExt},
     stream::{FuturesUnordered, StreamExt},
@@ -13,16 +22,22 @@ use serenity::{
     },
     http::{CacheHttp, Http},
     model::{
-        channel::Message,
+        channel::{ChannelType, Message},
         id::{ChannelId, GuildId, UserId},
     },
     prelude::*,
     utils::MessageBuilder,
 };
 
+use tokio::sync::Notify;
 use youmubot_db::DB;
+use youmubot_db_sql::{models::announcer as model, Pool};
 
-use crate::{AppData, MemberCache, Result};
+use crate::{
+    shutdown::Shutdown,
+    table_format::{table_formatting, Align},
+    AppData, Config, MemberCache, Result, SQLClient, Schedule,
+};
 
 #[derive(Debug, Clone)]
 pub struct CacheAndHttp(Arc<Cache>, Arc<Http>);
@@ -46,8 +61,136 @@ impl CacheHttp for CacheAndHttp {
     }
 }
 
-/// A list of assigned channels for an announcer.
-pub(crate) type AnnouncerChannels = DB<HashMap<String, HashMap<GuildId, ChannelId>>>;
+/// (Depreciated) A list of assigned channels for an announcer, as it was stored in YAML.
+type LegacyAnnouncerChannels = DB<HashMap<String, HashMap<GuildId, ChannelId>>>;
+
+/// Imports the announcer channels from the old YAML file into SQL.
+/// The imported file is renamed, so this only happens once.
+pub(crate) async fn import_announcer_channels(
+    pool: &Pool,
+    path: impl AsRef<std::path::Path>,
+) -> Result<()> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(());
+    }
+    let list = LegacyAnnouncerChannels::load_from_path(path)
+        .and_then(|v| v.get_data(true))
+        .map_err(|e| crate::Error::msg(format!("cannot load announcer channels: {}", e)))?;
+    let mut tx = pool.begin().await?;
+    for (key, channels) in list {
+        for (guild, channel) in channels {
+            model::AnnouncerChannel {
+                announcer_key: key.clone(),
+                guild_id: guild.get() as i64,
+                channel_id: channel.get() as i64,
+                thread_id: None,
+                filter: None,
+            }
+            .store(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".imported");
+    std::fs::rename(path, backup)?;
+    eprintln!("Imported announcer channels from {:?}.", path);
+    Ok(())
+}
+
+/// Where an announcer posts to in a guild.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnouncerChannel {
+    pub guild: GuildId,
+    pub channel: ChannelId,
+    /// Posts go to this thread of the channel instead, e.g. a forum post.
+    pub thread: Option<ChannelId>,
+    /// Limits what gets posted here. What it means depends on the announcer,
+    /// announcers that don't understand filters post everything.
+    pub filter: Option<String>,
+}
+
+impl AnnouncerChannel {
+    /// The channel to send messages to.
+    pub fn target(&self) -> ChannelId {
+        self.thread.unwrap_or(self.channel)
+    }
+
+    /// Whether something with the given tag should be posted here.
+    ///
+    /// The filter is a list of tags separated by commas or spaces, matched case-insensitively.
+    pub fn accepts(&self, tag: &str) -> bool {
+        match &self.filter {
+            None => true,
+            Some(f) => filter_tags(f).any(|t| t.eq_ignore_ascii_case(tag)),
+        }
+    }
+
+    /// Describes the channel for listing, e.g. `#thread in #forum, only mania`.
+    fn describe(&self) -> String {
+        let mut s = match self.thread {
+            Some(t) => format!("{} in {}", t.mention(), self.channel.mention()),
+            None => self.channel.mention().to_string(),
+        };
+        if let Some(f) = &self.filter {
+            s += &format!(", only `{}`", f);
+        }
+        s
+    }
+
+    fn from_model(m: model::AnnouncerChannel) -> Self {
+        Self {
+            guild: GuildId::new(m.guild_id as u64),
+            channel: ChannelId::new(m.channel_id as u64),
+            thread: m.thread_id.map(|v| ChannelId::new(v as u64)),
+            filter: m.filter,
+        }
+    }
+
+    fn to_model(&self, key: &str) -> model::AnnouncerChannel {
+        model::AnnouncerChannel {
+            announcer_key: key.to_owned(),
+            guild_id: self.guild.get() as i64,
+            channel_id: self.channel.get() as i64,
+            thread_id: self.thread.map(|v| v.get() as i64),
+            filter: self.filter.clone(),
+        }
+    }
+
+    /// The announcer channel that posts to the channel a message was sent in.
+    async fn of_message(ctx: &Context, m: &Message, filter: Option<String>) -> Result<Self> {
+        let channel = m
+            .channel_id
+            .to_channel(ctx)
+            .await?
+            .guild()
+            .ok_or_else(|| crate::Error::msg("Announcers can only post to server channels"))?;
+        let (channel, thread) = match (channel.kind, channel.parent_id) {
+            (
+                ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread,
+                Some(parent),
+            ) => (parent, Some(channel.id)),
+            _ => (channel.id, None),
+        };
+        Ok(Self {
+            guild: m.guild_id.expect("Guild-only command"),
+            channel,
+            thread,
+            filter,
+        })
+    }
+}
+
+fn filter_tags(filter: &str) -> impl Iterator<Item = &str> {
+    filter
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+}
+
+/// The tags an announcer's posts can be filtered by, each with its aliases.
+pub type FilterTags = &'static [(&'static str, &'static [&'static str])];
 
 /// The Announcer trait.
 ///
@@ -55,8 +198,10 @@ pub(crate) type AnnouncerChannels = DB<HashMap<String, HashMap<GuildId, ChannelI
 /// This method is called "updates", which takes:
 ///  - A CacheHttp implementation, for interaction with Discord itself.
 ///  - An AppData, which can be used for interacting with internal databases.
-///  - A function "channels", which takes an UserId and returns the list of ChannelIds, which any update related to that user should be
+///  - A function "channels", which takes an UserId and returns the list of [`AnnouncerChannel`]s, which any update related to that user should be
 ///  sent to.
+///
+/// How often updates are looked for is decided by the announcer's [`Schedule`].
 #[async_trait]
 pub trait Announcer: Send {
     /// Look for updates and send them to respective channels.
@@ -68,26 +213,40 @@ pub trait Announcer: Send {
         d: AppData,
         channels: MemberToChannels,
     ) -> Result<()>;
+
+    /// When to look for updates. Defaults to every 5 minutes, without retrying on errors.
+    fn schedule(&self) -> Schedule {
+        Schedule::default()
+    }
+
+    /// The tags that channels can filter the posts by, see [`AnnouncerChannel::accepts`].
+    /// Defaults to none, so channels of this announcer cannot have filters.
+    fn filter_tags(&self) -> FilterTags {
+        &[]
+    }
 }
 
 /// A simple struct that allows looking up the relevant channels to an user.
-pub struct MemberToChannels(Vec<(GuildId, ChannelId)>, AppData);
+///
+/// It also counts the users looked up, which are reported as the users processed by the announcer.
+pub struct MemberToChannels(Vec<AnnouncerChannel>, AppData, Arc<AtomicUsize>);
 
 impl MemberToChannels {
-    /// Gets the channel list of an user related to that channel.
+    /// Gets the channels to post to about an user, in the guilds that the user is in.
     pub async fn channels_of(
         &self,
         http: impl CacheHttp + Clone,
         u: impl Into<UserId>,
-    ) -> Vec<ChannelId> {
+    ) -> Vec<AnnouncerChannel> {
         let u: UserId = u.into();
+        self.2.fetch_add(1, Ordering::Relaxed);
         let member_cache = self.1.read().await.get::<MemberCache>().unwrap().clone();
         self.0
             .clone()
             .into_iter()
-            .map(|(guild, channel)| {
+            .map(|channel| {
                 member_cache
-                    .query(http.clone(), u, guild)
+                    .query(http.clone(), u, channel.guild)
                     .map(move |t| t.map(|_| channel))
             })
             .collect::<FuturesUnordered<_>>()
@@ -141,12 +300,28 @@ impl AnnouncerHandler {
         }
     }
 
-    pub fn run(self, client: &Client) -> AnnouncerRunner {
-        AnnouncerRunner {
+    /// Prepares the announcers to be run, leaving out the ones disabled in the configuration.
+    ///
+    /// Fails if the configuration has settings for announcers that don't exist.
+    pub fn run(self, client: &Client, config: Arc<Config>) -> Result<AnnouncerRunner> {
+        config.validate_announcers(self.announcers.keys().copied())?;
+        let announcers = self
+            .announcers
+            .into_iter()
+            .filter(|(key, _)| {
+                let enabled = config.announcer_enabled(key);
+                if !enabled {
+                    tracing::info!(announcer = *key, "disabled by configuration");
+                }
+                enabled
+            })
+            .collect();
+        Ok(AnnouncerRunner {
             cache_http: CacheAndHttp::from_client(client),
             data: client.data.clone(),
-            announcers: self.announcers,
-        }
+            announcers,
+            config,
+        })
     }
 }
 
@@ -154,6 +329,7 @@ pub struct AnnouncerRunner {
     cache_http: CacheAndHttp,
     data: AppData,
     announcers: HashMap<&'static str, RwLock<Box<dyn Announcer + Send + Sync>>>,
+    config: Arc<Config>,
 }
 
 // Querying for the AnnouncerRunner in the internal data returns a vec of keys.
@@ -161,16 +337,132 @@ impl TypeMapKey for AnnouncerRunner {
     type Value = Vec<&'static str>;
 }
 
+/// The tags each announcer's posts can be filtered by.
+#[derive(Debug, Clone, Default)]
+pub struct AnnouncerFilters(Arc<HashMap<&'static str, FilterTags>>);
+
+impl TypeMapKey for AnnouncerFilters {
+    type Value = AnnouncerFilters;
+}
+
+impl AnnouncerFilters {
+    /// Checks a filter for the announcer, replacing aliases with their tags.
+    /// Returns the message to show to the user if the filter is not valid.
+    pub fn normalize(&self, key: &str, filter: &str) -> std::result::Result<String, String> {
+        let tags = self.0.get(key).copied().unwrap_or_default();
+        if tags.is_empty() {
+            return Err(format!("Announcer `{}` does not support filters.", key));
+        }
+        let mut normalized: Vec<&str> = vec![];
+        for t in filter_tags(filter) {
+            let tag = tags
+                .iter()
+                .find(|(tag, aliases)| {
+                    tag.eq_ignore_ascii_case(t) || aliases.iter().any(|a| a.eq_ignore_ascii_case(t))
+                })
+                .map(|(tag, _)| *tag)
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown filter `{}` for announcer `{}`. Accepted values are: {}",
+                        t,
+                        key,
+                        Self::describe(tags)
+                    )
+                })?;
+            if !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        Ok(normalized.join(", "))
+    }
+
+    /// Lists the tags, e.g. "`osu` (or `std`), `taiko`".
+    fn describe(tags: FilterTags) -> String {
+        tags.iter()
+            .map(|(tag, aliases)| {
+                if aliases.is_empty() {
+                    format!("`{}`", tag)
+                } else {
+                    let aliases = aliases
+                        .iter()
+                        .map(|a| format!("`{}`", a))
+                        .collect::<Vec<_>>();
+                    format!("`{}` (or {})", tag, aliases.join(", "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Lets announcers be run right away, outside of their schedule.
+#[derive(Debug, Clone, Default)]
+pub struct AnnouncerTriggers(Arc<HashMap<&'static str, Arc<Notify>>>);
+
+impl TypeMapKey for AnnouncerTriggers {
+    type Value = AnnouncerTriggers;
+}
+
+impl AnnouncerTriggers {
+    /// Runs the announcer as soon as possible.
+    /// If it is running right now, it is run again once done.
+    ///
+    /// Returns false if there is no such announcer.
+    pub fn trigger(&self, key: &str) -> bool {
+        match self.0.get(key) {
+            Some(n) => {
+                n.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// How the runs of an announcer went.
+#[derive(Debug, Clone, Default)]
+pub struct AnnouncerStatus {
+    pub running: bool,
+    pub last_start: Option<DateTime<Utc>>,
+    pub last_success: Option<DateTime<Utc>>,
+    /// How long the last run took.
+    pub last_duration: Option<std::time::Duration>,
+    /// The error of the last run, if it failed.
+    pub last_error: Option<String>,
+    /// The number of users the last run went through.
+    pub users_processed: usize,
+    pub consecutive_failures: u32,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+/// The status of each announcer, by key.
+#[derive(Debug, Clone, Default)]
+pub struct AnnouncerStatuses(Arc<DashMap<&'static str, AnnouncerStatus>>);
+
+impl TypeMapKey for AnnouncerStatuses {
+    type Value = AnnouncerStatuses;
+}
+
+impl AnnouncerStatuses {
+    pub fn get(&self, key: &str) -> Option<AnnouncerStatus> {
+        self.0.get(key).map(|s| s.clone())
+    }
+
+    fn update(&self, key: &'static str, f: impl FnOnce(&mut AnnouncerStatus)) {
+        f(&mut self.0.entry(key).or_default())
+    }
+}
+
 /// Execution-related.
 impl AnnouncerRunner {
-    /// Collect the list of guilds and their respective channels, by the key of the announcer.
-    async fn get_guilds(data: &AppData, key: &'static str) -> Result<Vec<(GuildId, ChannelId)>> {
-        let data = AnnouncerChannels::open(&*data.read().await)
-            .borrow()?
-            .get(key)
-            .map(|m| m.iter().map(|(a, b)| (*a, *b)).collect())
-            .unwrap_or_else(Vec::new);
-        Ok(data)
+    /// Collect the list of channels of the announcer, by its key.
+    async fn get_guilds(data: &AppData, key: &'static str) -> Result<Vec<AnnouncerChannel>> {
+        let pool = data.read().await.get::<SQLClient>().unwrap().clone();
+        Ok(model::AnnouncerChannel::by_key(key, &pool)
+            .await?
+            .into_iter()
+            .map(AnnouncerChannel::from_model)
+            .collect())
     }
 
     /// Run the announcing sequence on a certain announcer.
@@ -179,8 +471,10 @@ impl AnnouncerRunner {
         cache_http: CacheAndHttp,
         key: &'static str,
         announcer: &'_ RwLock<Box<dyn Announcer + Send + Sync>>,
+        processed: Arc<AtomicUsize>,
     ) -> Result<()> {
-        let channels = MemberToChannels(Self::get_guilds(&data, key).await?, data.clone());
+        let channels =
+            MemberToChannels(Self::get_guilds(&data, key).await?, data.clone(), processed);
         announcer
             .write()
             .await
@@ -190,111 +484,231 @@ impl AnnouncerRunner {
 
     /// Start the AnnouncerHandler, looping forever.
     ///
-    /// It will run all the announcers every *cooldown* seconds.
-    pub async fn scan(self, cooldown: std::time::Duration) {
+    /// Each announcer is run on its own schedule, or when triggered through [`AnnouncerTriggers`].
+    /// Stops once the [`Shutdown`] starts, letting running cycles finish.
+    pub async fn scan(self) {
         // First we store all the keys inside the database.
         let keys = self.announcers.keys().cloned().collect::<Vec<_>>();
-        self.data.write().await.insert::<Self>(keys.clone());
+        let statuses = AnnouncerStatuses::default();
+        let mut filters = HashMap::new();
+        for (key, announcer) in &self.announcers {
+            filters.insert(*key, announcer.read().await.filter_tags());
+        }
+        let triggers = keys
+            .iter()
+            .map(|&key| (key, Arc::new(Notify::new())))
+            .collect::<HashMap<_, _>>();
+        let shutdown = {
+            let mut data = self.data.write().await;
+            data.insert::<Self>(keys.clone());
+            data.insert::<AnnouncerFilters>(AnnouncerFilters(Arc::new(filters)));
+            data.insert::<AnnouncerTriggers>(AnnouncerTriggers(Arc::new(triggers.clone())));
+            data.insert::<AnnouncerStatuses>(statuses.clone());
+            data.get::<Shutdown>().cloned().unwrap_or_default()
+        };
         join_all(self.announcers.iter().map(|(key, announcer)| {
             let data = self.data.clone();
             let cache = self.cache_http.clone();
+            let trigger = triggers[key].clone();
+            let statuses = statuses.clone();
+            let config = &self.config;
+            let shutdown = &shutdown;
             async move {
+                let schedule = config.announcer_schedule(key, announcer.read().await.schedule());
+                let mut failures = 0u32;
                 loop {
-                    eprintln!(" - scanning key `{}`", key);
-                    match Self::announce(data.clone(), cache.clone(), key, announcer).await {
+                    let Some(hold) = shutdown.hold() else {
+                        break;
+                    };
+                    tracing::debug!(announcer = *key, "scanning");
+                    let start = Instant::now();
+                    statuses.update(key, |s| {
+                        s.running = true;
+                        s.last_start = Some(Utc::now());
+                        s.next_run = None;
+                    });
+                    let processed = Arc::new(AtomicUsize::new(0));
+                    let result = Self::announce(
+                        data.clone(),
+                        cache.clone(),
+                        key,
+                        announcer,
+                        processed.clone(),
+                    )
+                    .await;
+                    let elapsed = start.elapsed();
+                    let processed = processed.load(Ordering::Relaxed);
+                    crate::metrics::announcer_run(key, elapsed, result.is_ok());
+                    match &result {
                         Err(e) => {
-                            eprintln!(" - key `{}`: {:?}", *key, e)
+                            failures += 1;
+                            tracing::error!(announcer = *key, failures, "scan failed: {:?}", e);
                         }
                         Ok(()) => {
-                            eprintln!(" - key `{}`: complete", *key)
+                            failures = 0;
+                            tracing::info!(announcer = *key, ?elapsed, processed, "scan complete");
                         }
                     };
-                    tokio::time::sleep(cooldown).await;
+                    let wait = schedule.next_wait(Utc::now(), failures);
+                    tracing::debug!(announcer = *key, ?wait, "next scan scheduled");
+                    statuses.update(key, |s| {
+                        s.running = false;
+                        s.last_duration = Some(elapsed);
+                        s.users_processed = processed;
+                        s.consecutive_failures = failures;
+                        match result {
+                            Ok(()) => {
+                                s.last_success = Some(Utc::now());
+                                s.last_error = None;
+                            }
+                            Err(e) => s.last_error = Some(format!("{:#}", e)),
+                        }
+                        s.next_run = chrono::Duration::from_std(wait)
+                            .ok()
+                            .and_then(|w| Utc::now().checked_add_signed(w));
+                    });
+                    drop(hold);
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => (),
+                        _ = trigger.notified() => {
+                            tracing::info!(announcer = *key, "manually triggered");
+                        }
+                        _ = shutdown.started() => break,
+                    }
                 }
+                tracing::info!(announcer = *key, "stopped");
             }
         }))
         .await;
     }
 }
 
-/// Gets the announcer of the given guild.
+/// Gets the channels of the announcer in the given guild.
 pub async fn announcer_of(
     ctx: &Context,
     key: &'static str,
     guild: GuildId,
-) -> Result<Option<ChannelId>> {
-    Ok(AnnouncerChannels::open(&*ctx.data.read().await)
-        .borrow()?
-        .get(key)
-        .and_then(|channels| channels.get(&guild).cloned()))
+) -> Result<Vec<AnnouncerChannel>> {
+    let pool = ctx.data.read().await.get::<SQLClient>().unwrap().clone();
+    Ok(model::AnnouncerChannel::by_guild(guild.get() as i64, &pool)
+        .await?
+        .into_iter()
+        .filter(|c| c.announcer_key == key)
+        .map(AnnouncerChannel::from_model)
+        .collect())
 }
 
 #[command("list")]
-#[description = "List the registered announcers of this server"]
+#[description = "List the announcers and the channels they post to in this server"]
 #[num_args(0)]
 #[only_in(guilds)]
 pub async fn list_announcers(ctx: &Context, m: &Message, _: Args) -> CommandResult {
     let guild_id = m.guild_id.unwrap();
     let data = &*ctx.data.read().await;
-    let announcers = AnnouncerChannels::open(data);
-    let channels = data.get::<AnnouncerRunner>().unwrap();
-    let channels = channels
-        .iter()
-        .filter_map(|&key| {
-            announcers.borrow().ok().and_then(|announcers| {
-                announcers
-                    .get(key)
-                    .and_then(|channels| channels.get(&guild_id))
-                    .map(|&ch| (key, ch))
-            })
+    let pool = data.get::<SQLClient>().unwrap();
+    let mut channels = HashMap::<String, Vec<AnnouncerChannel>>::new();
+    for c in model::AnnouncerChannel::by_guild(guild_id.get() as i64, pool).await? {
+        channels
+            .entry(c.announcer_key.clone())
+            .or_default()
+            .push(AnnouncerChannel::from_model(c));
+    }
+    let statuses = data.get::<AnnouncerStatuses>().cloned().unwrap_or_default();
+    let mut keys = data.get::<AnnouncerRunner>().unwrap().clone();
+    keys.sort();
+    let lines = keys
+        .into_iter()
+        .map(|key| {
+            let Some(channels) = channels.get(key) else {
+                return format!(" - `{}`: not activated", key);
+            };
+            let status = statuses.get(key).unwrap_or_default();
+            let health = match (&status.last_error, status.last_success) {
+                (Some(_), _) => " (last update failed)".to_owned(),
+                (None, Some(t)) => format!(", last updated <t:{}:R>", t.timestamp()),
+                (None, None) => "".to_owned(),
+            };
+            let mut line = format!(" - `{}`{}:", key, health);
+            for c in channels {
+                line += &format!("\n   - posting to {}", c.describe());
+            }
+            line
         })
-        .map(|(key, ch)| format!(" - `{}`: activated on channel {}", key, ch.mention()))
         .collect::<Vec<_>>();
 
     m.reply(
         &ctx,
-        format!(
-            "Activated announcers on this server:\n{}",
-            channels.join("\n")
-        ),
+        format!("Announcers on this server:\n{}", lines.join("\n")),
     )
     .await?;
 
     Ok(())
 }
 
+/// Checks that the announcer key exists, replying with the available keys if not.
+async fn check_key(ctx: &Context, m: &Message, key: &str) -> Result<bool> {
+    let keys = ctx
+        .data
+        .read()
+        .await
+        .get::<AnnouncerRunner>()
+        .unwrap()
+        .clone();
+    if keys.contains(&key) {
+        return Ok(true);
+    }
+    m.reply(
+        &ctx,
+        format!(
+            "Key not found. Available announcer keys are: `{}`",
+            keys.join(", ")
+        ),
+    )
+    .await?;
+    Ok(false)
+}
+
 #[command("register")]
-#[description = "Register the current channel with an announcer"]
-#[usage = "[announcer key]"]
+#[description = "Register the current channel or thread with an announcer. A channel can get only some of the announcements with a filter, e.g. `mania` for osu! plays. The osu! announcers can be filtered by mode: `osu` (or `std`), `taiko`, `fruits` (or `ctb`, `catch`) and `mania`, separated by commas."]
+#[usage = "[announcer key] / [filter]"]
+#[example = "osu / mania"]
 #[required_permissions(MANAGE_CHANNELS)]
 #[only_in(guilds)]
-#[num_args(1)]
+#[min_args(1)]
+#[max_args(2)]
 pub async fn register_announcer(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
-    let data = ctx.data.read().await;
     let key = args.single::<String>()?;
-    let keys = data.get::<AnnouncerRunner>().unwrap();
-    if !keys.contains(&&key[..]) {
-        m.reply(
-            &ctx,
-            format!(
-                "Key not found. Available announcer keys are: `{}`",
-                keys.join(", ")
-            ),
-        )
-        .await?;
+    if !check_key(ctx, m, &key).await? {
         return Ok(());
     }
-    let guild = m
-        .guild_id
-        .expect("Guild-only command")
-        .to_partial_guild(&ctx)
-        .await?;
-    let channel = m.channel_id.to_channel(&ctx).await?;
-    AnnouncerChannels::open(&data)
-        .borrow_mut()?
-        .entry(key.clone())
-        .or_default()
-        .insert(guild.id, m.channel_id);
+    let filter = args
+        .single::<String>()
+        .ok()
+        .map(|f| f.trim().to_owned())
+        .filter(|f| !f.is_empty());
+    let filter = match filter {
+        Some(f) => {
+            let filters = ctx
+                .data
+                .read()
+                .await
+                .get::<AnnouncerFilters>()
+                .cloned()
+                .unwrap_or_default();
+            match filters.normalize(&key, &f) {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    m.reply(&ctx, e).await?;
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+    let channel = AnnouncerChannel::of_message(ctx, m, filter).await?;
+    let pool = ctx.data.read().await.get::<SQLClient>().unwrap().clone();
+    channel.to_model(&key).store(&pool).await?;
+    let guild = channel.guild.to_partial_guild(&ctx).await?;
     m.reply(
         &ctx,
         MessageBuilder::new()
@@ -302,8 +716,8 @@ pub async fn register_announcer(ctx: &Context, m: &Message, mut args: Args) -> C
             .push_mono_safe(key)
             .push(" has been activated for server ")
             .push_bold_safe(&guild.name)
-            .push(" on channel ")
-            .push_bold_safe(channel.mention().to_string())
+            .push(" on ")
+            .push(channel.describe())
             .build(),
     )
     .await?;
@@ -311,47 +725,155 @@ pub async fn register_announcer(ctx: &Context, m: &Message, mut args: Args) -> C
 }
 
 #[command("remove")]
-#[description = "Remove an announcer from the server"]
-#[usage = "[announcer key]"]
+#[description = "Remove an announcer from the current channel or thread, or with `all`, from the whole server"]
+#[usage = "[announcer key] / [all]"]
 #[required_permissions(MANAGE_CHANNELS)]
 #[only_in(guilds)]
-#[num_args(1)]
+#[min_args(1)]
+#[max_args(2)]
 pub async fn remove_announcer(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
-    let data = ctx.data.read().await;
     let key = args.single::<String>()?;
-    let keys = data.get::<AnnouncerRunner>().unwrap();
-    if !keys.contains(&key.as_str()) {
-        m.reply(
-            &ctx,
-            format!(
-                "Key not found. Available announcer keys are: `{}`",
-                keys.join(", ")
-            ),
-        )
-        .await?;
+    if !check_key(ctx, m, &key).await? {
         return Ok(());
     }
+    let all = args.single::<String>().is_ok_and(|v| v.trim() == "all");
+    let pool = ctx.data.read().await.get::<SQLClient>().unwrap().clone();
     let guild = m
         .guild_id
         .expect("Guild-only command")
         .to_partial_guild(&ctx)
         .await?;
-    AnnouncerChannels::open(&data)
-        .borrow_mut()?
-        .entry(key.clone())
-        .and_modify(|m| {
-            m.remove(&guild.id);
-        });
-    m.reply(
-        &ctx,
+    let reply = if all {
+        model::AnnouncerChannel::delete_all(&key, guild.id.get() as i64, &pool).await?;
         MessageBuilder::new()
             .push("Announcer ")
             .push_mono_safe(key)
             .push(" has been de-activated for server ")
             .push_bold_safe(&guild.name)
-            .build(),
-    )
-    .await?;
+            .build()
+    } else {
+        let channel = AnnouncerChannel::of_message(ctx, m, None).await?;
+        if channel.to_model(&key).delete(&pool).await? {
+            MessageBuilder::new()
+                .push("Announcer ")
+                .push_mono_safe(key)
+                .push(" has been de-activated on ")
+                .push(channel.describe())
+                .build()
+        } else {
+            MessageBuilder::new()
+                .push("Announcer ")
+                .push_mono_safe(key)
+                .push(" is not activated here. Use ")
+                .push_mono("all")
+                .push(" to remove it from the whole server.")
+                .build()
+        }
+    };
+    m.reply(&ctx, reply).await?;
+    Ok(())
+}
+
+#[command("run")]
+#[description = "Run an announcer right away, instead of waiting for its next scheduled run"]
+#[usage = "[announcer key]"]
+#[owners_only]
+#[num_args(1)]
+pub async fn run_announcer(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+    let key = args.single::<String>()?;
+    let data = ctx.data.read().await;
+    let keys = data.get::<AnnouncerRunner>().unwrap();
+    let reply = if data.get::<AnnouncerTriggers>().unwrap().trigger(&key) {
+        MessageBuilder::new()
+            .push("Announcer ")
+            .push_mono_safe(key)
+            .push(" will run shortly.")
+            .build()
+    } else {
+        format!(
+            "Key not found. Available announcer keys are: `{}`",
+            keys.join(", ")
+        )
+    };
+    m.reply(&ctx, reply).await?;
+    Ok(())
+}
+
+#[command("status")]
+#[description = "Show how the announcers have been running"]
+#[owners_only]
+#[num_args(0)]
+pub async fn announcer_status(ctx: &Context, m: &Message, _: Args) -> CommandResult {
+    const HEADERS: [&str; 6] = [
+        "Key",
+        "State",
+        "Last start",
+        "Last success",
+        "Took",
+        "Users",
+    ];
+    const ALIGNS: [Align; 6] = [
+        Align::Left,
+        Align::Left,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+    ];
+    let data = ctx.data.read().await;
+    let statuses = data.get::<AnnouncerStatuses>().cloned().unwrap_or_default();
+    let mut keys = data.get::<AnnouncerRunner>().cloned().unwrap_or_default();
+    keys.sort();
+    let now = Utc::now();
+    let ago = |t: Option<DateTime<Utc>>| match t {
+        Some(t) => format!(
+            "{:.1} ago",
+            crate::Duration::from_secs((now - t).num_seconds().max(1) as u64)
+        ),
+        None => "never".to_owned(),
+    };
+    let mut errors = vec![];
+    let rows = keys
+        .iter()
+        .map(|&key| {
+            let s = statuses.get(key).unwrap_or_default();
+            let state = if s.running {
+                "running".to_owned()
+            } else if s.consecutive_failures > 0 {
+                format!("failing ({}x)", s.consecutive_failures)
+            } else if s.last_start.is_some() {
+                "ok".to_owned()
+            } else {
+                "waiting".to_owned()
+            };
+            if let Some(e) = &s.last_error {
+                errors.push((key, e.chars().take(200).collect::<String>()));
+            }
+            [
+                key.to_owned(),
+                state,
+                ago(s.last_start),
+                ago(s.last_success),
+                s.last_duration
+                    .map(|d| format!("{:.1}s", d.as_secs_f64()))
+                    .unwrap_or_default(),
+                s.users_processed.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut content = MessageBuilder::new();
+    content.push_line(table_formatting(&HEADERS, &ALIGNS, rows));
+    if !errors.is_empty() {
+        content.push_line("Last errors:");
+        for (key, e) in errors {
+            content
+                .push(" - ")
+                .push_mono(key)
+                .push(": ")
+                .push_line_safe(e);
+        }
+    }
+    m.reply(&ctx, content.build()).await?;
     Ok(())
 }
 
@@ -360,5 +882,11 @@ pub async fn remove_announcer(ctx: &Context, m: &Message, mut args: Args) -> Com
 #[only_in(guilds)]
 #[required_permissions(MANAGE_CHANNELS)]
 #[description = "Manage the announcers in the server."]
-#[commands(remove_announcer, register_announcer, list_announcers)]
+#[commands(
+    remove_announcer,
+    register_announcer,
+    list_announcers,
+    run_announcer,
+    announcer_status
+)]
 pub struct AnnouncerCommands;
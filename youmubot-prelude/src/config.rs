@@ -0,0 +1,587 @@
+//! The configuration of the bot.
+//!
+//! Configuration is read from a TOML file (`youmubot.toml`, or the path in `CONFIG_PATH`),
+//! then overridden by environment variables, and validated before anything is set up.
+//! Every field has a default, so an empty (or missing) file is fine as long as the
+//! required secrets come from the environment.
+//!
+//! ```toml
+//! [discord]
+//! token = "..."
+//! prefixes = ["y!", "y2!"]
+//! owners = [123456789]
+//!
+//! [paths]
+//! db = "/data"
+//!
+//! [osu]
+//! client_id = 1234
+//! client_secret = "..."
+//!
+//! [announcers.osu]
+//! cron = "0 */10 * * * *"
+//!
+//! [pagination]
+//! timeout_secs = 120
+//! persist_days = 7
+//!
+//! [rate_limits.server_rank]
+//! scope = "guild"
+//! burst = 2
+//! refill_secs = 60
+//! ```
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    bail, error,
+    ratelimit::{Limit, Scope},
+    Result, Schedule,
+};
+
+/// The default location of the configuration file.
+const DEFAULT_PATH: &str = "youmubot.toml";
+/// The osu! client spaces requests by `120000 / rpm` milliseconds, which must not round to zero.
+const MAX_RPM: u64 = 120_000;
+/// The longest time a rate limit can take to give back one use.
+const MAX_REFILL_SECS: f64 = 24.0 * 60.0 * 60.0;
+/// The longest restorable lists can be kept for, well within what dates can represent.
+const MAX_PERSIST_DAYS: u64 = 10 * 366;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discord: Discord,
+    pub paths: Paths,
+    pub features: Features,
+    pub osu: Osu,
+    pub codeforces: Codeforces,
+    /// Schedule overrides of announcers, by announcer key (`osu`, `osu-mapping`, `codeforces`
+    /// or `atcoder`). Unknown keys are rejected once the announcers are set up.
+    pub announcers: HashMap<String, AnnouncerConfig>,
+    /// Overrides of command rate limits, by name (e.g. `server_rank`).
+    pub rate_limits: HashMap<String, RateLimitConfig>,
+    pub metrics: Metrics,
+    pub shutdown: ShutdownConfig,
+    pub pagination: Pagination,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Discord {
+    /// The bot token. Overridden by `TOKEN`.
+    pub token: String,
+    /// The default command prefixes. Overridden by `PREFIX` (comma separated).
+    pub prefixes: Vec<String>,
+    /// Bot owners, on top of the owner of the application. Overridden by `OWNERS` (comma separated).
+    pub owners: Vec<u64>,
+}
+
+impl Default for Discord {
+    fn default() -> Self {
+        Self {
+            token: String::new(),
+            prefixes: vec!["y!".to_owned(), "y2!".to_owned()],
+            owners: vec![],
+        }
+    }
+}
+
+// Keep the token out of logs.
+impl std::fmt::Debug for Discord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Discord")
+            .field("token", &"<hidden>")
+            .field("prefixes", &self.prefixes)
+            .field("owners", &self.owners)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Paths {
+    /// The directory of the legacy databases. Overridden by `DBPATH`.
+    pub db: PathBuf,
+    /// The SQLite database, `youmubot.db` inside `db` if not set. Overridden by `SQLPATH`.
+    pub sql: Option<PathBuf>,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Self {
+            db: PathBuf::from("/data"),
+            sql: None,
+        }
+    }
+}
+
+impl Paths {
+    pub fn sql(&self) -> PathBuf {
+        self.sql
+            .clone()
+            .unwrap_or_else(|| self.db.join("youmubot.db"))
+    }
+}
+
+/// Switches for optional parts of the bot.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// Run the message hooks, e.g. link previews.
+    pub hooks: bool,
+    /// Run the announcers.
+    pub announcers: bool,
+    /// Serve metrics, if built with the `metrics` feature.
+    pub metrics: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            hooks: true,
+            announcers: true,
+            metrics: true,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Osu {
+    /// The osu! API v2 client ID. Overridden by `OSU_API_CLIENT_ID`.
+    pub client_id: Option<u64>,
+    /// The osu! API v2 client secret. Overridden by `OSU_API_CLIENT_SECRET`.
+    pub client_secret: Option<String>,
+    /// Requests per minute made for commands, at most 120000.
+    pub foreground_rpm: u64,
+    /// Requests per minute made by the announcer, at most 120000.
+    pub background_rpm: u64,
+    /// The osu! server for API requests and beatmap files, `https://osu.ppy.sh` if not set.
+    /// Only meant for pointing the bot at a stub: API requests to it are made without rosu-v2.
+    pub url: Option<String>,
+}
+
+impl Default for Osu {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            client_secret: None,
+            foreground_rpm: 600,
+            background_rpm: 60,
+            url: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for Osu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Osu")
+            .field("client_id", &self.client_id)
+            .field(
+                "client_secret",
+                &self.client_secret.as_ref().map(|_| "<hidden>"),
+            )
+            .field("foreground_rpm", &self.foreground_rpm)
+            .field("background_rpm", &self.background_rpm)
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Codeforces {
+    /// The Codeforces API, used for submissions. Only changed to point at a stub.
+    pub api_url: String,
+}
+
+impl Default for Codeforces {
+    fn default() -> Self {
+        Self {
+            api_url: "https://codeforces.com/api".to_owned(),
+        }
+    }
+}
+
+/// Overrides the schedule of an announcer. Unset fields keep the announcer's own defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnnouncerConfig {
+    pub enabled: bool,
+    /// Seconds between runs.
+    pub interval_secs: Option<u64>,
+    /// A cron expression with seconds, see [`Schedule::cron`].
+    pub cron: Option<String>,
+    /// Maximum random delay added to every wait, in seconds.
+    pub jitter_secs: Option<u64>,
+}
+
+impl Default for AnnouncerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: None,
+            cron: None,
+            jitter_secs: None,
+        }
+    }
+}
+
+impl AnnouncerConfig {
+    /// Applies the overrides on top of the announcer's own schedule.
+    pub fn schedule(&self, base: Schedule) -> Result<Schedule> {
+        let mut schedule = match (&self.cron, self.interval_secs) {
+            (Some(_), Some(_)) => bail!("only one of `cron` and `interval_secs` can be set"),
+            (Some(expr), None) => Schedule {
+                interval: Schedule::cron(expr)?.interval,
+                ..base
+            },
+            (None, Some(0)) => bail!("interval_secs must be positive"),
+            (None, Some(secs)) => Schedule {
+                interval: Schedule::every(Duration::from_secs(secs)).interval,
+                ..base
+            },
+            (None, None) => base,
+        };
+        if let Some(jitter) = self.jitter_secs {
+            schedule.jitter = Duration::from_secs(jitter);
+        }
+        Ok(schedule)
+    }
+}
+
+/// A token bucket: up to `burst` uses at once, and one more use every `refill_secs` (at most a day).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// One of `user`, `guild`, `channel` or `global`.
+    pub scope: Scope,
+    pub burst: u32,
+    pub refill_secs: f64,
+}
+
+impl RateLimitConfig {
+    pub fn limit(&self) -> Limit {
+        Limit::new(
+            self.scope,
+            self.burst,
+            Duration::from_secs_f64(self.refill_secs),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Metrics {
+    /// Where to serve metrics. Overridden by `METRICS_ADDR`.
+    pub address: SocketAddr,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            address: SocketAddr::from(([127, 0, 0, 1], 9184)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// How long to wait for background tasks to finish when shutting down.
+    pub timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 30 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pagination {
+    /// How long the buttons of paginated osu! lists keep working after the last interaction.
+    pub timeout_secs: u64,
+    /// How long lists that can be restored (osu! scores and rankings) keep working after the last
+    /// interaction, across restarts. 0 disables restoring them.
+    pub persist_days: u64,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 60,
+            persist_days: 14,
+        }
+    }
+}
+
+impl Pagination {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn persist_for(&self) -> Duration {
+        Duration::from_secs(self.persist_days * 24 * 60 * 60)
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the file in `CONFIG_PATH` (or `youmubot.toml`),
+    /// applies the environment overrides and validates it.
+    pub fn load() -> Result<Self> {
+        let (path, required) = match std::env::var("CONFIG_PATH") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_PATH), false),
+        };
+        let mut config = if required || path.exists() {
+            Self::from_file(&path)?
+        } else {
+            Self::default()
+        };
+        config.apply_env(|key| std::env::var(key).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| error!("cannot read config file {}: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| error!("invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Overrides the configuration with the (legacy) environment variables.
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        if let Some(token) = var("TOKEN") {
+            self.discord.token = token;
+        }
+        if let Some(prefixes) = var("PREFIX") {
+            self.discord.prefixes = split_list(&prefixes).map(|p| p.to_owned()).collect();
+        }
+        if let Some(owners) = var("OWNERS") {
+            self.discord.owners = split_list(&owners)
+                .map(|o| {
+                    o.parse()
+                        .map_err(|_| error!("OWNERS: `{}` is not a user id", o))
+                })
+                .collect::<Result<_>>()?;
+        }
+        if let Some(db) = var("DBPATH") {
+            self.paths.db = db.into();
+        }
+        if let Some(sql) = var("SQLPATH") {
+            self.paths.sql = Some(sql.into());
+        }
+        if let Some(id) = var("OSU_API_CLIENT_ID") {
+            self.osu.client_id = Some(
+                id.parse()
+                    .map_err(|_| error!("OSU_API_CLIENT_ID: `{}` is not a client id", id))?,
+            );
+        }
+        if let Some(secret) = var("OSU_API_CLIENT_SECRET") {
+            self.osu.client_secret = Some(secret);
+        }
+        if let Some(addr) = var("METRICS_ADDR") {
+            self.metrics.address = addr
+                .parse()
+                .map_err(|_| error!("METRICS_ADDR: `{}` is not a socket address", addr))?;
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.discord.token.trim().is_empty() {
+            bail!("discord.token is not set: set it in the config file or with TOKEN");
+        }
+        if self.discord.prefixes.is_empty() || self.discord.prefixes.iter().any(|p| p.is_empty()) {
+            bail!("discord.prefixes must have at least one prefix, and none of them can be empty");
+        }
+        let rpm = 1..=MAX_RPM;
+        if !rpm.contains(&self.osu.foreground_rpm) || !rpm.contains(&self.osu.background_rpm) {
+            bail!(
+                "osu.foreground_rpm and osu.background_rpm must be between 1 and {}",
+                MAX_RPM
+            );
+        }
+        let urls = self
+            .osu
+            .url
+            .iter()
+            .map(|u| ("osu.url", u))
+            .chain([("codeforces.api_url", &self.codeforces.api_url)]);
+        for (key, url) in urls {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                bail!("{} must be an http(s) URL", key);
+            }
+        }
+        if self.pagination.timeout_secs == 0 {
+            bail!("pagination.timeout_secs must be positive");
+        }
+        if self.pagination.persist_days > MAX_PERSIST_DAYS {
+            bail!(
+                "pagination.persist_days must be at most {}",
+                MAX_PERSIST_DAYS
+            );
+        }
+        for (name, limit) in &self.rate_limits {
+            if limit.burst == 0 || !(0.0..=MAX_REFILL_SECS).contains(&limit.refill_secs) {
+                bail!(
+                    "rate_limits.{}: burst must be positive and refill_secs must be between 0 and {}",
+                    name,
+                    MAX_REFILL_SECS
+                );
+            }
+        }
+        for (key, announcer) in &self.announcers {
+            announcer
+                .schedule(Schedule::default())
+                .map_err(|e| error!("announcers.{}: {}", key, e))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every configured announcer is one of the given keys.
+    pub fn validate_announcers<'a>(&self, keys: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let mut keys = keys.into_iter().collect::<Vec<_>>();
+        keys.sort_unstable();
+        if let Some(key) = self.announcers.keys().find(|k| !keys.contains(&k.as_str())) {
+            bail!(
+                "announcers.{}: unknown announcer, expected one of: {}",
+                key,
+                keys.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// The osu! API credentials.
+    pub fn osu_credentials(&self) -> Result<(u64, &str)> {
+        match (self.osu.client_id, self.osu.client_secret.as_deref()) {
+            (Some(id), Some(secret)) => Ok((id, secret)),
+            _ => bail!(
+                "osu.client_id and osu.client_secret are required: set them in the config file or with OSU_API_CLIENT_ID and OSU_API_CLIENT_SECRET"
+            ),
+        }
+    }
+
+    /// The schedule of an announcer, with the configured overrides.
+    pub fn announcer_schedule(&self, key: &str, base: Schedule) -> Schedule {
+        match self.announcers.get(key) {
+            // Validated on load.
+            Some(c) => c.schedule(base.clone()).unwrap_or(base),
+            None => base,
+        }
+    }
+
+    /// Whether the announcer with the given key should run.
+    pub fn announcer_enabled(&self, key: &str) -> bool {
+        self.features.announcers && self.announcers.get(key).is_none_or(|c| c.enabled)
+    }
+}
+
+fn split_list(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::Interval;
+
+    #[test]
+    fn env_overrides_file() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [discord]
+            token = "from-file"
+            owners = [1]
+
+            [paths]
+            db = "/var/lib/youmubot"
+
+            [announcers.osu]
+            interval_secs = 60
+            "#,
+        )
+        .unwrap();
+        let env = HashMap::from([("TOKEN", "from-env"), ("OWNERS", "2, 3"), ("PREFIX", "!")]);
+        config
+            .apply_env(|k| env.get(k).map(|v| v.to_string()))
+            .unwrap();
+        config.validate().unwrap();
+
+        assert_eq!(config.discord.token, "from-env");
+        assert_eq!(config.discord.owners, vec![2, 3]);
+        assert_eq!(config.discord.prefixes, vec!["!"]);
+        assert_eq!(
+            config.paths.sql(),
+            PathBuf::from("/var/lib/youmubot/youmubot.db")
+        );
+        let schedule = config.announcer_schedule(
+            "osu",
+            Schedule::every(Duration::from_secs(300)).with_jitter(Duration::from_secs(5)),
+        );
+        assert!(matches!(schedule.interval, Interval::Every(d) if d == Duration::from_secs(60)));
+        assert_eq!(schedule.jitter, Duration::from_secs(5));
+        assert!(config.announcer_enabled("osu"));
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        let validate = |s: &str| {
+            let mut config: Config = toml::from_str(s)?;
+            config.discord.token = "token".to_owned();
+            config.validate()
+        };
+        assert!(validate("").is_ok());
+        assert!(validate("[discord]\ntokn = \"typo\"").is_err());
+        assert!(validate("[announcers.osu]\ncron = \"every monday\"").is_err());
+        assert!(validate("[announcers.osu]\ncron = \"0 0 * * * *\"\ninterval_secs = 5").is_err());
+        assert!(validate("[announcers.osu]\ninterval_secs = 0").is_err());
+        assert!(validate("[discord]\nprefixes = [\"y!\", \"\"]").is_err());
+        assert!(validate("[osu]\nbackground_rpm = 0").is_err());
+        assert!(validate("[osu]\nforeground_rpm = 120001").is_err());
+        assert!(validate("[osu]\nurl = \"http://127.0.0.1:8080\"").is_ok());
+        assert!(validate("[codeforces]\napi_url = \"codeforces.com/api\"").is_err());
+        assert!(validate("[pagination]\ntimeout_secs = 0").is_err());
+        assert!(validate("[pagination]\npersist_days = 0").is_ok());
+        assert!(validate("[pagination]\npersist_days = 18446744073709551615").is_err());
+        assert!(
+            validate("[rate_limits.image]\nscope = \"channel\"\nburst = 2\nrefill_secs = 30")
+                .is_ok()
+        );
+        assert!(
+            validate("[rate_limits.image]\nscope = \"channel\"\nburst = 0\nrefill_secs = 30")
+                .is_err()
+        );
+        assert!(validate(
+            "[rate_limits.image]\nscope = \"channel\"\nburst = 2\nrefill_secs = 1e30"
+        )
+        .is_err());
+        assert!(
+            validate("[rate_limits.image]\nscope = \"room\"\nburst = 2\nrefill_secs = 30").is_err()
+        );
+        assert!(Config::default().validate().is_err());
+
+        let config: Config = toml::from_str("[announcers.osu]\n[announcers.cf]").unwrap();
+        assert!(config.validate_announcers(["osu", "codeforces"]).is_err());
+        assert!(config
+            .validate_announcers(["osu", "cf", "codeforces"])
+            .is_ok());
+
+        let mut config = Config::default();
+        assert!(config
+            .apply_env(|k| (k == "OSU_API_CLIENT_ID").then(|| "abc".to_owned()))
+            .is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{Context, Result};
 use serenity::{
-    all::{CreateInteractionResponse, Interaction, MessageId},
+    all::{ComponentInteraction, CreateInteractionResponse, Interaction, MessageId},
     prelude::TypeMapKey,
 };
 use std::sync::Arc;
@@ -8,20 +8,46 @@ use std::sync::Arc;
 #[derive(Debug, Clone)]
 /// Handles distributing interaction to the handlers.
 pub struct InteractionCollector {
-    pub(crate) channels: Arc<dashmap::DashMap<MessageId, flume::Sender<String>>>,
+    pub(crate) channels: Arc<dashmap::DashMap<MessageId, flume::Sender<ComponentInteraction>>>,
 }
 
 /// Wraps the interfaction receiver channel, automatically cleaning up upon drop.
 #[derive(Debug)]
 pub struct InteractionCollectorGuard {
-    msg_id: MessageId,
-    ch: flume::Receiver<String>,
+    msg_ids: Vec<MessageId>,
+    send: flume::Sender<ComponentInteraction>,
+    ch: flume::Receiver<ComponentInteraction>,
     collector: InteractionCollector,
 }
 
 impl InteractionCollectorGuard {
-    /// Returns the next fetched interaction, with the given timeout.
+    /// Also receive the interactions on another message.
+    pub fn watch(&mut self, msg: MessageId) {
+        self.collector.channels.insert(msg, self.send.clone());
+        self.msg_ids.push(msg);
+    }
+
+    /// Stop receiving the interactions on a message watched with [`Self::watch`].
+    pub fn unwatch(&mut self, msg: MessageId) {
+        self.collector.channels.remove(&msg);
+        self.msg_ids.retain(|&id| id != msg);
+    }
+
+    /// Returns the custom ID of the next fetched interaction, with the given timeout.
     pub async fn next(&self, timeout: std::time::Duration) -> Option<String> {
+        self.next_interaction(timeout)
+            .await
+            .map(|interaction| interaction.data.custom_id)
+    }
+
+    /// Returns the next fetched interaction, with the given timeout.
+    ///
+    /// The interaction is already acknowledged, so only follow-ups can be sent.
+    /// The exception is the pagination's "go to page" button, which is answered with a modal.
+    pub async fn next_interaction(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Option<ComponentInteraction> {
         match tokio::time::timeout(timeout, self.ch.clone().into_recv_async()).await {
             Err(_) => None,
             Ok(Err(_)) => None,
@@ -30,15 +56,17 @@ impl InteractionCollectorGuard {
     }
 }
 
-impl AsRef<flume::Receiver<String>> for InteractionCollectorGuard {
-    fn as_ref(&self) -> &flume::Receiver<String> {
+impl AsRef<flume::Receiver<ComponentInteraction>> for InteractionCollectorGuard {
+    fn as_ref(&self) -> &flume::Receiver<ComponentInteraction> {
         &self.ch
     }
 }
 
 impl Drop for InteractionCollectorGuard {
     fn drop(&mut self) {
-        self.collector.channels.remove(&self.msg_id);
+        for msg_id in &self.msg_ids {
+            self.collector.channels.remove(msg_id);
+        }
     }
 }
 
@@ -57,9 +85,10 @@ impl InteractionCollector {
     /// Create a new collector, returning a receiver.
     pub fn create_collector(&self, msg: MessageId) -> InteractionCollectorGuard {
         let (send, recv) = flume::unbounded();
-        self.channels.insert(msg, send);
+        self.channels.insert(msg, send.clone());
         InteractionCollectorGuard {
-            msg_id: msg,
+            msg_ids: vec![msg],
+            send,
             ch: recv,
             collector: self.clone(),
         }
@@ -86,13 +115,18 @@ impl crate::hook::InteractionHook for InteractionCollector {
     async fn call(&self, ctx: &Context, interaction: &Interaction) -> Result<()> {
         match interaction {
             Interaction::Component(component_interaction) => {
-                if let Some(ch) = self.channels.get(&component_interaction.message.id) {
-                    component_interaction
-                        .create_response(ctx, CreateInteractionResponse::Acknowledge)
-                        .await?;
-                    ch.send_async(component_interaction.data.custom_id.clone())
-                        .await
-                        .ok();
+                // Clone the sender out, so that the map is not locked across awaits.
+                let ch = self
+                    .channels
+                    .get(&component_interaction.message.id)
+                    .map(|ch| ch.clone());
+                if let Some(ch) = ch {
+                    if component_interaction.data.custom_id != crate::pagination::JUMP {
+                        component_interaction
+                            .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                            .await?;
+                    }
+                    ch.send_async(component_interaction.clone()).await.ok();
                 }
                 Ok(())
             }
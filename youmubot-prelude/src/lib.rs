@@ -14,6 +14,7 @@ pub use tokio::spawn as spawn_future;
 
 pub use announcer::{Announcer, AnnouncerRunner};
 pub use args::{ChannelId, Duration, RoleId, UserId, UsernameArg};
+pub use config::Config;
 pub use debugging_ok::OkPrint;
 pub use flags::Flags;
 pub use hook::Hook;
@@ -21,16 +22,21 @@ pub use interaction_collector::InteractionCollector;
 pub use member_cache::MemberCache;
 pub use pagination::{paginate, paginate_from_fn, paginate_reply, CanEdit, Paginate};
 pub use poise::CreateReply;
+pub use schedule::Schedule;
 
 pub mod announcer;
 pub mod args;
+pub mod config;
 pub mod flags;
 pub mod hook;
 pub mod interaction_collector;
 pub mod member_cache;
+pub mod metrics;
 pub mod pagination;
 pub mod ratelimit;
+pub mod schedule;
 pub mod setup;
+pub mod shutdown;
 pub mod table_format;
 
 /// The global app data.
@@ -60,8 +66,10 @@ pub struct Env {
     pub http: reqwest::Client,
     pub sql: youmubot_db_sql::Pool,
     pub members: Arc<MemberCache>,
-    // databases
-    // pub(crate) announcer_channels: announcer::AnnouncerChannels,
+    pub ratelimits: ratelimit::Ratelimits,
+    pub shutdown: shutdown::Shutdown,
+    pub paginators: pagination::Paginators,
+    pub config: Arc<Config>,
 }
 
 pub mod prelude_commands {
@@ -56,6 +56,7 @@ impl MemberCache {
         // Check cache
         if let Some(r) = self.per_user.lock().await.get(&(user_id, guild_id)) {
             if r.timeout > now {
+                crate::metrics::cache_lookup("members", true);
                 return r.value.clone();
             }
         }
@@ -71,6 +72,7 @@ impl MemberCache {
                 return oe.get().value.clone();
             }
         }
+        crate::metrics::cache_lookup("members", false);
         let t = guild_id.member(&cache_http, user_id).await.ok();
         entry
             .or_insert(Expiring::new(
@@ -92,6 +94,7 @@ impl MemberCache {
         // Check cache
         if let Some(oe) = &entry {
             if oe.timeout > now {
+                crate::metrics::cache_lookup("guild_members", true);
                 return match &oe.value {
                     Some(v) => Ok(v.clone()),
                     None => bail!("guild members for {} unavailable", guild_id),
@@ -99,6 +102,7 @@ impl MemberCache {
             }
         }
         // query
+        crate::metrics::cache_lookup("guild_members", false);
         eprintln!("querying members of {}", guild_id);
         let members: Option<Arc<[Member]>> = guild_id
             .members(cache_http.http(), None, None)
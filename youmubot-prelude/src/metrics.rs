@@ -0,0 +1,217 @@
+//! Prometheus-style metrics of the bot.
+//!
+//! The recording functions can be called from anywhere. They do nothing unless the `metrics`
+//! feature is enabled, in which case [`serve`] exposes the collected metrics over HTTP.
+
+use std::time::Duration;
+
+/// Marks the start of a command invocation, identified by `id` (a message or interaction id).
+pub fn command_started(id: u64) {
+    #[cfg(feature = "metrics")]
+    imp::command_started(id);
+    #[cfg(not(feature = "metrics"))]
+    let _ = id;
+}
+
+/// Marks the end of a command invocation started with [`command_started`].
+pub fn command_finished(id: u64, name: &str, ok: bool) {
+    #[cfg(feature = "metrics")]
+    imp::command_finished(id, name, ok);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (id, name, ok);
+}
+
+/// Counts an invocation of a message hook.
+pub fn hook(name: &str) {
+    #[cfg(feature = "metrics")]
+    imp::HOOKS.with_label_values(&[name]).inc();
+    #[cfg(not(feature = "metrics"))]
+    let _ = name;
+}
+
+/// Counts a request to an external API, e.g. `osu` or `codeforces`.
+pub fn api_request(service: &str) {
+    #[cfg(feature = "metrics")]
+    imp::API_REQUESTS.with_label_values(&[service]).inc();
+    #[cfg(not(feature = "metrics"))]
+    let _ = service;
+}
+
+/// Counts a failed request to an external API.
+pub fn api_failure(service: &str) {
+    #[cfg(feature = "metrics")]
+    imp::API_FAILURES.with_label_values(&[service]).inc();
+    #[cfg(not(feature = "metrics"))]
+    let _ = service;
+}
+
+/// Counts a lookup into a cache.
+pub fn cache_lookup(cache: &str, hit: bool) {
+    #[cfg(feature = "metrics")]
+    imp::CACHE_LOOKUPS
+        .with_label_values(&[cache, if hit { "hit" } else { "miss" }])
+        .inc();
+    #[cfg(not(feature = "metrics"))]
+    let _ = (cache, hit);
+}
+
+/// Records a run of an announcer.
+pub fn announcer_run(key: &str, elapsed: Duration, ok: bool) {
+    #[cfg(feature = "metrics")]
+    imp::ANNOUNCER_RUNS
+        .with_label_values(&[key, imp::status(ok)])
+        .observe(elapsed.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = (key, elapsed, ok);
+}
+
+/// Serves the metrics in the Prometheus text format on the given address, forever.
+#[cfg(feature = "metrics")]
+pub async fn serve(addr: std::net::SocketAddr) -> crate::Result<()> {
+    imp::serve(addr).await
+}
+
+#[cfg(feature = "metrics")]
+mod imp {
+    use std::{
+        net::SocketAddr,
+        sync::LazyLock,
+        time::{Duration, Instant},
+    };
+
+    use dashmap::DashMap;
+    use prometheus::{
+        register_histogram_vec, register_int_counter_vec, Encoder, HistogramVec, IntCounterVec,
+        TextEncoder,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::Result;
+
+    /// How long a connection gets to send its request before it is dropped.
+    const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Invocations that are not finished after this long are dropped, so the map doesn't grow
+    /// with commands that never report back.
+    const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+    static STARTED: LazyLock<DashMap<u64, Instant>> = LazyLock::new(DashMap::new);
+
+    static COMMANDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec!(
+            "youmubot_commands_total",
+            "Commands executed",
+            &["command", "status"]
+        )
+        .unwrap()
+    });
+    static COMMAND_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec!(
+            "youmubot_command_duration_seconds",
+            "Time taken to execute commands",
+            &["command"],
+            vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]
+        )
+        .unwrap()
+    });
+    pub(super) static HOOKS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec!(
+            "youmubot_hooks_total",
+            "Message hook invocations",
+            &["hook"]
+        )
+        .unwrap()
+    });
+    pub(super) static API_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec!(
+            "youmubot_api_requests_total",
+            "Requests made to external APIs",
+            &["service"]
+        )
+        .unwrap()
+    });
+    pub(super) static API_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec!(
+            "youmubot_api_failures_total",
+            "Failed requests to external APIs",
+            &["service"]
+        )
+        .unwrap()
+    });
+    pub(super) static CACHE_LOOKUPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+        register_int_counter_vec!(
+            "youmubot_cache_lookups_total",
+            "Cache lookups",
+            &["cache", "result"]
+        )
+        .unwrap()
+    });
+    pub(super) static ANNOUNCER_RUNS: LazyLock<HistogramVec> = LazyLock::new(|| {
+        register_histogram_vec!(
+            "youmubot_announcer_run_duration_seconds",
+            "Time taken by announcer runs",
+            &["announcer", "status"],
+            vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0]
+        )
+        .unwrap()
+    });
+
+    pub(super) fn status(ok: bool) -> &'static str {
+        if ok {
+            "ok"
+        } else {
+            "error"
+        }
+    }
+
+    pub(super) fn command_started(id: u64) {
+        STARTED.retain(|_, t| t.elapsed() < STALE_AFTER);
+        STARTED.insert(id, Instant::now());
+    }
+
+    pub(super) fn command_finished(id: u64, name: &str, ok: bool) {
+        COMMANDS.with_label_values(&[name, status(ok)]).inc();
+        if let Some((_, start)) = STARTED.remove(&id) {
+            COMMAND_DURATION
+                .with_label_values(&[name])
+                .observe(start.elapsed().as_secs_f64());
+        }
+    }
+
+    pub(super) async fn serve(addr: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        tracing::info!("serving metrics on {}", addr);
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("cannot accept a metrics connection: {}", e);
+                    // Errors like running out of file descriptors don't go away right away.
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                // Every request gets the metrics, we only read it to be polite.
+                let mut buf = [0u8; 1024];
+                match tokio::time::timeout(READ_TIMEOUT, stream.read(&mut buf)).await {
+                    Ok(Ok(_)) => (),
+                    _ => return,
+                }
+                let mut body = vec![];
+                let encoder = TextEncoder::new();
+                encoder.encode(&prometheus::gather(), &mut body).ok();
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    encoder.format_type(),
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).await.ok();
+                stream.write_all(&body).await.ok();
+            });
+        }
+    }
+}
@@ -1,18 +1,41 @@
-use crate::{CmdContext, Context, OkPrint, Result};
-use futures_util::future::Future;
+use std::{any::Any, collections::HashMap, sync::Arc, time::Instant};
+
+use crate::{
+    config, hook::InteractionHook, shutdown::Shutdown, CmdContext, Context, OkPrint, Result,
+};
+use dashmap::DashMap;
+use futures_util::future::{BoxFuture, Future};
 use poise::{CreateReply, ReplyHandle};
 use serenity::{
     all::{
-        ComponentInteraction, CreateActionRow, CreateButton, EditInteractionResponse, EditMessage,
+        ButtonStyle, ComponentInteraction, ComponentInteractionDataKind, CreateActionRow,
+        CreateButton, CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateQuickModal, CreateSelectMenu, CreateSelectMenuKind,
+        CreateSelectMenuOption, EditInteractionResponse, EditMessage, Interaction, MessageId,
+        UserId,
     },
-    builder::CreateMessage,
+    builder::{Builder, CreateMessage},
     model::{channel::Message, id::ChannelId},
+    prelude::TypeMapKey,
 };
+use youmubot_db_sql::{models::paginator::Paginator as Row, Pool};
 
 const NEXT: &str = "youmubot_pagination_next";
 const PREV: &str = "youmubot_pagination_prev";
-const FAST_NEXT: &str = "youmubot_pagination_fast_next";
-const FAST_PREV: &str = "youmubot_pagination_fast_prev";
+const FIRST: &str = "youmubot_pagination_first";
+const LAST: &str = "youmubot_pagination_last";
+/// Opens the "go to page" modal.
+/// The [`crate::InteractionCollector`] does not acknowledge it, as the modal is the response.
+pub(crate) const JUMP: &str = "youmubot_pagination_jump";
+const SECTION: &str = "youmubot_pagination_section";
+
+/// Whether the custom ID belongs to one of the pagination controls.
+fn is_control(custom_id: &str) -> bool {
+    [NEXT, PREV, FIRST, LAST, JUMP, SECTION].contains(&custom_id)
+}
+
+/// Discord allows at most 25 options in a select menu.
+const MAX_SECTIONS: usize = 25;
 
 pub trait CanEdit: Send + Sized {
     fn get_message(&self) -> impl Future<Output = Result<Message>> + Send;
@@ -22,6 +45,13 @@ pub trait CanEdit: Send + Sized {
         None
     }
 
+    /// The user controlling the pagination. Anyone else gets their own copy to page through.
+    ///
+    /// If `None`, anyone can control the pagination.
+    fn owner(&self) -> Option<UserId> {
+        None
+    }
+
     fn with_header(self, header: String) -> impl CanEdit {
         WithHeaders(self, header)
     }
@@ -41,6 +71,10 @@ impl<T: CanEdit> CanEdit for WithHeaders<T> {
     fn headers(&self) -> Option<&str> {
         Some(&self.1)
     }
+
+    fn owner(&self) -> Option<UserId> {
+        self.0.owner()
+    }
 }
 
 impl CanEdit for (Message, &Context) {
@@ -54,6 +88,11 @@ impl CanEdit for (Message, &Context) {
             .await?;
         Ok(())
     }
+
+    /// The author of the message being replied to, if any.
+    fn owner(&self) -> Option<UserId> {
+        self.0.referenced_message.as_ref().map(|m| m.author.id)
+    }
 }
 
 impl CanEdit for (&ComponentInteraction, &Context) {
@@ -70,6 +109,10 @@ impl CanEdit for (&ComponentInteraction, &Context) {
             .await?;
         Ok(())
     }
+
+    fn owner(&self) -> Option<UserId> {
+        Some(self.0.user.id)
+    }
 }
 
 impl<Env: Send + Sync> CanEdit for (ReplyHandle<'_>, CmdContext<'_, Env>) {
@@ -81,6 +124,35 @@ impl<Env: Send + Sync> CanEdit for (ReplyHandle<'_>, CmdContext<'_, Env>) {
         self.0.edit(self.1, edit).await?;
         Ok(())
     }
+
+    fn owner(&self) -> Option<UserId> {
+        Some(self.1.author().id)
+    }
+}
+
+/// A copy of a paginated message that only one user can see, sent as a follow-up to their interaction.
+struct Copy<'a> {
+    /// The token of the interaction followed up on: the button click, or the "go to page" modal.
+    token: String,
+    message: MessageId,
+    ctx: &'a Context,
+}
+
+impl CanEdit for Copy<'_> {
+    async fn get_message(&self) -> Result<Message> {
+        Ok(self
+            .ctx
+            .http
+            .get_followup_message(&self.token, self.message)
+            .await?)
+    }
+
+    async fn apply_edit(&mut self, edit: CreateReply) -> Result<()> {
+        edit.to_slash_followup_response(CreateInteractionResponseFollowup::new())
+            .execute(self.ctx, (Some(self.message), &self.token))
+            .await?;
+        Ok(())
+    }
 }
 
 /// A trait that provides the implementation of a paginator.
@@ -126,6 +198,19 @@ pub trait Paginate: Send + Sized {
         self.len().map(|v| v == 0)
     }
 
+    /// Sections to jump to from a select menu, as their labels and first pages, in page order.
+    /// The menu is only shown if there are any.
+    fn sections(&self) -> Vec<(String, u8)> {
+        vec![]
+    }
+
+    /// The kind and parameters to restore this paginator from, see [`Paginators`].
+    /// If given and the kind is registered, the pagination keeps working after it times out,
+    /// or after a restart.
+    async fn persist(&mut self) -> Result<Option<Persisted>> {
+        Ok(None)
+    }
+
     /// Add a page count to the pagination.
     fn with_page_count(self, page_count: usize) -> impl Paginate {
         WithPageCount {
@@ -136,12 +221,41 @@ pub trait Paginate: Send + Sized {
 }
 
 pub async fn do_render(p: &mut impl Paginate, page: u8, m: &mut impl CanEdit) -> Result<bool> {
-    let btns = vec![CreateActionRow::Buttons(
-        p.interaction_buttons(default_buttons(p)),
-    )];
+    let btns = controls(p, page);
     do_render_with_btns(p, page, m, btns).await
 }
 
+/// The action rows controlling the pagination: the buttons, and the section menu if any.
+fn controls(p: &impl Paginate, page: u8) -> Vec<CreateActionRow> {
+    // Discord allows at most 5 buttons in a row.
+    let mut rows = p
+        .interaction_buttons(default_buttons(p, page))
+        .chunks(5)
+        .map(|btns| CreateActionRow::Buttons(btns.to_vec()))
+        .collect::<Vec<_>>();
+    let sections = p.sections();
+    if !sections.is_empty() {
+        let current = sections.iter().rposition(|(_, start)| *start <= page);
+        let options = sections
+            .into_iter()
+            .take(MAX_SECTIONS)
+            .enumerate()
+            .map(|(i, (label, start))| {
+                CreateSelectMenuOption::new(
+                    label.chars().take(100).collect::<String>(),
+                    start.to_string(),
+                )
+                .default_selection(current == Some(i))
+            })
+            .collect();
+        rows.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(SECTION, CreateSelectMenuKind::String { options })
+                .placeholder("Jump to..."),
+        ));
+    }
+    rows
+}
+
 async fn do_render_with_btns(
     p: &mut impl Paginate,
     page: u8,
@@ -167,14 +281,25 @@ pub fn paginate_from_fn(
     pager
 }
 
-pub fn default_buttons(p: &impl Paginate) -> Vec<CreateButton> {
+/// The navigation buttons for the given page.
+/// The page counter in the middle opens a modal to go to any page.
+pub fn default_buttons(p: &impl Paginate, page: u8) -> Vec<CreateButton> {
+    let pages = p.len();
+    let is_last = pages.is_some_and(|v| page as usize + 1 >= v);
+    let counter = match pages {
+        Some(pages) => format!("{} / {}", page + 1, pages),
+        None => format!("{}", page + 1),
+    };
     let mut btns = vec![
-        CreateButton::new(PREV).label("<"),
-        CreateButton::new(NEXT).label(">"),
+        CreateButton::new(PREV).label("<").disabled(page == 0),
+        CreateButton::new(JUMP)
+            .label(counter)
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(NEXT).label(">").disabled(is_last),
     ];
-    if p.len().is_some_and(|v| v > 5) {
-        btns.insert(0, CreateButton::new(FAST_PREV).label("<<"));
-        btns.push(CreateButton::new(FAST_NEXT).label(">>"))
+    if pages.is_some_and(|v| v > 2) {
+        btns.insert(0, CreateButton::new(FIRST).label("<<").disabled(page == 0));
+        btns.push(CreateButton::new(LAST).label(">>").disabled(is_last));
     }
     btns
 }
@@ -220,6 +345,14 @@ impl<Inner: Paginate> Paginate for WithPageCount<Inner> {
     fn interaction_buttons(&self, buttons: Vec<CreateButton>) -> Vec<CreateButton> {
         self.inner.interaction_buttons(buttons)
     }
+
+    fn sections(&self) -> Vec<(String, u8)> {
+        self.inner.sections()
+    }
+
+    async fn persist(&mut self) -> Result<Option<Persisted>> {
+        self.inner.persist().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -275,6 +408,11 @@ pub async fn paginate(
 }
 
 /// Paginate with the first message already created.
+///
+/// If the message has an [owner](CanEdit::owner), only they control it:
+/// anyone else interacting with it gets their own copy, visible only to them.
+/// The buttons stop working after `timeout` without any interaction,
+/// unless the paginator can be [restored](Paginators) when they are used.
 pub async fn paginate_with_first_message(
     mut pager: impl Paginate,
     ctx: &Context,
@@ -289,30 +427,181 @@ pub async fn paginate_with_first_message(
         return Ok(());
     }
 
-    let msg_id = message.get_message().await?.id;
-    let recv = crate::InteractionCollector::create(ctx, msg_id).await?;
+    let (msg_id, channel_id) = {
+        let msg = message.get_message().await?;
+        (msg.id, msg.channel_id)
+    };
+    let owner = message.owner();
+    let mut recv = crate::InteractionCollector::create(ctx, msg_id).await?;
 
     do_render(&mut pager, 0, &mut message).await?;
     let mut page = 0;
+    // The copies of other users, with their pages. Each user has at most one.
+    let mut copies: HashMap<UserId, (Copy<'_>, u8)> = HashMap::new();
+    // Pages asked for with the "go to page" modal, answered without holding up the other buttons.
+    let (jumps, jumped) = flume::unbounded::<Event>();
+    let mut pending_jumps = 0usize;
+
+    // Store the paginator, so that it can be restored once we stop watching it.
+    let paginators = ctx.data.read().await.get::<Paginators>().cloned();
+    let persisted = match &paginators {
+        Some(paginators) => paginators.persist(&mut pager).await,
+        None => None,
+    };
+    let paginators = paginators.filter(|_| persisted.is_some());
+    if let (Some(paginators), Some(persisted)) = (&paginators, &persisted) {
+        paginators
+            .store(msg_id, channel_id, owner, None, persisted, page)
+            .await
+            .pls_ok();
+    }
 
     // Loop the handler function.
+    let mut stopped = false;
     let res: Result<()> = loop {
-        match recv.next(timeout).await {
+        let event = tokio::select! {
+            interaction = recv.next_interaction(timeout) => interaction.map(Event::Click),
+            Ok(event) = jumped.recv_async() => Some(event),
+        };
+        let event = match event {
+            Some(event) => event,
+            // Someone is still choosing a page, wait for them.
+            None if pending_jumps > 0 => match jumped.recv_async().await {
+                Ok(event) => event,
+                Err(_) => break Ok(()),
+            },
             None => break Ok(()),
-            Some(reaction) => {
-                page = match pager
-                    .handle_reaction(page, ctx, &mut message, &reaction)
+        };
+        let (interaction, jump) = match event {
+            Event::Click(interaction) if interaction.data.custom_id == JUMP => {
+                pending_jumps += 1;
+                let (ctx, jumps, pages) = (ctx.clone(), jumps.clone(), pager.len());
+                crate::spawn_future(async move {
+                    let jump = ask_for_page(&ctx, &interaction, pages, timeout)
+                        .await
+                        .pls_ok()
+                        .flatten();
+                    jumps.send_async(Event::Jump(interaction, jump)).await.ok();
+                });
+                continue;
+            }
+            Event::Click(interaction) => (interaction, None),
+            Event::Jump(interaction, jump) => {
+                pending_jumps -= 1;
+                match jump {
+                    Some(jump) => (interaction, Some(jump)),
+                    None => continue,
+                }
+            }
+        };
+        if interaction.message.id != msg_id {
+            let Some((copy, copy_page)) = copies
+                .values_mut()
+                .find(|(copy, _)| copy.message == interaction.message.id)
+            else {
+                continue;
+            };
+            let result = match &jump {
+                Some((new_page, _)) => go_to_page(&mut pager, *copy_page, *new_page, copy)
                     .await
-                {
-                    Ok(Some(v)) => v,
-                    Ok(None) => break Ok(()),
-                    Err(e) => break Err(e),
-                };
+                    .map(Some),
+                None => handle_interaction(&mut pager, *copy_page, ctx, copy, &interaction).await,
+            };
+            match result {
+                Ok(Some(v)) if v != *copy_page => {
+                    *copy_page = v;
+                    if let Some(paginators) = &paginators {
+                        paginators
+                            .set_page(interaction.message.id, v)
+                            .await
+                            .pls_ok();
+                    }
+                }
+                Ok(_) => (),
+                Err(e) => eprintln!("cannot page copy: {:?}", e),
+            }
+            continue;
+        }
+        if owner.is_some_and(|owner| owner != interaction.user.id) {
+            match open_copy(&mut pager, page, ctx, &interaction, jump).await {
+                Ok(Some((copy, copy_page))) => {
+                    if let (Some(paginators), Some(persisted)) = (&paginators, &persisted) {
+                        paginators
+                            .store(
+                                copy.message,
+                                channel_id,
+                                Some(interaction.user.id),
+                                Some(msg_id),
+                                persisted,
+                                copy_page,
+                            )
+                            .await
+                            .pls_ok();
+                    }
+                    recv.watch(copy.message);
+                    // The old copy stops working, so that they don't pile up.
+                    if let Some((mut old, old_page)) =
+                        copies.insert(interaction.user.id, (copy, copy_page))
+                    {
+                        recv.unwatch(old.message);
+                        if let Some(paginators) = &paginators {
+                            paginators.remove(old.message).await.pls_ok();
+                        }
+                        do_render_with_btns(&mut pager, old_page, &mut old, vec![])
+                            .await
+                            .pls_ok();
+                    }
+                }
+                Ok(None) => (),
+                Err(e) => eprintln!("cannot open a copy: {:?}", e),
             }
+            continue;
         }
+        let result = match jump {
+            Some((new_page, _)) => go_to_page(&mut pager, page, new_page, &mut message)
+                .await
+                .map(Some),
+            None => handle_interaction(&mut pager, page, ctx, &mut message, &interaction).await,
+        };
+        match result {
+            Ok(Some(v)) if v != page => {
+                page = v;
+                if let Some(paginators) = &paginators {
+                    paginators.set_page(msg_id, page).await.pls_ok();
+                }
+            }
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                stopped = true;
+                break Ok(());
+            }
+            Err(e) => {
+                stopped = true;
+                break Err(e);
+            }
+        };
     };
 
+    if let Some(paginators) = &paginators {
+        if !stopped {
+            // Leave the buttons, the paginator is restored when they are used.
+            return res;
+        }
+        for id in copies
+            .values()
+            .map(|(copy, _)| copy.message)
+            .chain([msg_id])
+        {
+            paginators.remove(id).await.pls_ok();
+        }
+    }
+
     // Render one last time with no buttons
+    for (mut copy, copy_page) in copies.into_values() {
+        do_render_with_btns(&mut pager, copy_page, &mut copy, vec![])
+            .await
+            .pls_ok();
+    }
     do_render_with_btns(&mut pager, page, &mut message, vec![])
         .await
         .pls_ok();
@@ -320,6 +609,175 @@ pub async fn paginate_with_first_message(
     res
 }
 
+/// What the pagination waits for.
+enum Event {
+    /// A control was used.
+    Click(ComponentInteraction),
+    /// A page was chosen with the "go to page" modal opened by the interaction,
+    /// see [`ask_for_page`]. `None` if nothing (valid) was entered.
+    Jump(ComponentInteraction, Option<(u8, String)>),
+}
+
+/// Handle an interaction with the pagination controls, other than the "go to page" button.
+///
+/// Return the resulting current page, or `None` if the pagination should stop.
+async fn handle_interaction(
+    pager: &mut impl Paginate,
+    page: u8,
+    ctx: &Context,
+    message: &mut impl CanEdit,
+    interaction: &ComponentInteraction,
+) -> Result<Option<u8>> {
+    match interaction.data.custom_id.as_str() {
+        SECTION => match selected_section(interaction) {
+            Some(new_page) => go_to_page(pager, page, new_page, message).await.map(Some),
+            None => Ok(Some(page)),
+        },
+        reaction => pager.handle_reaction(page, ctx, message, reaction).await,
+    }
+}
+
+/// The first page of the section chosen in the select menu.
+fn selected_section(interaction: &ComponentInteraction) -> Option<u8> {
+    match &interaction.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => {
+            values.first().and_then(|v| v.parse::<u8>().ok())
+        }
+        _ => None,
+    }
+}
+
+/// The page that the navigation control leads to from `page`, or `None` if it is not one.
+fn page_after(pages: Option<usize>, page: u8, control: &str) -> Option<u8> {
+    Some(match control {
+        PREV | FIRST if page == 0 => page,
+        PREV => page - 1,
+        FIRST => 0,
+        NEXT if pages.is_some_and(|pages| page as usize + 1 >= pages) => page,
+        NEXT => page.saturating_add(1),
+        LAST => match pages {
+            Some(pages) if pages > 0 => (pages - 1).min(u8::MAX as usize) as u8,
+            _ => page,
+        },
+        _ => return None,
+    })
+}
+
+/// Asks for a page to go to with a modal, answering the interaction.
+/// Returns the page and the token of the modal's interaction, or `None` if nothing (valid) was entered.
+async fn ask_for_page(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    pages: Option<usize>,
+    timeout: std::time::Duration,
+) -> Result<Option<(u8, String)>> {
+    let label = match pages {
+        Some(pages) => format!("Page (1-{})", pages),
+        None => "Page".to_owned(),
+    };
+    let Some(response) = interaction
+        .quick_modal(
+            ctx,
+            CreateQuickModal::new("Go to page")
+                .timeout(timeout)
+                .short_field(label),
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let input = response.inputs.first().map(|v| v.trim()).unwrap_or("");
+    let max = pages
+        .unwrap_or(u8::MAX as usize + 1)
+        .min(u8::MAX as usize + 1);
+    match input.parse::<usize>() {
+        Ok(p) if (1..=max).contains(&p) => {
+            response
+                .interaction
+                .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                .await?;
+            Ok(Some(((p - 1) as u8, response.interaction.token.clone())))
+        }
+        _ => {
+            response
+                .interaction
+                .create_response(
+                    ctx,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .ephemeral(true)
+                            .content(format!("`{}` is not a page between 1 and {}.", input, max)),
+                    ),
+                )
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Sends a copy of the paginated message to the user of the interaction, that only they can see,
+/// with what they clicked applied to it. `jump` is the page they chose with the "go to page" modal,
+/// and the token of the modal's interaction, see [`ask_for_page`].
+///
+/// Returns the copy and its page.
+async fn open_copy<'a>(
+    pager: &mut impl Paginate,
+    page: u8,
+    ctx: &'a Context,
+    interaction: &ComponentInteraction,
+    jump: Option<(u8, String)>,
+) -> Result<Option<(Copy<'a>, u8)>> {
+    let (target, token) = match jump {
+        Some((new_page, token)) => (Some(new_page), token),
+        None => {
+            let target = match interaction.data.custom_id.as_str() {
+                SECTION => selected_section(interaction),
+                control => page_after(pager.len(), page, control),
+            };
+            (target, interaction.token.clone())
+        }
+    };
+    if let Some(target) = target.filter(|&v| v != page) {
+        if let Some(copy) = send_copy(pager, target, ctx, token.clone()).await? {
+            return Ok(Some((copy, target)));
+        }
+    }
+    let Some(mut copy) = send_copy(pager, page, ctx, token).await? else {
+        return Ok(None);
+    };
+    if target.is_some() {
+        return Ok(Some((copy, page)));
+    }
+    // Only the paginator knows what the button does.
+    let copy_page = handle_interaction(pager, page, ctx, &mut copy, interaction)
+        .await?
+        .unwrap_or(page);
+    Ok(Some((copy, copy_page)))
+}
+
+/// Sends the page as a follow-up to the interaction with the given token, that only its user can see.
+async fn send_copy<'a>(
+    pager: &mut impl Paginate,
+    page: u8,
+    ctx: &'a Context,
+    token: String,
+) -> Result<Option<Copy<'a>>> {
+    let btns = controls(pager, page);
+    let Some(reply) = pager.render(page, btns).await? else {
+        return Ok(None);
+    };
+    let message = reply
+        .ephemeral(true)
+        .to_slash_followup_response(CreateInteractionResponseFollowup::new())
+        .execute(ctx, (None, &token))
+        .await?;
+    Ok(Some(Copy {
+        token,
+        message: message.id,
+        ctx,
+    }))
+}
+
 // Handle the reaction and return a new page number.
 pub async fn handle_pagination_reaction(
     page: u8,
@@ -327,20 +785,383 @@ pub async fn handle_pagination_reaction(
     message: &mut impl CanEdit,
     reaction: &str,
 ) -> Result<u8> {
-    let pages = pager.len();
-    let fast = pages.map(|v| v / 10).unwrap_or(5).max(5) as u8;
-    let new_page = match reaction {
-        PREV | FAST_PREV if page == 0 => return Ok(page),
-        PREV => page - 1,
-        FAST_PREV => page.saturating_sub(fast),
-        NEXT if pages.filter(|&pages| page as usize + 1 >= pages).is_some() => return Ok(page),
-        NEXT => page + 1,
-        FAST_NEXT => (pages.unwrap() as u8 - 1).min(page + fast),
-        _ => return Ok(page),
-    };
+    match page_after(pager.len(), page, reaction) {
+        Some(new_page) => go_to_page(pager, page, new_page, message).await,
+        None => Ok(page),
+    }
+}
+
+/// Renders the new page, returning it, or the current page if there is no such page.
+pub async fn go_to_page(
+    pager: &mut impl Paginate,
+    page: u8,
+    new_page: u8,
+    message: &mut impl CanEdit,
+) -> Result<u8> {
+    if new_page == page {
+        return Ok(page);
+    }
     Ok(if do_render(pager, new_page, message).await? {
         new_page
     } else {
         page
     })
 }
+
+/// The kind and parameters to restore a paginator from, see [`Paginate::persist`].
+#[derive(Debug, Clone)]
+pub struct Persisted {
+    /// The kind, as registered with [`Paginators::register`].
+    pub kind: &'static str,
+    /// The parameters, as understood by the kind. Usually JSON.
+    pub params: String,
+}
+
+/// Restores the paginator of a message, and handles the interaction with it.
+type Restore = Arc<dyn Fn(Resume) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// How often the paginators unused for too long are forgotten.
+const CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// The paginators that can be restored from the database after their pagination stopped,
+/// e.g. after a timeout or a restart.
+///
+/// Paginated messages whose paginator [persists](Paginate::persist) keep their buttons:
+/// when one is used later, the paginator is restored by the function registered for its kind.
+#[derive(Clone)]
+pub struct Paginators(Arc<PaginatorsInner>);
+
+struct PaginatorsInner {
+    kinds: DashMap<&'static str, Restore>,
+    /// The restored paginators, by message. Interactions with a message are handled one at a time.
+    restored: DashMap<MessageId, Arc<tokio::sync::Mutex<Restored>>>,
+    sql: Pool,
+    config: config::Pagination,
+}
+
+impl TypeMapKey for Paginators {
+    type Value = Paginators;
+}
+
+impl std::fmt::Debug for Paginators {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Paginators")
+            .field("config", &self.0.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Paginators {
+    pub fn new(sql: Pool, config: config::Pagination) -> Self {
+        Self(Arc::new(PaginatorsInner {
+            kinds: DashMap::new(),
+            restored: DashMap::new(),
+            sql,
+            config,
+        }))
+    }
+
+    /// Registers how to restore the paginators of the given kind from their parameters.
+    pub fn register<P, F, Fut>(&self, kind: &'static str, restore: F)
+    where
+        P: Paginate + 'static,
+        F: Fn(Context, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<P>> + Send + 'static,
+    {
+        let restore = Arc::new(restore);
+        let restore: Restore = Arc::new(move |resume: Resume| {
+            let restore = restore.clone();
+            Box::pin(async move { resume.run::<P, _>(&*restore).await })
+        });
+        self.0.kinds.insert(kind, restore);
+    }
+
+    /// Forgets the paginators unused for too long, every hour until the shutdown starts:
+    /// restored ones after the pagination timeout, stored ones after `persist_days`.
+    pub async fn clean_up(self, shutdown: Shutdown) {
+        loop {
+            let timeout = self.0.config.timeout();
+            // Only forget the ones no one is waiting on.
+            self.0.restored.retain(|_, r| {
+                Arc::strong_count(r) > 1
+                    || r.try_lock().map_or(true, |r| r.used.elapsed() < timeout)
+            });
+            let before = chrono::Utc::now() - self.0.config.persist_for();
+            match Row::delete_unused(before, &self.0.sql).await {
+                Ok(0) => (),
+                Ok(n) => tracing::info!("forgot {} unused paginators", n),
+                Err(e) => tracing::warn!("cannot clean up the paginators: {:?}", e),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(CLEANUP_INTERVAL) => (),
+                _ = shutdown.started() => return,
+            }
+        }
+    }
+
+    /// The kind and parameters of the paginator, if it persists and its kind is registered.
+    async fn persist(&self, pager: &mut impl Paginate) -> Option<Persisted> {
+        if self.0.config.persist_days == 0 {
+            return None;
+        }
+        pager
+            .persist()
+            .await
+            .pls_ok()
+            .flatten()
+            .filter(|p| self.0.kinds.contains_key(p.kind))
+    }
+
+    async fn store(
+        &self,
+        message: MessageId,
+        channel: ChannelId,
+        owner: Option<UserId>,
+        copy_of: Option<MessageId>,
+        persisted: &Persisted,
+        page: u8,
+    ) -> Result<()> {
+        self.store_row(Row {
+            message_id: message.get() as i64,
+            channel_id: channel.get() as i64,
+            owner_id: owner.map(|v| v.get() as i64),
+            copy_of: copy_of.map(|v| v.get() as i64),
+            kind: persisted.kind.to_owned(),
+            params: persisted.params.clone(),
+            page: page as i64,
+            updated_at: chrono::Utc::now(),
+        })
+        .await
+    }
+
+    /// Stores the row. A copy replaces the other copies of the same message its user has,
+    /// and does not store the parameters, which can be large: they are read from the message it is a copy of.
+    async fn store_row(&self, mut row: Row) -> Result<()> {
+        if row.copy_of.is_some() {
+            row.params = String::new();
+        }
+        Row::delete_unused(row.updated_at - self.0.config.persist_for(), &self.0.sql).await?;
+        row.store(&self.0.sql).await?;
+        if row.copy_of.is_some() {
+            row.delete_other_copies(&self.0.sql).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_page(&self, message: MessageId, page: u8) -> Result<()> {
+        Row::set_page(
+            message.get() as i64,
+            page as i64,
+            chrono::Utc::now(),
+            &self.0.sql,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, message: MessageId) -> Result<()> {
+        self.0.restored.remove(&message);
+        Row::delete(message.get() as i64, &self.0.sql).await?;
+        Ok(())
+    }
+
+    /// The stored paginator of the message, if it has not expired.
+    /// Copies get the parameters of the message they are a copy of, and expire along with it.
+    async fn get(&self, message: MessageId) -> Result<Option<Row>> {
+        let Some(mut row) = Row::by_message(message.get() as i64, &self.0.sql).await? else {
+            return Ok(None);
+        };
+        if row.updated_at < chrono::Utc::now() - self.0.config.persist_for() {
+            self.remove(message).await?;
+            return Ok(None);
+        }
+        if let Some(original) = row.copy_of {
+            match Row::by_message(original, &self.0.sql).await? {
+                Some(original) => row.params = original.params,
+                None => {
+                    self.remove(message).await?;
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(Some(row))
+    }
+}
+
+#[async_trait::async_trait]
+impl InteractionHook for Paginators {
+    async fn call(&self, ctx: &Context, interaction: &Interaction) -> Result<()> {
+        let Interaction::Component(interaction) = interaction else {
+            return Ok(());
+        };
+        if !is_control(&interaction.data.custom_id) {
+            return Ok(());
+        }
+        // Paginations still running are handled by the [`crate::InteractionCollector`].
+        let running = ctx
+            .data
+            .read()
+            .await
+            .get::<crate::InteractionCollector>()
+            .is_some_and(|c| c.channels.contains_key(&interaction.message.id));
+        if running {
+            return Ok(());
+        }
+        let Some(row) = self.get(interaction.message.id).await? else {
+            return Ok(());
+        };
+        let Some(restore) = self.0.kinds.get(row.kind.as_str()).map(|v| v.clone()) else {
+            return Ok(());
+        };
+        if interaction.data.custom_id != JUMP {
+            interaction
+                .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                .await?;
+        }
+        let restored = self
+            .0
+            .restored
+            .entry(interaction.message.id)
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(Restored::new())))
+            .clone();
+        restore(Resume {
+            ctx: ctx.clone(),
+            interaction: interaction.clone(),
+            paginators: self.clone(),
+            restored,
+        })
+        .await
+    }
+}
+
+/// The restored paginator of a message, kept for a while so that it is not restored on every use.
+struct Restored {
+    pager: Option<Box<dyn Any + Send>>,
+    used: Instant,
+}
+
+impl Restored {
+    fn new() -> Self {
+        Self {
+            pager: None,
+            used: Instant::now(),
+        }
+    }
+
+    /// Takes the kept paginator, or restores it from the stored row.
+    async fn take<P: Paginate + 'static, Fut: Future<Output = Result<P>>>(
+        &mut self,
+        ctx: &Context,
+        row: &Row,
+        restore: &(impl Fn(Context, String) -> Fut + Sync),
+    ) -> Result<P> {
+        match self.pager.take().and_then(|p| p.downcast::<P>().ok()) {
+            Some(pager) => Ok(*pager),
+            None => restore(ctx.clone(), row.params.clone()).await,
+        }
+    }
+
+    fn keep(&mut self, pager: impl Paginate + 'static) {
+        self.pager = Some(Box::new(pager));
+        self.used = Instant::now();
+    }
+}
+
+/// An interaction with a paginated message, whose paginator is being restored.
+struct Resume {
+    ctx: Context,
+    interaction: ComponentInteraction,
+    paginators: Paginators,
+    restored: Arc<tokio::sync::Mutex<Restored>>,
+}
+
+impl Resume {
+    async fn run<P: Paginate + 'static, Fut: Future<Output = Result<P>>>(
+        self,
+        restore: &(impl Fn(Context, String) -> Fut + Sync),
+    ) -> Result<()> {
+        let Resume {
+            ctx,
+            interaction,
+            paginators,
+            restored,
+        } = self;
+        let ctx = &ctx;
+        let message_id = interaction.message.id;
+        let timeout = paginators.0.config.timeout();
+        let jump = if interaction.data.custom_id == JUMP {
+            // Don't hold up the others while the page is being chosen.
+            let pages = {
+                let mut restored = restored.lock().await;
+                let Some(row) = paginators.get(message_id).await? else {
+                    return Ok(());
+                };
+                let pager = restored.take::<P, _>(ctx, &row, restore).await?;
+                let pages = pager.len();
+                restored.keep(pager);
+                pages
+            };
+            match ask_for_page(ctx, &interaction, pages, timeout).await? {
+                Some(jump) => Some(jump),
+                None => return Ok(()),
+            }
+        } else {
+            None
+        };
+
+        let mut restored = restored.lock().await;
+        // Read the row only now, as the interactions before this one might have moved the page.
+        let Some(row) = paginators.get(message_id).await? else {
+            return Ok(());
+        };
+        let mut pager = restored.take::<P, _>(ctx, &row, restore).await?;
+        let page = row.page as u8;
+
+        if row
+            .owner_id
+            .is_some_and(|owner| owner != interaction.user.id.get() as i64)
+        {
+            let copy = open_copy(&mut pager, page, ctx, &interaction, jump).await;
+            restored.keep(pager);
+            if let Some((copy, copy_page)) = copy? {
+                paginators
+                    .store_row(Row {
+                        message_id: copy.message.get() as i64,
+                        channel_id: interaction.channel_id.get() as i64,
+                        owner_id: Some(interaction.user.id.get() as i64),
+                        copy_of: Some(message_id.get() as i64),
+                        page: copy_page as i64,
+                        updated_at: chrono::Utc::now(),
+                        ..row
+                    })
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let mut message = (&interaction, ctx);
+        let new_page = match jump {
+            Some((new_page, _)) => Some(go_to_page(&mut pager, page, new_page, &mut message).await),
+            None => handle_interaction(&mut pager, page, ctx, &mut message, &interaction)
+                .await
+                .transpose(),
+        };
+        match new_page {
+            Some(Ok(new_page)) => {
+                restored.keep(pager);
+                if new_page != page {
+                    paginators.set_page(message_id, new_page).await?;
+                }
+            }
+            Some(Err(e)) => {
+                restored.keep(pager);
+                return Err(e);
+            }
+            None => {
+                paginators.remove(message_id).await?;
+                do_render_with_btns(&mut pager, page, &mut message, vec![]).await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,24 @@
 use std::ops::Deref;
 /// Provides a simple ratelimit lock (that only works in tokio)
 // use tokio::time::
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use dashmap::DashMap;
 use flume::{bounded as channel, Receiver, Sender};
+use serde::Deserialize;
+use serenity::{
+    model::{
+        channel::Message,
+        id::{ChannelId, GuildId, UserId},
+    },
+    prelude::TypeMapKey,
+};
 
-use crate::Result;
+use crate::{CmdContext, Result};
 
 /// Holds the underlying `T` in a rate-limited way.
 #[derive(Debug, Clone)]
@@ -67,3 +80,213 @@ impl<T> Drop for RatelimitGuard<'_, T> {
         });
     }
 }
+
+/// What a [`KeyedRatelimit`] counts uses against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Each user has their own bucket.
+    User,
+    /// Each guild has its own bucket. Direct messages count against the user.
+    Guild,
+    /// Each channel has its own bucket.
+    Channel,
+    /// Everyone shares one bucket.
+    Global,
+}
+
+/// A token bucket: up to `burst` uses at once, and one more use every `refill`.
+#[derive(Debug, Clone, Copy)]
+pub struct Limit {
+    pub scope: Scope,
+    pub burst: u32,
+    pub refill: Duration,
+}
+
+impl Limit {
+    pub const fn new(scope: Scope, burst: u32, refill: Duration) -> Self {
+        Self {
+            scope,
+            burst,
+            refill,
+        }
+    }
+}
+
+/// Who is invoking a rate-limited command.
+#[derive(Debug, Clone, Copy)]
+pub struct Invoker {
+    pub user: UserId,
+    pub guild: Option<GuildId>,
+    pub channel: ChannelId,
+}
+
+impl Invoker {
+    pub fn of_message(msg: &Message) -> Self {
+        Self {
+            user: msg.author.id,
+            guild: msg.guild_id,
+            channel: msg.channel_id,
+        }
+    }
+
+    pub fn of_context<U: Send + Sync>(ctx: CmdContext<'_, U>) -> Self {
+        Self {
+            user: ctx.author().id,
+            guild: ctx.guild_id(),
+            channel: ctx.channel_id(),
+        }
+    }
+
+    fn key(&self, scope: Scope) -> u64 {
+        match scope {
+            Scope::User => self.user.get(),
+            Scope::Guild => self.guild.map_or(self.user.get(), |g| g.get()),
+            Scope::Channel => self.channel.get(),
+            Scope::Global => 0,
+        }
+    }
+}
+
+/// Returned when a rate limit is hit, with the time until the next use is allowed.
+#[derive(Debug)]
+pub struct RateLimited(pub Duration);
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "⏳ You are being rate-limited! Try this again in **{}**.",
+            // Anything shorter is shown as nothing at all.
+            crate::Duration(self.0.max(Duration::from_secs(1)))
+        )
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token bucket rate limit, with one bucket per key.
+#[derive(Debug)]
+pub struct KeyedRatelimit {
+    limit: Limit,
+    buckets: DashMap<u64, Bucket>,
+}
+
+impl KeyedRatelimit {
+    /// Buckets are cleaned up once there are this many of them.
+    const MAX_BUCKETS: usize = 1024;
+
+    pub fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// Takes one use for the invoker, or returns how long to wait for one.
+    pub fn acquire(&self, invoker: &Invoker) -> Result<(), RateLimited> {
+        self.acquire_at(invoker.key(self.limit.scope), Instant::now())
+    }
+
+    fn acquire_at(&self, key: u64, now: Instant) -> Result<(), RateLimited> {
+        if self.buckets.len() >= Self::MAX_BUCKETS {
+            // Full buckets are the same as no buckets.
+            self.buckets
+                .retain(|_, b| self.refilled(b, now) < self.limit.burst as f64);
+        }
+        let mut bucket = self.buckets.entry(key).or_insert(Bucket {
+            tokens: self.limit.burst as f64,
+            updated: now,
+        });
+        let tokens = self.refilled(&bucket, now);
+        if tokens >= 1.0 {
+            *bucket = Bucket {
+                tokens: tokens - 1.0,
+                updated: now,
+            };
+            Ok(())
+        } else {
+            Err(RateLimited(self.limit.refill.mul_f64(1.0 - tokens)))
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let refilled = if self.limit.refill.is_zero() {
+            f64::INFINITY
+        } else {
+            now.saturating_duration_since(bucket.updated).as_secs_f64()
+                / self.limit.refill.as_secs_f64()
+        };
+        (bucket.tokens + refilled).min(self.limit.burst as f64)
+    }
+}
+
+/// The keyed rate limits of commands, by name.
+///
+/// Limits are set by the commands themselves, and can be overridden in the configuration.
+/// Commands sharing a name (e.g. the prefix and slash versions of a command) share their limits.
+#[derive(Debug, Clone, Default)]
+pub struct Ratelimits {
+    limits: Arc<DashMap<String, KeyedRatelimit>>,
+    overrides: Arc<HashMap<String, Limit>>,
+}
+
+impl TypeMapKey for Ratelimits {
+    type Value = Ratelimits;
+}
+
+impl Ratelimits {
+    pub fn new(overrides: HashMap<String, Limit>) -> Self {
+        Self {
+            limits: Arc::new(DashMap::new()),
+            overrides: Arc::new(overrides),
+        }
+    }
+
+    /// Takes one use of the `name` rate limit, which is `default` unless overridden.
+    pub fn check(&self, name: &str, default: Limit, invoker: &Invoker) -> Result<(), RateLimited> {
+        if let Some(limit) = self.limits.get(name) {
+            return limit.acquire(invoker);
+        }
+        let limit = self.overrides.get(name).copied().unwrap_or(default);
+        self.limits
+            .entry(name.to_owned())
+            .or_insert_with(|| KeyedRatelimit::new(limit))
+            .acquire(invoker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_then_refills() {
+        let rl = KeyedRatelimit::new(Limit::new(Scope::User, 2, Duration::from_secs(10)));
+        let start = Instant::now();
+        assert!(rl.acquire_at(1, start).is_ok());
+        assert!(rl.acquire_at(1, start).is_ok());
+        let wait = rl.acquire_at(1, start).unwrap_err().0;
+        assert_eq!(wait, Duration::from_secs(10));
+        // Other keys are not affected.
+        assert!(rl.acquire_at(2, start).is_ok());
+        // Half a token is not enough.
+        let wait = rl
+            .acquire_at(1, start + Duration::from_secs(5))
+            .unwrap_err()
+            .0;
+        assert_eq!(wait, Duration::from_secs(5));
+        assert!(rl.acquire_at(1, start + Duration::from_secs(10)).is_ok());
+        // Never refills over the burst size.
+        let later = start + Duration::from_secs(1000);
+        assert!(rl.acquire_at(1, later).is_ok());
+        assert!(rl.acquire_at(1, later).is_ok());
+        assert!(rl.acquire_at(1, later).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{str::FromStr, time::Duration};
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+
+use crate::Result;
+
+/// When a recurring job runs.
+#[derive(Debug, Clone)]
+pub enum Interval {
+    /// Wait for a fixed duration after each run.
+    Every(Duration),
+    /// Run on the ticks of a cron expression, in UTC.
+    Cron(Box<cron::Schedule>),
+}
+
+/// How long to wait before retrying a failed run.
+///
+/// The `n`-th consecutive failure waits `initial * 2^(n-1)`, up to `max`.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    fn delay(&self, failures: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(failures.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// The schedule of a recurring job.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub interval: Interval,
+    /// A random delay of up to this duration is added to every wait.
+    pub jitter: Duration,
+    /// Retries failed runs with this policy. Without one, failed runs just wait for the next one.
+    pub backoff: Option<Backoff>,
+}
+
+impl Default for Schedule {
+    /// Runs every 5 minutes.
+    fn default() -> Self {
+        Self::every(Duration::from_secs(300))
+    }
+}
+
+impl Schedule {
+    /// Runs with a fixed delay between runs.
+    pub fn every(d: Duration) -> Self {
+        Self {
+            interval: Interval::Every(d),
+            jitter: Duration::ZERO,
+            backoff: None,
+        }
+    }
+
+    /// Runs on a cron expression, with seconds: `sec min hour day-of-month month day-of-week [year]`.
+    ///
+    /// For example, `0 0 12 * * Mon` runs every Monday at noon UTC.
+    pub fn cron(expr: &str) -> Result<Self> {
+        let schedule = cron::Schedule::from_str(expr)
+            .map_err(|e| crate::error!("invalid cron expression `{}`: {}", expr, e))?;
+        Ok(Self {
+            interval: Interval::Cron(Box::new(schedule)),
+            jitter: Duration::ZERO,
+            backoff: None,
+        })
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = Some(Backoff { initial, max });
+        self
+    }
+
+    /// How long to wait before the next run, given the number of consecutive failures so far.
+    pub fn next_wait(&self, now: DateTime<Utc>, failures: u32) -> Duration {
+        self.base_wait(now, failures)
+            .saturating_add(self.random_jitter())
+    }
+
+    fn base_wait(&self, now: DateTime<Utc>, failures: u32) -> Duration {
+        if let Some(backoff) = self.backoff.filter(|_| failures > 0) {
+            return backoff.delay(failures);
+        }
+        match &self.interval {
+            Interval::Every(d) => *d,
+            Interval::Cron(s) => s
+                .after(&now)
+                .next()
+                .and_then(|t| (t - now).to_std().ok())
+                // A schedule that never fires again: wait for a manual run.
+                .unwrap_or(Duration::MAX),
+        }
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+        rand::thread_rng().gen_range(Duration::ZERO..self.jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        let s = Schedule::every(Duration::from_secs(300))
+            .with_backoff(Duration::from_secs(60), Duration::from_secs(600));
+        let now = Utc::now();
+        let wait = |f| s.base_wait(now, f).as_secs();
+        assert_eq!(wait(0), 300);
+        assert_eq!(wait(1), 60);
+        assert_eq!(wait(2), 120);
+        assert_eq!(wait(4), 480);
+        assert_eq!(wait(5), 600);
+        assert_eq!(wait(100), 600);
+    }
+
+    #[test]
+    fn waits_for_cron_ticks() {
+        // Every Monday at noon.
+        let s = Schedule::cron("0 0 12 * * Mon").unwrap();
+        // 2024-06-01 is a Saturday.
+        let wait = s.base_wait(at("2024-06-01T12:00:00Z"), 0);
+        assert_eq!(wait, Duration::from_secs(2 * 24 * 60 * 60));
+        // Without a backoff, failures don't change anything.
+        assert_eq!(s.base_wait(at("2024-06-01T12:00:00Z"), 3), wait);
+        assert!(Schedule::cron("every monday").is_err());
+    }
+
+    #[test]
+    fn jitters_within_bounds() {
+        let s = Schedule::every(Duration::from_secs(10)).with_jitter(Duration::from_secs(5));
+        for _ in 0..100 {
+            let wait = s.next_wait(Utc::now(), 0);
+            assert!(wait >= Duration::from_secs(10) && wait < Duration::from_secs(15));
+        }
+    }
+}
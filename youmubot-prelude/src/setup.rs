@@ -1,29 +1,23 @@
-use std::{path::Path, time::Duration};
+use std::{sync::Arc, time::Duration};
 
 use serenity::prelude::*;
 
-use crate::Env;
+use crate::{Config, Env};
 
 /// Set up the prelude libraries.
 ///
 /// Panics on failure: Youmubot should *NOT* attempt to continue when this function fails.
-pub async fn setup_prelude(
-    db_path: impl AsRef<Path>,
-    sql_path: impl AsRef<Path>,
-    data: &mut TypeMap,
-) -> Env {
-    // Set up the announcer DB.
-    crate::announcer::AnnouncerChannels::insert_into(
-        data,
-        db_path.as_ref().join("announcers.yaml"),
-    )
-    .expect("Announcers DB set up");
-
+pub async fn setup_prelude(config: Config, data: &mut TypeMap) -> Env {
     // Set up the database
-    let sql_pool = youmubot_db_sql::connect(sql_path)
+    let sql_pool = youmubot_db_sql::connect(config.paths.sql())
         .await
         .expect("SQL database set up");
 
+    // Move the announcer channels into the database.
+    crate::announcer::import_announcer_channels(&sql_pool, config.paths.db.join("announcers.yaml"))
+        .await
+        .expect("Announcer channels imported");
+
     // Set up the HTTP client.
     let http_client = reqwest::ClientBuilder::new()
         .connect_timeout(Duration::from_secs(5))
@@ -39,9 +33,33 @@ pub async fn setup_prelude(
     // Set up the SQL client.
     data.insert::<crate::SQLClient>(sql_pool.clone());
 
+    // Set up the command rate limits.
+    let ratelimits = crate::ratelimit::Ratelimits::new(
+        config
+            .rate_limits
+            .iter()
+            .map(|(name, limit)| (name.clone(), limit.limit()))
+            .collect(),
+    );
+    data.insert::<crate::ratelimit::Ratelimits>(ratelimits.clone());
+
+    // Set up the shutdown coordinator.
+    let shutdown = crate::shutdown::Shutdown::default();
+    data.insert::<crate::shutdown::Shutdown>(shutdown.clone());
+
+    // Set up the restorable paginators.
+    let paginators =
+        crate::pagination::Paginators::new(sql_pool.clone(), config.pagination.clone());
+    data.insert::<crate::pagination::Paginators>(paginators.clone());
+    crate::spawn_future(paginators.clone().clean_up(shutdown.clone()));
+
     Env {
         http: http_client,
         sql: sql_pool,
         members: member_cache,
+        ratelimits,
+        shutdown,
+        paginators,
+        config: Arc::new(config),
     }
 }
@@ -0,0 +1,143 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serenity::prelude::TypeMapKey;
+use tokio::sync::Notify;
+
+/// Coordinates shutting the bot down.
+///
+/// Background tasks either stop as soon as [`Shutdown::started`] resolves, or [`Shutdown::hold`]
+/// the shutdown while doing something that should not be interrupted (e.g. an announcer cycle).
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    started: AtomicBool,
+    holds: AtomicUsize,
+    /// Notified when the shutdown starts.
+    on_start: Notify,
+    /// Notified when the last hold is released.
+    on_idle: Notify,
+}
+
+impl TypeMapKey for Shutdown {
+    type Value = Shutdown;
+}
+
+/// Delays the shutdown until dropped.
+#[derive(Debug)]
+pub struct ShutdownHold(Shutdown);
+
+impl Drop for ShutdownHold {
+    fn drop(&mut self) {
+        if self.0 .0.holds.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0 .0.on_idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /// Whether the shutdown has started.
+    pub fn is_started(&self) -> bool {
+        self.0.started.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the shutdown has started.
+    pub async fn started(&self) {
+        let notified = self.0.on_start.notified();
+        if self.is_started() {
+            return;
+        }
+        notified.await
+    }
+
+    /// Delays the shutdown until the returned hold is dropped.
+    ///
+    /// Returns `None` if the shutdown has already started: the work should not begin at all.
+    pub fn hold(&self) -> Option<ShutdownHold> {
+        self.0.holds.fetch_add(1, Ordering::SeqCst);
+        let hold = ShutdownHold(self.clone());
+        if self.is_started() {
+            return None;
+        }
+        Some(hold)
+    }
+
+    /// Starts the shutdown, without waiting for anything.
+    pub fn start(&self) {
+        self.0.started.store(true, Ordering::SeqCst);
+        self.0.on_start.notify_waiters();
+    }
+
+    /// Starts the shutdown, and waits up to `timeout` for all holds to be released.
+    ///
+    /// Returns whether everything finished in time.
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        self.start();
+        tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.0.on_idle.notified();
+                if self.0.holds.load(Ordering::SeqCst) == 0 {
+                    break;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on unix.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => (),
+                    _ = term.recv() => (),
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("cannot listen to SIGTERM: {}", e),
+        }
+    }
+    tokio::signal::ctrl_c().await.ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_holds() {
+        let shutdown = Shutdown::default();
+        let hold = shutdown.hold().unwrap();
+        let task = {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                shutdown.started().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                drop(hold);
+            })
+        };
+        assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+        assert!(shutdown.hold().is_none());
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let shutdown = Shutdown::default();
+        let _hold = shutdown.hold().unwrap();
+        assert!(!shutdown.shutdown(Duration::from_millis(50)).await);
+    }
+}
@@ -0,0 +1,676 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use hyper::Method;
+use serde_json::{json, Value};
+use serenity::{
+    all::{ClientBuilder, GatewayIntents, HttpBuilder},
+    http::Http,
+    Client,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, Notify},
+};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use crate::{
+    fixtures,
+    server::{FakeServer, Request, Response},
+};
+
+/// The token the fake expects. Any token works, this is just what [`FakeDiscord::http`] uses.
+pub const TOKEN: &str = "fake-token";
+/// The user ID of the bot.
+pub const BOT_ID: u64 = 100;
+/// The user ID of the bot's owner.
+pub const OWNER_ID: u64 = 101;
+/// The application ID of the bot.
+pub const APPLICATION_ID: u64 = 102;
+
+const API: &str = "/api/v10";
+
+/// An in-process fake of the parts of Discord that the bot talks to.
+///
+/// It consists of:
+/// - An HTTP server standing in for the REST API, see [`FakeDiscord::server`] for adding routes.
+///   Sent messages are echoed back as if they were created, and recorded.
+/// - A gateway that lets any client identify, answers heartbeats, and sends the events
+///   passed to [`FakeDiscord::dispatch`].
+///
+/// Clients built with [`FakeDiscord::client`] connect to both instead of Discord.
+#[derive(Clone)]
+pub struct FakeDiscord {
+    server: FakeServer,
+    gateway: SocketAddr,
+    state: Arc<State>,
+}
+
+struct State {
+    events: broadcast::Sender<(String, Value)>,
+    seq: AtomicU64,
+    next_id: AtomicU64,
+    ready: AtomicBool,
+    on_ready: Notify,
+    members: Mutex<HashMap<u64, Vec<Value>>>,
+    channels: Mutex<HashMap<u64, Value>>,
+    /// All messages, by ID.
+    messages: Mutex<HashMap<u64, Value>>,
+    /// Messages sent by the bot, including interaction follow-ups.
+    sent: Mutex<Vec<Value>>,
+    /// Messages edited by the bot, as they are after each edit.
+    edits: Mutex<Vec<Value>>,
+    /// The channel of each interaction and the message it is on, by token.
+    interactions: Mutex<HashMap<String, (u64, Option<u64>)>>,
+    /// Notified whenever a message is sent or edited.
+    on_message: Notify,
+}
+
+impl FakeDiscord {
+    /// Starts the fake REST API and gateway on random local ports.
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let gateway = listener.local_addr()?;
+        let state = Arc::new(State {
+            events: broadcast::channel(256).0,
+            seq: AtomicU64::new(0),
+            next_id: AtomicU64::new(1_000_000),
+            ready: AtomicBool::new(false),
+            on_ready: Notify::new(),
+            members: Mutex::new(HashMap::new()),
+            channels: Mutex::new(HashMap::new()),
+            messages: Mutex::new(HashMap::new()),
+            sent: Mutex::new(Vec::new()),
+            edits: Mutex::new(Vec::new()),
+            interactions: Mutex::new(HashMap::new()),
+            on_message: Notify::new(),
+        });
+        tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = state.serve_gateway(stream).await {
+                            eprintln!("fake gateway connection closed: {}", e);
+                        }
+                    });
+                }
+            }
+        });
+        let discord = Self {
+            server: FakeServer::start().await?,
+            gateway,
+            state,
+        };
+        discord.add_routes();
+        Ok(discord)
+    }
+
+    /// The underlying HTTP server, for adding routes and inspecting requests.
+    pub fn server(&self) -> &FakeServer {
+        &self.server
+    }
+
+    /// An HTTP client that talks to the fake.
+    pub fn http(&self) -> Arc<Http> {
+        Arc::new(self.build_http())
+    }
+
+    fn build_http(&self) -> Http {
+        HttpBuilder::new(TOKEN)
+            .proxy(self.server.url())
+            .ratelimiter_disabled(true)
+            .build()
+    }
+
+    /// A client builder that talks to the fake. Add the event handlers and framework, then [`FakeDiscord::run`] it.
+    pub fn client(&self, intents: GatewayIntents) -> ClientBuilder {
+        ClientBuilder::new_with_http(self.build_http(), intents)
+    }
+
+    /// Starts the client in the background, and waits until it has identified with the gateway.
+    pub async fn run(&self, mut client: Client) -> Result<tokio::task::JoinHandle<()>> {
+        let handle = tokio::spawn(async move {
+            if let Err(e) = client.start().await {
+                eprintln!("client stopped: {}", e);
+            }
+        });
+        self.ready(Duration::from_secs(10)).await?;
+        Ok(handle)
+    }
+
+    /// Waits until a client has identified with the gateway.
+    pub async fn ready(&self, timeout: Duration) -> Result<()> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let notified = self.state.on_ready.notified();
+                if self.state.ready.load(Ordering::SeqCst) {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("no client identified within {:?}", timeout))
+    }
+
+    /// A fresh snowflake.
+    pub fn next_id(&self) -> u64 {
+        self.state.next_id()
+    }
+
+    /// Makes the user a member of the guild.
+    pub fn add_member(&self, guild: u64, user: Value) -> &Self {
+        self.state
+            .members
+            .lock()
+            .unwrap()
+            .entry(guild)
+            .or_default()
+            .push(fixtures::member(guild, user));
+        self
+    }
+
+    /// Adds a text channel to the guild.
+    pub fn add_channel(&self, guild: u64, channel: u64) -> &Self {
+        self.state.channels.lock().unwrap().insert(
+            channel,
+            fixtures::guild_channel(guild, channel, &format!("channel-{}", channel)),
+        );
+        self
+    }
+
+    /// Sends a gateway event to all connected clients.
+    pub fn dispatch(&self, event: &str, data: Value) {
+        // No receivers just means no client is connected, the event is dropped like Discord would.
+        self.state.events.send((event.to_owned(), data)).ok();
+    }
+
+    /// Posts a message as `author`, as if sent from a Discord client.
+    /// Returns the message object.
+    pub fn send_message(
+        &self,
+        guild: Option<u64>,
+        channel: u64,
+        author: Value,
+        content: &str,
+    ) -> Value {
+        let message = fixtures::message(self.next_id(), channel, guild, author, content);
+        self.state
+            .messages
+            .lock()
+            .unwrap()
+            .insert(id_of(&message), message.clone());
+        self.dispatch("MESSAGE_CREATE", message.clone());
+        message
+    }
+
+    /// Clicks the button with the given custom ID on the message.
+    /// Returns the interaction token, see [`FakeDiscord::wait_for_response`].
+    pub fn click(&self, user: Value, message: &Value, custom_id: &str) -> String {
+        self.interact(
+            user,
+            message,
+            json!({ "custom_id": custom_id, "component_type": 2 }),
+        )
+    }
+
+    /// Selects the values in the string select menu with the given custom ID on the message.
+    /// Returns the interaction token, see [`FakeDiscord::wait_for_response`].
+    pub fn select(&self, user: Value, message: &Value, custom_id: &str, values: &[&str]) -> String {
+        self.interact(
+            user,
+            message,
+            json!({ "custom_id": custom_id, "component_type": 3, "values": values }),
+        )
+    }
+
+    /// Submits the modal with the given custom ID, filling its text inputs in order.
+    /// Returns the interaction token, see [`FakeDiscord::wait_for_response`].
+    pub fn submit_modal(
+        &self,
+        user: Value,
+        channel: u64,
+        custom_id: &str,
+        inputs: &[&str],
+    ) -> String {
+        let rows = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                json!({
+                    "type": 1,
+                    "components": [{ "type": 4, "custom_id": i.to_string(), "value": value }],
+                })
+            })
+            .collect::<Vec<_>>();
+        let (id, token) = self.new_interaction(channel, None);
+        self.dispatch(
+            "INTERACTION_CREATE",
+            json!({
+                "id": id.to_string(),
+                "application_id": APPLICATION_ID.to_string(),
+                "type": 5,
+                "data": { "custom_id": custom_id, "components": rows },
+                "channel_id": channel.to_string(),
+                "user": user,
+                "token": token,
+                "version": 1,
+                "locale": "en-US",
+                "entitlements": [],
+            }),
+        );
+        token
+    }
+
+    fn interact(&self, user: Value, message: &Value, data: Value) -> String {
+        let channel = message["channel_id"].as_str().unwrap().parse().unwrap();
+        let (id, token) = self.new_interaction(channel, Some(id_of(message)));
+        let mut interaction = json!({
+            "id": id.to_string(),
+            "application_id": APPLICATION_ID.to_string(),
+            "type": 3,
+            "data": data,
+            "channel_id": channel.to_string(),
+            "user": user,
+            "token": token,
+            "version": 1,
+            "message": message,
+            "locale": "en-US",
+            "entitlements": [],
+        });
+        if let Some(guild) = message.get("guild_id") {
+            interaction["guild_id"] = guild.clone();
+        }
+        self.dispatch("INTERACTION_CREATE", interaction);
+        token
+    }
+
+    fn new_interaction(&self, channel: u64, message: Option<u64>) -> (u64, String) {
+        let id = self.next_id();
+        let token = format!("interaction-{}", id);
+        self.state
+            .interactions
+            .lock()
+            .unwrap()
+            .insert(token.clone(), (channel, message));
+        (id, token)
+    }
+
+    /// Waits for the bot to respond to the interaction, returning the response body.
+    pub async fn wait_for_response(&self, token: &str, timeout: Duration) -> Result<Value> {
+        let pattern = format!("{}/interactions/{{}}/{}/callback", API, token);
+        Ok(self
+            .server
+            .wait_for(Method::POST, &pattern, timeout)
+            .await?
+            .json())
+    }
+
+    /// The messages the bot sent to the channel so far, including follow-ups to interactions.
+    pub fn sent_messages(&self, channel: u64) -> Vec<Value> {
+        self.state.sent_to(channel)
+    }
+
+    /// Waits for the bot to send a message to the channel, returning it.
+    pub async fn wait_for_message(&self, channel: u64, timeout: Duration) -> Result<Value> {
+        Ok(self
+            .wait_for_messages(channel, 1, timeout)
+            .await?
+            .swap_remove(0))
+    }
+
+    /// Waits until the bot has sent `count` messages to the channel, returning all of them.
+    pub async fn wait_for_messages(
+        &self,
+        channel: u64,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<Vec<Value>> {
+        self.state
+            .wait_until(timeout, || {
+                Some(self.state.sent_to(channel)).filter(|v| v.len() >= count)
+            })
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "no {} messages sent to {} within {:?}",
+                    count,
+                    channel,
+                    timeout
+                )
+            })
+    }
+
+    /// The edits of the message so far, as the message is after each edit.
+    pub fn edits(&self, message: &Value) -> Vec<Value> {
+        self.state.edits_of(message)
+    }
+
+    /// Waits until the bot has edited the message `count` times, returning all the edits.
+    pub async fn wait_for_edits(
+        &self,
+        message: &Value,
+        count: usize,
+        timeout: Duration,
+    ) -> Result<Vec<Value>> {
+        self.state
+            .wait_until(timeout, || {
+                Some(self.state.edits_of(message)).filter(|v| v.len() >= count)
+            })
+            .await
+            .map_err(|_| {
+                anyhow!(
+                    "no {} edits of {} within {:?}",
+                    count,
+                    message["id"],
+                    timeout
+                )
+            })
+    }
+
+    fn add_routes(&self) {
+        let s = &self.server;
+        let gateway = format!("ws://{}", self.gateway);
+        s.json(Method::GET, api("/gateway"), json!({ "url": gateway }))
+            .json(
+                Method::GET,
+                api("/gateway/bot"),
+                json!({
+                    "url": gateway,
+                    "shards": 1,
+                    "session_start_limit": {
+                        "total": 1000,
+                        "remaining": 1000,
+                        "reset_after": 0,
+                        "max_concurrency": 1,
+                    },
+                }),
+            )
+            .json(Method::GET, api("/users/@me"), bot_user())
+            .json(
+                Method::GET,
+                api("/oauth2/applications/@me"),
+                json!({
+                    "id": APPLICATION_ID.to_string(),
+                    "name": "Youmu",
+                    "icon": null,
+                    "description": "",
+                    "bot_public": false,
+                    "bot_require_code_grant": false,
+                    "owner": fixtures::user(OWNER_ID, "owner"),
+                    "verify_key": "",
+                    "team": null,
+                }),
+            );
+
+        // Messages
+        let state = self.state.clone();
+        s.route(Method::POST, api("/channels/{}/messages"), move |r| {
+            let channel = r.segments(&api("/channels/{}/messages"))[0]
+                .parse()
+                .unwrap_or(0);
+            Response::json(state.echo_message(r, None, channel))
+        });
+        let state = self.state.clone();
+        s.route(Method::PATCH, api("/channels/{}/messages/{}"), move |r| {
+            let seg = r.segments(&api("/channels/{}/messages/{}"));
+            let (channel, id) = (seg[0].parse().unwrap_or(0), seg[1].parse().ok());
+            Response::json(state.echo_message(r, id, channel))
+        });
+        s.route(Method::DELETE, api("/channels/{}/messages/{}"), |_| {
+            Response::no_content()
+        });
+        s.route(
+            Method::PUT,
+            api("/channels/{}/messages/{}/reactions/{}/@me"),
+            |_| Response::no_content(),
+        );
+        s.route(
+            Method::DELETE,
+            api("/channels/{}/messages/{}/reactions/{}/{}"),
+            |_| Response::no_content(),
+        );
+        s.route(Method::POST, api("/channels/{}/typing"), |_| {
+            Response::no_content()
+        });
+
+        // Interactions
+        s.route(Method::POST, api("/interactions/{}/{}/callback"), |_| {
+            Response::no_content()
+        });
+        let state = self.state.clone();
+        s.route(Method::POST, api("/webhooks/{}/{}"), move |r| {
+            let token = r.segments(&api("/webhooks/{}/{}"))[1];
+            let (channel, _) = state.interaction(token);
+            Response::json(state.echo_message(r, None, channel))
+        });
+        let state = self.state.clone();
+        s.route(
+            Method::PATCH,
+            api("/webhooks/{}/{}/messages/{}"),
+            move |r| {
+                let seg = r.segments(&api("/webhooks/{}/{}/messages/{}"));
+                let (channel, message) = state.interaction(seg[1]);
+                // The original response of a component interaction is the message it is on.
+                let id = match seg[2] {
+                    "@original" => message,
+                    id => id.parse().ok(),
+                };
+                Response::json(state.echo_message(r, id, channel))
+            },
+        );
+
+        // Channels
+        let state = self.state.clone();
+        s.route(Method::GET, api("/channels/{}"), move |r| {
+            let id: u64 = r.segments(&api("/channels/{}"))[0].parse().unwrap_or(0);
+            match state.channels.lock().unwrap().get(&id) {
+                Some(c) => Response::json(c.clone()),
+                None => Response::not_found(),
+            }
+        });
+
+        // Guilds
+        s.route(Method::GET, api("/guilds/{}"), |r| {
+            let id: u64 = r.segments(&api("/guilds/{}"))[0].parse().unwrap_or(0);
+            Response::json(fixtures::guild(id, &format!("guild-{}", id)))
+        });
+
+        // Members
+        let state = self.state.clone();
+        s.route(Method::GET, api("/guilds/{}/members"), move |r| {
+            let guild: u64 = r.segments(&api("/guilds/{}/members"))[0]
+                .parse()
+                .unwrap_or(0);
+            let members = state.members.lock().unwrap();
+            Response::json(Value::Array(
+                members.get(&guild).cloned().unwrap_or_default(),
+            ))
+        });
+        let state = self.state.clone();
+        s.route(Method::GET, api("/guilds/{}/members/{}"), move |r| {
+            let seg = r.segments(&api("/guilds/{}/members/{}"));
+            let (guild, user): (u64, &str) = (seg[0].parse().unwrap_or(0), seg[1]);
+            let members = state.members.lock().unwrap();
+            match members
+                .get(&guild)
+                .and_then(|ms| ms.iter().find(|m| m["user"]["id"] == user))
+            {
+                Some(m) => Response::json(m.clone()),
+                None => Response::with_status(
+                    404,
+                    json!({ "message": "Unknown Member", "code": 10007 }),
+                ),
+            }
+        });
+    }
+}
+
+impl State {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Sends (if `id` is `None`) or edits a message from the bot, answering like Discord would.
+    fn echo_message(&self, r: &Request, id: Option<u64>, channel: u64) -> Value {
+        let body = r.json();
+        let existing = id.and_then(|id| self.messages.lock().unwrap().get(&id).cloned());
+        let mut message = existing.clone().unwrap_or_else(|| {
+            let guild = self
+                .channels
+                .lock()
+                .unwrap()
+                .get(&channel)
+                .and_then(|c| c["guild_id"].as_str()?.parse().ok());
+            let id = id.unwrap_or_else(|| self.next_id());
+            fixtures::message(id, channel, guild, bot_user(), "")
+        });
+        for field in ["content", "embeds", "components", "flags"] {
+            if let Some(v) = body.get(field) {
+                message[field] = v.clone();
+            }
+        }
+        if let Some(reply_to) = body["message_reference"]["message_id"].as_str() {
+            let reply_to = reply_to.parse().unwrap_or(0);
+            if let Some(m) = self.messages.lock().unwrap().get(&reply_to) {
+                message["referenced_message"] = m.clone();
+            }
+        }
+        self.messages
+            .lock()
+            .unwrap()
+            .insert(id_of(&message), message.clone());
+        if existing.is_some() {
+            self.edits.lock().unwrap().push(message.clone());
+        } else {
+            self.sent.lock().unwrap().push(message.clone());
+        }
+        self.on_message.notify_waiters();
+        message
+    }
+
+    fn interaction(&self, token: &str) -> (u64, Option<u64>) {
+        self.interactions
+            .lock()
+            .unwrap()
+            .get(token)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn sent_to(&self, channel: u64) -> Vec<Value> {
+        let channel = channel.to_string();
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m["channel_id"] == channel.as_str())
+            .cloned()
+            .collect()
+    }
+
+    fn edits_of(&self, message: &Value) -> Vec<Value> {
+        self.edits
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m["id"] == message["id"])
+            .cloned()
+            .collect()
+    }
+
+    async fn wait_until<T>(&self, timeout: Duration, f: impl Fn() -> Option<T>) -> Result<T> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let notified = self.on_message.notified();
+                if let Some(v) = f() {
+                    return v;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("timed out"))
+    }
+
+    async fn serve_gateway(&self, stream: TcpStream) -> Result<()> {
+        let mut ws = tokio_tungstenite::accept_async(stream).await?;
+        let mut events = self.events.subscribe();
+        ws.send(op(10, json!({ "heartbeat_interval": 45000 })))
+            .await?;
+        loop {
+            tokio::select! {
+                msg = ws.next() => {
+                    let Some(msg) = msg else { return Ok(()) };
+                    let payload: Value = match msg? {
+                        WsMessage::Text(t) => serde_json::from_str(&t)?,
+                        WsMessage::Close(_) => return Ok(()),
+                        _ => continue,
+                    };
+                    match payload["op"].as_u64() {
+                        // Heartbeat
+                        Some(1) => ws.send(WsMessage::Text(json!({ "op": 11 }).to_string())).await?,
+                        // Identify
+                        Some(2) => {
+                            let ready = json!({
+                                "v": 10,
+                                "user": bot_user(),
+                                "guilds": [],
+                                "session_id": "fake-session",
+                                "resume_gateway_url": "",
+                                "shard": [0, 1],
+                                "application": { "id": APPLICATION_ID.to_string(), "flags": 0 },
+                            });
+                            ws.send(self.dispatch_frame("READY", ready)).await?;
+                            self.ready.store(true, Ordering::SeqCst);
+                            self.on_ready.notify_waiters();
+                        }
+                        _ => (),
+                    }
+                }
+                event = events.recv() => {
+                    match event {
+                        Ok((name, data)) => ws.send(self.dispatch_frame(&name, data)).await?,
+                        Err(broadcast::error::RecvError::Lagged(_)) => (),
+                        Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn dispatch_frame(&self, event: &str, data: Value) -> WsMessage {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        WsMessage::Text(json!({ "op": 0, "s": seq, "t": event, "d": data }).to_string())
+    }
+}
+
+fn op(code: u8, data: Value) -> WsMessage {
+    WsMessage::Text(json!({ "op": code, "d": data }).to_string())
+}
+
+fn api(path: &str) -> String {
+    format!("{}{}", API, path)
+}
+
+fn id_of(message: &Value) -> u64 {
+    message["id"]
+        .as_str()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+fn bot_user() -> Value {
+    fixtures::bot_user(BOT_ID, "Youmu")
+}
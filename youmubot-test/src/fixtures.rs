@@ -0,0 +1,115 @@
+//! Builders for the Discord objects the fake backends send around, as JSON.
+//!
+//! Only the fields serenity requires are filled in, the rest can be added with [`with`].
+use serde_json::{json, Value};
+
+/// A fixed timestamp, so that outputs stay stable.
+pub const TIMESTAMP: &str = "2024-01-01T00:00:00.000000+00:00";
+
+/// Merges the fields of `extra` into the object `base`.
+pub fn with(mut base: Value, extra: Value) -> Value {
+    if let (Some(base), Value::Object(extra)) = (base.as_object_mut(), extra) {
+        base.extend(extra);
+    }
+    base
+}
+
+/// A user.
+pub fn user(id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": name,
+        "global_name": null,
+        "avatar": null,
+        "bot": false,
+    })
+}
+
+/// A bot user.
+pub fn bot_user(id: u64, name: &str) -> Value {
+    with(user(id, name), json!({ "bot": true }))
+}
+
+/// A guild member.
+pub fn member(guild: u64, user: Value) -> Value {
+    json!({
+        "guild_id": guild.to_string(),
+        "user": user,
+        "nick": null,
+        "roles": [],
+        "joined_at": TIMESTAMP,
+        "deaf": false,
+        "mute": false,
+        "flags": 0,
+    })
+}
+
+/// A guild, owned by the bot owner.
+pub fn guild(id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "name": name,
+        "icon": null,
+        "splash": null,
+        "discovery_splash": null,
+        "owner_id": crate::discord::OWNER_ID.to_string(),
+        "verification_level": 0,
+        "default_message_notifications": 0,
+        "explicit_content_filter": 0,
+        "roles": [],
+        "emojis": [],
+        "features": [],
+        "mfa_level": 0,
+        "application_id": null,
+        "system_channel_id": null,
+        "system_channel_flags": 0,
+        "rules_channel_id": null,
+        "vanity_url_code": null,
+        "description": null,
+        "banner": null,
+        "premium_tier": 0,
+        "preferred_locale": "en-US",
+        "public_updates_channel_id": null,
+        "nsfw_level": 0,
+        "stickers": [],
+        "premium_progress_bar_enabled": false,
+    })
+}
+
+/// A text channel in a guild.
+pub fn guild_channel(guild: u64, id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "guild_id": guild.to_string(),
+        "type": 0,
+        "name": name,
+        "position": 0,
+        "permission_overwrites": [],
+        "nsfw": false,
+    })
+}
+
+/// A message. Leave out `guild` for direct messages.
+pub fn message(id: u64, channel: u64, guild: Option<u64>, author: Value, content: &str) -> Value {
+    let mut m = json!({
+        "id": id.to_string(),
+        "channel_id": channel.to_string(),
+        "author": author,
+        "content": content,
+        "timestamp": TIMESTAMP,
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "components": [],
+        "pinned": false,
+        "type": 0,
+    });
+    if let Some(guild) = guild {
+        m["guild_id"] = guild.to_string().into();
+    }
+    m
+}
@@ -0,0 +1,98 @@
+//! Offline test harness for Youmubot.
+//!
+//! Everything here runs in-process, without network access or credentials:
+//! - [`FakeDiscord`] stands in for Discord's REST API and gateway, so a real serenity [`Client`]
+//!   can be started against it, fed messages, and its replies inspected.
+//! - [`FakeServer`] serves fixture JSON, with [`FakeServer::osu`] and [`FakeServer::codeforces`]
+//!   stubbing the parts of those APIs that the bot uses.
+//! - [`TestEnv`] sets the prelude up in a temporary directory.
+//!
+//! Point the bot at the stubs with `osu.url` and `codeforces.api_url` in [`TestEnv::config`].
+//! The `codeforces` crate has its API URL built in, so only the Codeforces requests the bot makes
+//! itself (e.g. finding linked submissions) go to the stub.
+//!
+//! [`Client`]: serenity::Client
+use std::path::Path;
+
+use anyhow::Result;
+use serenity::{
+    all::{Context, EventHandler, Interaction, Message},
+    async_trait,
+    prelude::{RwLock, TypeMap},
+};
+use tempfile::TempDir;
+use youmubot_prelude::{hook::InteractionHook, setup::setup_prelude, Config, Env, Hook};
+
+pub use discord::FakeDiscord;
+pub use server::{FakeServer, Request, Response};
+
+pub mod discord;
+pub mod fixtures;
+pub mod server;
+
+/// A configuration and data directory for a test, deleted when dropped.
+pub struct TestEnv {
+    dir: TempDir,
+    pub config: Config,
+}
+
+impl TestEnv {
+    /// Creates an empty data directory, with the default configuration pointing to it.
+    pub fn new() -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let mut config = Config::default();
+        config.discord.token = discord::TOKEN.to_owned();
+        config.discord.owners = vec![discord::OWNER_ID];
+        config.paths.db = dir.path().to_owned();
+        config.paths.sql = Some(dir.path().join("youmubot.db"));
+        Ok(Self { dir, config })
+    }
+
+    /// The data directory.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Sets the prelude up into `data`, like the bot does on start.
+    pub async fn setup(&self, data: &mut TypeMap) -> Env {
+        setup_prelude(self.config.clone(), data).await
+    }
+}
+
+/// An event handler running hooks on every message and interaction, like the bot does.
+#[derive(Default)]
+pub struct Hooks {
+    hooks: Vec<RwLock<Box<dyn Hook>>>,
+    interaction_hooks: Vec<Box<dyn InteractionHook>>,
+}
+
+impl Hooks {
+    pub fn push(mut self, hook: impl Hook + 'static) -> Self {
+        self.hooks.push(RwLock::new(Box::new(hook)));
+        self
+    }
+
+    pub fn push_interaction(mut self, hook: impl InteractionHook + 'static) -> Self {
+        self.interaction_hooks.push(Box::new(hook));
+        self
+    }
+}
+
+#[async_trait]
+impl EventHandler for Hooks {
+    async fn message(&self, ctx: Context, message: Message) {
+        for hook in &self.hooks {
+            if let Err(e) = hook.write().await.call(&ctx, &message).await {
+                eprintln!("hook failed: {:?}", e);
+            }
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        for hook in &self.interaction_hooks {
+            if let Err(e) = hook.call(&ctx, &interaction).await {
+                eprintln!("interaction hook failed: {:?}", e);
+            }
+        }
+    }
+}
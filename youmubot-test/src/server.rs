@@ -0,0 +1,346 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method,
+};
+use serde_json::Value;
+use tokio::sync::Notify;
+
+/// A request received by a [`FakeServer`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The body parsed as JSON, or `null` if it isn't.
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+
+    /// Gets a query parameter.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .as_deref()?
+            .split('&')
+            .filter_map(|kv| kv.split_once('='))
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v)
+    }
+
+    /// The segments of the path matched by `{}` in the route pattern.
+    pub fn segments(&self, pattern: &str) -> Vec<&str> {
+        matches(pattern, &self.path).unwrap_or_default()
+    }
+}
+
+/// A response to be sent by a [`FakeServer`].
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(value: Value) -> Self {
+        Self::with_status(200, value)
+    }
+
+    pub fn with_status(status: u16, value: Value) -> Self {
+        Self {
+            status,
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    pub fn no_content() -> Self {
+        Self {
+            status: 204,
+            body: vec![],
+        }
+    }
+
+    pub fn not_found() -> Self {
+        Self::with_status(
+            404,
+            serde_json::json!({ "message": "Unknown route", "code": 0 }),
+        )
+    }
+}
+
+type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync>;
+
+struct Route {
+    method: Method,
+    pattern: String,
+    handler: Handler,
+}
+
+#[derive(Default)]
+struct State {
+    routes: Mutex<Vec<Route>>,
+    requests: Mutex<Vec<Request>>,
+    on_request: Notify,
+}
+
+/// An in-process HTTP server answering requests with canned responses.
+///
+/// Routes are matched by method and path, where `{}` in the pattern matches any single path segment.
+/// Routes added later take precedence, so a test can override the defaults of a stub.
+/// Every request is recorded, and can be inspected with [`FakeServer::requests`] and [`FakeServer::wait_for`].
+#[derive(Clone)]
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<State>,
+}
+
+impl FakeServer {
+    /// Starts an empty server on a random local port.
+    pub async fn start() -> Result<Self> {
+        let state = Arc::new(State::default());
+        let make_service = make_service_fn({
+            let state = state.clone();
+            move |_| {
+                let state = state.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req| {
+                        let state = state.clone();
+                        async move { Ok::<_, Infallible>(state.handle(req).await) }
+                    }))
+                }
+            }
+        });
+        let server = hyper::Server::try_bind(&([127, 0, 0, 1], 0).into())?.serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                eprintln!("fake server stopped: {}", e);
+            }
+        });
+        Ok(Self { addr, state })
+    }
+
+    /// A stub of the osu! API and beatmap downloads, serving the fixtures in `fixtures/osu`.
+    pub async fn osu() -> Result<Self> {
+        let s = Self::start().await?;
+        s.fixture(Method::POST, "/oauth/token", "osu/token.json")?
+            .fixture(Method::GET, "/api/v2/users/{}", "osu/user.json")?
+            .fixture(Method::GET, "/api/v2/users/{}/{}", "osu/user.json")?
+            .fixture(Method::GET, "/api/v2/users/{}/scores/{}", "osu/scores.json")?
+            .fixture(Method::GET, "/api/v2/scores/{}", "osu/score.json")?
+            .fixture(Method::GET, "/api/v2/beatmaps/{}", "osu/beatmap.json")?
+            .fixture(Method::GET, "/api/v2/beatmaps/lookup", "osu/beatmap.json")?
+            .fixture(
+                Method::GET,
+                "/api/v2/beatmaps/{}/scores",
+                "osu/beatmap_scores.json",
+            )?;
+        let beatmap = fixture_file("osu/beatmap.osu")?;
+        s.route(Method::GET, "/osu/{}", move |_| Response {
+            status: 200,
+            body: beatmap.clone(),
+        });
+        Ok(s)
+    }
+
+    /// A stub of the Codeforces API, serving the fixtures in `fixtures/codeforces`.
+    pub async fn codeforces() -> Result<Self> {
+        let s = Self::start().await?;
+        s.fixture(Method::GET, "/api/user.info", "codeforces/user.info.json")?
+            .fixture(
+                Method::GET,
+                "/api/user.rating",
+                "codeforces/user.rating.json",
+            )?
+            .fixture(
+                Method::GET,
+                "/api/contest.list",
+                "codeforces/contest.list.json",
+            )?
+            .fixture(
+                Method::GET,
+                "/api/contest.standings",
+                "codeforces/contest.standings.json",
+            )?
+            .fixture(
+                Method::GET,
+                "/api/problemset.problems",
+                "codeforces/problemset.problems.json",
+            )?
+            .fixture(
+                Method::GET,
+                "/api/contest.status",
+                "codeforces/contest.status.json",
+            )?
+            .fixture(
+                Method::GET,
+                "/api/user.status",
+                "codeforces/user.status.json",
+            )?;
+        Ok(s)
+    }
+
+    /// The base URL of the server, e.g. `http://127.0.0.1:1234`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Answers matching requests with the given handler.
+    pub fn route(
+        &self,
+        method: Method,
+        pattern: impl Into<String>,
+        handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) -> &Self {
+        self.state.routes.lock().unwrap().push(Route {
+            method,
+            pattern: pattern.into(),
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Answers matching requests with the given JSON.
+    pub fn json(&self, method: Method, pattern: impl Into<String>, value: Value) -> &Self {
+        self.route(method, pattern, move |_| Response::json(value.clone()))
+    }
+
+    /// Answers matching requests with a fixture file, relative to the `fixtures` directory.
+    pub fn fixture(&self, method: Method, pattern: impl Into<String>, name: &str) -> Result<&Self> {
+        Ok(self.json(method, pattern, fixture(name)?))
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Waits for a request matching the method and path pattern, including ones already received.
+    pub async fn wait_for(
+        &self,
+        method: Method,
+        pattern: &str,
+        timeout: Duration,
+    ) -> Result<Request> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let notified = self.state.on_request.notified();
+                if let Some(r) = self
+                    .requests()
+                    .into_iter()
+                    .find(|r| r.method == method && matches(pattern, &r.path).is_some())
+                {
+                    return r;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("no {} {} request within {:?}", method, pattern, timeout))
+    }
+}
+
+impl State {
+    async fn handle(&self, req: hyper::Request<Body>) -> hyper::Response<Body> {
+        let (parts, body) = req.into_parts();
+        let body = hyper::body::to_bytes(body)
+            .await
+            .map(|b| b.to_vec())
+            .unwrap_or_default();
+        let request = Request {
+            method: parts.method,
+            path: parts.uri.path().to_owned(),
+            query: parts.uri.query().map(|q| q.to_owned()),
+            body,
+        };
+        let response = {
+            let routes = self.routes.lock().unwrap();
+            routes
+                .iter()
+                .rev()
+                .find(|r| {
+                    r.method == request.method && matches(&r.pattern, &request.path).is_some()
+                })
+                .map(|r| (r.handler)(&request))
+                .unwrap_or_else(Response::not_found)
+        };
+        self.requests.lock().unwrap().push(request);
+        self.on_request.notify_waiters();
+        hyper::Response::builder()
+            .status(response.status)
+            .header("content-type", "application/json")
+            .body(Body::from(response.body))
+            .unwrap()
+    }
+}
+
+/// Loads a fixture file, relative to the `fixtures` directory of this crate.
+pub fn fixture(name: &str) -> Result<Value> {
+    Ok(serde_json::from_slice(&fixture_file(name)?)?)
+}
+
+/// Reads a fixture file as is, relative to the `fixtures` directory of this crate.
+pub fn fixture_file(name: &str) -> Result<Vec<u8>> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(name);
+    std::fs::read(&path).map_err(|e| anyhow!("cannot read {:?}: {}", path, e))
+}
+
+/// Matches the path against the pattern, returning the segments matched by `{}`.
+fn matches<'a>(pattern: &str, path: &'a str) -> Option<Vec<&'a str>> {
+    let mut pattern = pattern.trim_end_matches('/').split('/');
+    let mut path = path.trim_end_matches('/').split('/');
+    let mut captures = vec![];
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return Some(captures),
+            (Some("{}"), Some(seg)) if !seg.is_empty() => captures.push(seg),
+            (Some(p), Some(seg)) if p == seg => (),
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_patterns() {
+        assert_eq!(
+            matches(
+                "/api/v10/channels/{}/messages",
+                "/api/v10/channels/42/messages"
+            ),
+            Some(vec!["42"])
+        );
+        assert_eq!(matches("/api/user.info", "/api/user.info/"), Some(vec![]));
+        assert_eq!(
+            matches("/api/v10/channels/{}", "/api/v10/channels/42/messages"),
+            None
+        );
+        assert_eq!(
+            matches(
+                "/api/v10/channels/{}/messages",
+                "/api/v10/channels//messages"
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use serenity::all::{GatewayIntents, UserId};
+use youmubot_db_sql::models::announcer::AnnouncerChannel;
+use youmubot_prelude::{
+    announcer::{AnnouncerHandler, CacheAndHttp, MemberToChannels},
+    *,
+};
+use youmubot_test::{fixtures, FakeDiscord, TestEnv};
+
+const GUILD: u64 = 1;
+const CHANNEL: u64 = 10;
+const MEMBER: u64 = 5;
+const STRANGER: u64 = 6;
+
+/// Greets every user it knows, in the channels of the guilds they are in.
+struct Greeter(Vec<u64>);
+
+#[async_trait]
+impl Announcer for Greeter {
+    async fn updates(
+        &mut self,
+        c: CacheAndHttp,
+        _d: AppData,
+        channels: MemberToChannels,
+    ) -> Result<()> {
+        for &user in &self.0 {
+            for channel in channels.channels_of(c.clone(), UserId::new(user)).await {
+                channel.target().say(&c, format!("hello {}", user)).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn announces_to_members_until_shutdown() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord
+        .add_channel(GUILD, CHANNEL)
+        .add_member(GUILD, fixtures::user(MEMBER, "member"));
+
+    let env = TestEnv::new()?;
+    let client = discord.client(GatewayIntents::empty()).await?;
+    let prelude = env.setup(&mut *client.data.write().await).await;
+    AnnouncerChannel {
+        announcer_key: "greeter".to_owned(),
+        guild_id: GUILD as i64,
+        channel_id: CHANNEL as i64,
+        thread_id: None,
+        filter: None,
+    }
+    .store(&prelude.sql)
+    .await?;
+
+    let mut announcers = AnnouncerHandler::new();
+    announcers.add("greeter", Greeter(vec![MEMBER, STRANGER]));
+    let runner = tokio::spawn(announcers.run(&client, prelude.config.clone())?.scan());
+
+    let sent = discord
+        .wait_for_message(CHANNEL, Duration::from_secs(10))
+        .await?;
+    assert_eq!(sent["content"], format!("hello {}", MEMBER));
+
+    prelude.shutdown.start();
+    tokio::time::timeout(Duration::from_secs(10), runner).await??;
+    assert_eq!(discord.sent_messages(CHANNEL).len(), 1);
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use serenity::all::GatewayIntents;
+use youmubot_prelude::{announcer::AnnouncerHandler, *};
+use youmubot_test::{fixtures, FakeDiscord, FakeServer, Hooks, TestEnv};
+
+const GUILD: u64 = 1;
+const CHANNEL: u64 = 10;
+
+#[tokio::test]
+async fn shows_linked_submissions() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord.add_channel(GUILD, CHANNEL);
+    let codeforces = FakeServer::codeforces().await?;
+
+    let mut env = TestEnv::new()?;
+    env.config.codeforces.api_url = format!("{}/api", codeforces.url());
+    let client = discord
+        .client(GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+        .event_handler(Hooks::default().push(youmubot_cf::InfoHook))
+        .await?;
+    {
+        let mut data = client.data.write().await;
+        let prelude = env.setup(&mut data).await;
+        youmubot_cf::setup(&prelude.config, &mut data, &mut AnnouncerHandler::new()).await;
+    }
+    discord.run(client).await?;
+
+    discord.send_message(
+        Some(GUILD),
+        CHANNEL,
+        fixtures::user(5, "user"),
+        "look: https://codeforces.com/contest/1900/submission/235000002",
+    );
+    let reply = discord
+        .wait_for_message(CHANNEL, Duration::from_secs(10))
+        .await?;
+    assert_eq!(reply["content"], "Here are the info of the given links!");
+    let info = reply["embeds"][0]["description"].as_str().unwrap();
+    assert!(info.contains("**#235000002**"), "{}", info);
+    assert!(info.contains("`tourist`"), "{}", info);
+    assert!(info.contains("**Accepted**"), "{}", info);
+
+    let status = codeforces
+        .requests()
+        .into_iter()
+        .find(|r| r.path == "/api/contest.status")
+        .unwrap();
+    assert_eq!(status.param("contestId"), Some("1900"));
+    Ok(())
+}
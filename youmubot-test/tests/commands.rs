@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use serenity::{
+    all::{Context, GatewayIntents, Message},
+    framework::standard::{Configuration, StandardFramework},
+};
+use youmubot_prelude::*;
+use youmubot_test::{fixtures, FakeDiscord, Hooks, TestEnv};
+
+const GUILD: u64 = 1;
+const CHANNEL: u64 = 10;
+
+fn echo_hook<'a>(
+    ctx: &'a Context,
+    msg: &'a Message,
+) -> std::pin::Pin<Box<dyn future::Future<Output = Result<()>> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(text) = msg.content.strip_prefix("echo ") {
+            msg.channel_id.say(ctx, text).await?;
+        }
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn runs_commands() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord.add_channel(GUILD, CHANNEL);
+
+    let env = TestEnv::new()?;
+    let framework = StandardFramework::new().group(&youmubot_core::FUN_GROUP);
+    framework.configure(Configuration::new().prefix("y!"));
+    let client = discord
+        .client(GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+        .framework(framework)
+        .await?;
+    env.setup(&mut *client.data.write().await).await;
+    discord.run(client).await?;
+
+    discord.send_message(Some(GUILD), CHANNEL, fixtures::user(5, "user"), "y!roll 1");
+    let reply = discord
+        .wait_for_message(CHANNEL, Duration::from_secs(10))
+        .await?;
+    assert!(reply["content"]
+        .as_str()
+        .unwrap()
+        .ends_with("I rolled a 🎲 of **1** faces, and got **1**!"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn runs_hooks() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord.add_channel(GUILD, CHANNEL);
+
+    let client = discord
+        .client(GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+        .event_handler(Hooks::default().push(echo_hook))
+        .await?;
+    discord.run(client).await?;
+
+    discord.send_message(Some(GUILD), CHANNEL, fixtures::user(5, "user"), "hello");
+    discord.send_message(Some(GUILD), CHANNEL, fixtures::user(5, "user"), "echo hi");
+    let sent = discord
+        .wait_for_message(CHANNEL, Duration::from_secs(10))
+        .await?;
+    assert_eq!(sent["content"], "hi");
+    assert_eq!(discord.sent_messages(CHANNEL).len(), 1);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use serenity::{
+    all::GatewayIntents,
+    framework::standard::{Configuration, StandardFramework},
+};
+use youmubot_db_sql::models::{
+    osu_user::{OsuUser, OsuUserMode},
+    DateTime,
+};
+use youmubot_prelude::{announcer::AnnouncerHandler, *};
+use youmubot_test::{fixtures, FakeDiscord, FakeServer, Hooks, TestEnv};
+
+const GUILD: u64 = 1;
+const CHANNEL: u64 = 10;
+const MEMBER: u64 = 5;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Starts the bot with the osu! commands and `score_hook`, talking to the osu! stub.
+async fn start(discord: &FakeDiscord, osu: &FakeServer) -> Result<(TestEnv, Env)> {
+    let mut env = TestEnv::new()?;
+    env.config.osu.client_id = Some(1);
+    env.config.osu.client_secret = Some("secret".to_owned());
+    env.config.osu.url = Some(osu.url());
+    let framework = StandardFramework::new().group(&youmubot_osu::discord::OSU_GROUP);
+    framework.configure(Configuration::new().prefix("y!"));
+    let client = discord
+        .client(GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+        .framework(framework)
+        .event_handler(Hooks::default().push(youmubot_osu::discord::score_hook))
+        .await?;
+    let prelude = {
+        let mut data = client.data.write().await;
+        let prelude = env.setup(&mut data).await;
+        youmubot_osu::discord::setup(&mut data, prelude.clone(), &mut AnnouncerHandler::new())
+            .await?;
+        prelude
+    };
+    discord.run(client).await?;
+    Ok((env, prelude))
+}
+
+#[tokio::test]
+async fn ranks_saved_members() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord
+        .add_channel(GUILD, CHANNEL)
+        .add_member(GUILD, fixtures::user(MEMBER, "member"));
+    let osu = FakeServer::osu().await?;
+    let (_env, prelude) = start(&discord, &osu).await?;
+
+    let mut tx = prelude.sql.begin().await?;
+    OsuUser {
+        user_id: MEMBER as i64,
+        username: Some("peppy".to_owned()),
+        id: 2,
+        modes: [(
+            0,
+            OsuUserMode {
+                pp: 1234.5,
+                map_length: 90.0,
+                map_age: 1_600_000_000,
+                last_update: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            },
+        )]
+        .into(),
+        preferred_mode: 0,
+        failures: 0,
+    }
+    .store(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    discord.send_message(
+        Some(GUILD),
+        CHANNEL,
+        fixtures::user(MEMBER, "member"),
+        "y!osu ranks",
+    );
+    let reply = discord.wait_for_message(CHANNEL, TIMEOUT).await?;
+    assert_eq!(
+        reply["content"],
+        "Rankings for **guild-1**, ordered by _pp_"
+    );
+    let page = discord.wait_for_edits(&reply, 1, TIMEOUT).await?.remove(0);
+    let page = page["content"].as_str().unwrap();
+    assert!(page.contains("1234.50"), "{}", page);
+    assert!(page.contains("peppy"), "{}", page);
+    assert!(page.contains("Page **1**/**1**"), "{}", page);
+    Ok(())
+}
+
+#[tokio::test]
+async fn shows_linked_scores() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord.add_channel(GUILD, CHANNEL);
+    let osu = FakeServer::osu().await?;
+    let (_env, _prelude) = start(&discord, &osu).await?;
+
+    discord.send_message(
+        Some(GUILD),
+        CHANNEL,
+        fixtures::user(MEMBER, "member"),
+        "nice play https://osu.ppy.sh/scores/4000000000",
+    );
+    let reply = discord.wait_for_message(CHANNEL, TIMEOUT).await?;
+    assert_eq!(
+        reply["content"],
+        "Here is the score mentioned in the message!"
+    );
+    let embed = reply["embeds"][0].to_string();
+    assert!(embed.contains("DISCO PRINCE"), "{}", embed);
+    assert!(embed.contains("peppy"), "{}", embed);
+
+    let paths = osu
+        .requests()
+        .into_iter()
+        .map(|r| r.path)
+        .collect::<Vec<_>>();
+    assert!(paths.contains(&"/api/v2/scores/4000000000".to_owned()));
+    assert!(paths.contains(&"/osu/75".to_owned()));
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use serde_json::Value;
+use serenity::all::{Context, CreateActionRow, GatewayIntents, Message};
+use youmubot_db_sql::models::paginator::Paginator;
+use youmubot_prelude::{pagination::Persisted, *};
+use youmubot_test::{fixtures, FakeDiscord, Hooks, TestEnv};
+
+const GUILD: u64 = 1;
+const CHANNEL: u64 = 10;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const NEXT: &str = "youmubot_pagination_next";
+const PREV: &str = "youmubot_pagination_prev";
+const FIRST: &str = "youmubot_pagination_first";
+const JUMP: &str = "youmubot_pagination_jump";
+const SECTION: &str = "youmubot_pagination_section";
+
+struct Pages(usize);
+
+#[async_trait]
+impl Paginate for Pages {
+    async fn render(
+        &mut self,
+        page: u8,
+        btns: Vec<CreateActionRow>,
+    ) -> Result<Option<CreateReply>> {
+        Ok(Some(
+            CreateReply::default()
+                .content(format!("page {}", page + 1))
+                .components(btns),
+        ))
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.0)
+    }
+
+    fn sections(&self) -> Vec<(String, u8)> {
+        vec![("first".to_owned(), 0), ("second".to_owned(), 3)]
+    }
+
+    async fn persist(&mut self) -> Result<Option<Persisted>> {
+        Ok(Some(Persisted {
+            kind: "pages",
+            params: self.0.to_string(),
+        }))
+    }
+}
+
+/// How many times [`restore_pages`] was called.
+static RESTORED: AtomicUsize = AtomicUsize::new(0);
+
+async fn restore_pages(_: Context, params: String) -> Result<Pages> {
+    RESTORED.fetch_add(1, Ordering::SeqCst);
+    Ok(Pages(params.parse()?))
+}
+
+/// Paginates 5 pages on `list <timeout in seconds>`.
+fn list_hook<'a>(
+    ctx: &'a Context,
+    msg: &'a Message,
+) -> std::pin::Pin<Box<dyn future::Future<Output = Result<()>> + Send + 'a>> {
+    Box::pin(async move {
+        if let Some(timeout) = msg.content.strip_prefix("list ") {
+            let timeout = Duration::from_secs(timeout.parse()?);
+            paginate_reply(Pages(5), ctx, msg, timeout).await?;
+        }
+        Ok(())
+    })
+}
+
+/// Waits for the message to be stored at the page (or not at all), as it is stored after being edited.
+async fn wait_for_stored_page(env: &Env, message: &Value, page: Option<i64>) -> Result<()> {
+    let id = message["id"].as_str().unwrap().parse()?;
+    tokio::time::timeout(TIMEOUT, async {
+        while Paginator::by_message(id, &env.sql).await?.map(|p| p.page) != page {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Ok(())
+    })
+    .await?
+}
+
+/// Fills in the modal that the interaction was answered with.
+///
+/// The bot only starts waiting for the modal after sending it, so wait a bit like a user would.
+async fn fill_modal(
+    discord: &FakeDiscord,
+    user: Value,
+    token: &str,
+    inputs: &[&str],
+) -> Result<()> {
+    let modal = discord.wait_for_response(token, TIMEOUT).await?;
+    assert_eq!(modal["type"], 9);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let modal_id = modal["data"]["custom_id"].as_str().unwrap();
+    discord.submit_modal(user, CHANNEL, modal_id, inputs);
+    Ok(())
+}
+
+/// The latest version of the message.
+fn latest(discord: &FakeDiscord, message: &Value) -> Value {
+    discord
+        .edits(message)
+        .pop()
+        .unwrap_or_else(|| message.clone())
+}
+
+#[tokio::test]
+async fn paginates_per_user() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord.add_channel(GUILD, CHANNEL);
+    let owner = fixtures::user(5, "owner");
+    let stranger = fixtures::user(6, "stranger");
+
+    let collector = InteractionCollector::new();
+    let client = discord
+        .client(GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+        .event_handler(
+            Hooks::default()
+                .push(list_hook)
+                .push_interaction(collector.clone()),
+        )
+        .await?;
+    client
+        .data
+        .write()
+        .await
+        .insert::<InteractionCollector>(collector);
+    discord.run(client).await?;
+
+    discord.send_message(Some(GUILD), CHANNEL, owner.clone(), "list 3");
+    let reply = discord.wait_for_message(CHANNEL, TIMEOUT).await?;
+    let edits = discord.wait_for_edits(&reply, 1, TIMEOUT).await?;
+    assert_eq!(edits[0]["content"], "page 1");
+
+    // The owner moves the message itself.
+    discord.click(owner.clone(), &latest(&discord, &reply), NEXT);
+    let edits = discord.wait_for_edits(&reply, 2, TIMEOUT).await?;
+    assert_eq!(edits[1]["content"], "page 2");
+
+    // Anyone else gets their own copy, with what they clicked applied to it.
+    discord.click(stranger.clone(), &latest(&discord, &reply), NEXT);
+    let copy = discord.wait_for_messages(CHANNEL, 2, TIMEOUT).await?[1].clone();
+    assert_eq!(copy["content"], "page 3");
+    assert_eq!(copy["flags"], 64);
+    discord.click(stranger.clone(), &copy, NEXT);
+    let copy_edits = discord.wait_for_edits(&copy, 1, TIMEOUT).await?;
+    assert_eq!(copy_edits[0]["content"], "page 4");
+    assert_eq!(discord.edits(&reply).len(), 2);
+
+    // Sections.
+    discord.select(owner.clone(), &latest(&discord, &reply), SECTION, &["3"]);
+    let edits = discord.wait_for_edits(&reply, 3, TIMEOUT).await?;
+    assert_eq!(edits[2]["content"], "page 4");
+
+    // Jumping to a page, which doesn't hold up the other buttons.
+    let token = discord.click(owner.clone(), &latest(&discord, &reply), JUMP);
+    discord.wait_for_response(&token, TIMEOUT).await?;
+    discord.click(owner.clone(), &latest(&discord, &reply), PREV);
+    let edits = discord.wait_for_edits(&reply, 4, TIMEOUT).await?;
+    assert_eq!(edits[3]["content"], "page 3");
+    fill_modal(&discord, owner.clone(), &token, &["5"]).await?;
+    let edits = discord.wait_for_edits(&reply, 5, TIMEOUT).await?;
+    assert_eq!(edits[4]["content"], "page 5");
+
+    // Others can jump too, getting a new copy that replaces their old one.
+    let token = discord.click(stranger.clone(), &latest(&discord, &reply), JUMP);
+    fill_modal(&discord, stranger.clone(), &token, &["2"]).await?;
+    let new_copy = discord.wait_for_messages(CHANNEL, 3, TIMEOUT).await?[2].clone();
+    assert_eq!(new_copy["content"], "page 2");
+    let copy_edits = discord.wait_for_edits(&copy, 2, TIMEOUT).await?;
+    assert_eq!(copy_edits[1]["components"], serde_json::json!([]));
+    assert_eq!(discord.edits(&reply).len(), 5);
+
+    // Buttons are removed once the pagination times out.
+    let edits = discord.wait_for_edits(&reply, 6, TIMEOUT).await?;
+    assert_eq!(edits[5]["components"], serde_json::json!([]));
+    let new_copy_edits = discord.wait_for_edits(&new_copy, 1, TIMEOUT).await?;
+    assert_eq!(new_copy_edits[0]["components"], serde_json::json!([]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn restores_after_timeout() -> Result<()> {
+    let discord = FakeDiscord::start().await?;
+    discord.add_channel(GUILD, CHANNEL);
+    let owner = fixtures::user(5, "owner");
+    let stranger = fixtures::user(6, "stranger");
+
+    let env = TestEnv::new()?;
+    let collector = InteractionCollector::new();
+    let mut data = TypeMap::new();
+    let prelude = env.setup(&mut data).await;
+    prelude.paginators.register("pages", restore_pages);
+    let client = discord
+        .client(GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT)
+        .event_handler(
+            Hooks::default()
+                .push(list_hook)
+                .push_interaction(collector.clone())
+                .push_interaction(prelude.paginators.clone()),
+        )
+        .type_map(data)
+        .await?;
+    client
+        .data
+        .write()
+        .await
+        .insert::<InteractionCollector>(collector);
+    discord.run(client).await?;
+
+    discord.send_message(Some(GUILD), CHANNEL, owner.clone(), "list 1");
+    let reply = discord.wait_for_message(CHANNEL, TIMEOUT).await?;
+    discord.wait_for_edits(&reply, 1, TIMEOUT).await?;
+    discord.click(owner.clone(), &latest(&discord, &reply), NEXT);
+    discord.wait_for_edits(&reply, 2, TIMEOUT).await?;
+
+    // The buttons are kept after the timeout...
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(discord.edits(&reply).len(), 2);
+    assert_ne!(
+        latest(&discord, &reply)["components"],
+        serde_json::json!([])
+    );
+
+    // ...and keep working, one click at a time.
+    let message = latest(&discord, &reply);
+    let token = discord.click(owner.clone(), &message, NEXT);
+    discord.click(owner.clone(), &message, NEXT);
+    assert_eq!(discord.wait_for_response(&token, TIMEOUT).await?["type"], 6);
+    let edits = discord.wait_for_edits(&reply, 4, TIMEOUT).await?;
+    assert_eq!(edits[2]["content"], "page 3");
+    assert_eq!(edits[3]["content"], "page 4");
+
+    // Copies are restored too.
+    discord.click(stranger.clone(), &latest(&discord, &reply), NEXT);
+    let copy = discord.wait_for_messages(CHANNEL, 2, TIMEOUT).await?[1].clone();
+    assert_eq!(copy["content"], "page 5");
+    assert_eq!(copy["flags"], 64);
+    discord.click(stranger.clone(), &copy, PREV);
+    let copy_edits = discord.wait_for_edits(&copy, 1, TIMEOUT).await?;
+    assert_eq!(copy_edits[0]["content"], "page 4");
+    assert_eq!(discord.edits(&reply).len(), 4);
+
+    wait_for_stored_page(&prelude, &reply, Some(3)).await?;
+    wait_for_stored_page(&prelude, &copy, Some(3)).await?;
+    // Copies are restored from the parameters of their original.
+    let copy_id = copy["id"].as_str().unwrap().parse()?;
+    let copy_row = Paginator::by_message(copy_id, &prelude.sql).await?.unwrap();
+    assert_eq!(copy_row.params, "");
+
+    // A new copy replaces the old one.
+    discord.click(stranger.clone(), &latest(&discord, &reply), FIRST);
+    let new_copy = discord.wait_for_messages(CHANNEL, 3, TIMEOUT).await?[2].clone();
+    assert_eq!(new_copy["content"], "page 1");
+    wait_for_stored_page(&prelude, &new_copy, Some(0)).await?;
+    wait_for_stored_page(&prelude, &copy, None).await?;
+
+    // Each message was restored only once.
+    assert_eq!(RESTORED.load(Ordering::SeqCst), 2);
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use serenity::framework::standard::CommandGroup;
+
+use youmubot_prelude::*;
+
+/// Maps prefix commands to the command groups that can be disabled.
+pub(crate) struct CommandGroups {
+    groups: Vec<(&'static CommandGroup, &'static str)>,
+}
+
+impl TypeMapKey for CommandGroups {
+    type Value = CommandGroups;
+}
+
+impl CommandGroups {
+    /// Collects the enabled groups.
+    pub fn new() -> Self {
+        #[allow(unused_mut)]
+        let mut groups: Vec<(&'static CommandGroup, &'static str)> = vec![];
+        #[cfg(feature = "core")]
+        groups.extend([
+            (&youmubot_core::FUN_GROUP, "fun"),
+            (&youmubot_core::COMMUNITY_GROUP, "community"),
+        ]);
+        #[cfg(feature = "osu")]
+        groups.push((&youmubot_osu::discord::OSU_GROUP, "osu"));
+        #[cfg(feature = "codeforces")]
+        groups.extend([
+            (&youmubot_cf::CODEFORCES_GROUP, "codeforces"),
+            (&youmubot_cf::ATCODER_GROUP, "atcoder"),
+        ]);
+        Self { groups }
+    }
+
+    /// Returns the group of the command invoked by the message, given the prefixes in effect,
+    /// if it belongs to one.
+    ///
+    /// The framework only tells us the command name, which is not unique among prefixed groups
+    /// (think `osu profile` and `cf profile`), so we look at the message ourselves.
+    pub fn resolve(&self, prefixes: &[String], content: &str) -> Option<&'static str> {
+        let rest = prefixes
+            .iter()
+            .find_map(|p| content.strip_prefix(p.as_str()))?;
+        let word = rest.split_whitespace().next()?.to_lowercase();
+        let word = word.as_str();
+        self.groups
+            .iter()
+            .find(|(g, _)| g.options.prefixes.contains(&word))
+            .or_else(|| {
+                self.groups.iter().find(|(g, _)| {
+                    g.options.prefixes.is_empty()
+                        && g.options
+                            .commands
+                            .iter()
+                            .any(|c| c.options.names.contains(&word))
+                })
+            })
+            .map(|(_, key)| *key)
+    }
+}
@@ -1,4 +1,3 @@
-use dotenv::var;
 use hook::InteractionHook;
 use serenity::{
     all::{CreateInteractionResponseMessage, Interaction},
@@ -8,20 +7,24 @@ use serenity::{
     model::{
         channel::{Channel, Message},
         gateway,
+        id::{ChannelId, GuildId},
         permissions::Permissions,
     },
 };
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use youmubot_prelude::announcer::AnnouncerHandler;
+use youmubot_prelude::ratelimit::RateLimited;
 use youmubot_prelude::*;
 
+use crate::command_groups::CommandGroups;
 use crate::compose_framework::ComposedFramework;
 
+mod command_groups;
 mod compose_framework;
 
 struct Handler {
-    hooks: Vec<RwLock<Box<dyn Hook>>>,
+    hooks: Vec<(&'static str, RwLock<Box<dyn Hook>>)>,
     interaction_hooks: Vec<Box<dyn InteractionHook>>,
     ready_hooks: Vec<fn(&Context) -> CommandResult>,
 }
@@ -36,7 +39,8 @@ impl Handler {
     }
 
     fn push_hook<T: Hook + 'static>(&mut self, f: T) {
-        self.hooks.push(RwLock::new(Box::new(f)));
+        self.hooks
+            .push((std::any::type_name::<T>(), RwLock::new(Box::new(f))));
     }
 
     #[allow(unused)]
@@ -88,9 +92,10 @@ impl EventHandler for Handler {
     async fn message(&self, ctx: Context, message: Message) {
         self.hooks
             .iter()
-            .map(|hook| {
+            .map(|(name, hook)| {
                 let ctx = ctx.clone();
                 let message = message.clone();
+                youmubot_prelude::metrics::hook(name);
                 hook.write()
                     .then(|mut h| async move { h.call(&ctx, &message).await })
             })
@@ -104,15 +109,23 @@ impl EventHandler for Handler {
     }
 
     async fn ready(&self, ctx: Context, ready: gateway::Ready) {
-        // Start ReactionWatchers for community.
+        // Start ReactionWatchers, resume polls and reminders, watch starboards for community and scheduled unbans for admin.
         #[cfg(feature = "core")]
-        ctx.data
-            .read()
-            .await
-            .get::<youmubot_core::community::ReactionWatchers>()
-            .unwrap()
-            .init(&ctx)
-            .await;
+        {
+            ctx.data
+                .read()
+                .await
+                .get::<youmubot_core::community::ReactionWatchers>()
+                .unwrap()
+                .init(&ctx)
+                .await;
+            youmubot_core::community::Polls::init(&ctx).await.pls_ok();
+            youmubot_core::community::Reminders::init(&ctx)
+                .await
+                .pls_ok();
+            youmubot_core::community::Starboards::init(&ctx).await;
+            youmubot_core::admin::ModJobs::init(&ctx).await.pls_ok();
+        }
         println!("{} is connected!", ready.user.name);
 
         for f in &self.ready_hooks {
@@ -171,24 +184,38 @@ async fn main() {
         println!("Loaded dotenv from {:?}", path);
     }
 
+    // Load the configuration
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
     let mut handler = Handler::new();
     // Set up hooks
     #[cfg(feature = "osu")]
     {
-        handler.push_hook(youmubot_osu::discord::hook);
-        handler.push_hook(youmubot_osu::discord::dot_osu_hook);
-        handler.push_hook(youmubot_osu::discord::score_hook);
+        if config.features.hooks {
+            handler.push_hook(youmubot_osu::discord::hook);
+            handler.push_hook(youmubot_osu::discord::dot_osu_hook);
+            handler.push_hook(youmubot_osu::discord::score_hook);
+        }
         handler.push_interaction_hook(youmubot_osu::discord::interaction::handle_check_button);
         handler.push_interaction_hook(youmubot_osu::discord::interaction::handle_last_button);
         handler.push_interaction_hook(youmubot_osu::discord::interaction::handle_last_set_button);
         handler.push_interaction_hook(youmubot_osu::discord::interaction::handle_simulate_button);
         handler.push_interaction_hook(youmubot_osu::discord::interaction::handle_lb_button);
     }
+    #[cfg(feature = "core")]
+    handler.push_interaction_hook(youmubot_core::community::handle_role_menu);
     #[cfg(feature = "codeforces")]
-    handler.push_hook(youmubot_cf::InfoHook);
+    if config.features.hooks {
+        handler.push_hook(youmubot_cf::InfoHook);
+    }
 
-    // Collect the token
-    let token = var("TOKEN").expect("Please set TOKEN as the Discord Bot's token to be used.");
+    let token = config.discord.token.clone();
 
     // Data to be put into context
     let mut data = TypeMap::new();
@@ -198,23 +225,10 @@ async fn main() {
 
     // Setup each package starting from the prelude.
     let env = {
-        let db_path = var("DBPATH")
-            .map(std::path::PathBuf::from)
-            .unwrap_or_else(|e| {
-                println!("No DBPATH set up ({:?}), using `/data`", e);
-                std::path::PathBuf::from("/data")
-            });
-        let sql_path = var("SQLPATH")
-            .map(std::path::PathBuf::from)
-            .unwrap_or_else(|e| {
-                let res = db_path.join("youmubot.db");
-                println!("No SQLPATH set up ({:?}), using `{:?}`", e, res);
-                res
-            });
-        let prelude = setup::setup_prelude(&db_path, sql_path, &mut data).await;
+        let prelude = setup::setup_prelude(config, &mut data).await;
         // Setup core
         #[cfg(feature = "core")]
-        let core = youmubot_core::setup(&db_path, &mut data, prelude.clone())
+        let core = youmubot_core::setup(&mut data, prelude.clone())
             .await
             .expect("Setup db should succeed");
         // osu!
@@ -224,7 +238,7 @@ async fn main() {
             .expect("osu! is initialized");
         // codeforces
         #[cfg(feature = "codeforces")]
-        youmubot_cf::setup(&db_path, &mut data, &mut announcers).await;
+        youmubot_cf::setup(&prelude.config, &mut data, &mut announcers).await;
 
         Env {
             prelude,
@@ -239,8 +253,11 @@ async fn main() {
     let paginator = youmubot_prelude::InteractionCollector::new();
     handler.push_interaction_hook(paginator.clone());
     data.insert::<youmubot_prelude::InteractionCollector>(paginator);
+    handler.push_interaction_hook(env.prelude.paginators.clone());
 
     data.insert::<Env>(env.clone());
+    let config = env.prelude.config.clone();
+    let (shutdown, sql) = (env.prelude.shutdown.clone(), env.prelude.sql.clone());
 
     #[cfg(feature = "core")]
     println!("Core enabled.");
@@ -250,7 +267,8 @@ async fn main() {
     println!("codeforces enabled.");
 
     // Set up base framework
-    let fw = setup_framework(&token[..]).await;
+    let fw = setup_framework(&token[..], &config).await;
+    data.insert::<CommandGroups>(CommandGroups::new());
 
     // Poise for application commands
     let poise_fw = poise::Framework::builder()
@@ -266,17 +284,54 @@ async fn main() {
                 ..Default::default()
             },
             command_check: Some(|ctx| {
-                Box::pin(command_check(ctx.data(), UserId(ctx.author().id)).map(Ok))
+                let group = ctx
+                    .parent_commands()
+                    .first()
+                    .copied()
+                    .unwrap_or(ctx.command())
+                    .category
+                    .as_deref();
+                Box::pin(
+                    command_check(
+                        ctx.data(),
+                        &ctx.serenity_context().cache,
+                        UserId(ctx.author().id),
+                        ctx.guild_id(),
+                        ctx.channel_id(),
+                        group,
+                    )
+                    .map(|r| r.map(|()| true).map_err(Error::msg)),
+                )
             }),
+            pre_command: |ctx| {
+                Box::pin(async move { youmubot_prelude::metrics::command_started(ctx.id()) })
+            },
+            post_command: |ctx| {
+                Box::pin(async move {
+                    youmubot_prelude::metrics::command_finished(
+                        ctx.id(),
+                        &ctx.command().qualified_name,
+                        true,
+                    )
+                })
+            },
             on_error: |err| {
                 Box::pin(async move {
                     match err {
                         poise::FrameworkError::Command { error, ctx, .. } => {
-                            let reply = format!(
-                                "Command '{}' returned error: {:?}",
-                                ctx.invoked_command_name(),
-                                error
+                            youmubot_prelude::metrics::command_finished(
+                                ctx.id(),
+                                &ctx.command().qualified_name,
+                                false,
                             );
+                            let reply = match error.downcast_ref::<RateLimited>() {
+                                Some(rl) => rl.to_string(),
+                                None => format!(
+                                    "Command '{}' returned error: {:?}",
+                                    ctx.invoked_command_name(),
+                                    error
+                                ),
+                            };
                             eprintln!("{}\n{:?}", reply, error);
                             ctx.send(poise::CreateReply::default().content(reply).ephemeral(true))
                                 .await
@@ -291,12 +346,19 @@ async fn main() {
                             .await
                             .pls_ok();
                         }
-                        poise::FrameworkError::CommandCheckFailed { error: _, ctx, .. }
-                        | poise::FrameworkError::CooldownHit {
-                            remaining_cooldown: _,
-                            ctx,
-                            ..
-                        } => {
+                        poise::FrameworkError::CommandCheckFailed { error, ctx, .. } => {
+                            let reason = error
+                                .map(|e| e.to_string())
+                                .unwrap_or_else(|| "you cannot use this command here".to_owned());
+                            ctx.send(
+                                poise::CreateReply::default()
+                                    .content(format!("🔇 Sorry, {}.", reason))
+                                    .ephemeral(true),
+                            )
+                            .await
+                            .pls_ok();
+                        }
+                        poise::FrameworkError::CooldownHit { ctx, .. } => {
                             ctx.send(
                                 poise::CreateReply::default()
                                     .content("You are being rate-limited, please try again later!")
@@ -317,7 +379,28 @@ async fn main() {
                 youmubot_osu::discord::osu_command(),
                 #[cfg(feature = "core")]
                 youmubot_core::admin::ignore::ignore(),
+                #[cfg(feature = "core")]
+                youmubot_core::admin::ignore::guildignore(),
+                #[cfg(feature = "core")]
+                youmubot_core::admin::groups::commandgroup(),
+                #[cfg(feature = "core")]
+                youmubot_core::community::poll(),
+                #[cfg(feature = "core")]
+                youmubot_core::community::remind(),
+                #[cfg(feature = "core")]
+                youmubot_core::community::starboard(),
+                #[cfg(feature = "core")]
+                youmubot_core::admin::modlog(),
+                #[cfg(feature = "core")]
+                youmubot_core::admin::clean::clean(),
             ],
+            // The application owner is added on startup.
+            owners: config
+                .discord
+                .owners
+                .iter()
+                .map(|&id| serenity::all::UserId::new(id))
+                .collect(),
             ..Default::default()
         })
         .build();
@@ -344,17 +427,51 @@ async fn main() {
             .unwrap()
     };
 
-    let announcers = announcers.run(&client);
-    tokio::spawn(announcers.scan(std::time::Duration::from_secs(300)));
+    let announcers = announcers
+        .run(&client, config.clone())
+        .expect("announcers are configured correctly");
+    tokio::spawn(announcers.scan());
+
+    #[cfg(feature = "metrics")]
+    if config.features.metrics {
+        let addr = config.metrics.address;
+        tokio::spawn(async move { youmubot_prelude::metrics::serve(addr).await.pls_ok() });
+    }
+
+    // Stop taking commands and disconnect on Ctrl-C or SIGTERM.
+    {
+        let shutdown = shutdown.clone();
+        let shard_manager = client.shard_manager.clone();
+        tokio::spawn(async move {
+            youmubot_prelude::shutdown::signal().await;
+            println!("Shutting down...");
+            shutdown.start();
+            shard_manager.shutdown_all().await;
+        });
+    }
 
     println!("Starting...");
     if let Err(v) = client.start().await {
         panic!("{}", v)
     }
+
+    // Let background tasks finish, then close the databases.
+    let timeout = std::time::Duration::from_secs(config.shutdown.timeout_secs);
+    if !shutdown.shutdown(timeout).await {
+        eprintln!(
+            "Background tasks did not finish in {:?}, stopping anyway",
+            timeout
+        );
+    }
+    tokio::task::spawn_blocking(youmubot_db::close)
+        .await
+        .pls_ok();
+    sql.close().await;
+    println!("Bye!");
 }
 
 // Sets up a framework for a client
-async fn setup_framework(token: &str) -> StandardFramework {
+async fn setup_framework(token: &str, config: &Config) -> StandardFramework {
     let http = serenity::http::Http::new(token);
     // Collect owners
     let owner = http
@@ -377,11 +494,6 @@ async fn setup_framework(token: &str) -> StandardFramework {
                 .limit(1)
         })
         .await
-        .bucket(
-            "images",
-            BucketBuilder::new_channel().time_span(60).limit(2),
-        )
-        .await
         .bucket(
             "community",
             BucketBuilder::new_guild()
@@ -395,13 +507,26 @@ async fn setup_framework(token: &str) -> StandardFramework {
     fw.configure(
         Configuration::new()
             .with_whitespace(false)
-            .prefixes(
-                var("PREFIX")
-                    .map(|v| v.split(',').map(|v| v.trim().to_owned()).collect())
-                    .unwrap_or_else(|_| vec!["y!".to_owned(), "y2!".to_owned()]),
-            )
+            // Prefixes are resolved per guild, see `prefixes_for`.
+            .prefixes(Vec::<String>::new())
+            .dynamic_prefix(|ctx, msg| {
+                Box::pin(async move {
+                    let data = ctx.data.read().await;
+                    prefixes_for(&data, msg.guild_id)
+                        .into_iter()
+                        .find(|p| msg.content.starts_with(p.as_str()))
+                })
+            })
             .delimiters(vec![" / ", "/ ", " /", "/"])
-            .owners([owner.id].iter().cloned().collect()),
+            .owners(
+                config
+                    .discord
+                    .owners
+                    .iter()
+                    .map(|&id| serenity::all::UserId::new(id))
+                    .chain([owner.id])
+                    .collect(),
+            ),
     );
     // groups here
     #[cfg(feature = "core")]
@@ -412,10 +537,26 @@ async fn setup_framework(token: &str) -> StandardFramework {
     #[cfg(feature = "osu")]
     let fw = fw.group(&youmubot_osu::discord::OSU_GROUP);
     #[cfg(feature = "codeforces")]
-    let fw = fw.group(&youmubot_cf::CODEFORCES_GROUP);
+    let fw = fw
+        .group(&youmubot_cf::CODEFORCES_GROUP)
+        .group(&youmubot_cf::ATCODER_GROUP);
     fw
 }
 
+/// The command prefixes in effect in the guild, or in DMs.
+fn prefixes_for(data: &TypeMap, guild: Option<GuildId>) -> Vec<String> {
+    #[cfg(feature = "core")]
+    if let Some(prefixes) = guild.and_then(|guild| {
+        data.get::<youmubot_core::admin::GuildPrefixes>()?
+            .get(guild)
+    }) {
+        return prefixes;
+    }
+    data.get::<Env>()
+        .map(|env| env.prelude.config.discord.prefixes.clone())
+        .unwrap_or_default()
+}
+
 // Poise command to register
 #[poise::command(
     prefix_command,
@@ -428,35 +569,82 @@ async fn poise_register(ctx: CmdContext<'_, Env>) -> Result<()> {
     Ok(())
 }
 
-async fn command_check(env: &Env, author: UserId) -> bool {
+/// Checks whether the command can be run, returning the reason why not otherwise.
+async fn command_check(
+    env: &Env,
+    cache: &serenity::cache::Cache,
+    author: UserId,
+    guild: Option<GuildId>,
+    channel: ChannelId,
+    group: Option<&str>,
+) -> std::result::Result<(), String> {
+    if env.prelude.shutdown.is_started() {
+        tracing::info!("Shutting down, skipping...");
+        return Err("I am shutting down".to_owned());
+    }
     #[cfg(feature = "core")]
-    if youmubot_core::admin::ignore::should_ignore(env, author) {
-        tracing::info!("User is in ignore list, skipping...");
-        return false;
+    {
+        if youmubot_core::admin::ignore::should_ignore(env, author) {
+            tracing::info!("User is in ignore list, skipping...");
+            return Err("you are on my ignore list".to_owned());
+        }
+        if let Some(guild) = guild {
+            if youmubot_core::admin::ignore::should_ignore_in(env, guild, author) {
+                tracing::info!("User is in the guild's ignore list, skipping...");
+                return Err("you are on this server's ignore list".to_owned());
+            }
+            if let Some(group) = group {
+                if youmubot_core::admin::groups::is_disabled(env, cache, guild, channel, group) {
+                    tracing::info!("Command group '{}' is disabled here, skipping...", group);
+                    return Err(format!("the command group `{}` is disabled here", group));
+                }
+            }
+        }
     }
-    true
+    Ok(())
 }
 
 // Hooks!
 
 #[hook]
 async fn before_hook(ctx: &Context, msg: &Message, command_name: &str) -> bool {
-    let env = ctx.data.read().await;
-    let env = env.get::<Env>().unwrap();
+    let data = ctx.data.read().await;
+    let env = data.get::<Env>().unwrap();
     tracing::info!(
         "Got command '{}' by user '{}'",
         command_name,
         msg.author.name
     );
-    command_check(env, UserId(msg.author.id)).await
+    let prefixes = prefixes_for(&data, msg.guild_id);
+    let group = data
+        .get::<CommandGroups>()
+        .and_then(|g| g.resolve(&prefixes, &msg.content));
+    let allowed = command_check(
+        env,
+        &ctx.cache,
+        UserId(msg.author.id),
+        msg.guild_id,
+        msg.channel_id,
+        group,
+    )
+    .await
+    .is_ok();
+    if allowed {
+        youmubot_prelude::metrics::command_started(msg.id.get());
+    }
+    allowed
 }
 
 #[hook]
 async fn after_hook(ctx: &Context, msg: &Message, command_name: &str, error: CommandResult) {
+    youmubot_prelude::metrics::command_finished(msg.id.get(), command_name, error.is_ok());
     match error {
         Ok(()) => tracing::info!("Processed command '{}'", command_name),
         Err(why) => {
-            let reply = format!("Command '{}' returned error {:?}", command_name, why);
+            let reply = match why.downcast_ref::<RateLimited>() {
+                Some(rl) => rl.to_string(),
+                None => format!("Command '{}' returned error {:?}", command_name, why),
+            };
             msg.reply(&ctx, &reply).await.ok();
             tracing::info!("{}", reply)
         }